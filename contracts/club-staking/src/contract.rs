@@ -14,7 +14,7 @@ use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, ProxyQueryMsgs, QueryMs
 use crate::state::{
     CLUB_BONDING_DETAILS, CLUB_OWNERSHIP_DETAILS, CLUB_PREVIOUS_OWNER_DETAILS, CLUB_REWARD_NEXT_TIMESTAMP, CLUB_STAKING_DETAILS,
     CLUB_STAKING_SNAPSHOT, ClubBondingDetails, ClubOwnershipDetails,
-    ClubPreviousOwnerDetails, ClubStakingDetails, Config, CONFIG, REWARD, REWARD_GIVEN_IN_CURRENT_TIMESTAMP,
+    ClubPreviousOwnerDetails, ClubStakingDetails, Config, CONFIG, PRICE_TWAP_WINDOW, REWARD, REWARD_GIVEN_IN_CURRENT_TIMESTAMP,
     WINNING_CLUB_DETAILS_SNAPSHOT, WinningClubDetails,
 };
 
@@ -49,6 +49,8 @@ const CLUB_STAKING_DURATION: u64 = 0u64;
 
 const HUNDRED_PERCENT: u128 = 10000u128;
 const NINETY_NINE_NINE_PERCENT: u128 = 9990u128;
+// Longest TWAP window the proxy keeps price observations for
+const MAX_PRICE_TWAP_WINDOW_SECS: u64 = 7 * 24 * 60 * 60;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -172,6 +174,9 @@ pub fn execute(
         } => {
             increase_reward_amount(deps, env, info, reward_from, amount)
         }
+        ExecuteMsg::SetPriceTwapWindow { window_secs } => {
+            set_price_twap_window(deps, info, window_secs)
+        }
     }
 }
 
@@ -1409,6 +1414,37 @@ fn save_bonding_details(
     return Ok(Response::default());
 }
 
+fn set_price_twap_window(
+    deps: DepsMut,
+    info: MessageInfo,
+    window_secs: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    // For SECURITY This message MUST only come from the Admin
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let window = match window_secs {
+        Some(window_secs) => {
+            if window_secs == 0 || window_secs > MAX_PRICE_TWAP_WINDOW_SECS {
+                return Err(ContractError::InvalidTwapWindow {
+                    max_secs: MAX_PRICE_TWAP_WINDOW_SECS,
+                });
+            }
+            PRICE_TWAP_WINDOW.save(deps.storage, &window_secs)?;
+            window_secs.to_string()
+        }
+        None => {
+            // back to the spot price for the platform fees
+            PRICE_TWAP_WINDOW.remove(deps.storage);
+            String::from("spot")
+        }
+    };
+    return Ok(Response::new()
+        .add_attribute("action", "set_price_twap_window")
+        .add_attribute("window_secs", window));
+}
+
 fn increase_reward_amount(
     deps: DepsMut,
     env: Env,
@@ -1963,6 +1999,9 @@ pub fn query_platform_fees(deps: Deps, msg: Binary) -> StdResult<Uint128> {
            }) => {
             return Ok(Uint128::zero());
         }
+        Ok(ExecuteMsg::SetPriceTwapWindow { window_secs: _ }) => {
+            return Ok(Uint128::zero());
+        }
         Ok(ExecuteMsg::BuyAClub {
                buyer: _,
                seller: _,
@@ -2033,6 +2072,7 @@ pub fn query_platform_fees(deps: Deps, msg: Binary) -> StdResult<Uint128> {
         .querier
         .query_wasm_smart(config.astro_proxy_address, &ProxyQueryMsgs::get_ust_equivalent_to_fury {
             fury_count: fury_amount_provided,
+            twap_window_secs: PRICE_TWAP_WINDOW.may_load(deps.storage)?,
         })?;
 
     return Ok(ust_equiv_for_fury
@@ -2247,7 +2287,7 @@ pub fn query_club_ownership_details_for_owner(
 
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{Addr, coins, CosmosMsg, from_binary, StdError, SubMsg, WasmMsg};
+    use cosmwasm_std::{Addr, coins, ContractResult, CosmosMsg, from_binary, StdError, SubMsg, SystemResult, WasmMsg, WasmQuery};
     use cosmwasm_std::coin;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 
@@ -4269,4 +4309,60 @@ mod tests {
             club_name3, false, true).unwrap_err();
         assert_eq!(res3, (ContractError::Std(StdError::GenericErr {msg: String::from("Time for Reward not yet arrived")})));
     }
+
+    #[test]
+    fn test_platform_fees_priced_at_the_twap_window() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            admin_address: "admin11111".to_string(),
+            minting_contract_address: "minting_admin11111".to_string(),
+            astro_proxy_address: "astro_proxy_address1111".to_string(),
+            club_fee_collector_wallet: "club_fee_collector_wallet11111".to_string(),
+            club_reward_next_timestamp: mock_env().block.time,
+            reward_periodicity: 24 * 60 * 60u64,
+            club_price: Uint128::from(1000000u128),
+            bonding_duration: 5 * 60u64,
+            owner_release_locking_duration: 24 * 60 * 60u64,
+            platform_fees_collector_wallet: "platform_fee_collector_wallet_1111".to_string(),
+            platform_fees: Uint128::from(100u128),
+            transaction_fees: Uint128::from(30u128),
+            control_fees: Uint128::from(50u128),
+            max_bonding_limit_per_user: 10u64,
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(deps.as_mut(), mock_env(), adminInfo.clone(), instantiate_msg).unwrap();
+
+        // The mocked proxy prices FURY at 10 UST spot and at 8 UST over a 1 hour TWAP
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let response = match from_binary(msg).unwrap() {
+                    ProxyQueryMsgs::get_ust_equivalent_to_fury { fury_count, twap_window_secs: None } => fury_count * Uint128::from(10u128),
+                    ProxyQueryMsgs::get_ust_equivalent_to_fury { fury_count, twap_window_secs: Some(3600) } => fury_count * Uint128::from(8u128),
+                    _ => panic!("unexpected proxy query"),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&response).unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        });
+        let withdraw = to_binary(&ExecuteMsg::StakeWithdrawFromAClub {
+            staker: "staker001".to_string(),
+            club_name: "CLUB001".to_string(),
+            amount: Uint128::from(10000u128),
+            immediate_withdrawal: false,
+        }).unwrap();
+        // 1.3% of 100000 UST
+        assert_eq!(query_platform_fees(deps.as_ref(), withdraw.clone()).unwrap(), Uint128::from(1300u128));
+
+        let err = set_price_twap_window(deps.as_mut(), mock_info("staker001", &[]), Some(3600)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = set_price_twap_window(deps.as_mut(), adminInfo.clone(), Some(8 * 24 * 60 * 60)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidTwapWindow { max_secs: 604800 });
+        set_price_twap_window(deps.as_mut(), adminInfo.clone(), Some(3600)).unwrap();
+        assert_eq!(query_platform_fees(deps.as_ref(), withdraw.clone()).unwrap(), Uint128::from(1040u128));
+
+        // 10 UST per FURY again at the spot price
+        set_price_twap_window(deps.as_mut(), adminInfo.clone(), None).unwrap();
+        assert_eq!(query_platform_fees(deps.as_ref(), withdraw).unwrap(), Uint128::from(1300u128));
+    }
 }
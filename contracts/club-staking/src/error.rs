@@ -31,6 +31,11 @@ pub enum ContractError {
         backtrace: Backtrace,
     },
 
+    #[error("TWAP window must be between 1 and {max_secs} seconds")]
+    InvalidTwapWindow {
+        max_secs: u64,
+    },
+
     #[error("Fees received = {received}uusd whereas required = {required}uusd")]
     InsufficientFees {
         received: Uint128,
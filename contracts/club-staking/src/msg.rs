@@ -105,6 +105,11 @@ pub enum ExecuteMsg {
         reward_from: String,
         amount: Uint128,
     },
+    /// Platform fees are priced at the FURY TWAP over window_secs (a week
+    /// at most), or at the spot price if None
+    SetPriceTwapWindow {
+        window_secs: Option<u64>,
+    },
 
 }
 
@@ -171,9 +176,11 @@ pub struct MigrateMsg {}
 pub enum ProxyQueryMsgs {
     get_fury_equivalent_to_ust {
         ust_count: Uint128,
+        twap_window_secs: Option<u64>,
     },
     get_ust_equivalent_to_fury {
         fury_count: Uint128,
+        twap_window_secs: Option<u64>,
     },
 }
 
@@ -153,3 +153,7 @@ pub const CLUB_STAKING_SNAPSHOT: Map<String, Uint128> =
 
 /// Snapshot of winning club details
 pub const WINNING_CLUB_DETAILS_SNAPSHOT: Item<WinningClubDetails> = Item::new("winning_club_details_snapshot");
+
+/// TWAP window in seconds for pricing the FURY of the platform fees,
+/// spot price if not set
+pub const PRICE_TWAP_WINDOW: Item<u64> = Item::new("price_twap_window");
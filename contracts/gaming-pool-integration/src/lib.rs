@@ -0,0 +1,46 @@
+//! Contracts standing in for the chain side of the gaming pool in the
+//! multi contract tests under `tests/`, along with the builders of every
+//! contract the tests deploy.
+
+use cosmwasm_std::Empty;
+use cw_multi_test::{Contract, ContractWrapper};
+
+pub mod mock_pair;
+
+pub fn cw20_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    ))
+}
+
+pub fn mock_pair_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        mock_pair::execute,
+        mock_pair::instantiate,
+        mock_pair::query,
+    ))
+}
+
+pub fn proxy_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            terra_swap_proxy::contract::execute,
+            terra_swap_proxy::contract::instantiate,
+            terra_swap_proxy::contract::query,
+        )
+        .with_reply(terra_swap_proxy::contract::reply),
+    )
+}
+
+pub fn gaming_pool_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            gaming_pool::contract::execute,
+            gaming_pool::contract::instantiate,
+            gaming_pool::contract::query,
+        )
+        .with_reply(gaming_pool::contract::reply),
+    )
+}
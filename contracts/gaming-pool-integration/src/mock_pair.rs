@@ -0,0 +1,145 @@
+//! A FURY/UST pair for the multi contract tests. It speaks the terraswap
+//! pair messages the proxy sends and prices swaps at constant product on
+//! its own balances, without commission and without LP tokens.
+
+use cosmwasm_std::{from_binary, from_slice, to_binary, to_vec, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps,
+                   DepsMut, Env, MessageInfo, QuerierWrapper, Response, StdError, StdResult, Uint128, WasmMsg};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use terraswap::asset::{Asset, AssetInfo};
+use terraswap::pair::{Cw20HookMsg, ExecuteMsg, PoolResponse, QueryMsg, SimulationResponse};
+
+const CONFIG_KEY: &[u8] = b"config";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// The FURY token contract
+    pub token: String,
+    /// The native denom FURY is traded against
+    pub denom: String,
+}
+
+pub fn instantiate(deps: DepsMut, _env: Env, _info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+    deps.api.addr_validate(&msg.token)?;
+    deps.storage.set(CONFIG_KEY, &to_vec(&msg)?);
+    Ok(Response::new())
+}
+
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+    let config = load_config(deps.as_ref())?;
+    match msg {
+        ExecuteMsg::Receive(cw20_msg) => {
+            if info.sender != config.token {
+                return Err(StdError::generic_err("Only FURY can be sold to the pair"));
+            }
+            let to = match from_binary(&cw20_msg.msg)? {
+                Cw20HookMsg::Swap { to, .. } => to.unwrap_or(cw20_msg.sender),
+                Cw20HookMsg::WithdrawLiquidity {} => {
+                    return Err(StdError::generic_err("Liquidity is not supported"));
+                }
+            };
+            // The FURY sold is already in the reserves
+            let (ust_pool, fury_pool) = reserves(&deps.querier, &env.contract.address, &config)?;
+            let return_amount = swap_return(fury_pool.checked_sub(cw20_msg.amount)?, ust_pool, cw20_msg.amount)?;
+            Ok(Response::new()
+                .add_message(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: to,
+                    amount: vec![Coin {
+                        denom: config.denom,
+                        amount: return_amount,
+                    }],
+                }))
+                .add_attribute("action", "swap")
+                .add_attribute("return_amount", return_amount))
+        }
+        ExecuteMsg::Swap { offer_asset, to, .. } => {
+            let sent = info
+                .funds
+                .iter()
+                .find(|coin| coin.denom == config.denom)
+                .map(|coin| coin.amount)
+                .unwrap_or_default();
+            if !offer_asset.is_native_token() || sent != offer_asset.amount {
+                return Err(StdError::generic_err("Offer must be sent as UST funds"));
+            }
+            // The UST sent along is already in the reserves
+            let (ust_pool, fury_pool) = reserves(&deps.querier, &env.contract.address, &config)?;
+            let return_amount = swap_return(ust_pool.checked_sub(sent)?, fury_pool, sent)?;
+            Ok(Response::new()
+                .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: config.token,
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: to.unwrap_or_else(|| info.sender.to_string()),
+                        amount: return_amount,
+                    })?,
+                    funds: vec![],
+                }))
+                .add_attribute("action", "swap")
+                .add_attribute("return_amount", return_amount))
+        }
+        ExecuteMsg::ProvideLiquidity { .. } => Err(StdError::generic_err("Liquidity is not supported")),
+        ExecuteMsg::HelloSub {} => Ok(Response::new()),
+    }
+}
+
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let config = load_config(deps)?;
+    let (ust_pool, fury_pool) = reserves(&deps.querier, &env.contract.address, &config)?;
+    match msg {
+        QueryMsg::Pool {} => to_binary(&PoolResponse {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken { denom: config.denom },
+                    amount: ust_pool,
+                },
+                Asset {
+                    info: AssetInfo::Token { contract_addr: config.token },
+                    amount: fury_pool,
+                },
+            ],
+            total_share: Uint128::zero(),
+        }),
+        QueryMsg::Simulation { offer_asset } => {
+            let return_amount = if offer_asset.is_native_token() {
+                swap_return(ust_pool, fury_pool, offer_asset.amount)?
+            } else {
+                swap_return(fury_pool, ust_pool, offer_asset.amount)?
+            };
+            to_binary(&SimulationResponse {
+                return_amount,
+                spread_amount: Uint128::zero(),
+                commission_amount: Uint128::zero(),
+            })
+        }
+        QueryMsg::Pair {} | QueryMsg::ReverseSimulation { .. } | QueryMsg::CumulativePrices {} => {
+            Err(StdError::generic_err("Query is not supported"))
+        }
+    }
+}
+
+fn load_config(deps: Deps) -> StdResult<InstantiateMsg> {
+    match deps.storage.get(CONFIG_KEY) {
+        Some(data) => from_slice(&data),
+        None => Err(StdError::not_found("config")),
+    }
+}
+
+// Returns the UST and the FURY held by the pair
+fn reserves(querier: &QuerierWrapper, pair: &Addr, config: &InstantiateMsg) -> StdResult<(Uint128, Uint128)> {
+    let ust = querier.query_balance(pair, &config.denom)?.amount;
+    let fury: BalanceResponse = querier.query_wasm_smart(
+        &config.token,
+        &Cw20QueryMsg::Balance {
+            address: pair.to_string(),
+        },
+    )?;
+    Ok((ust, fury.balance))
+}
+
+fn swap_return(offer_pool: Uint128, ask_pool: Uint128, offer_amount: Uint128) -> StdResult<Uint128> {
+    let remaining_ask = offer_pool
+        .checked_mul(ask_pool)?
+        .checked_div(offer_pool.checked_add(offer_amount)?)?;
+    Ok(ask_pool.checked_sub(remaining_ask)?)
+}
@@ -0,0 +1,358 @@
+//! Drives a game through the gaming pool, the proxy, the FURY token and the
+//! mock pair together, so the submessages and replies between them run
+//! the way they do on chain.
+
+use cosmwasm_std::{coins, Addr, Coin, Uint128, Uint64};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw_multi_test::{App, Executor};
+
+use gaming_pool::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use gaming_pool::state::{GameResult, PoolTeamDetails, SwapBalanceDetails, WalletPercentage};
+use gaming_pool_integration::{cw20_contract, gaming_pool_contract, mock_pair, mock_pair_contract, proxy_contract};
+
+const UUSD: &str = "uusd";
+const ADMIN: &str = "admin";
+const GAMER_1: &str = "gamer1";
+const GAMER_2: &str = "gamer2";
+const FEE_COLLECTOR: &str = "fee_collector";
+const RAKE_WALLET: &str = "rake_wallet";
+const GAME_ID: &str = "Game001";
+const POOL_TYPE: &str = "oneToTwo";
+
+// 10 FURY per UST at the start
+const PAIR_UST: u128 = 100_000_000;
+const PAIR_FURY: u128 = 1_000_000_000;
+const POOL_FEE: u128 = 10_000_000;
+
+struct Suite {
+    app: App,
+    fury: Addr,
+    proxy: Addr,
+    gaming_pool: Addr,
+}
+
+impl Suite {
+    fn new() -> Self {
+        let mut app = App::new(|router, _, storage| {
+            for (wallet, amount) in [(ADMIN, 1_000_000_000u128), (GAMER_1, 10_000_000), (GAMER_2, 10_000_000)] {
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(wallet), coins(amount, UUSD))
+                    .unwrap();
+            }
+        });
+        let admin = Addr::unchecked(ADMIN);
+
+        let cw20_code = app.store_code(cw20_contract());
+        let fury = app
+            .instantiate_contract(
+                cw20_code,
+                admin.clone(),
+                &cw20_base::msg::InstantiateMsg {
+                    name: String::from("Fury"),
+                    symbol: String::from("FURY"),
+                    decimals: 6,
+                    initial_balances: vec![
+                        Cw20Coin {
+                            address: ADMIN.to_string(),
+                            amount: Uint128::from(PAIR_FURY),
+                        },
+                        Cw20Coin {
+                            address: GAMER_1.to_string(),
+                            amount: Uint128::from(500_000_000u128),
+                        },
+                        Cw20Coin {
+                            address: GAMER_2.to_string(),
+                            amount: Uint128::from(500_000_000u128),
+                        },
+                    ],
+                    mint: None,
+                    marketing: None,
+                },
+                &[],
+                "fury",
+                None,
+            )
+            .unwrap();
+
+        let pair_code = app.store_code(mock_pair_contract());
+        let pair = app
+            .instantiate_contract(
+                pair_code,
+                admin.clone(),
+                &mock_pair::InstantiateMsg {
+                    token: fury.to_string(),
+                    denom: UUSD.to_string(),
+                },
+                &[],
+                "pair",
+                None,
+            )
+            .unwrap();
+        app.send_tokens(admin.clone(), pair.clone(), &coins(PAIR_UST, UUSD))
+            .unwrap();
+        app.execute_contract(
+            admin.clone(),
+            fury.clone(),
+            &Cw20ExecuteMsg::Transfer {
+                recipient: pair.to_string(),
+                amount: Uint128::from(PAIR_FURY),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let proxy_code = app.store_code(proxy_contract());
+        let proxy = app
+            .instantiate_contract(
+                proxy_code,
+                admin.clone(),
+                &terra_swap_proxy::msg::InstantiateMsg {
+                    admin_address: ADMIN.to_string(),
+                    custom_token_address: fury.to_string(),
+                    pair_discount_rate: 0,
+                    pair_bonding_period_in_sec: 0,
+                    pair_fury_reward_wallet: ADMIN.to_string(),
+                    pair_lp_tokens_holder: ADMIN.to_string(),
+                    native_discount_rate: 0,
+                    native_bonding_period_in_sec: 0,
+                    native_investment_reward_wallet: ADMIN.to_string(),
+                    native_investment_receive_wallet: ADMIN.to_string(),
+                    authorized_liquidity_provider: ADMIN.to_string(),
+                    swap_opening_date: Uint64::zero(),
+                    pool_pair_address: Some(pair.to_string()),
+                    platform_fees_collector_wallet: FEE_COLLECTOR.to_string(),
+                    platform_fees: Uint128::from(100u128),
+                    transaction_fees: Uint128::zero(),
+                    swap_fees: Uint128::zero(),
+                    max_bonding_limit_per_user: 10,
+                    usdc_ibc_symbol: UUSD.to_string(),
+                },
+                &[],
+                "proxy",
+                None,
+            )
+            .unwrap();
+
+        let gaming_pool_code = app.store_code(gaming_pool_contract());
+        let gaming_pool = app
+            .instantiate_contract(
+                gaming_pool_code,
+                admin.clone(),
+                &InstantiateMsg {
+                    admin_address: ADMIN.to_string(),
+                    minting_contract_address: fury.to_string(),
+                    platform_fees_collector_wallet: FEE_COLLECTOR.to_string(),
+                    transaction_fee: Uint128::from(100u128),
+                    astro_proxy_address: proxy.to_string(),
+                    platform_fee: Uint128::from(100u128),
+                    game_id: GAME_ID.to_string(),
+                    usdc_ibc_symbol: UUSD.to_string(),
+                },
+                &[],
+                "gaming-pool",
+                None,
+            )
+            .unwrap();
+
+        let mut suite = Suite {
+            app,
+            fury,
+            proxy,
+            gaming_pool,
+        };
+        suite.execute_admin(&ExecuteMsg::SetPoolTypeParams {
+            pool_type: POOL_TYPE.to_string(),
+            pool_fee: Uint128::from(POOL_FEE),
+            min_teams_for_pool: 2,
+            max_teams_for_pool: 10,
+            max_teams_for_gamer: 2,
+            wallet_percentages: vec![WalletPercentage {
+                wallet_address: RAKE_WALLET.to_string(),
+                wallet_name: String::from("rake"),
+                percentage: 100,
+            }],
+            entry_asset: None,
+        });
+        suite.execute_admin(&ExecuteMsg::CreatePool {
+            pool_type: POOL_TYPE.to_string(),
+        });
+        suite
+    }
+
+    fn execute_admin(&mut self, msg: &ExecuteMsg) {
+        self.app
+            .execute_contract(Addr::unchecked(ADMIN), self.gaming_pool.clone(), msg, &[])
+            .unwrap();
+    }
+
+    fn fury_balance(&self, address: &Addr) -> Uint128 {
+        let response: BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &self.fury,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        response.balance
+    }
+
+    fn ust_balance(&self, address: &Addr) -> Uint128 {
+        self.app.wrap().query_balance(address, UUSD).unwrap().amount
+    }
+
+    fn ust_equivalent_to_fury(&self, fury_count: Uint128) -> Uint128 {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                &self.proxy,
+                &terra_swap_proxy::msg::QueryMsg::GetUstEquivalentToFury {
+                    fury_count,
+                    twap_window_secs: None,
+                    pair: None,
+                },
+            )
+            .unwrap()
+    }
+
+    // Approves the FURY of the bid and pays the fee of the pool in UST
+    fn bid(&mut self, gamer: &str, team_id: &str, amount: u128) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(gamer),
+                self.fury.clone(),
+                &Cw20ExecuteMsg::IncreaseAllowance {
+                    spender: self.gaming_pool.to_string(),
+                    amount: Uint128::from(amount),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        self.app
+            .execute_contract(
+                Addr::unchecked(gamer),
+                self.gaming_pool.clone(),
+                &ExecuteMsg::GamePoolBidSubmitCommand {
+                    gamer: gamer.to_string(),
+                    pool_type: POOL_TYPE.to_string(),
+                    pool_id: Some(String::from("1")),
+                    team_id: team_id.to_string(),
+                    amount: Uint128::from(amount),
+                },
+                &coins(POOL_FEE * 2 / 100, UUSD),
+            )
+            .unwrap();
+    }
+}
+
+#[test]
+fn bid_lock_swap_distribute_and_claim() {
+    let mut suite = Suite::new();
+    let gamer_1 = Addr::unchecked(GAMER_1);
+
+    // 10 UST is a little over 90.9 FURY at the starting price
+    suite.bid(GAMER_1, "team1", 91_000_000);
+    suite.bid(GAMER_2, "team2", 91_000_000);
+    assert_eq!(suite.fury_balance(&suite.gaming_pool), Uint128::from(182_000_000u128));
+    assert_eq!(suite.ust_balance(&suite.gaming_pool), Uint128::from(400_000u128));
+
+    // Locking sells the FURY of the pool through the proxy
+    suite.execute_admin(&ExecuteMsg::LockGame { max_spread: None });
+    assert_eq!(suite.fury_balance(&suite.gaming_pool), Uint128::zero());
+    let ust_after_lock = suite.ust_balance(&suite.gaming_pool);
+    assert!(ust_after_lock > Uint128::from(15_000_000u128));
+    assert!(!suite.ust_balance(&Addr::unchecked(FEE_COLLECTOR)).is_zero());
+
+    // Buying back the prize pot, the reply books the FURY received
+    suite.execute_admin(&ExecuteMsg::Swap {
+        amount: Uint128::from(14_000_000u128),
+        pool_id: String::from("1"),
+        max_spread: None,
+    });
+    let swap_info: SwapBalanceDetails = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.gaming_pool,
+            &QueryMsg::SwapInfo {
+                pool_id: String::from("1"),
+            },
+        )
+        .unwrap();
+    let fury_bought = suite.fury_balance(&suite.gaming_pool);
+    assert!(!fury_bought.is_zero());
+    assert_eq!(swap_info.balance_post_swap, fury_bought);
+    assert!(!swap_info.swap_failed);
+    assert!(fury_bought >= swap_info.min_fury_expected);
+
+    let reward = fury_bought.multiply_ratio(9u128, 10u128);
+    suite.execute_admin(&ExecuteMsg::GamePoolRewardDistribute {
+        pool_id: String::from("1"),
+        game_winners: vec![GameResult {
+            gamer_address: GAMER_1.to_string(),
+            team_id: String::from("team1"),
+            reward_amount: reward,
+            team_points: 0,
+        }],
+        is_final_batch: true,
+        ust_for_rake: Uint128::from(100_000u128),
+        game_id: GAME_ID.to_string(),
+    });
+    assert_eq!(suite.ust_balance(&Addr::unchecked(RAKE_WALLET)), Uint128::from(100_000u128));
+
+    let teams: Vec<PoolTeamDetails> = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.gaming_pool,
+            &QueryMsg::PoolTeamDetails {
+                pool_id: String::from("1"),
+                user: GAMER_1.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(teams[0].reward_amount, reward);
+
+    // The claim fee is 2% of the reward priced in UST
+    let fee = suite.ust_equivalent_to_fury(reward).multiply_ratio(2u128, 100u128) + Uint128::from(1u128);
+    let fury_before_claim = suite.fury_balance(&gamer_1);
+    let collected_before_claim = suite.ust_balance(&Addr::unchecked(FEE_COLLECTOR));
+    suite
+        .app
+        .execute_contract(
+            gamer_1.clone(),
+            suite.gaming_pool.clone(),
+            &ExecuteMsg::ClaimReward {
+                gamer: GAMER_1.to_string(),
+                pool_ids: None,
+            },
+            &[Coin {
+                denom: UUSD.to_string(),
+                amount: fee,
+            }],
+        )
+        .unwrap();
+    assert_eq!(suite.fury_balance(&gamer_1), fury_before_claim + reward);
+    assert_eq!(
+        suite.ust_balance(&Addr::unchecked(FEE_COLLECTOR)),
+        collected_before_claim + fee
+    );
+
+    // Claiming twice pays nothing more
+    suite
+        .app
+        .execute_contract(
+            gamer_1.clone(),
+            suite.gaming_pool.clone(),
+            &ExecuteMsg::ClaimReward {
+                gamer: GAMER_1.to_string(),
+                pool_ids: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+}
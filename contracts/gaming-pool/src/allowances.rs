@@ -1,9 +1,13 @@
+//! Delegated play. A gamer gives a manager a FURY budget with an expiry
+//! and the manager can then bid, claim refunds and claim rewards on behalf
+//! of the gamer. Each bid by the manager draws down the budget.
+
 use cosmwasm_std::{
-    Addr, attr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    Addr, attr, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
     Storage, Uint128,
 };
 
-use cw20::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
+use cw20::{AllowanceResponse, Expiration};
 
 use crate::error::ContractError;
 use crate::state::ALLOWANCES;
@@ -82,15 +86,15 @@ pub fn execute_decrease_allowance(
     Ok(res)
 }
 
-// this can be used to update a lower allowance - call bucket.update with proper keys
+// Draws the amount from the budget given by the gamer to the manager
 pub fn deduct_allowance(
     storage: &mut dyn Storage,
-    owner: &Addr,
-    spender: &Addr,
+    gamer: &Addr,
+    manager: &Addr,
     block: &BlockInfo,
     amount: Uint128,
 ) -> Result<AllowanceResponse, ContractError> {
-    ALLOWANCES.update(storage, (owner, spender), |current| {
+    ALLOWANCES.update(storage, (gamer, manager), |current| {
         match current {
             Some(mut a) => {
                 if a.expires.is_expired(block) {
@@ -109,125 +113,29 @@ pub fn deduct_allowance(
     })
 }
 
-pub fn execute_transfer_from(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    owner: String,
-    recipient: String,
-    amount: Uint128,
-) -> Result<Response, ContractError> {
-    let _rcpt_addr = deps.api.addr_validate(&recipient)?;
-    let owner_addr = deps.api.addr_validate(&owner)?;
-
-    // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
-
-    // BALANCES.update(
-    //     deps.storage,
-    //     &owner_addr,
-    //     |balance: Option<Uint128>| -> StdResult<_> {
-    //         Ok(balance.unwrap_or_default().checked_sub(amount)?)
-    //     },
-    // )?;
-    // BALANCES.update(
-    //     deps.storage,
-    //     &rcpt_addr,
-    //     |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    // )?;
-
-    let res = Response::new().add_attributes(vec![
-        attr("action", "transfer_from"),
-        attr("from", owner),
-        attr("to", recipient),
-        attr("by", info.sender),
-        attr("amount", amount),
-    ]);
-    Ok(res)
-}
-
-pub fn execute_burn_from(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    owner: String,
-    amount: Uint128,
-) -> Result<Response, ContractError> {
-    let owner_addr = deps.api.addr_validate(&owner)?;
-
-    // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
-
-    // // lower balance
-    // BALANCES.update(
-    //     deps.storage,
-    //     &owner_addr,
-    //     |balance: Option<Uint128>| -> StdResult<_> {
-    //         Ok(balance.unwrap_or_default().checked_sub(amount)?)
-    //     },
-    // )?;
-    // // reduce total_supply
-    // TOKEN_INFO.update(deps.storage, |mut meta| -> StdResult<_> {
-    //     meta.total_supply = meta.total_supply.checked_sub(amount)?;
-    //     Ok(meta)
-    // })?;
-
-    let res = Response::new().add_attributes(vec![
-        attr("action", "burn_from"),
-        attr("from", owner),
-        attr("by", info.sender),
-        attr("amount", amount),
-    ]);
-    Ok(res)
-}
-
-pub fn execute_send_from(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    owner: String,
-    contract: String,
-    amount: Uint128,
-    msg: Binary,
-) -> Result<Response, ContractError> {
-    let _rcpt_addr = deps.api.addr_validate(&contract)?;
-    let owner_addr = deps.api.addr_validate(&owner)?;
-
-    // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
-
-    // // move the tokens to the contract
-    // BALANCES.update(
-    //     deps.storage,
-    //     &owner_addr,
-    //     |balance: Option<Uint128>| -> StdResult<_> {
-    //         Ok(balance.unwrap_or_default().checked_sub(amount)?)
-    //     },
-    // )?;
-    // BALANCES.update(
-    //     deps.storage,
-    //     &rcpt_addr,
-    //     |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    // )?;
-
-    let attrs = vec![
-        attr("action", "send_from"),
-        attr("from", &owner),
-        attr("to", &contract),
-        attr("by", &info.sender),
-        attr("amount", amount),
-    ];
-
-    // create a send message
-    let msg = Cw20ReceiveMsg {
-        sender: info.sender.into(),
-        amount,
-        msg,
+// Checks the sender is the gamer or a manager with an unexpired allowance
+// from the gamer. The budget is not drawn here, only the bids draw it.
+pub fn check_gamer_or_manager(
+    storage: &dyn Storage,
+    gamer: &Addr,
+    sender: &Addr,
+    block: &BlockInfo,
+) -> Result<(), ContractError> {
+    if gamer == sender {
+        return Ok(());
+    }
+    match ALLOWANCES.may_load(storage, (gamer, sender))? {
+        Some(a) => {
+            if a.expires.is_expired(block) {
+                Err(ContractError::Expired {})
+            } else {
+                Ok(())
+            }
+        }
+        None => Err(ContractError::Unauthorized {
+            invoker: sender.to_string(),
+        }),
     }
-        .into_cosmos_msg(contract)?;
-
-    let res = Response::new().add_message(msg).add_attributes(attrs);
-    Ok(res)
 }
 
 pub fn query_allowance(deps: Deps, owner: String, spender: String) -> StdResult<AllowanceResponse> {
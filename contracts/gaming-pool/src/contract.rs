@@ -2,28 +2,36 @@ use std::convert::TryFrom;
 use std::ops::{Div, Mul};
 use std::str::FromStr;
 
-use cosmwasm_std::{Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult, to_binary, Uint128};
+use cosmwasm_std::{Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Reply, Response, StdError, StdResult, SubMsgResult, to_binary, Uint128};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::OverflowOperation::Add;
 use schemars::_serde_json::ser::State;
 
 use cw20::Cw20QueryMsg;
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 
+use crate::allowances::{execute_decrease_allowance, execute_increase_allowance, query_allowance};
+use crate::enumerable::query_all_allowances;
 use crate::error::ContractError;
-use crate::execute::{cancel_game, claim_refund, claim_reward, create_pool, execute_sweep,
+use crate::execute::{cancel_game, claim_referral_rewards, claim_refund, claim_reward, close_season, create_pool, execute_sweep,
                      game_pool_bid_submit, game_pool_reward_distribute, lock_game,
-                     save_team_details, set_platform_fee_wallets,
-                     set_pool_type_params, swap};
+                     reclaim_challenge_stake, received_message, register_referrer, reveal_lineup, save_team_details, settle_lock_swap,
+                     set_activation_status, set_challenge_params, set_entry_asset_whitelist, set_referral_percentage, set_platform_fee_wallets, set_pool_type_params, set_price_twap_window, set_whitelist_check,
+                     settle_challenge, start_season, swap, update_open_pool_index, update_team, withdraw_team};
 use crate::msg::{BalanceResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::query::{get_team_count_for_user_in_pool_type, query_all_pool_type_details, query_all_pools_in_game, query_all_teams, query_game_details, query_game_result, query_pool_collection, query_pool_details, query_pool_team_details, query_pool_type_details, query_refund, query_reward, query_swap_data_for_pool, query_team_details, query_total_fees};
-use crate::state::{Config, CONFIG, GAME_DETAILS, GAME_RESULT_DUMMY, GameDetails, GameResult, SWAP_BALANCE_INFO};
+use crate::responsible_gaming::{execute_self_exclude, execute_set_self_imposed_limits, execute_set_wagering_limits, query_wagering_limits};
+use crate::query::{get_team_count_for_user_in_pool_type, query_activation_status, query_challenge_details, query_challenge_params, query_all_pool_type_details, query_all_pools_in_game, query_all_teams, query_game_details, query_game_events, query_game_result, query_gamer_pools, query_pool_collection, query_pool_details, query_pool_events, query_pool_team_details, query_pool_type_details, query_referral_stats, query_referrer, query_refund, query_reward, query_season_details, query_season_leaderboard, query_swap_data_for_pool, query_team_details, query_total_fees};
+use crate::state::{Config, CONFIG, GAME_DETAILS, GAME_RESULT_DUMMY, GAMER_POOLS, GameDetails, GameResult, POOL_DETAILS, POOL_TEAM_DETAILS, POOL_TYPE_DETAILS, PoolDetails, PoolTeamDetails, SWAP_BALANCE_INFO};
 
 // This is a comment
 // version info for migration info
 pub const CONTRACT_NAME: &str = "crates.io:gaming-pool";
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// First version booking the deposits of entry asset pools apart from FURY
+pub const ENTRY_ASSET_DEPOSITS_VERSION: &str = "0.10.0";
+// First version keeping the gamer pools and open pools indexes
+pub const POOL_INDEXES_VERSION: &str = "0.10.0";
 
 pub const DUMMY_WALLET: &str = "juno1ev8q3fml0d79aafd9zgzvxdt7fvmu4ac9czj4u";
 
@@ -49,10 +57,19 @@ pub const GAME_POOL_OPEN: u64 = 1u64;
 pub const GAME_POOL_CLOSED: u64 = 2u64;
 pub const GAME_CANCELLED: u64 = 3u64;
 pub const GAME_COMPLETED: u64 = 4u64;
+pub const CHALLENGE_OPEN: u64 = 1u64;
+pub const CHALLENGE_ACCEPTED: u64 = 2u64;
+pub const CHALLENGE_SETTLED: u64 = 3u64;
 pub const HUNDRED_PERCENT: u128 = 10000u128;
 pub const NINETY_NINE_NINE_PERCENT: u128 = 9990u128;
+// Max spread used for the min receive of a swap when none is given
+pub const DEFAULT_MAX_SPREAD_PERCENT: u64 = 1u64;
+// Longest TWAP window the proxy keeps price observations for
+pub const MAX_PRICE_TWAP_WINDOW_SECS: u64 = 7 * 24 * 60 * 60;
 
 pub const DUMMY_TEAM_ID: &str = "DUMMY_TEAM_ID";
+// Reply id of the swap sent at lock, pool ids used by the other swaps start at 1
+pub const LOCK_SWAP_REPLY_ID: u64 = 0u64;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -74,6 +91,7 @@ pub fn instantiate(
         transaction_fee: msg.transaction_fee,
         game_id: msg.game_id.clone(),
         usdc_ibc_symbol: msg.usdc_ibc_symbol,
+        whitelist_check_enabled: false,
     };
     CONFIG.save(deps.storage, &config)?;
 
@@ -87,6 +105,7 @@ pub fn instantiate(
             gamer_address: DUMMY_WALLET.to_string(),
             team_id: DUMMY_TEAM_ID.to_string(),
             reward_amount: Uint128::from(INITIAL_REWARD_AMOUNT),
+            team_points: INITIAL_TEAM_POINTS,
         },
     )?;
 
@@ -108,8 +127,13 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    // Query Cw20 Check list
-    // check_and_confirm_whitelist_status(&deps, &info, &env)?;
+    // Query Cw20 Check list. Admin messages are left out so that the admin
+    // can always manage the contract, turning the check off included
+    if CONFIG.load(deps.storage)?.whitelist_check_enabled && !is_admin_message(&msg) {
+        for wallet in whitelist_wallets(&info, &msg) {
+            check_and_confirm_whitelist_status(&deps, &wallet, &env)?;
+        }
+    }
     match msg {
         ExecuteMsg::SetPlatformFeeWallets { wallet_percentages } => {
             set_platform_fee_wallets(deps, info, wallet_percentages)
@@ -121,6 +145,7 @@ pub fn execute(
             max_teams_for_pool,
             max_teams_for_gamer,
             wallet_percentages,
+            entry_asset,
         } => set_pool_type_params(
             deps,
             env,
@@ -131,12 +156,17 @@ pub fn execute(
             max_teams_for_pool,
             max_teams_for_gamer,
             wallet_percentages,
+            entry_asset,
         ),
+        ExecuteMsg::SetEntryAssetWhitelist { asset_info, whitelisted } => {
+            set_entry_asset_whitelist(deps, info, asset_info, whitelisted)
+        }
+        ExecuteMsg::SetPriceTwapWindow { window_secs } => set_price_twap_window(deps, info, window_secs),
         ExecuteMsg::CancelGame {} => cancel_game(deps, env, info),
-        ExecuteMsg::LockGame {} => lock_game(deps, env, info),
+        ExecuteMsg::LockGame { max_spread } => lock_game(deps, env, info, false, max_spread),
         ExecuteMsg::CreatePool { pool_type } => create_pool(deps, env, info, pool_type),
-        ExecuteMsg::ClaimReward { gamer } => claim_reward(deps, info, gamer, env),
-        ExecuteMsg::ClaimRefund { gamer, max_spread } => claim_refund(deps, info, gamer, env, None, max_spread),
+        ExecuteMsg::ClaimReward { gamer, pool_ids } => claim_reward(deps, info, gamer, pool_ids, env),
+        ExecuteMsg::ClaimRefund { gamer, pool_ids } => claim_refund(deps, info, gamer, pool_ids, env, None),
         ExecuteMsg::GamePoolRewardDistribute {
             pool_id,
             game_winners,
@@ -150,10 +180,76 @@ pub fn execute(
             pool_id,
             team_id,
             amount,
-            max_spread
         } => game_pool_bid_submit(
-            deps, env, info, gamer, pool_type, pool_id, team_id, amount, false, max_spread),
+            deps, env, info, gamer, pool_type, pool_id, team_id, amount, false),
+        ExecuteMsg::UpdateTeam {
+            gamer,
+            pool_id,
+            team_id,
+            lineup,
+            lineup_hash,
+        } => update_team(deps, info, gamer, pool_id, team_id, lineup, lineup_hash),
+        ExecuteMsg::WithdrawTeam {
+            gamer,
+            pool_id,
+            team_id,
+            max_spread,
+        } => withdraw_team(deps, env, info, gamer, pool_id, team_id, false, max_spread),
+        ExecuteMsg::RevealLineup {
+            gamer,
+            pool_id,
+            team_id,
+            lineup,
+            salt,
+        } => reveal_lineup(deps, info, gamer, pool_id, team_id, lineup, salt),
+        ExecuteMsg::SetChallengeParams {
+            min_stake,
+            max_stake,
+            rake_percentage,
+            wallet_percentages,
+        } => set_challenge_params(deps, info, min_stake, max_stake, rake_percentage, wallet_percentages),
+        ExecuteMsg::SettleChallenge {
+            challenge_id,
+            winner,
+        } => settle_challenge(deps, info, challenge_id, winner),
+        ExecuteMsg::ReclaimChallengeStake { challenge_id } => {
+            reclaim_challenge_stake(deps, info, challenge_id)
+        }
+        ExecuteMsg::StartSeason {
+            season_id,
+            rake_percentage,
+            payout_percentages,
+        } => start_season(deps, info, season_id, rake_percentage, payout_percentages),
+        ExecuteMsg::CloseSeason {} => close_season(deps, info),
+        ExecuteMsg::SetReferralPercentage { percentage } => {
+            set_referral_percentage(deps, info, percentage)
+        }
+        ExecuteMsg::RegisterReferrer { referrer } => register_referrer(deps, info, referrer),
+        ExecuteMsg::ClaimReferralRewards {} => claim_referral_rewards(deps, info),
+        ExecuteMsg::SetActivationStatus {
+            activation_status,
+            claims_activation_status,
+        } => set_activation_status(deps, info, activation_status, claims_activation_status),
+        ExecuteMsg::SetWhitelistCheck { enabled } => set_whitelist_check(deps, info, enabled),
         ExecuteMsg::Sweep { funds } => execute_sweep(deps, info, funds),
+        ExecuteMsg::SetWageringLimits {
+            limits,
+            window_seconds,
+            cooldown_seconds,
+        } => execute_set_wagering_limits(deps, info, limits, window_seconds, cooldown_seconds),
+        ExecuteMsg::SetSelfImposedLimits { limits } => execute_set_self_imposed_limits(deps, env, info, limits),
+        ExecuteMsg::SelfExclude { duration_seconds } => execute_self_exclude(deps, env, info, duration_seconds),
+        ExecuteMsg::Receive(message) => received_message(deps, env, info, message, false),
+        ExecuteMsg::IncreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => execute_increase_allowance(deps, env, info, spender, amount, expires),
+        ExecuteMsg::DecreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => execute_decrease_allowance(deps, env, info, spender, amount, expires),
         ExecuteMsg::Swap {
             amount,
             pool_id, max_spread
@@ -161,13 +257,64 @@ pub fn execute(
     }
 }
 
+// Messages only the admin can execute
+fn is_admin_message(msg: &ExecuteMsg) -> bool {
+    matches!(
+        msg,
+        ExecuteMsg::SetPlatformFeeWallets { .. }
+            | ExecuteMsg::SetPoolTypeParams { .. }
+            | ExecuteMsg::SetEntryAssetWhitelist { .. }
+            | ExecuteMsg::SetPriceTwapWindow { .. }
+            | ExecuteMsg::CancelGame {}
+            | ExecuteMsg::LockGame { .. }
+            | ExecuteMsg::CreatePool { .. }
+            | ExecuteMsg::GamePoolRewardDistribute { .. }
+            | ExecuteMsg::SetChallengeParams { .. }
+            | ExecuteMsg::SettleChallenge { .. }
+            | ExecuteMsg::StartSeason { .. }
+            | ExecuteMsg::CloseSeason {}
+            | ExecuteMsg::SetReferralPercentage { .. }
+            | ExecuteMsg::SetActivationStatus { .. }
+            | ExecuteMsg::SetWhitelistCheck { .. }
+            | ExecuteMsg::Sweep { .. }
+            | ExecuteMsg::SetWageringLimits { .. }
+            | ExecuteMsg::Swap { .. }
+    )
+}
+
+// Wallets the whitelist restriction is checked for. Tokens sent through
+// Receive come from the CW20 sender rather than the token contract, and
+// bids and claims made on behalf of a gamer check the gamer as well.
+fn whitelist_wallets(info: &MessageInfo, msg: &ExecuteMsg) -> Vec<String> {
+    let sender = match msg {
+        ExecuteMsg::Receive(message) => message.sender.clone(),
+        _ => info.sender.to_string(),
+    };
+    let gamer = match msg {
+        ExecuteMsg::GamePoolBidSubmitCommand { gamer, .. }
+        | ExecuteMsg::ClaimReward { gamer, .. }
+        | ExecuteMsg::ClaimRefund { gamer, .. }
+        | ExecuteMsg::UpdateTeam { gamer, .. }
+        | ExecuteMsg::WithdrawTeam { gamer, .. }
+        | ExecuteMsg::RevealLineup { gamer, .. } => Some(gamer.clone()),
+        _ => None,
+    };
+    let mut wallets = vec![sender];
+    if let Some(gamer) = gamer {
+        if gamer != wallets[0] {
+            wallets.push(gamer);
+        }
+    }
+    wallets
+}
+
 pub fn check_and_confirm_whitelist_status(
     deps: &DepsMut,
-    info: &MessageInfo,
+    wallet_address: &str,
     env: &Env,
 ) -> Result<Response, ContractError> {
     let query = cw20_base::msg::QueryMsg::WhitelistRestriction {
-        wallet_address: info.sender.to_string(),
+        wallet_address: wallet_address.to_string(),
         contract_address: env.contract.address.to_string(),
         contract_check_needed: true,
     };
@@ -187,11 +334,84 @@ pub fn check_and_confirm_whitelist_status(
 // We can add expose specific state properties to
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Cannot migrate from {}", stored.contract
+        ))));
+    }
+    let before_entry_asset_deposits = is_older_version(&stored.version, ENTRY_ASSET_DEPOSITS_VERSION)?;
+    // The backfill scans every team and pool, so it only runs when
+    // migrating from a version without the indexes
+    if is_older_version(&stored.version, POOL_INDEXES_VERSION)? {
+        // Build the gamer to pools index for teams saved before it existed
+        let all_teams: Vec<Vec<PoolTeamDetails>> = POOL_TEAM_DETAILS
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| Ok(item?.1))
+            .collect::<StdResult<_>>()?;
+        for mut teams in all_teams {
+            for team in teams.iter() {
+                GAMER_POOLS.save(deps.storage, (team.gamer_address.as_ref(), team.pool_id.as_ref()), &true)?;
+            }
+            // Teams of pools with an entry asset used to book their deposit
+            // as FURY deposited. Done once, a pool type switched to an entry
+            // asset later on keeps the FURY deposited of its teams.
+            if !before_entry_asset_deposits {
+                continue;
+            }
+            let first = match teams.first() {
+                Some(team) => team.clone(),
+                None => continue,
+            };
+            let entry_asset = POOL_TYPE_DETAILS
+                .may_load(deps.storage, first.pool_type.clone())?
+                .and_then(|pool_type| pool_type.entry_asset);
+            if entry_asset.is_none() {
+                continue;
+            }
+            for team in teams.iter_mut() {
+                team.entry_asset_deposited += team.fury_deposited;
+                team.fury_deposited = Uint128::zero();
+            }
+            POOL_TEAM_DETAILS.save(deps.storage, (first.pool_id.as_ref(), first.gamer_address.as_ref()), &teams)?;
+        }
+        // Build the open pools index for the pools of games still open for bidding
+        let all_pools: Vec<PoolDetails> = POOL_DETAILS
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| Ok(item?.1))
+            .collect::<StdResult<_>>()?;
+        for pool in all_pools {
+            let game_open = GAME_DETAILS
+                .may_load(deps.storage, pool.game_id.clone())?
+                .map_or(false, |game| game.game_status == GAME_POOL_OPEN);
+            if !game_open {
+                continue;
+            }
+            let pool_type = POOL_TYPE_DETAILS.load(deps.storage, pool.pool_type.clone())?;
+            update_open_pool_index(deps.storage, &pool, pool_type.max_teams_for_pool)?;
+        }
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::default()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version))
+}
+
+// Compares two major.minor.patch versions
+fn is_older_version(version: &str, than: &str) -> StdResult<bool> {
+    let parse = |version: &str| -> StdResult<Vec<u64>> {
+        version
+            .split('.')
+            .map(|part| part.parse::<u64>().map_err(|_| {
+                StdError::generic_err(format!("Invalid contract version {}", version))
+            }))
+            .collect()
+    };
+    Ok(parse(version)? < parse(than)?)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::PoolTeamDetails { pool_id, user } => {
             to_binary(&query_pool_team_details(deps.storage, pool_id, user)?)
@@ -238,7 +458,51 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         } => to_binary(&query_total_fees(
             deps,
             amount,
-        )?)
+        )?),
+        QueryMsg::GamerPools {
+            gamer,
+            start_after,
+            limit,
+        } => to_binary(&query_gamer_pools(
+            deps.storage,
+            gamer,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ChallengeDetails { challenge_id } => {
+            to_binary(&query_challenge_details(deps.storage, challenge_id)?)
+        }
+        QueryMsg::ChallengeParams {} => to_binary(&query_challenge_params(deps.storage)?),
+        QueryMsg::SeasonDetails { season_id } => {
+            to_binary(&query_season_details(deps.storage, season_id)?)
+        }
+        QueryMsg::SeasonLeaderboard {
+            season_id,
+            start_after,
+            limit,
+        } => to_binary(&query_season_leaderboard(deps.storage, season_id, start_after, limit)?),
+        QueryMsg::Referrer { gamer } => to_binary(&query_referrer(deps.storage, gamer)?),
+        QueryMsg::ReferralStats { referrer } => {
+            to_binary(&query_referral_stats(deps.storage, referrer)?)
+        }
+        QueryMsg::ActivationStatus {} => to_binary(&query_activation_status(deps.storage)?),
+        QueryMsg::WageringLimits { gamer } => {
+            to_binary(&query_wagering_limits(deps.storage, &env.block, gamer)?)
+        }
+        QueryMsg::GameEvents { game_id, start_after, limit } => {
+            to_binary(&query_game_events(deps.storage, game_id, start_after, limit)?)
+        }
+        QueryMsg::PoolEvents { pool_id, start_after, limit } => {
+            to_binary(&query_pool_events(deps.storage, pool_id, start_after, limit)?)
+        }
+        QueryMsg::Allowance { owner, spender } => {
+            to_binary(&query_allowance(deps, owner, spender)?)
+        }
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_all_allowances(deps, owner, start_after, limit)?),
     }
 }
 
@@ -246,20 +510,52 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 #[allow(dead_code)]
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id == LOCK_SWAP_REPLY_ID {
+        return settle_lock_swap(deps, &_env, msg.result);
+    }
     let config = CONFIG.load(deps.storage)?;
     let pool_id = msg.id.to_string();
+    let mut balance_info = SWAP_BALANCE_INFO.load(deps.storage, pool_id.clone())?;
+    // A failed swap leaves the UST with the contract, the pool is marked
+    // so that the swap can be tried again or the game cancelled
+    if let SubMsgResult::Err(err) = msg.result {
+        balance_info.swap_failed = true;
+        SWAP_BALANCE_INFO.save(deps.storage, pool_id.clone(), &balance_info)?;
+        return Ok(Response::default()
+            .add_attribute("swap_failed", err)
+            .add_attribute("pool_id", pool_id));
+    }
     let current_fury_balance: BalanceResponse = deps.querier.query_wasm_smart(
         config.clone().minting_contract_address,
         &Cw20QueryMsg::Balance {
             address: _env.contract.address.clone().to_string()
         },
     )?;
-    let mut balance_info = SWAP_BALANCE_INFO.load(deps.storage, pool_id.clone())?;
     balance_info.balance_post_swap = current_fury_balance.balance;
-    let balance_gained = balance_info.balance_post_swap - balance_info.balance_pre_swap;
+    let balance_gained = balance_info.balance_post_swap
+        .checked_sub(balance_info.balance_pre_swap)
+        .unwrap_or_default();
+    // Too little FURY received marks the pool the same way as a failed
+    // swap instead of reverting the transaction
+    if balance_gained < balance_info.min_fury_expected {
+        balance_info.swap_failed = true;
+        SWAP_BALANCE_INFO.save(deps.storage, pool_id.clone(), &balance_info)?;
+        return Ok(Response::default()
+            .add_attribute("swap_failed", "min receive not met")
+            .add_attribute("min_fury_expected", balance_info.min_fury_expected.to_string())
+            .add_attribute("fury_balance_gained", balance_gained.to_string())
+            .add_attribute("pool_id", pool_id));
+    }
     // ((Balance gained * 10_000) / Amount In UST Swapped)
     // (poolcollection * exchange rate)/10_000 at time of use
-    balance_info.exchange_rate = balance_gained.checked_mul(Uint128::from(10000u128)).unwrap().checked_div(balance_info.ust_amount_swapped).unwrap();
+    // A rate which cannot be worked out reverts the swap rather than
+    // leaving the pool with a zero rate
+    balance_info.exchange_rate = balance_gained
+        .checked_mul(Uint128::from(10000u128))
+        .map_err(StdError::from)?
+        .checked_div(balance_info.ust_amount_swapped)
+        .map_err(StdError::from)?;
+    balance_info.swap_failed = false;
     SWAP_BALANCE_INFO.save(deps.storage, pool_id.clone(), &balance_info)?;
     return Ok(Response::default()
         .add_attribute("fury_balance_gained", balance_gained.to_string())
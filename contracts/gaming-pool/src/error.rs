@@ -41,6 +41,9 @@ pub enum ContractError {
     #[error("Insufficient fees in UST sent")]
     InsufficientFeesUst {},
 
+    #[error("Insufficient FURY sent to cover the fees")]
+    InsufficientFeesFury {},
+
     #[error("Number Of Coins Sent Is Invalid")]
     InvalidNumberOfCoinsSent {},
 
@@ -74,4 +77,87 @@ pub enum ContractError {
     #[error("User Is Restricted..!")]
     UserIsRestricted {},
 
+    #[error("Max spread cannot be more than 100%")]
+    InvalidMaxSpread {},
+
+    #[error("Contract is paused")]
+    ContractPaused {},
+
+    #[error("Claims are paused")]
+    ClaimsPaused {},
+
+    #[error("Team {team_id} not found in pool {pool_id}")]
+    TeamNotFound {
+        pool_id: String,
+        team_id: String,
+    },
+
+    #[error("Lineup hash must be a hex encoded sha256")]
+    InvalidLineupHash {},
+
+    #[error("Revealed lineup does not match the committed lineup hash")]
+    LineupMismatch {},
+
+    #[error("A committed lineup can only be given through RevealLineup")]
+    LineupWithHash {},
+
+    #[error("Stake must be between {min_stake} and {max_stake}")]
+    InvalidStake {
+        min_stake: Uint128,
+        max_stake: Uint128,
+    },
+
+    #[error("Challenge {challenge_id} cannot be {action}")]
+    InvalidChallengeStatus {
+        challenge_id: String,
+        action: String,
+    },
+
+    #[error("Lineup not revealed for team {team_id}")]
+    LineupNotRevealed {
+        team_id: String,
+    },
+
+    #[error("Asset {asset} is not whitelisted as entry asset")]
+    EntryAssetNotWhitelisted {
+        asset: String,
+    },
+
+    #[error("Insufficient {asset} sent to cover the pool fee and the fees")]
+    InsufficientEntryFunds {
+        asset: String,
+    },
+
+    #[error("Bid exceeds the {limit} limit, {remaining} FURY left")]
+    WageringLimitExceeded {
+        limit: String,
+        remaining: Uint128,
+    },
+
+    #[error("Wallet is self excluded till {until}")]
+    SelfExcluded {
+        until: u64,
+    },
+
+    #[error("Limits cannot be loosened before {until}")]
+    LimitCooldownActive {
+        until: u64,
+    },
+
+    #[error("TWAP window must be between 1 and {max_secs} seconds")]
+    InvalidTwapWindow {
+        max_secs: u64,
+    },
+
+    #[error("Pool {pool_id} is of pool type {pool_type}")]
+    PoolTypeMismatch {
+        pool_id: String,
+        pool_type: String,
+    },
+
+    #[error("Pool {pool_id} is already refunded or rewarded")]
+    PoolNotOpen {
+        pool_id: String,
+    },
+
 }
@@ -3,27 +3,31 @@ use std::ops::{Add, Div, Mul};
 use std::str::FromStr;
 
 use terraswap::asset::{Asset, AssetInfo};
-use terraswap::pair::ExecuteMsg as AstroPortExecute;
-use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, Env,
+use terraswap::pair::{ExecuteMsg as AstroPortExecute, SimulationResponse};
+use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
                    from_binary, MessageInfo, Order, Response, StdError,
-                   StdResult, Storage, SubMsg, to_binary, Uint128, WasmMsg};
+                   StdResult, Storage, SubMsg, SubMsgResult, to_binary, Uint128, WasmMsg};
 
 use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use cw_storage_plus::U64Key;
+use sha2::{Digest, Sha256};
 
-use crate::contract::{CLAIMED_REFUND, CLAIMED_REWARD, DUMMY_WALLET, GAME_CANCELLED,
+use crate::contract::{CHALLENGE_ACCEPTED, CHALLENGE_OPEN, CHALLENGE_SETTLED, CLAIMED_REFUND, CLAIMED_REWARD, DEFAULT_MAX_SPREAD_PERCENT, MAX_PRICE_TWAP_WINDOW_SECS, DUMMY_WALLET, GAME_CANCELLED,
                       GAME_COMPLETED, GAME_POOL_CLOSED, GAME_POOL_OPEN, HUNDRED_PERCENT,
                       INITIAL_REFUND_AMOUNT, INITIAL_REWARD_AMOUNT, INITIAL_TEAM_POINTS,
-                      INITIAL_TEAM_RANK, NINETY_NINE_NINE_PERCENT, REWARDS_DISTRIBUTED,
+                      INITIAL_TEAM_RANK, LOCK_SWAP_REPLY_ID, NINETY_NINE_NINE_PERCENT, REWARDS_DISTRIBUTED,
                       REWARDS_NOT_DISTRIBUTED, UNCLAIMED_REFUND, UNCLAIMED_REWARD};
+use crate::allowances::{check_gamer_or_manager, deduct_allowance};
+use crate::responsible_gaming::{check_and_record_wager, release_wager};
 use crate::ContractError;
-use crate::msg::{BalanceResponse, ProxyQueryMsgs, QueryMsgSimulation, ReceivedMsg};
-use crate::query::{get_team_count_for_user_in_pool_type,
+use crate::msg::{AcceptChallengeCommand, BalanceResponse, CreateChallengeCommand, GamePoolBidSubmitCommand, ProxyQueryMsgs, QueryMsgSimulation, ReceivedMsg};
+use crate::query::{get_team_count_for_user_in_pool_type, query_game_details, query_gamer_pool_ids,
                    query_pool_details, query_pool_type_details, query_swap_data_for_pool};
-use crate::state::{CONFIG, CONTRACT_POOL_COUNT, CURRENT_REWARD_FOR_POOL, FeeDetails,
-                   GAME_DETAILS, GameDetails, GameResult, PLATFORM_WALLET_PERCENTAGES,
-                   POOL_DETAILS, POOL_TEAM_DETAILS, POOL_TYPE_DETAILS, PoolDetails, PoolTeamDetails,
-                   PoolTypeDetails, SWAP_BALANCE_INFO, SwapBalanceDetails,
-                   WalletPercentage, WalletTransferDetails};
+use crate::state::{ACTIVATION_STATUS, CHALLENGE_COUNT, CHALLENGE_DETAILS, CHALLENGE_PARAMS, ChallengeDetails, ChallengeParams, CLAIMS_ACTIVATION_STATUS, Config, CONFIG, CONTRACT_POOL_COUNT, CURRENT_REWARD_FOR_POOL, CURRENT_SEASON, LOCK_SWAP_INFO, LockSwapDetails, ENTRY_ASSET_WHITELIST, FeeDetails, GAME_EVENT_COUNT, GAME_EVENTS, GameEvent, GameEventRecord,
+                   GAME_DETAILS, GAMER_POOLS, GameDetails, GameResult, LineupPlayer, PLATFORM_WALLET_PERCENTAGES,
+                   OPEN_POOLS, POOL_DETAILS, POOL_EVENTS, POOL_SWAPPED_FURY, POOL_SWAPPED_UST, POOL_TEAM_DETAILS, POOL_TYPE_DETAILS, PoolDetails, PoolTeamDetails,
+                   PoolTypeDetails, PRICE_TWAP_WINDOW, REFERRAL_PERCENTAGE, REFERRAL_STATS, REFERRERS, SEASON_DETAILS, SEASON_LEADERBOARD, SEASON_POINTS, SeasonDetails, SeasonStanding,
+                   SWAP_BALANCE_INFO, SwapBalanceDetails, WagerEntry, WalletPercentage, WalletTransferDetails};
 
 pub fn set_platform_fee_wallets(
     deps: DepsMut,
@@ -51,6 +55,59 @@ pub fn set_platform_fee_wallets(
     return Ok(Response::default());
 }
 
+pub fn set_activation_status(
+    deps: DepsMut,
+    info: MessageInfo,
+    activation_status: bool,
+    claims_activation_status: Option<bool>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    ACTIVATION_STATUS.save(deps.storage, &activation_status)?;
+    if let Some(claims_activation_status) = claims_activation_status {
+        CLAIMS_ACTIVATION_STATUS.save(deps.storage, &claims_activation_status)?;
+    }
+    return Ok(Response::new()
+        .add_attribute("activation_status", activation_status.to_string()));
+}
+
+pub fn set_whitelist_check(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    config.whitelist_check_enabled = enabled;
+    CONFIG.save(deps.storage, &config)?;
+    return Ok(Response::default());
+}
+
+// Bids and pool creation are only allowed while the contract is active
+fn check_activation_status(storage: &dyn Storage) -> Result<(), ContractError> {
+    if !ACTIVATION_STATUS.may_load(storage)?.unwrap_or(true) {
+        return Err(ContractError::ContractPaused {});
+    }
+    Ok(())
+}
+
+// Claims and refunds have their own switch so that they can stay open
+// while the contract is paused
+fn check_claims_activation_status(storage: &dyn Storage) -> Result<(), ContractError> {
+    if !CLAIMS_ACTIVATION_STATUS.may_load(storage)?.unwrap_or(true) {
+        return Err(ContractError::ClaimsPaused {});
+    }
+    Ok(())
+}
+
 pub fn set_pool_type_params(
     deps: DepsMut,
     _env: Env,
@@ -61,6 +118,7 @@ pub fn set_pool_type_params(
     max_teams_for_pool: u32,
     max_teams_for_gamer: u32,
     wallet_percentages: Vec<WalletPercentage>,
+    entry_asset: Option<AssetInfo>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     if info.sender != config.admin_address {
@@ -68,6 +126,9 @@ pub fn set_pool_type_params(
             invoker: info.sender.to_string(),
         });
     }
+    if let Some(asset_info) = entry_asset.clone() {
+        check_entry_asset_allowed(deps.storage, &config, &asset_info)?;
+    }
     let ptd = POOL_TYPE_DETAILS.may_load(deps.storage, pool_type.clone())?;
     match ptd {
         Some(_ptd) => {
@@ -92,12 +153,127 @@ pub fn set_pool_type_params(
             max_teams_for_pool: max_teams_for_pool,
             max_teams_for_gamer: max_teams_for_gamer,
             rake_list: rake_list,
+            entry_asset: entry_asset,
         },
     )?;
     return Ok(Response::default());
 }
 
-pub fn cancel_game(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+pub fn set_entry_asset_whitelist(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset_info: AssetInfo,
+    whitelisted: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    ENTRY_ASSET_WHITELIST.save(deps.storage, asset_info.to_string(), &whitelisted)?;
+    return Ok(Response::new()
+        .add_attribute("entry_asset", asset_info.to_string())
+        .add_attribute("whitelisted", whitelisted.to_string()));
+}
+
+// Bids, fees and payouts are priced at the TWAP over the window, or at the
+// current reserves of the pair once it is cleared. The proxy only keeps a
+// week of price observations, a longer window would fail every bid.
+pub fn set_price_twap_window(
+    deps: DepsMut,
+    info: MessageInfo,
+    window_secs: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    let window = match window_secs {
+        Some(window_secs) => {
+            if window_secs == 0 || window_secs > MAX_PRICE_TWAP_WINDOW_SECS {
+                return Err(ContractError::InvalidTwapWindow {
+                    max_secs: MAX_PRICE_TWAP_WINDOW_SECS,
+                });
+            }
+            PRICE_TWAP_WINDOW.save(deps.storage, &window_secs)?;
+            window_secs.to_string()
+        }
+        None => {
+            PRICE_TWAP_WINDOW.remove(deps.storage);
+            String::from("spot")
+        }
+    };
+    return Ok(Response::new()
+        .add_attribute("action", "set_price_twap_window")
+        .add_attribute("window_secs", window));
+}
+
+/// FURY worth the given UST as priced by the proxy
+pub fn fury_equivalent_to_ust(deps: Deps, config: &Config, ust_count: Uint128) -> StdResult<Uint128> {
+    deps.querier.query_wasm_smart(
+        config.astro_proxy_address.clone(),
+        &ProxyQueryMsgs::get_fury_equivalent_to_ust {
+            ust_count,
+            twap_window_secs: PRICE_TWAP_WINDOW.may_load(deps.storage)?,
+        },
+    )
+}
+
+/// UST worth the given FURY as priced by the proxy
+pub fn ust_equivalent_to_fury(deps: Deps, config: &Config, fury_count: Uint128) -> StdResult<Uint128> {
+    deps.querier.query_wasm_smart(
+        config.astro_proxy_address.clone(),
+        &ProxyQueryMsgs::get_ust_equivalent_to_fury {
+            fury_count,
+            twap_window_secs: PRICE_TWAP_WINDOW.may_load(deps.storage)?,
+        },
+    )
+}
+
+// FURY and USDC can always be the entry asset, other tokens and denoms
+// have to be whitelisted by the admin first
+fn check_entry_asset_allowed(
+    storage: &dyn Storage,
+    config: &Config,
+    asset_info: &AssetInfo,
+) -> Result<(), ContractError> {
+    let allowed = match asset_info {
+        AssetInfo::Token { contract_addr } => *contract_addr == config.minting_contract_address,
+        AssetInfo::NativeToken { denom } => *denom == config.usdc_ibc_symbol,
+    };
+    if !allowed && !ENTRY_ASSET_WHITELIST.may_load(storage, asset_info.to_string())?.unwrap_or(false) {
+        return Err(ContractError::EntryAssetNotWhitelisted {
+            asset: asset_info.to_string(),
+        });
+    }
+    Ok(())
+}
+
+// The platform and transaction fee of pools with an entry asset is paid
+// in that asset on top of the pool fee
+fn entry_fee_in_asset(config: &Config, pool_fee: Uint128) -> StdResult<Uint128> {
+    let fee_details = query_platform_fees(pool_fee, config.platform_fee, config.transaction_fee)?;
+    Ok(fee_details.platform_fee.add(fee_details.transaction_fee))
+}
+
+// Adds the amount to the total kept for its asset
+fn add_to_asset_totals(totals: &mut Vec<Asset>, asset_info: &AssetInfo, amount: Uint128) {
+    if amount.is_zero() {
+        return;
+    }
+    match totals.iter_mut().find(|asset| asset.info.equal(asset_info)) {
+        Some(asset) => asset.amount += amount,
+        None => totals.push(Asset {
+            info: asset_info.clone(),
+            amount,
+        }),
+    }
+}
+
+pub fn cancel_game(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     if info.sender != config.admin_address {
         return Err(ContractError::Unauthorized {
@@ -137,6 +313,7 @@ pub fn cancel_game(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Respon
             game_status: GAME_CANCELLED,
         },
     )?;
+    record_game_event(deps.storage, &env, &game_id, GameEvent::GameCancelled {})?;
 
     // Get all pools
     let all_pools: Vec<String> = POOL_DETAILS
@@ -170,13 +347,20 @@ pub fn cancel_game(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Respon
         };
         pool.pool_refund_status = true; // We skip the iteration and update the status
         POOL_DETAILS.save(deps.storage, pool_id.clone(), &pool)?;
+        update_open_pool_index(deps.storage, &pool, pool_type.max_teams_for_pool)?;
     }
     return Ok(Response::new()
         .add_attribute("game_id", game_id.clone())
         .add_attribute("game_status", "GAME_CANCELLED".to_string()));
 }
 
-pub fn lock_game(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+pub fn lock_game(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    testing: bool,
+    max_spread: Option<Decimal>,
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     if info.sender != config.admin_address {
         return Err(ContractError::Unauthorized {
@@ -212,6 +396,7 @@ pub fn lock_game(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Response
         },
     )?;
 
+    let mut swapped_fury = Uint128::zero();
     // Get all pools
     let all_pools: Vec<String> = POOL_DETAILS
         .keys(deps.storage, None, None, Order::Ascending)
@@ -243,15 +428,190 @@ pub fn lock_game(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Response
                 }));
             }
         };
-        if pool.current_teams_count >= pool_type.min_teams_for_pool {
+        // No more teams join once the game is locked
+        OPEN_POOLS.remove(deps.storage, open_pool_key(&pool)?);
+        if pool.current_teams_count < pool_type.min_teams_for_pool {
+            pool.pool_refund_status = true; // We skip the iteration and update the status
+            POOL_DETAILS.save(deps.storage, pool_id.clone(), &pool)?;
             continue;
         }
-        pool.pool_refund_status = true; // We skip the iteration and update the status
-        POOL_DETAILS.save(deps.storage, pool_id.clone(), &pool)?;
+        // Pools paid in their entry asset keep it for the payouts
+        if pool_type.entry_asset.is_some() {
+            continue;
+        }
+        // The pool is confirmed so the FURY of all its teams gets swapped
+        let all_teams: StdResult<Vec<(Vec<u8>, Vec<PoolTeamDetails>)>> = POOL_TEAM_DETAILS
+            .prefix(&pool_id)
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect();
+        let mut pool_fury = Uint128::zero();
+        for (_, teams) in all_teams? {
+            for team in teams {
+                pool_fury += team.fury_deposited;
+            }
+        }
+        if pool_fury.is_zero() {
+            continue;
+        }
+        POOL_SWAPPED_FURY.save(deps.storage, pool_id.clone(), &pool_fury)?;
+        swapped_fury += pool_fury;
+    }
+    let mut messages = Vec::new();
+    let mut submessages = Vec::new();
+    if !swapped_fury.is_zero() {
+        let (allowance_msg, swap_msg, ust_fee_paid, min_ust_expected) = fury_swap_messages(&deps, &env, swapped_fury, testing, max_spread)?;
+        // The UST received is worked out in the reply from the balance change
+        let ust_balance_pre_swap = deps.querier
+            .query_balance(env.contract.address.clone(), config.usdc_ibc_symbol.clone())?
+            .amount;
+        LOCK_SWAP_INFO.save(deps.storage, &LockSwapDetails {
+            ust_balance_pre_swap,
+            ust_fee_paid,
+            min_ust_expected,
+        })?;
+        messages.push(allowance_msg);
+        submessages.push(SubMsg::reply_always(swap_msg, LOCK_SWAP_REPLY_ID));
     }
+    record_game_event(deps.storage, &env, &game_id, GameEvent::GameLocked {
+        fury_swapped: swapped_fury,
+    })?;
     return Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(submessages)
         .add_attribute("game_id", game_id.clone())
-        .add_attribute("game_status", "GAME_POOL_CLOSED".to_string()));
+        .add_attribute("game_status", "GAME_POOL_CLOSED".to_string())
+        .add_attribute("fury_swapped", swapped_fury.to_string()));
+}
+
+// Builds the allowance and the swap message to swap the given FURY held by
+// the contract to UST, returned along with the UST paid as swap fee and the
+// least UST the swap has to give
+fn fury_swap_messages(
+    deps: &DepsMut,
+    env: &Env,
+    amount: Uint128,
+    testing: bool,
+    max_spread: Option<Decimal>,
+) -> Result<(CosmosMsg, CosmosMsg, Uint128, Uint128), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let increase_allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
+        spender: String::from(config.clone().astro_proxy_address),
+        amount,
+        expires: None,
+    };
+    let allowance_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.clone().minting_contract_address.to_string(),
+        msg: to_binary(&increase_allowance_msg)?,
+        funds: vec![],
+    });
+
+    let fury_asset_info = Asset {
+        info: AssetInfo::Token {
+            contract_addr: config.clone().minting_contract_address.to_string(),
+        },
+        amount,
+    };
+    let mut belief_price = None;
+    let mut min_ust_expected = Uint128::zero();
+    if !testing {
+        let (price, min_receive) = simulate_swap(deps, &config, fury_asset_info.clone(), max_spread)?;
+        belief_price = Some(price);
+        min_ust_expected = min_receive;
+    }
+    let swap_message = AstroPortExecute::Swap {
+        offer_asset: fury_asset_info,
+        belief_price,
+        max_spread: max_spread,
+        to: Option::from(env.contract.address.to_string()),
+    };
+    let mut platform_fees_for_swap = Uint128::zero();
+    if !testing {
+        platform_fees_for_swap = deps.querier.query_wasm_smart(
+            config.clone().astro_proxy_address,
+            &QueryMsgSimulation::QueryPlatformFees {
+                msg: to_binary(&swap_message)?
+            },
+        )?;
+    }
+    let swap_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.clone().astro_proxy_address.to_string(),
+        msg: to_binary(&swap_message)?,
+        funds: vec![Coin {
+            denom: config.usdc_ibc_symbol.clone(),
+            amount: platform_fees_for_swap,
+        }],
+    });
+    return Ok((allowance_msg, swap_msg, platform_fees_for_swap, min_ust_expected));
+}
+
+// Books the UST received for the FURY swapped at lock against the pools it
+// came from, pro rata to the FURY of each pool. A failed swap leaves the
+// FURY with the contract, the pools are then marked for refund and give
+// back the FURY deposited. Pools swapped for less than the min receive are
+// marked for refund as well, their share of the UST received is swapped back
+// to FURY on refund.
+pub fn settle_lock_swap(
+    deps: DepsMut,
+    env: &Env,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let lock_swap = LOCK_SWAP_INFO.load(deps.storage)?;
+    LOCK_SWAP_INFO.remove(deps.storage);
+    let swapped_pools: Vec<(String, Uint128)> = POOL_SWAPPED_FURY
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (pool_id, pool_fury) = item?;
+            Ok((String::from_utf8(pool_id)?, pool_fury))
+        })
+        .collect::<StdResult<_>>()?;
+    if let SubMsgResult::Err(err) = result {
+        for (pool_id, _) in swapped_pools {
+            POOL_SWAPPED_FURY.remove(deps.storage, pool_id.clone());
+            let mut pool = query_pool_details(deps.storage, pool_id.clone())?;
+            pool.pool_refund_status = true;
+            POOL_DETAILS.save(deps.storage, pool_id, &pool)?;
+        }
+        return Ok(Response::new()
+            .add_attribute("action", "settle_lock_swap")
+            .add_attribute("swap_failed", err));
+    }
+    let ust_balance = deps.querier
+        .query_balance(env.contract.address.clone(), config.usdc_ibc_symbol)?
+        .amount;
+    let ust_received = ust_balance
+        .checked_add(lock_swap.ust_fee_paid)
+        .map_err(StdError::from)?
+        .checked_sub(lock_swap.ust_balance_pre_swap)
+        .unwrap_or_default();
+    let total_fury = swapped_pools
+        .iter()
+        .fold(Uint128::zero(), |total, (_, pool_fury)| total + *pool_fury);
+    let mut ust_left = ust_received;
+    for (index, (pool_id, pool_fury)) in swapped_pools.iter().enumerate() {
+        // The last pool takes what rounding left over
+        let pool_ust = if index + 1 == swapped_pools.len() {
+            ust_left
+        } else {
+            ust_received.multiply_ratio(*pool_fury, total_fury)
+        };
+        ust_left -= pool_ust;
+        POOL_SWAPPED_UST.save(deps.storage, pool_id.clone(), &pool_ust)?;
+    }
+    let mut rsp = Response::new()
+        .add_attribute("action", "settle_lock_swap")
+        .add_attribute("ust_received", ust_received.to_string());
+    if ust_received < lock_swap.min_ust_expected {
+        for (pool_id, _) in swapped_pools {
+            let mut pool = query_pool_details(deps.storage, pool_id.clone())?;
+            pool.pool_refund_status = true;
+            POOL_DETAILS.save(deps.storage, pool_id, &pool)?;
+        }
+        rsp = rsp
+            .add_attribute("swap_failed", "min receive not met")
+            .add_attribute("min_ust_expected", lock_swap.min_ust_expected.to_string());
+    }
+    return Ok(rsp);
 }
 
 pub fn create_pool(
@@ -260,6 +620,7 @@ pub fn create_pool(
     info: MessageInfo,
     pool_type: String,
 ) -> Result<Response, ContractError> {
+    check_activation_status(deps.storage)?;
     let config = CONFIG.load(deps.storage)?;
     if info.sender != config.admin_address {
         return Err(ContractError::Unauthorized {
@@ -285,9 +646,18 @@ pub fn create_pool(
         }));
     }
 
-    let dummy_wallet = String::from(DUMMY_WALLET);
-    let address = deps.api.addr_validate(dummy_wallet.clone().as_str())?;
-    let cpc = CONTRACT_POOL_COUNT.may_load(deps.storage, &address)?;
+    let pool_id_str = create_new_pool(deps.storage, game_id, pool_type)?;
+    return Ok(Response::new().add_attribute("pool_id", pool_id_str.clone()));
+}
+
+// Saves a new empty pool of the given type under the next pool id
+fn create_new_pool(
+    storage: &mut dyn Storage,
+    game_id: String,
+    pool_type: String,
+) -> StdResult<String> {
+    let address = Addr::unchecked(DUMMY_WALLET);
+    let cpc = CONTRACT_POOL_COUNT.may_load(storage, &address)?;
     let global_pool_id;
     match cpc {
         Some(cpc) => {
@@ -299,7 +669,7 @@ pub fn create_pool(
     }
     let mut count = global_pool_id;
     CONTRACT_POOL_COUNT.update(
-        deps.storage,
+        storage,
         &address,
         |global_pool_id: Option<Uint128>| -> StdResult<_> {
             Ok(global_pool_id.unwrap_or_default() + Uint128::from(1u128))
@@ -308,20 +678,70 @@ pub fn create_pool(
     count += Uint128::from(1u128);
     let pool_id_str: String = count.to_string();
 
-    POOL_DETAILS.save(
-        deps.storage,
-        pool_id_str.clone(),
-        &PoolDetails {
-            game_id: game_id.clone(),
-            pool_id: pool_id_str.clone(),
-            pool_type: pool_type.clone(),
-            current_teams_count: 0u32,
-            rewards_distributed: REWARDS_NOT_DISTRIBUTED,
-            pool_refund_status: false,
-            pool_reward_status: false,
-        },
-    )?;
-    return Ok(Response::new().add_attribute("pool_id", pool_id_str.clone()));
+    let pool = PoolDetails {
+        game_id: game_id.clone(),
+        pool_id: pool_id_str.clone(),
+        pool_type: pool_type.clone(),
+        current_teams_count: 0u32,
+        rewards_distributed: REWARDS_NOT_DISTRIBUTED,
+        pool_refund_status: false,
+        pool_reward_status: false,
+    };
+    POOL_DETAILS.save(storage, pool_id_str.clone(), &pool)?;
+    OPEN_POOLS.save(storage, open_pool_key(&pool)?, &pool_id_str)?;
+    return Ok(pool_id_str);
+}
+
+fn open_pool_key(pool: &PoolDetails) -> StdResult<(&str, &str, U64Key)> {
+    let pool_id = pool.pool_id.parse::<u64>().map_err(|_| {
+        StdError::generic_err(format!("Invalid pool id {}", pool.pool_id))
+    })?;
+    Ok((pool.game_id.as_str(), pool.pool_type.as_str(), U64Key::from(pool_id)))
+}
+
+// Keeps the pool in the open pools index for as long as it can take
+// another team
+pub fn update_open_pool_index(
+    storage: &mut dyn Storage,
+    pool: &PoolDetails,
+    max_teams_for_pool: u32,
+) -> StdResult<()> {
+    if pool.pool_refund_status
+        || pool.pool_reward_status
+        || pool.current_teams_count >= max_teams_for_pool {
+        OPEN_POOLS.remove(storage, open_pool_key(pool)?);
+    } else {
+        OPEN_POOLS.save(storage, open_pool_key(pool)?, &pool.pool_id)?;
+    }
+    Ok(())
+}
+
+// Returns the open pool of the given type with the lowest pool id which
+// can still take a team from the gamer. A new pool is created when all
+// the pools of this type are full.
+fn find_or_create_open_pool(
+    storage: &mut dyn Storage,
+    game_id: String,
+    gamer: &str,
+    pool_type_details: &PoolTypeDetails,
+) -> StdResult<String> {
+    // The index only holds pools which can take another team, in
+    // ascending pool id order
+    let open_pool_ids: Vec<String> = OPEN_POOLS
+        .prefix((&game_id, &pool_type_details.pool_type))
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| Ok(item?.1))
+        .collect::<StdResult<_>>()?;
+    for pool_id in open_pool_ids {
+        let gamer_team_count = POOL_TEAM_DETAILS
+            .may_load(storage, (pool_id.as_ref(), gamer))?
+            .unwrap_or_default()
+            .len();
+        if gamer_team_count < pool_type_details.max_teams_for_gamer as usize {
+            return Ok(pool_id);
+        }
+    }
+    create_new_pool(storage, game_id, pool_type_details.pool_type.clone())
 }
 
 pub fn query_platform_fees(
@@ -345,22 +765,14 @@ pub fn game_pool_bid_submit(
     info: MessageInfo,
     gamer: String,
     pool_type: String,
-    pool_id: String,
+    pool_id: Option<String>,
     team_id: String,
     amount: Uint128,
     testing: bool,
-    max_spread: Option<Decimal>,
 ) -> Result<Response, ContractError> {
-    //Check if gamer is same as invoker
-    if gamer != info.sender {
-        return Err(ContractError::Unauthorized {
-            invoker: info.sender.to_string(),
-        });
-    }
-
+    check_activation_status(deps.storage)?;
+    let gamer_addr = deps.api.addr_validate(&gamer)?;
     let config = CONFIG.load(deps.storage)?;
-    // Calculate
-    let platform_fee = config.platform_fee; //  Should be in %
     let game_id = config.clone().game_id;
     let mut messages = Vec::new(); //  Use this to append any execute messaages in the funciton
     let gd = GAME_DETAILS.may_load(deps.storage, game_id.clone())?;
@@ -393,109 +805,438 @@ pub fn game_pool_bid_submit(
             }));
         }
     }
-    let required_platform_fee_ust;
-    let transaction_fee;
-    match testing {
-        true => {
-            required_platform_fee_ust = Uint128::zero();
-            transaction_fee = Uint128::zero();
+
+    // The token pulled from the gamer, FURY unless the pool type has an entry
+    // asset. Native denoms come along with the message instead
+    let mut bid_token = Some(config.minting_contract_address.to_string());
+    let mut fee_paid = None;
+    match pool_type_details.entry_asset.clone() {
+        None => {
+            if !testing {
+                let fee_details = query_platform_fees(
+                    pool_type_details.pool_fee,
+                    config.platform_fee,
+                    config.transaction_fee,
+                )?;
+                let required_fee_ust = fee_details.platform_fee.add(fee_details.transaction_fee);
+                check_fees_in_ust(&deps, &info.funds, required_fee_ust)?;
+                fee_paid = Some(Asset {
+                    info: AssetInfo::NativeToken { denom: config.usdc_ibc_symbol.clone() },
+                    amount: required_fee_ust,
+                });
+            }
         }
-        false => {
-            let fee_details = query_platform_fees(
-                pool_type_details.pool_fee,
-                platform_fee,
-                config.transaction_fee,
-            )?;
-            required_platform_fee_ust = fee_details.platform_fee;
-            transaction_fee = fee_details.transaction_fee;
+        Some(AssetInfo::Token { contract_addr }) => {
+            fee_paid = Some(Asset {
+                info: AssetInfo::Token { contract_addr: contract_addr.clone() },
+                amount: entry_fee_in_asset(&config, pool_type_details.pool_fee)?,
+            });
+            bid_token = Some(contract_addr);
+        }
+        Some(AssetInfo::NativeToken { denom }) => {
+            bid_token = None;
+            let entry_fee = entry_fee_in_asset(&config, pool_type_details.pool_fee)?;
+            let required = amount.checked_add(entry_fee).map_err(StdError::from)?;
+            if info.funds.len() != 1 || info.funds[0].denom != denom || info.funds[0].amount < required {
+                return Err(ContractError::InsufficientEntryFunds { asset: denom });
+            }
+            // Anything sent above the bid and its fee goes back to the sender
+            let excess = info.funds[0].amount - required;
+            if !excess.is_zero() {
+                messages.push(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: info.sender.to_string(),
+                    amount: vec![Coin { denom: denom.clone(), amount: excess }],
+                }));
+            }
+            fee_paid = Some(Asset {
+                info: AssetInfo::NativeToken { denom },
+                amount: entry_fee,
+            });
         }
     }
 
-    if !testing {
-        if info.funds.clone().len() != 1 {
-            return Err(ContractError::InvalidNumberOfCoinsSent {});
-        }
-        let mut asset: Asset = Asset {
-            info: AssetInfo::NativeToken { denom: info.funds[0].denom.clone() },
-            amount: info.funds[0].amount,
-        };
-        let fund = info.funds.clone();
-        if fund[0].denom == uusd(&deps)? {
-            if fund[0].amount >= required_platform_fee_ust.add(transaction_fee) {
-                asset = Asset {
-                    info: AssetInfo::NativeToken { denom: fund[0].denom.clone() },
-                    amount: fund[0].amount,
-                };
-                println!("Asset {}", asset);
-            } else {
-                return Err(ContractError::InsufficientFeesUst {});
-            }
-        } else {
-            return Err(ContractError::InsufficientFeesUst {});
+    //Check if gamer is same as invoker, else the bid draws the FURY budget of the manager
+    if gamer_addr != info.sender {
+        if bid_token.as_deref() != Some(config.minting_contract_address.as_str()) {
+            return Err(ContractError::Unauthorized {
+                invoker: info.sender.to_string(),
+            });
         }
-        println!("Asset {}", asset);
+        deduct_allowance(deps.storage, &gamer_addr, &info.sender, &env.block, amount)?;
     }
 
+    let entry_asset = pool_type_details.entry_asset.clone();
+    // The fee of pools with an entry asset is forwarded below, the UST fee
+    // of FURY pools stays with the contract
+    let fee_held = match entry_asset {
+        Some(_) => fee_paid.as_ref().map(forwarded_fee),
+        None => fee_paid.clone(),
+    };
+    let pool_id_return = submit_team_for_pool(
+        deps,
+        &env,
+        gamer,
+        pool_type_details,
+        pool_id,
+        team_id,
+        amount,
+        fee_held,
+        testing,
+    )?;
 
-    let mut pool_fee: Uint128 = pool_type_details.pool_fee;
-    if !testing {
-        pool_fee = deps.querier.query_wasm_smart(
-            config.clone().astro_proxy_address,
-            &ProxyQueryMsgs::get_fury_equivalent_to_ust {
-                ust_count: pool_type_details.pool_fee,
-            },
-        )?;
-    }
-    let max_teams_for_pool = pool_type_details.max_teams_for_pool;
-    let max_teams_for_gamer = pool_type_details.max_teams_for_gamer;
-    let amount_required = pool_fee
-        * (Uint128::from(NINETY_NINE_NINE_PERCENT))
-        / (Uint128::from(HUNDRED_PERCENT));
-    if amount < amount_required {
-        return Err(ContractError::Std(StdError::GenericErr {
-            msg: String::from("Amount being bid does not match the pool fee and the platform fee"),
-        }));
+    // Sending the tokens of the gamer to the contract
+    if let Some(bid_token) = bid_token {
+        let transfer_msg = Cw20ExecuteMsg::TransferFrom {
+            owner: gamer_addr.to_string(),
+            recipient: env.clone().contract.address.to_string(),
+            amount,
+        };
+        let exec = WasmMsg::Execute {
+            contract_addr: bid_token,
+            msg: to_binary(&transfer_msg).unwrap(),
+            funds: vec![],
+        };
+        messages.push(CosmosMsg::Wasm(exec));
     }
-    let mut user_team_count = 0;
-    // Here we load the details based on the user placing the bid
-    let ptd = POOL_TEAM_DETAILS.may_load(deps.storage, (&pool_id.clone(), &gamer))?;
-    match ptd {
-        Some(std) => {
-            let all_teams = std;
-            for team in all_teams {
-                if team.gamer_address == gamer {
-                    user_team_count += 1;
-                }
-            }
+    // The fee of pools with an entry asset goes on to the platform fees
+    // collector in that asset
+    if let (Some(_), Some(fee)) = (entry_asset, fee_paid) {
+        if !fee.amount.is_zero() {
+            messages.push(match fee.info {
+                AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr,
+                    msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: gamer_addr.into_string(),
+                        recipient: config.platform_fees_collector_wallet.to_string(),
+                        amount: fee.amount,
+                    })?,
+                    funds: vec![],
+                }),
+                AssetInfo::NativeToken { .. } => fee.into_msg(config.platform_fees_collector_wallet.clone())?,
+            });
         }
-        None => {}
-    }
-    if user_team_count >= max_teams_for_gamer {
-        return Err(ContractError::Std(StdError::GenericErr {
-            msg: String::from("User max team limit reached "),
-        }));
     }
 
-    let pool_id_return;
-    let mut pool_details = query_pool_details(deps.storage, pool_id.clone())?;
+    return Ok(Response::new()
+        .add_attribute("pool_id", pool_id_return.clone())
+        .add_messages(messages)
+        .set_data(to_binary(&pool_id_return)?));
+}
 
-    // check if the pool can accomodate the team
-    if pool_details.current_teams_count < max_teams_for_pool {
-        pool_id_return = pool_id.clone();
-        pool_details.current_teams_count += 1;
-        POOL_DETAILS.save(
-            deps.storage,
-            pool_id.clone(),
-            &PoolDetails {
-                pool_type: pool_type.clone(),
-                pool_id: pool_id.clone(),
-                game_id: pool_details.game_id.clone(),
-                current_teams_count: pool_details.current_teams_count,
-                rewards_distributed: pool_details.rewards_distributed,
-                pool_refund_status: false,
-                pool_reward_status: false,
-            },
+pub fn received_message(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    message: Cw20ReceiveMsg,
+    testing: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let received: ReceivedMsg = from_binary(&message.msg)?;
+    // Only FURY tokens are accepted, bids check the entry asset of their pool type
+    let is_bid = matches!(received, ReceivedMsg::GamePoolBidSubmit(_));
+    if info.sender != config.minting_contract_address && !is_bid {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    match received {
+        ReceivedMsg::GamePoolBidSubmit(command) => game_pool_bid_submit_from_cw20(
+            deps,
+            env,
+            info,
+            message.sender,
+            message.amount,
+            command,
+            testing,
+        ),
+        ReceivedMsg::CreateChallenge(command) => create_challenge(
+            deps,
+            env,
+            message.sender,
+            message.amount,
+            command,
+        ),
+        ReceivedMsg::AcceptChallenge(command) => accept_challenge(
+            deps,
+            env,
+            message.sender,
+            message.amount,
+            command,
+        ),
+    }
+}
+
+// The FURY tokens are already with the contract when the bid is placed
+// through a CW20 Send. The FURY equivalent of the platform and transaction
+// fee is deducted from the sent amount and forwarded to the platform fees
+// collector wallet. Pools with a CW20 entry asset take the bid in that
+// token with the fee deducted and forwarded the same way.
+fn game_pool_bid_submit_from_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: String,
+    amount: Uint128,
+    command: GamePoolBidSubmitCommand,
+    testing: bool,
+) -> Result<Response, ContractError> {
+    check_activation_status(deps.storage)?;
+    //Check if gamer is same as the sender of the tokens
+    if command.gamer != sender {
+        return Err(ContractError::Unauthorized {
+            invoker: sender,
+        });
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let game_id = config.clone().game_id;
+    let gd = GAME_DETAILS.may_load(deps.storage, game_id.clone())?;
+    let game;
+    match gd {
+        Some(gd) => {
+            game = gd;
+        }
+        None => {
+            return Err(ContractError::Std(StdError::GenericErr {
+                msg: String::from("Game status cannot be retrieved"),
+            }));
+        }
+    }
+    if game.game_status != GAME_POOL_OPEN {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Game is not open for bidding"),
+        }));
+    }
+
+    let pool_type_details;
+    let ptd = POOL_TYPE_DETAILS.may_load(deps.storage, command.pool_type.clone())?;
+    match ptd {
+        Some(ptd) => {
+            pool_type_details = ptd;
+        }
+        None => {
+            return Err(ContractError::Std(StdError::GenericErr {
+                msg: String::from("Cant get details for pool type "),
+            }));
+        }
+    }
+
+    if let Some(entry_asset) = pool_type_details.entry_asset.clone() {
+        let is_sent_token = match &entry_asset {
+            AssetInfo::Token { contract_addr } => *contract_addr == info.sender,
+            AssetInfo::NativeToken { .. } => false,
+        };
+        if !is_sent_token {
+            return Err(ContractError::Unauthorized {
+                invoker: info.sender.to_string(),
+            });
+        }
+        let entry_fee = entry_fee_in_asset(&config, pool_type_details.pool_fee)?;
+        let bid_amount = amount
+            .checked_sub(entry_fee)
+            .map_err(|_| ContractError::InsufficientEntryFunds { asset: entry_asset.to_string() })?;
+        let pool_id_return = submit_team_for_pool(
+            deps,
+            &env,
+            command.gamer,
+            pool_type_details,
+            command.pool_id,
+            command.team_id,
+            bid_amount,
+            Some(forwarded_fee(&Asset {
+                info: entry_asset.clone(),
+                amount: entry_fee,
+            })),
+            testing,
+        )?;
+        // The fee goes on to the platform fees collector in the entry asset
+        let mut messages = Vec::new();
+        if !entry_fee.is_zero() {
+            messages.push(Asset {
+                info: entry_asset,
+                amount: entry_fee,
+            }.into_msg(config.platform_fees_collector_wallet.clone())?);
+        }
+        return Ok(Response::new()
+            .add_attribute("pool_id", pool_id_return.clone())
+            .add_messages(messages)
+            .set_data(to_binary(&pool_id_return)?));
+    }
+    if info.sender != config.minting_contract_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+
+    let mut fee_in_fury = Uint128::zero();
+    let mut fee_paid = None;
+    if !testing {
+        let fee_details = query_platform_fees(
+            pool_type_details.pool_fee,
+            config.platform_fee,
+            config.transaction_fee,
         )?;
+        let required_fee_ust = fee_details.platform_fee.add(fee_details.transaction_fee);
+        fee_in_fury = fury_equivalent_to_ust(deps.as_ref(), &config, required_fee_ust)?;
+        fee_paid = Some(forwarded_fee(&Asset {
+            info: AssetInfo::Token { contract_addr: config.minting_contract_address.to_string() },
+            amount: fee_in_fury,
+        }));
+    }
+    let bid_amount = amount
+        .checked_sub(fee_in_fury)
+        .map_err(|_| ContractError::InsufficientFeesFury {})?;
+
+    let pool_id_return = submit_team_for_pool(
+        deps,
+        &env,
+        command.gamer,
+        pool_type_details,
+        command.pool_id,
+        command.team_id,
+        bid_amount,
+        fee_paid,
+        testing,
+    )?;
+
+    let mut messages = Vec::new();
+    if !fee_in_fury.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.minting_contract_address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: config.platform_fees_collector_wallet.to_string(),
+                amount: fee_in_fury,
+            })?,
+            funds: vec![],
+        }));
+    }
+
+    return Ok(Response::new()
+        .add_attribute("pool_id", pool_id_return.clone())
+        .add_attribute("fee_in_fury", fee_in_fury.to_string())
+        .add_messages(messages)
+        .set_data(to_binary(&pool_id_return)?));
+}
+
+// The FURY entered with a bid, either held as FURY deposited or as the
+// entry asset when the pool type takes the FURY token. Bids in other
+// entry assets enter no FURY.
+fn fury_entered(
+    config: &Config,
+    entry_asset: &Option<AssetInfo>,
+    amount: Uint128,
+) -> Uint128 {
+    match entry_asset {
+        None => amount,
+        Some(AssetInfo::Token { contract_addr }) if *contract_addr == config.minting_contract_address => amount,
+        Some(_) => Uint128::zero(),
+    }
+}
+
+fn check_fees_in_ust(
+    deps: &DepsMut,
+    funds: &[Coin],
+    required_fee_ust: Uint128,
+) -> Result<(), ContractError> {
+    if funds.len() != 1 {
+        return Err(ContractError::InvalidNumberOfCoinsSent {});
+    }
+    if funds[0].denom != uusd(deps)? || funds[0].amount < required_fee_ust {
+        return Err(ContractError::InsufficientFeesUst {});
+    }
+    Ok(())
+}
+
+// Validates the bid against the pool and gamer limits, saves the team
+// along with the bid amount held by the contract, in FURY or in the entry
+// asset of the pool type, and returns the pool id. The FURY entered is
+// booked against the responsible gaming limits of the gamer. When no pool id is given the team is placed in an open pool
+// of the pool type. The FURY is only swapped once the pool is confirmed
+// at lock.
+fn submit_team_for_pool(
+    deps: DepsMut,
+    env: &Env,
+    gamer: String,
+    pool_type_details: PoolTypeDetails,
+    pool_id: Option<String>,
+    team_id: String,
+    amount: Uint128,
+    fee_paid: Option<Asset>,
+    testing: bool,
+) -> Result<String, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let game_id = config.clone().game_id;
+    let pool_type = pool_type_details.pool_type.clone();
+
+    let mut pool_fee: Uint128 = pool_type_details.pool_fee;
+    if !testing && pool_type_details.entry_asset.is_none() {
+        pool_fee = fury_equivalent_to_ust(deps.as_ref(), &config, pool_type_details.pool_fee)?;
+    }
+    let max_teams_for_pool = pool_type_details.max_teams_for_pool;
+    let max_teams_for_gamer = pool_type_details.max_teams_for_gamer;
+    let (fury_deposited, entry_asset_deposited) = match pool_type_details.entry_asset {
+        Some(_) => (Uint128::zero(), amount),
+        None => (amount, Uint128::zero()),
+    };
+    let amount_required = pool_fee
+        * (Uint128::from(NINETY_NINE_NINE_PERCENT))
+        / (Uint128::from(HUNDRED_PERCENT));
+    if amount < amount_required {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Amount being bid does not match the pool fee and the platform fee"),
+        }));
+    }
+    let pool_id = match pool_id {
+        Some(pool_id) => pool_id,
+        None => find_or_create_open_pool(
+            deps.storage,
+            game_id.clone(),
+            &gamer,
+            &pool_type_details,
+        )?,
+    };
+    // A pool named by the bid has to be of the pool type the bid pays for
+    // and still be taking teams
+    let mut pool_details = query_pool_details(deps.storage, pool_id.clone())?;
+    if pool_details.pool_type != pool_type {
+        return Err(ContractError::PoolTypeMismatch {
+            pool_id,
+            pool_type: pool_details.pool_type,
+        });
+    }
+    if pool_details.pool_refund_status || pool_details.pool_reward_status {
+        return Err(ContractError::PoolNotOpen { pool_id });
+    }
+    let mut user_team_count = 0;
+    // Here we load the details based on the user placing the bid
+    let ptd = POOL_TEAM_DETAILS.may_load(deps.storage, (&pool_id.clone(), &gamer))?;
+    match ptd {
+        Some(std) => {
+            let all_teams = std;
+            for team in all_teams {
+                if team.gamer_address == gamer {
+                    user_team_count += 1;
+                }
+            }
+        }
+        None => {}
+    }
+    if user_team_count >= max_teams_for_gamer {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("User max team limit reached "),
+        }));
+    }
+    let fury = fury_entered(&config, &pool_type_details.entry_asset, amount);
+    let wager = check_and_record_wager(deps.storage, &env.block, &gamer, &game_id, fury)?;
+
+    let pool_id_return;
+
+    // check if the pool can accomodate the team
+    if pool_details.current_teams_count < max_teams_for_pool {
+        pool_id_return = pool_id.clone();
+        pool_details.current_teams_count += 1;
+        POOL_DETAILS.save(deps.storage, pool_id.clone(), &pool_details)?;
+        update_open_pool_index(deps.storage, &pool_details, max_teams_for_pool)?;
         // Now save the team details
         save_team_details(
             deps.storage,
@@ -511,68 +1252,23 @@ pub fn game_pool_bid_submit(
             UNCLAIMED_REFUND,
             INITIAL_TEAM_POINTS,
             INITIAL_TEAM_RANK,
+            fury_deposited,
+            entry_asset_deposited,
+            fee_paid,
+            wager,
         )?;
+        record_game_event(deps.storage, env, &game_id, GameEvent::BidSubmitted {
+            pool_id: pool_id.clone(),
+            gamer: gamer.clone(),
+            team_id: team_id.clone(),
+            amount,
+        })?;
     } else {
         return Err(ContractError::Std(StdError::GenericErr {
             msg: String::from("pool max team limit reached "),
         }));
     }
-
-    // Sending Fury token to the contract
-    let transfer_msg = Cw20ExecuteMsg::TransferFrom {
-        owner: info.sender.into_string(),
-        recipient: env.clone().contract.address.to_string(),
-        amount,
-    };
-    let exec = WasmMsg::Execute {
-        contract_addr: config.minting_contract_address.to_string(),
-        msg: to_binary(&transfer_msg).unwrap(),
-        funds: vec![],
-    };
-    messages.push(CosmosMsg::Wasm(exec));
-
-
-    let increase_allowance_msg = Cw20ExecuteMsg::IncreaseAllowance {
-        spender: String::from(config.clone().astro_proxy_address),
-        amount,
-        expires: None,
-    };
-    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: config.clone().minting_contract_address.to_string(),
-        msg: to_binary(&increase_allowance_msg).unwrap(),
-        funds: vec![],
-    }));
-
-
-    let fury_asset_info = Asset {
-        info: AssetInfo::Token {
-            contract_addr: config.clone().minting_contract_address.to_string(),
-        },
-        amount,
-    };
-    let swap_message = AstroPortExecute::Swap {
-        offer_asset: fury_asset_info,
-        belief_price: None,
-        max_spread: max_spread,
-        to: Option::from(env.contract.address.to_string()),
-    };
-    let platform_fees_for_swap = deps.querier.query_wasm_smart(
-        config.clone().astro_proxy_address,
-        &QueryMsgSimulation::QueryPlatformFees {
-            msg: to_binary(&swap_message)?
-        },
-    )?;
-    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: config.clone().astro_proxy_address.to_string(),
-        msg: to_binary(&swap_message).unwrap(),
-        funds: vec![Coin {
-            denom: config.usdc_ibc_symbol.clone(),
-            amount: platform_fees_for_swap,
-        }],
-    }));
-    return Ok(Response::new()
-        .add_attribute("pool_id", pool_id_return.clone())
-        .add_messages(messages));
+    return Ok(pool_id_return);
 }
 
 pub fn save_team_details(
@@ -589,6 +1285,10 @@ pub fn save_team_details(
     claimed_refund: bool,
     team_points: u64,
     team_rank: u64,
+    fury_deposited: Uint128,
+    entry_asset_deposited: Uint128,
+    fee_paid: Option<Asset>,
+    wager: Option<WagerEntry>,
 ) -> Result<Response, ContractError> {
     // Get the existing teams for this pool
     let mut teams = Vec::new();
@@ -612,12 +1312,229 @@ pub fn save_team_details(
         claimed_refund,
         team_points,
         team_rank,
+        lineup: Vec::new(),
+        lineup_hash: None,
+        revealed: false,
+        season_points_added: false,
+        fury_deposited,
+        entry_asset_deposited,
+        fee_paid,
+        wager,
     });
     POOL_TEAM_DETAILS.save(storage, (&pool_id.clone(), gamer.as_ref()), &teams)?;
+    GAMER_POOLS.save(storage, (gamer.as_ref(), pool_id.as_ref()), &true)?;
 
     return Ok(Response::new().add_attribute("team_id", team_id.clone()));
 }
 
+// The lineup or its hash can be changed by the gamer as long as the game
+// is open for bidding. Both are replaced by the given values. A lineup
+// committed through its hash stays hidden till it is revealed, so the
+// lineup cannot be given along with the hash.
+pub fn update_team(
+    deps: DepsMut,
+    info: MessageInfo,
+    gamer: String,
+    pool_id: String,
+    team_id: String,
+    lineup: Vec<LineupPlayer>,
+    lineup_hash: Option<String>,
+) -> Result<Response, ContractError> {
+    check_activation_status(deps.storage)?;
+    if gamer != info.sender {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    let game = query_game_details(deps.storage)?;
+    if game.game_status != GAME_POOL_OPEN {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Team cant be updated as the game is not open"),
+        }));
+    }
+    if let Some(hash) = &lineup_hash {
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ContractError::InvalidLineupHash {});
+        }
+        if !lineup.is_empty() {
+            return Err(ContractError::LineupWithHash {});
+        }
+    }
+
+    let mut teams = POOL_TEAM_DETAILS
+        .may_load(deps.storage, (pool_id.as_ref(), gamer.as_ref()))?
+        .unwrap_or_default();
+    let team = teams
+        .iter_mut()
+        .find(|team| team.team_id == team_id)
+        .ok_or_else(|| ContractError::TeamNotFound {
+            pool_id: pool_id.clone(),
+            team_id: team_id.clone(),
+        })?;
+    team.lineup = lineup;
+    team.lineup_hash = lineup_hash.map(|hash| hash.to_lowercase());
+    team.revealed = false;
+    POOL_TEAM_DETAILS.save(deps.storage, (pool_id.as_ref(), gamer.as_ref()), &teams)?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "update_team")
+        .add_attribute("pool_id", pool_id)
+        .add_attribute("team_id", team_id));
+}
+
+// Takes the team out of the pool while the game is open and refunds
+// the pool fee along with the fees paid for the bid
+pub fn withdraw_team(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    gamer: String,
+    pool_id: String,
+    team_id: String,
+    testing: bool,
+    max_spread: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    check_claims_activation_status(deps.storage)?;
+    if gamer != info.sender {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    let game = query_game_details(deps.storage)?;
+    if game.game_status != GAME_POOL_OPEN {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Team cant be withdrawn as the game is not open"),
+        }));
+    }
+
+    let mut teams = POOL_TEAM_DETAILS
+        .may_load(deps.storage, (pool_id.as_ref(), gamer.as_ref()))?
+        .unwrap_or_default();
+    let position = teams
+        .iter()
+        .position(|team| team.team_id == team_id)
+        .ok_or_else(|| ContractError::TeamNotFound {
+            pool_id: pool_id.clone(),
+            team_id: team_id.clone(),
+        })?;
+    let team = teams.remove(position);
+    if teams.is_empty() {
+        POOL_TEAM_DETAILS.remove(deps.storage, (pool_id.as_ref(), gamer.as_ref()));
+        GAMER_POOLS.remove(deps.storage, (gamer.as_ref(), pool_id.as_ref()));
+    } else {
+        POOL_TEAM_DETAILS.save(deps.storage, (pool_id.as_ref(), gamer.as_ref()), &teams)?;
+    }
+
+    let mut pool_details = query_pool_details(deps.storage, pool_id.clone())?;
+    pool_details.current_teams_count -= 1;
+    POOL_DETAILS.save(deps.storage, pool_id.clone(), &pool_details)?;
+    let pool_type_details = query_pool_type_details(deps.storage, pool_details.pool_type.clone())?;
+    update_open_pool_index(deps.storage, &pool_details, pool_type_details.max_teams_for_pool)?;
+    if let Some(wager) = &team.wager {
+        release_wager(deps.storage, &gamer, &team.game_id, wager)?;
+    }
+    record_game_event(deps.storage, &env, &game.game_id, GameEvent::TeamWithdrawn {
+        pool_id: pool_id.clone(),
+        gamer: gamer.clone(),
+        team_id: team_id.clone(),
+    })?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut refunds: Vec<Asset> = Vec::new();
+    let deposit = bid_deposit(&config, &pool_type_details, &team);
+    add_to_asset_totals(&mut refunds, &deposit.info, deposit.amount);
+    let fee_refund = bid_fee_refund(&config, &pool_type_details, &team)?;
+    add_to_asset_totals(&mut refunds, &fee_refund.info, fee_refund.amount);
+    // Teams which bid before the FURY was held till lock have no deposit
+    // recorded, their pool fee is swapped back instead. The deposit of
+    // pools with an entry asset goes back as is.
+    let mut ust_to_swap = Uint128::zero();
+    if pool_type_details.entry_asset.is_none() && team.fury_deposited.is_zero() {
+        ust_to_swap = pool_type_details.pool_fee;
+    }
+    let asset_amounts: Vec<String> = refunds.iter().map(|refund| refund.to_string()).collect();
+    let (final_amount, messages) = refund_messages(
+        &deps,
+        gamer,
+        ust_to_swap,
+        refunds,
+        testing,
+        max_spread,
+    )?;
+    return Ok(Response::new()
+        .add_attribute("action", "withdraw_team")
+        .add_attribute("pool_id", pool_id)
+        .add_attribute("team_id", team_id)
+        .add_attribute("amount", final_amount.to_string())
+        .add_attribute("fury_amount", team.fury_deposited.to_string())
+        .add_attribute("asset_amounts", asset_amounts.join(","))
+        .add_messages(messages));
+}
+
+// Once the game is locked the gamer or the admin reveals the lineup
+// committed through the lineup hash. The hash is the sha256 of the
+// json encoded lineup followed by the salt.
+pub fn reveal_lineup(
+    deps: DepsMut,
+    info: MessageInfo,
+    gamer: String,
+    pool_id: String,
+    team_id: String,
+    lineup: Vec<LineupPlayer>,
+    salt: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if gamer != info.sender && info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    let game = query_game_details(deps.storage)?;
+    if game.game_status != GAME_POOL_CLOSED {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Lineup can only be revealed once the game is locked"),
+        }));
+    }
+
+    let mut teams = POOL_TEAM_DETAILS
+        .may_load(deps.storage, (pool_id.as_ref(), gamer.as_ref()))?
+        .unwrap_or_default();
+    let team = teams
+        .iter_mut()
+        .find(|team| team.team_id == team_id)
+        .ok_or_else(|| ContractError::TeamNotFound {
+            pool_id: pool_id.clone(),
+            team_id: team_id.clone(),
+        })?;
+    match &team.lineup_hash {
+        Some(lineup_hash) => {
+            if *lineup_hash != lineup_hash_hex(&lineup, &salt)? {
+                return Err(ContractError::LineupMismatch {});
+            }
+        }
+        None => return Err(ContractError::InvalidLineupHash {}),
+    }
+    team.lineup = lineup;
+    team.revealed = true;
+    POOL_TEAM_DETAILS.save(deps.storage, (pool_id.as_ref(), gamer.as_ref()), &teams)?;
+
+    return Ok(Response::new()
+        .add_attribute("action", "reveal_lineup")
+        .add_attribute("pool_id", pool_id)
+        .add_attribute("team_id", team_id));
+}
+
+pub fn lineup_hash_hex(lineup: &[LineupPlayer], salt: &str) -> StdResult<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(to_binary(&lineup)?.as_slice());
+    hasher.update(salt.as_bytes());
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
 // Reward:Platform fee has to charged. Reward amount here is in FURY.
 // Make a call to astroport to get the platform fee, that is to be charged.
 // Here we only transfer the FURY and here since the amount is in
@@ -626,22 +1543,18 @@ pub fn claim_reward(
     deps: DepsMut,
     info: MessageInfo,
     gamer: String,
+    pool_ids: Option<Vec<String>>,
     env: Env,
 ) -> Result<Response, ContractError> {
+    check_claims_activation_status(deps.storage)?;
     let gamer_addr = deps.api.addr_validate(&gamer)?;
-    //Check if withdrawer is same as invoker
-    if gamer_addr != info.sender {
-        return Err(ContractError::Unauthorized {
-            invoker: info.sender.to_string(),
-        });
-    }
+    //Check if withdrawer is the gamer or a manager of the gamer
+    check_gamer_or_manager(deps.storage, &gamer_addr, &info.sender, &env.block)?;
 
     let mut user_reward = Uint128::zero();
-    // Get all pools
-    let all_pools: Vec<String> = POOL_DETAILS
-        .keys(deps.storage, None, None, Order::Ascending)
-        .map(|k| String::from_utf8(k).unwrap())
-        .collect();
+    let mut asset_rewards: Vec<Asset> = Vec::new();
+    // Get the requested pools, or all pools joined by the gamer
+    let all_pools = query_gamer_pool_ids(deps.storage, &gamer, pool_ids)?;
     for pool_id in all_pools {
         // Get the existing teams for this pool
         let mut pool_details: PoolDetails = Default::default();
@@ -655,6 +1568,8 @@ pub fn claim_reward(
         if !pool_details.pool_reward_status {
             continue;
         }
+        // Pools with an entry asset pay their rewards in that asset
+        let entry_asset = POOL_TYPE_DETAILS.load(deps.storage, pool_details.pool_type.clone())?.entry_asset;
         let mut pool_team_details;
         match POOL_TEAM_DETAILS.load(deps.storage, (&*pool_id.clone(), &gamer.clone())) {
             Ok(some) => { pool_team_details = some; }
@@ -666,7 +1581,16 @@ pub fn claim_reward(
         for team_details in pool_team_details {
             if !team_details.claimed_reward {
                 let mut updated_team = team_details.clone();
-                user_reward += team_details.reward_amount;
+                record_game_event(deps.storage, &env, &team_details.game_id, GameEvent::RewardClaimed {
+                    pool_id: pool_id.clone(),
+                    gamer: gamer.clone(),
+                    team_id: team_details.team_id.clone(),
+                    amount: team_details.reward_amount,
+                })?;
+                match &entry_asset {
+                    Some(asset_info) => add_to_asset_totals(&mut asset_rewards, asset_info, team_details.reward_amount),
+                    None => user_reward += team_details.reward_amount,
+                }
                 updated_team.claimed_reward = true;
                 updated_details.push(updated_team);
             } else {
@@ -678,21 +1602,32 @@ pub fn claim_reward(
         }
     }
 
-    if user_reward == Uint128::zero() {
+    if user_reward == Uint128::zero() && asset_rewards.is_empty() {
         return Err(ContractError::Std(StdError::GenericErr {
             msg: String::from("No reward for this user"),
         }));
     }
 
+    // The fees of pools with an entry asset are paid at bid, so their
+    // rewards go out as they are
+    let mut messages = Vec::new();
+    let mut asset_amounts = Vec::new();
+    for reward in asset_rewards {
+        asset_amounts.push(reward.to_string());
+        messages.push(reward.into_msg(gamer_addr.clone())?);
+    }
+    if user_reward.is_zero() {
+        return Ok(Response::new()
+            .add_attribute("amount", user_reward.to_string())
+            .add_attribute("asset_amounts", asset_amounts.join(","))
+            .add_attribute("action", "reward")
+            .add_messages(messages)
+        );
+    }
+
     // Do the transfer of reward to the actual gamer_addr from the contract
     let config = CONFIG.load(deps.storage)?;
-    let mut messages = Vec::new();
-    let user_reward_in_ust = deps.querier.query_wasm_smart(
-        config.clone().astro_proxy_address,
-        &ProxyQueryMsgs::get_ust_equivalent_to_fury {
-            fury_count: user_reward,
-        },
-    )?;
+    let user_reward_in_ust = ust_equivalent_to_fury(deps.as_ref(), &config, user_reward)?;
     let fee_details = query_platform_fees(user_reward_in_ust, config.platform_fee, config.transaction_fee)?;
     // We only take the first coin object since we only expect UST here
     let funds_sent;
@@ -713,7 +1648,7 @@ pub fn claim_reward(
 
 
     let transfer_msg = Cw20ExecuteMsg::Transfer {
-        recipient: info.sender.into_string(),
+        recipient: gamer_addr.into_string(),
         amount: user_reward,
     };
     messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -723,6 +1658,7 @@ pub fn claim_reward(
     }));
     return Ok(Response::new()
         .add_attribute("amount", user_reward.to_string())
+        .add_attribute("asset_amounts", asset_amounts.join(","))
         .add_attribute("action", "reward")
         .add_messages(messages)
     );
@@ -739,27 +1675,22 @@ pub fn claim_refund(
     deps: DepsMut,
     info: MessageInfo,
     gamer: String,
+    pool_ids: Option<Vec<String>>,
     env: Env,
     testing: Option<bool>,
-    max_spread: Option<Decimal>,
 ) -> Result<Response, ContractError> {
+    check_claims_activation_status(deps.storage)?;
     let testing_status = testing.unwrap_or(false);
-    let mut refund_in_ust_fees = Uint128::default();
     let gamer_addr = deps.api.addr_validate(&gamer)?;
-    //Check if withdrawer is same as invoker
-    if gamer_addr != info.sender {
-        return Err(ContractError::Unauthorized {
-            invoker: info.sender.to_string(),
-        });
-    }
-    let config = CONFIG.load(deps.storage)?;
-    // Get all pools
-
-    let all_pools: Vec<String> = POOL_DETAILS
-        .keys(deps.storage, None, None, Order::Ascending)
-        .map(|k| String::from_utf8(k).unwrap())
-        .collect();
-    let mut total_refund_amount = Uint128::zero();
+    //Check if withdrawer is the gamer or a manager of the gamer
+    check_gamer_or_manager(deps.storage, &gamer_addr, &info.sender, &env.block)?;
+    // Get the requested pools, or all pools joined by the gamer
+    let all_pools = query_gamer_pool_ids(deps.storage, &gamer, pool_ids)?;
+    let mut teams_refunded = 0u32;
+    let mut total_fury_refund = Uint128::zero();
+    let mut ust_to_swap = Uint128::zero();
+    let mut asset_refunds: Vec<Asset> = Vec::new();
+    let mut refund_already_claimed = false;
     for pool_id in all_pools {
         let mut pool_details: PoolDetails = Default::default();
         let pd = POOL_DETAILS.load(deps.storage, pool_id.clone());
@@ -773,7 +1704,11 @@ pub fn claim_refund(
             continue;
         }
         let pool_type = POOL_TYPE_DETAILS.load(deps.storage, pool_details.pool_type)?;
-        let refund_amount = pool_type.pool_fee;
+        let config = CONFIG.load(deps.storage)?;
+        let pool_swap = match POOL_SWAPPED_FURY.may_load(deps.storage, pool_id.clone())? {
+            Some(pool_fury) => Some((pool_fury, POOL_SWAPPED_UST.load(deps.storage, pool_id.clone())?)),
+            None => None,
+        };
         let pool_team_details = POOL_TEAM_DETAILS.load(deps.storage, (pool_id.as_ref(), &gamer.clone()));
         match pool_team_details {
             Ok(some) => {
@@ -781,12 +1716,36 @@ pub fn claim_refund(
                 for team_details in some {
                     if !team_details.claimed_refund {
                         let mut updated_team = team_details.clone();
-                        updated_team.refund_amount = refund_amount;
-                        total_refund_amount += refund_amount;
+                        // The deposit is given back as is while the contract
+                        // still holds it, else the team's share of the UST the
+                        // FURY of the pool was swapped for at lock is swapped
+                        // back to FURY for the gamer. The fee paid with the bid
+                        // goes back in the asset it was paid in.
+                        match pool_swap {
+                            Some((pool_fury, pool_ust)) => {
+                                let ust_share = pool_ust.multiply_ratio(team_details.fury_deposited, pool_fury);
+                                updated_team.refund_amount = ust_share;
+                                ust_to_swap += ust_share;
+                            }
+                            None => {
+                                let deposit = bid_deposit(&config, &pool_type, &team_details);
+                                updated_team.refund_amount = deposit.amount;
+                                add_to_asset_totals(&mut asset_refunds, &deposit.info, deposit.amount);
+                                total_fury_refund += team_details.fury_deposited;
+                            }
+                        }
+                        let fee_refund = bid_fee_refund(&config, &pool_type, &team_details)?;
+                        add_to_asset_totals(&mut asset_refunds, &fee_refund.info, fee_refund.amount);
+                        if let Some(wager) = &team_details.wager {
+                            release_wager(deps.storage, &gamer, &team_details.game_id, wager)?;
+                        }
+                        teams_refunded += 1;
                         updated_team.claimed_refund = true;
+                        record_refund_claimed(deps.storage, &env, &updated_team)?;
                         updated_details.push(updated_team);
                     } else {
-                        return Err(ContractError::RefundAlreadyClaimed {});
+                        refund_already_claimed = true;
+                        updated_details.push(team_details);
                     }
                 }
                 if !updated_details.is_empty() {
@@ -800,20 +1759,118 @@ pub fn claim_refund(
     }
 
 
-    if total_refund_amount == Uint128::zero() {
+    if teams_refunded == 0 {
+        if refund_already_claimed {
+            return Err(ContractError::RefundAlreadyClaimed {});
+        }
         return Err(ContractError::Std(StdError::GenericErr {
             msg: String::from("No refund for this user"),
         }));
     }
-    let refund_details = query_platform_fees(total_refund_amount, config.platform_fee, config.transaction_fee)?;
-    refund_in_ust_fees = refund_details.transaction_fee.add(refund_details.platform_fee);
     // Do the transfer of refund to the actual gamer_addr from the contract
+    let asset_amounts: Vec<String> = asset_refunds.iter().map(|refund| refund.to_string()).collect();
+    let (final_amount, messages) = refund_messages(
+        &deps,
+        gamer_addr.to_string(),
+        ust_to_swap,
+        asset_refunds,
+        testing_status,
+        None,
+    )?;
+    return Ok(Response::new()
+        .add_attribute("amount", final_amount.to_string())
+        .add_attribute("fury_amount", total_fury_refund.to_string())
+        .add_attribute("asset_amounts", asset_amounts.join(","))
+        .add_attribute("action", "refund")
+        .add_messages(messages)
+    );
+}
+
+// Builds the messages of a refund. The deposits still held for the teams
+// and the fees paid at bid time are sent back in their own asset, the
+// UST of swapped pools is swapped to FURY for the recipient. Zero
+// amounts are left out.
+fn refund_messages(
+    deps: &DepsMut,
+    recipient: String,
+    ust_to_swap: Uint128,
+    refunds: Vec<Asset>,
+    testing: bool,
+    max_spread: Option<Decimal>,
+) -> Result<(Uint128, Vec<CosmosMsg>), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let recipient_addr = deps.api.addr_validate(&recipient)?;
     let mut messages = Vec::new();
+    let mut final_amount = Uint128::zero();
+    if !ust_to_swap.is_zero() {
+        let (swap_amount, swap_msg) = ust_swap_message(deps, &config, recipient, ust_to_swap, testing, max_spread)?;
+        final_amount = swap_amount;
+        messages.push(swap_msg);
+    }
+    for refund in refunds {
+        if refund.amount.is_zero() {
+            continue;
+        }
+        messages.push(refund.into_msg(recipient_addr.clone())?);
+    }
+    return Ok((final_amount, messages));
+}
+
+// The deposit held for the team, in FURY unless the pool type has an
+// entry asset
+fn bid_deposit(config: &Config, pool_type: &PoolTypeDetails, team: &PoolTeamDetails) -> Asset {
+    match &pool_type.entry_asset {
+        Some(entry_asset) => Asset {
+            info: entry_asset.clone(),
+            amount: team.entry_asset_deposited,
+        },
+        None => Asset {
+            info: AssetInfo::Token {
+                contract_addr: config.minting_contract_address.to_string(),
+            },
+            amount: team.fury_deposited,
+        },
+    }
+}
+
+// A fee forwarded to the platform fees collector at bid is booked at zero,
+// the contract holds none of it to give back
+fn forwarded_fee(fee: &Asset) -> Asset {
+    Asset {
+        info: fee.info.clone(),
+        amount: Uint128::zero(),
+    }
+}
+
+// The fee paid with the bid of the team. Teams saved before the fee was
+// recorded paid it in the entry asset of the pool type, else in UST
+fn bid_fee_refund(config: &Config, pool_type: &PoolTypeDetails, team: &PoolTeamDetails) -> StdResult<Asset> {
+    if let Some(fee_paid) = &team.fee_paid {
+        return Ok(fee_paid.clone());
+    }
+    Ok(Asset {
+        info: pool_type.entry_asset.clone().unwrap_or(AssetInfo::NativeToken {
+            denom: config.usdc_ibc_symbol.clone(),
+        }),
+        amount: entry_fee_in_asset(config, pool_type.pool_fee)?,
+    })
+}
+
+// Builds the message to swap the UST held by the contract to FURY for the
+// recipient, returned along with the UST sent including the swap fee
+fn ust_swap_message(
+    deps: &DepsMut,
+    config: &Config,
+    recipient: String,
+    amount: Uint128,
+    testing: bool,
+    max_spread: Option<Decimal>,
+) -> Result<(Uint128, CosmosMsg), ContractError> {
     let ust_asset = Asset {
         info: AssetInfo::NativeToken {
             denom: config.usdc_ibc_symbol.clone()
         },
-        amount: total_refund_amount,
+        amount,
     };
     //let tax = ust_asset.compute_tax(&deps.querier)?;
     // ust_asset.amount += tax;
@@ -821,12 +1878,12 @@ pub fn claim_refund(
         offer_asset: ust_asset.clone(),
         belief_price: None,
         max_spread: max_spread,
-        to: Option::from(info.sender.to_string()),
+        to: Option::from(recipient),
     };
 
     let mut swap_fee = Uint128::zero();
     // Swap fee should be platform+transaction fee for the transaction
-    if !testing_status {
+    if !testing {
         swap_fee = deps.querier.query_wasm_smart(
             config.clone().astro_proxy_address,
             &QueryMsgSimulation::QueryPlatformFees {
@@ -836,34 +1893,20 @@ pub fn claim_refund(
     }
     //let final_amount = ust_asset.amount.clone().add(swap_fee).add(tax);
     let final_amount = ust_asset.amount.clone().add(swap_fee);
-    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+    let swap_msg = CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: config.astro_proxy_address.to_string(),
         msg: to_binary(&swap_message)?,
         funds: vec![Coin {
             denom: config.usdc_ibc_symbol.clone(),
             amount: final_amount,
         }],
-    }));
-    let refund = Coin {
-        denom: config.usdc_ibc_symbol.clone(),
-        amount: refund_in_ust_fees,
-    };
-    let mut refund_: Vec<Coin> = vec![];
-    refund_.push(refund);
-    messages.push(CosmosMsg::Bank(BankMsg::Send {
-        to_address: String::from(info.sender),
-        amount: refund_,
-    }));
-    return Ok(Response::new()
-        .add_attribute("amount", final_amount.to_string())
-        .add_attribute("action", "refund")
-        .add_messages(messages)
-    );
+    });
+    return Ok((final_amount, swap_msg));
 }
 
 pub fn game_pool_reward_distribute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     game_id: String,
     pool_id: String,
@@ -904,6 +1947,19 @@ pub fn game_pool_reward_distribute(
             msg: String::from("Rewards cant be distributed as game not yet started"),
         }));
     }
+    // A committed lineup must be revealed before the team is paid
+    for winner in game_winners.iter() {
+        let teams = POOL_TEAM_DETAILS
+            .may_load(deps.storage, (pool_id.as_ref(), winner.gamer_address.as_ref()))?
+            .unwrap_or_default();
+        for team in teams {
+            if team.team_id == winner.team_id && team.lineup_hash.is_some() && !team.revealed {
+                return Err(ContractError::LineupNotRevealed {
+                    team_id: team.team_id,
+                });
+            }
+        }
+    }
     let reward_status;
     let game_status;
     let pool_status_string;
@@ -979,6 +2035,7 @@ pub fn game_pool_reward_distribute(
 
     let mut reward_given_so_far = Uint128::zero();
     let mut all_teams: Vec<PoolTeamDetails> = Vec::new();
+    let current_season = CURRENT_SEASON.may_load(deps.storage)?;
     for winner in game_winners.clone().into_iter() {
         let ptd = POOL_TEAM_DETAILS.may_load(deps.storage, (&pool_id.clone(), winner.gamer_address.as_ref()))?;
         match ptd {
@@ -1001,7 +2058,22 @@ pub fn game_pool_reward_distribute(
                     && team.game_id == game_id.clone()
                 {
                     updated_team.reward_amount = winner.reward_amount;
+                    updated_team.team_points = winner.team_points;
+                    // The points of a team only count once for the season,
+                    // even when the team comes again in a later batch
+                    if let Some(season_id) = current_season.clone() {
+                        if !updated_team.season_points_added {
+                            add_season_points(deps.storage, &season_id, &winner.gamer_address, winner.team_points)?;
+                            updated_team.season_points_added = true;
+                        }
+                    }
                     reward_given_so_far += winner.reward_amount;
+                    record_game_event(deps.storage, &env, &game_id, GameEvent::RewardAssigned {
+                        pool_id: pool_id.clone(),
+                        gamer: winner.gamer_address.clone(),
+                        team_id: winner.team_id.clone(),
+                        amount: winner.reward_amount,
+                    })?;
                     println!(
                         "reward for {:?} is {:?}",
                         team.team_id, updated_team.reward_amount
@@ -1029,11 +2101,60 @@ pub fn game_pool_reward_distribute(
     let rsp;
     // Transfer rake_amount to all the rake wallets. Can also be only one rake wallet
     if is_final_batch {
+        if !ust_for_rake.is_zero() {
+            record_game_event(deps.storage, &env, &game_id, GameEvent::RakeDistributed {
+                pool_id: pool_id.clone(),
+                amount: ust_for_rake,
+            })?;
+        }
         // Only when we are on the final batch and UST for rake is not zero we perform this
         if !ust_for_rake.is_zero() {
+            // The cut of the referrers and the slice for the open season
+            // stay with the contract till claimed or paid out. The rake of
+            // pools with an entry asset is in that asset.
+            let entry_asset = pool_type_details.entry_asset.clone();
+            let referral_cut = accrue_referral_rake(deps.storage, &pool_id, pool_count, ust_for_rake, entry_asset.as_ref())?;
+            let mut rake_amount = ust_for_rake - referral_cut;
+            if let Some(season_id) = current_season {
+                let mut season = SEASON_DETAILS.load(deps.storage, season_id.clone())?;
+                let season_cut = rake_amount
+                    .checked_mul(Uint128::from(season.rake_percentage))
+                    .unwrap_or_default()
+                    .checked_div(Uint128::from(100u128))
+                    .unwrap_or_default();
+                match &entry_asset {
+                    Some(asset_info) => add_to_asset_totals(&mut season.prize_pot_assets, asset_info, season_cut),
+                    None => season.prize_pot += season_cut,
+                }
+                SEASON_DETAILS.save(deps.storage, season_id, &season)?;
+                rake_amount = rake_amount - season_cut;
+            }
+            if let Some(entry_asset) = entry_asset {
+                let mut messages = Vec::new();
+                for wallet in pool_type_details.rake_list {
+                    let rake = Asset {
+                        info: entry_asset.clone(),
+                        amount: rake_amount
+                            .checked_mul(Uint128::from(wallet.percentage))
+                            .unwrap_or_default()
+                            .checked_div(Uint128::from(100u128))
+                            .unwrap_or_default(),
+                    };
+                    if rake.amount.is_zero() {
+                        continue;
+                    }
+                    messages.push(rake.into_msg(deps.api.addr_validate(&wallet.wallet_address)?)?);
+                }
+                return Ok(Response::new()
+                    .add_messages(messages)
+                    .add_attribute("action", "rake_and_platform_fee")
+                    .add_attribute("game_status", reward_status_string.to_string())
+                    .add_attribute("game_id", game_id.clone())
+                    .add_attribute("pool_status", pool_status_string.to_string())
+                    .add_attribute("pool_id", pool_id.clone()));
+            }
             for wallet in pool_type_details.rake_list {
                 let wallet_address = wallet.wallet_address;
-                let rake_amount = ust_for_rake;
                 let proportionate_amount = rake_amount
                     .checked_mul(Uint128::from(wallet.percentage))
                     .unwrap_or_default()
@@ -1065,6 +2186,323 @@ pub fn game_pool_reward_distribute(
         .add_attribute("pool_id", pool_id.clone()));
 }
 
+pub fn set_referral_percentage(
+    deps: DepsMut,
+    info: MessageInfo,
+    percentage: u32,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    if percentage > 100 {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Referral percentage cannot be more than 100"),
+        }));
+    }
+    REFERRAL_PERCENTAGE.save(deps.storage, &percentage)?;
+    return Ok(Response::default());
+}
+
+pub fn register_referrer(
+    deps: DepsMut,
+    info: MessageInfo,
+    referrer: String,
+) -> Result<Response, ContractError> {
+    let referrer_addr = deps.api.addr_validate(&referrer)?;
+    let gamer = info.sender.to_string();
+    if referrer_addr == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    if REFERRERS.may_load(deps.storage, &gamer)?.is_some() {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Referrer already registered"),
+        }));
+    }
+    REFERRERS.save(deps.storage, &gamer, &referrer_addr.to_string())?;
+    let mut stats = REFERRAL_STATS.may_load(deps.storage, referrer_addr.as_str())?.unwrap_or_default();
+    stats.referred_gamers += 1;
+    REFERRAL_STATS.save(deps.storage, referrer_addr.as_str(), &stats)?;
+    return Ok(Response::new()
+        .add_attribute("action", "register_referrer")
+        .add_attribute("gamer", gamer)
+        .add_attribute("referrer", referrer_addr.to_string()));
+}
+
+pub fn claim_referral_rewards(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    check_claims_activation_status(deps.storage)?;
+    let referrer = info.sender.to_string();
+    let mut stats = REFERRAL_STATS.may_load(deps.storage, &referrer)?.unwrap_or_default();
+    if stats.unclaimed.is_zero() && stats.unclaimed_assets.is_empty() {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("No referral rewards for this user"),
+        }));
+    }
+    let amount = stats.unclaimed;
+    let asset_rewards = std::mem::take(&mut stats.unclaimed_assets);
+    stats.unclaimed = Uint128::zero();
+    REFERRAL_STATS.save(deps.storage, &referrer, &stats)?;
+    // Rewards earned in entry assets are paid in those assets
+    let mut messages = Vec::new();
+    let mut asset_amounts = Vec::new();
+    for reward in asset_rewards {
+        asset_amounts.push(reward.to_string());
+        messages.push(reward.into_msg(info.sender.clone())?);
+    }
+    let mut wallet_transfer_details = Vec::new();
+    if !amount.is_zero() {
+        wallet_transfer_details.push(WalletTransferDetails {
+            wallet_address: referrer,
+            amount,
+        });
+    }
+    let rsp = _transfer_to_multiple_wallets(
+        wallet_transfer_details,
+        "claim_referral_rewards".to_string(),
+        deps,
+        false,
+    )?;
+    return Ok(rsp
+        .add_messages(messages)
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("asset_amounts", asset_amounts.join(",")));
+}
+
+// Each team has an equal share of the rake of the pool. The referrer of
+// the gamer of the team earns the referral percentage of that share, in
+// the entry asset of the pool if it has one. Returns the total taken out
+// of the rake.
+fn accrue_referral_rake(
+    storage: &mut dyn Storage,
+    pool_id: &str,
+    teams_count: u32,
+    ust_for_rake: Uint128,
+    entry_asset: Option<&AssetInfo>,
+) -> StdResult<Uint128> {
+    let percentage = REFERRAL_PERCENTAGE.may_load(storage)?.unwrap_or_default();
+    if percentage == 0 || teams_count == 0 {
+        return Ok(Uint128::zero());
+    }
+    let share_per_team = ust_for_rake
+        .checked_div(Uint128::from(teams_count))
+        .unwrap_or_default()
+        .checked_mul(Uint128::from(percentage))
+        .unwrap_or_default()
+        .checked_div(Uint128::from(100u128))
+        .unwrap_or_default();
+    if share_per_team.is_zero() {
+        return Ok(Uint128::zero());
+    }
+    let all_teams: StdResult<Vec<(Vec<u8>, Vec<PoolTeamDetails>)>> = POOL_TEAM_DETAILS
+        .prefix(pool_id)
+        .range(storage, None, None, Order::Ascending)
+        .collect();
+    let mut referral_cut = Uint128::zero();
+    for (_, teams) in all_teams? {
+        for team in teams {
+            let referrer = match REFERRERS.may_load(storage, &team.gamer_address)? {
+                Some(referrer) => referrer,
+                None => continue,
+            };
+            let mut stats = REFERRAL_STATS.may_load(storage, &referrer)?.unwrap_or_default();
+            match entry_asset {
+                Some(asset_info) => add_to_asset_totals(&mut stats.unclaimed_assets, asset_info, share_per_team),
+                None => {
+                    stats.total_earned += share_per_team;
+                    stats.unclaimed += share_per_team;
+                }
+            }
+            REFERRAL_STATS.save(storage, &referrer, &stats)?;
+            referral_cut += share_per_team;
+        }
+    }
+    Ok(referral_cut)
+}
+
+// Adds the points to the season standing of the gamer and moves the
+// gamer to the new place in the leaderboard
+fn add_season_points(
+    storage: &mut dyn Storage,
+    season_id: &str,
+    gamer: &str,
+    points: u64,
+) -> StdResult<()> {
+    if points == 0 {
+        return Ok(());
+    }
+    let current_points = SEASON_POINTS.may_load(storage, (season_id, gamer))?;
+    if let Some(current_points) = current_points {
+        SEASON_LEADERBOARD.remove(storage, (season_id, &season_rank_key(current_points, gamer)));
+    }
+    let total_points = current_points.unwrap_or_default().saturating_add(points);
+    SEASON_POINTS.save(storage, (season_id, gamer), &total_points)?;
+    SEASON_LEADERBOARD.save(
+        storage,
+        (season_id, &season_rank_key(total_points, gamer)),
+        &SeasonStanding {
+            gamer_address: gamer.to_string(),
+            points: total_points,
+        },
+    )?;
+    Ok(())
+}
+
+// Key of a gamer in the leaderboard, the points are inverted so that
+// an ascending range gives the highest points first
+pub fn season_rank_key(points: u64, gamer: &str) -> Vec<u8> {
+    let mut key = (u64::MAX - points).to_be_bytes().to_vec();
+    key.extend_from_slice(gamer.as_bytes());
+    key
+}
+
+pub fn start_season(
+    deps: DepsMut,
+    info: MessageInfo,
+    season_id: String,
+    rake_percentage: u32,
+    payout_percentages: Vec<u32>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    if CURRENT_SEASON.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Current season is not closed yet"),
+        }));
+    }
+    if SEASON_DETAILS.may_load(deps.storage, season_id.clone())?.is_some() {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Season already exists"),
+        }));
+    }
+    if rake_percentage > 100 || payout_percentages.iter().sum::<u32>() > 100 {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Percentages add up to more than 100"),
+        }));
+    }
+    SEASON_DETAILS.save(
+        deps.storage,
+        season_id.clone(),
+        &SeasonDetails {
+            season_id: season_id.clone(),
+            rake_percentage,
+            payout_percentages,
+            prize_pot: Uint128::zero(),
+            prize_pot_assets: vec![],
+            closed: false,
+        },
+    )?;
+    CURRENT_SEASON.save(deps.storage, &season_id)?;
+    return Ok(Response::new()
+        .add_attribute("action", "start_season")
+        .add_attribute("season_id", season_id));
+}
+
+// Pays the prize pot of the current season to the top of the leaderboard.
+// The shares of places without a gamer stay with the contract.
+pub fn close_season(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    let season_id = CURRENT_SEASON.may_load(deps.storage)?.ok_or_else(|| {
+        ContractError::Std(StdError::GenericErr {
+            msg: String::from("No season is open"),
+        })
+    })?;
+    let mut season = SEASON_DETAILS.load(deps.storage, season_id.clone())?;
+    let top_gamers: StdResult<Vec<(Vec<u8>, SeasonStanding)>> = SEASON_LEADERBOARD
+        .prefix(&season_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(season.payout_percentages.len())
+        .collect();
+
+    let mut wallet_transfer_details: Vec<WalletTransferDetails> = Vec::new();
+    let mut messages = Vec::new();
+    for ((_, standing), percentage) in top_gamers?.into_iter().zip(season.payout_percentages.iter()) {
+        // The prize pot collected in entry assets is paid out the same way
+        for pot in season.prize_pot_assets.iter() {
+            let prize = Asset {
+                info: pot.info.clone(),
+                amount: pot.amount
+                    .checked_mul(Uint128::from(*percentage))
+                    .unwrap_or_default()
+                    .checked_div(Uint128::from(100u128))
+                    .unwrap_or_default(),
+            };
+            if !prize.amount.is_zero() {
+                messages.push(prize.into_msg(deps.api.addr_validate(&standing.gamer_address)?)?);
+            }
+        }
+        let amount = season.prize_pot
+            .checked_mul(Uint128::from(*percentage))
+            .unwrap_or_default()
+            .checked_div(Uint128::from(100u128))
+            .unwrap_or_default();
+        if amount.is_zero() {
+            continue;
+        }
+        wallet_transfer_details.push(WalletTransferDetails {
+            wallet_address: standing.gamer_address,
+            amount,
+        });
+    }
+    season.closed = true;
+    SEASON_DETAILS.save(deps.storage, season_id.clone(), &season)?;
+    CURRENT_SEASON.remove(deps.storage);
+
+    let rsp = _transfer_to_multiple_wallets(
+        wallet_transfer_details,
+        "close_season".to_string(),
+        deps,
+        false,
+    )?;
+    return Ok(rsp
+        .add_messages(messages)
+        .add_attribute("season_id", season_id)
+        .add_attribute("prize_pot", season.prize_pot.to_string()));
+}
+
+// Appends the event to the log of the game, indexed by its pool if any
+fn record_game_event(
+    storage: &mut dyn Storage,
+    env: &Env,
+    game_id: &str,
+    event: GameEvent,
+) -> StdResult<()> {
+    let event_id = GAME_EVENT_COUNT.may_load(storage, game_id)?.unwrap_or_default() + 1;
+    GAME_EVENT_COUNT.save(storage, game_id, &event_id)?;
+    if let Some(pool_id) = event.pool_id() {
+        POOL_EVENTS.save(storage, (pool_id, U64Key::from(event_id)), &game_id.to_string())?;
+    }
+    GAME_EVENTS.save(
+        storage,
+        (game_id, U64Key::from(event_id)),
+        &GameEventRecord {
+            event_id,
+            block_height: env.block.height,
+            block_time: env.block.time,
+            event,
+        },
+    )
+}
+
+fn record_refund_claimed(storage: &mut dyn Storage, env: &Env, team: &PoolTeamDetails) -> StdResult<()> {
+    record_game_event(storage, env, &team.game_id, GameEvent::RefundClaimed {
+        pool_id: team.pool_id.clone(),
+        gamer: team.gamer_address.clone(),
+        team_id: team.team_id.clone(),
+        amount: team.refund_amount,
+    })
+}
+
 pub fn _transfer_to_multiple_wallets(
     wallet_details: Vec<WalletTransferDetails>,
     action: String,
@@ -1106,6 +2544,9 @@ pub fn swap(
             invoker: info.sender.to_string(),
         });
     }
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
     let pool_details = query_pool_details(deps.storage, pool_id.clone())?;
     let pool_type_details = POOL_TYPE_DETAILS.load(deps.storage, pool_details.pool_type.clone())?;
     // This is the total funds we have in the pool as UST
@@ -1123,6 +2564,14 @@ pub fn swap(
             address: env.contract.address.clone().to_string()
         },
     )?;
+    let ust_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: config.usdc_ibc_symbol.clone()
+        },
+        amount,
+    };
+    // The minimum FURY expected is stored and checked in the reply
+    let (belief_price, min_fury_expected) = simulate_swap(&deps, &config, ust_asset.clone(), max_spread)?;
     let mut swap_info;
     match SWAP_BALANCE_INFO.load(deps.storage, pool_id.clone()) {
         Ok(mut swap) => {
@@ -1137,21 +2586,19 @@ pub fn swap(
                 exchange_rate: Default::default(),
                 ust_amount_swapped: amount.clone(),
                 ust_for_rake: funds_for_rake,
+                min_fury_expected: Default::default(),
+                swap_failed: false,
             }
         }
     }
     swap_info.balance_pre_swap = current_fury_balance.balance;
+    swap_info.min_fury_expected = min_fury_expected;
+    swap_info.swap_failed = false;
     SWAP_BALANCE_INFO.save(deps.storage, pool_id.clone(), &swap_info)?;
-    let ust_asset = Asset {
-        info: AssetInfo::NativeToken {
-            denom: config.usdc_ibc_symbol.clone()
-        },
-        amount,
-    };
     //let tax = ust_asset.compute_tax(&deps.querier)?;
     let swap_message = AstroPortExecute::Swap {
         offer_asset: ust_asset.clone(),
-        belief_price: None,
+        belief_price: Some(belief_price),
         max_spread: max_spread,
         to: Option::from(env.contract.address.to_string()),
     };
@@ -1166,7 +2613,7 @@ pub fn swap(
     //let final_amount = ust_asset.amount.clone().add(swap_fee).add(tax);
     let final_amount = ust_asset.amount.clone().add(swap_fee);
 
-    let submsg = SubMsg::reply_on_success(
+    let submsg = SubMsg::reply_always(
         CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: config.astro_proxy_address.to_string(),
             msg: to_binary(&swap_message)?,
@@ -1177,7 +2624,342 @@ pub fn swap(
         }),
         pool_id.parse::<u64>().unwrap(),
     );
-    return Ok(Response::new().add_submessage(submsg).add_attribute("fury_balance_pre_swap", current_fury_balance.balance.to_string()));
+    return Ok(Response::new()
+        .add_submessage(submsg)
+        .add_attribute("fury_balance_pre_swap", current_fury_balance.balance.to_string())
+        .add_attribute("min_fury_expected", min_fury_expected.to_string()));
+}
+
+// Simulates the swap through the proxy and returns the belief price along
+// with the least amount to be received for the max spread
+fn simulate_swap(
+    deps: &DepsMut,
+    config: &Config,
+    offer_asset: Asset,
+    max_spread: Option<Decimal>,
+) -> Result<(Decimal, Uint128), ContractError> {
+    let max_spread = max_spread.unwrap_or_else(|| Decimal::percent(DEFAULT_MAX_SPREAD_PERCENT));
+    if max_spread > Decimal::one() {
+        return Err(ContractError::InvalidMaxSpread {});
+    }
+    let offer_amount = offer_asset.amount;
+    let simulation: SimulationResponse = deps.querier.query_wasm_smart(
+        config.astro_proxy_address.clone(),
+        &QueryMsgSimulation::Simulation { offer_asset },
+    )?;
+    if simulation.return_amount.is_zero() {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Swap simulation returned nothing"),
+        }));
+    }
+    let belief_price = Decimal::from_ratio(offer_amount, simulation.return_amount);
+    let min_receive = simulation.return_amount * (Decimal::one() - max_spread);
+    Ok((belief_price, min_receive))
+}
+
+pub fn set_challenge_params(
+    deps: DepsMut,
+    info: MessageInfo,
+    min_stake: Uint128,
+    max_stake: Uint128,
+    rake_percentage: Uint128,
+    wallet_percentages: Vec<WalletPercentage>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    if min_stake.is_zero() || min_stake > max_stake {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Invalid stake range"),
+        }));
+    }
+    if rake_percentage > Uint128::from(HUNDRED_PERCENT) {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Rake cannot be more than the pot"),
+        }));
+    }
+    // The whole rake goes to the rake wallets, none is left in the contract
+    let mut total_percentage = 0u32;
+    for wp in wallet_percentages.iter() {
+        total_percentage += wp.percentage;
+    }
+    let no_rake = rake_percentage.is_zero() && wallet_percentages.is_empty();
+    if total_percentage != 100 && !no_rake {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Rake wallet percentages must add up to 100"),
+        }));
+    }
+    CHALLENGE_PARAMS.save(
+        deps.storage,
+        &ChallengeParams {
+            min_stake,
+            max_stake,
+            rake_percentage,
+            rake_list: wallet_percentages,
+        },
+    )?;
+    return Ok(Response::default());
+}
+
+// The stake is already with the contract as it comes through a CW20 Send
+fn create_challenge(
+    deps: DepsMut,
+    env: Env,
+    creator: String,
+    stake: Uint128,
+    command: CreateChallengeCommand,
+) -> Result<Response, ContractError> {
+    check_activation_status(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+    let game_details = query_game_details(deps.storage)?;
+    if game_details.game_status != GAME_POOL_OPEN {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Game is not open for bidding"),
+        }));
+    }
+    let opponent = deps.api.addr_validate(&command.opponent)?;
+    if opponent.to_string() == creator {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Cannot challenge yourself"),
+        }));
+    }
+    let params = CHALLENGE_PARAMS.may_load(deps.storage)?.ok_or_else(|| {
+        ContractError::Std(StdError::GenericErr {
+            msg: String::from("Challenges are not enabled"),
+        })
+    })?;
+    if stake < params.min_stake || stake > params.max_stake {
+        return Err(ContractError::InvalidStake {
+            min_stake: params.min_stake,
+            max_stake: params.max_stake,
+        });
+    }
+    let creator_wager = check_and_record_wager(deps.storage, &env.block, &creator, &config.game_id, stake)?;
+
+    let count = CHALLENGE_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    CHALLENGE_COUNT.save(deps.storage, &count)?;
+    let challenge_id = count.to_string();
+    CHALLENGE_DETAILS.save(
+        deps.storage,
+        challenge_id.clone(),
+        &ChallengeDetails {
+            challenge_id: challenge_id.clone(),
+            game_id: config.game_id,
+            creator: creator.clone(),
+            creator_team_id: command.team_id,
+            opponent: opponent.to_string(),
+            opponent_team_id: None,
+            stake,
+            challenge_status: CHALLENGE_OPEN,
+            winner: None,
+            creator_reclaimed: false,
+            opponent_reclaimed: false,
+            creator_wager,
+            opponent_wager: None,
+        },
+    )?;
+    return Ok(Response::new()
+        .add_attribute("challenge_id", challenge_id.clone())
+        .add_attribute("creator", creator)
+        .add_attribute("opponent", opponent.to_string())
+        .add_attribute("stake", stake.to_string())
+        .set_data(to_binary(&challenge_id)?));
+}
+
+fn accept_challenge(
+    deps: DepsMut,
+    env: Env,
+    sender: String,
+    stake: Uint128,
+    command: AcceptChallengeCommand,
+) -> Result<Response, ContractError> {
+    check_activation_status(deps.storage)?;
+    let game_details = query_game_details(deps.storage)?;
+    if game_details.game_status != GAME_POOL_OPEN {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Game is not open for bidding"),
+        }));
+    }
+    let mut challenge = CHALLENGE_DETAILS.load(deps.storage, command.challenge_id.clone())?;
+    if challenge.opponent != sender {
+        return Err(ContractError::Unauthorized { invoker: sender });
+    }
+    if challenge.challenge_status != CHALLENGE_OPEN {
+        return Err(ContractError::InvalidChallengeStatus {
+            challenge_id: command.challenge_id,
+            action: String::from("accepted"),
+        });
+    }
+    if stake != challenge.stake {
+        return Err(ContractError::InvalidStake {
+            min_stake: challenge.stake,
+            max_stake: challenge.stake,
+        });
+    }
+    challenge.opponent_wager = check_and_record_wager(deps.storage, &env.block, &sender, &challenge.game_id, stake)?;
+    challenge.opponent_team_id = Some(command.team_id);
+    challenge.challenge_status = CHALLENGE_ACCEPTED;
+    CHALLENGE_DETAILS.save(deps.storage, command.challenge_id.clone(), &challenge)?;
+    return Ok(Response::new()
+        .add_attribute("challenge_id", command.challenge_id)
+        .add_attribute("opponent", sender));
+}
+
+pub fn settle_challenge(
+    deps: DepsMut,
+    info: MessageInfo,
+    challenge_id: String,
+    winner: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    let game_details = query_game_details(deps.storage)?;
+    if game_details.game_status != GAME_COMPLETED {
+        return Err(ContractError::Std(StdError::GenericErr {
+            msg: String::from("Challenges are settled once the game results are in"),
+        }));
+    }
+    let mut challenge = CHALLENGE_DETAILS.load(deps.storage, challenge_id.clone())?;
+    if challenge.challenge_status != CHALLENGE_ACCEPTED {
+        return Err(ContractError::InvalidChallengeStatus {
+            challenge_id,
+            action: String::from("settled"),
+        });
+    }
+
+    let mut wallet_transfer_details: Vec<WalletTransferDetails> = Vec::new();
+    match winner.clone() {
+        Some(winner) => {
+            if winner != challenge.creator && winner != challenge.opponent {
+                return Err(ContractError::Std(StdError::GenericErr {
+                    msg: String::from("Winner is not part of the challenge"),
+                }));
+            }
+            let params = CHALLENGE_PARAMS.load(deps.storage)?;
+            let pot = challenge.stake.checked_add(challenge.stake).map_err(StdError::from)?;
+            let rake_amount = pot
+                .checked_mul(params.rake_percentage)
+                .map_err(StdError::from)?
+                .checked_div(Uint128::from(HUNDRED_PERCENT))
+                .map_err(StdError::from)?;
+            let winner_amount = pot.checked_sub(rake_amount).map_err(StdError::from)?;
+            // The last rake wallet takes what is left of the rake after rounding
+            let mut rake_left = rake_amount;
+            let wallets_count = params.rake_list.len();
+            for (index, wallet) in params.rake_list.into_iter().enumerate() {
+                let mut proportionate_amount = rake_amount
+                    .checked_mul(Uint128::from(wallet.percentage))
+                    .unwrap_or_default()
+                    .checked_div(Uint128::from(100u128))
+                    .unwrap_or_default();
+                if index + 1 == wallets_count {
+                    proportionate_amount = rake_left;
+                }
+                rake_left = rake_left.checked_sub(proportionate_amount).map_err(StdError::from)?;
+                wallet_transfer_details.push(WalletTransferDetails {
+                    wallet_address: wallet.wallet_address,
+                    amount: proportionate_amount,
+                });
+            }
+            wallet_transfer_details.push(WalletTransferDetails {
+                wallet_address: winner,
+                amount: winner_amount,
+            });
+        }
+        None => {
+            // A draw gives each side its stake back
+            wallet_transfer_details.push(WalletTransferDetails {
+                wallet_address: challenge.creator.clone(),
+                amount: challenge.stake,
+            });
+            wallet_transfer_details.push(WalletTransferDetails {
+                wallet_address: challenge.opponent.clone(),
+                amount: challenge.stake,
+            });
+        }
+    }
+    challenge.challenge_status = CHALLENGE_SETTLED;
+    challenge.winner = winner.clone();
+    CHALLENGE_DETAILS.save(deps.storage, challenge_id.clone(), &challenge)?;
+
+    let mut rsp = Response::new();
+    for wallet in wallet_transfer_details {
+        if wallet.amount.is_zero() {
+            continue;
+        }
+        rsp = rsp.add_message(fury_transfer_msg(config.minting_contract_address.as_str(), wallet.wallet_address, wallet.amount)?);
+    }
+    return Ok(rsp
+        .add_attribute("challenge_id", challenge_id)
+        .add_attribute("winner", winner.unwrap_or_default()));
+}
+
+pub fn reclaim_challenge_stake(
+    deps: DepsMut,
+    info: MessageInfo,
+    challenge_id: String,
+) -> Result<Response, ContractError> {
+    check_claims_activation_status(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+    let game_details = query_game_details(deps.storage)?;
+    let mut challenge = CHALLENGE_DETAILS.load(deps.storage, challenge_id.clone())?;
+    let sender = info.sender.to_string();
+
+    // An open challenge can be reclaimed once the game is locked without
+    // an acceptance, any challenge not yet settled can be reclaimed when
+    // the game is cancelled
+    let reclaimable = match challenge.challenge_status {
+        CHALLENGE_OPEN => game_details.game_status != GAME_POOL_OPEN,
+        CHALLENGE_ACCEPTED => game_details.game_status == GAME_CANCELLED,
+        _ => false,
+    };
+    if !reclaimable {
+        return Err(ContractError::InvalidChallengeStatus {
+            challenge_id,
+            action: String::from("reclaimed"),
+        });
+    }
+    let wager = if sender == challenge.creator && !challenge.creator_reclaimed {
+        challenge.creator_reclaimed = true;
+        challenge.creator_wager.clone()
+    } else if sender == challenge.opponent
+        && challenge.challenge_status == CHALLENGE_ACCEPTED
+        && !challenge.opponent_reclaimed {
+        challenge.opponent_reclaimed = true;
+        challenge.opponent_wager.clone()
+    } else {
+        return Err(ContractError::Unauthorized { invoker: sender });
+    };
+    // The stake given back no longer counts against the limits
+    if let Some(wager) = &wager {
+        release_wager(deps.storage, &sender, &challenge.game_id, wager)?;
+    }
+    CHALLENGE_DETAILS.save(deps.storage, challenge_id.clone(), &challenge)?;
+
+    return Ok(Response::new()
+        .add_message(fury_transfer_msg(config.minting_contract_address.as_str(), sender.clone(), challenge.stake)?)
+        .add_attribute("challenge_id", challenge_id)
+        .add_attribute("reclaimed_by", sender)
+        .add_attribute("amount", challenge.stake.to_string()));
+}
+
+fn fury_transfer_msg(
+    minting_contract_address: &str,
+    recipient: String,
+    amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: minting_contract_address.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient, amount })?,
+        funds: vec![],
+    }))
 }
 
 pub fn execute_sweep(
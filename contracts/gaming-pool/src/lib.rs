@@ -5,6 +5,7 @@ pub mod contract;
 pub mod enumerable;
 mod error;
 pub mod msg;
+pub mod responsible_gaming;
 pub mod state;
 mod testing;
 mod execute;
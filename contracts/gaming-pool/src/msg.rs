@@ -4,10 +4,10 @@ use cosmwasm_std::{Addr, Binary, Coin, Decimal, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cw20::{Cw20ReceiveMsg, Logo};
+use cw20::{Cw20ReceiveMsg, Expiration, Logo};
 
 use crate::ContractError;
-use crate::state::{GameResult, SwapBalanceDetails, WalletPercentage};
+use crate::state::{GameResult, LineupPlayer, SwapBalanceDetails, WageringLimits, WalletPercentage};
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMarketingInfo {
@@ -45,18 +45,35 @@ pub enum ExecuteMsg {
         max_teams_for_pool: u32,
         max_teams_for_gamer: u32,
         wallet_percentages: Vec<WalletPercentage>,
+        /// Asset the pool fee is priced and paid in, FURY priced in USDC when not set
+        entry_asset: Option<terraswap::asset::AssetInfo>,
+    },
+    /// Allows or disallows a CW20 token or native denom as entry asset of pool types
+    SetEntryAssetWhitelist {
+        asset_info: terraswap::asset::AssetInfo,
+        whitelisted: bool,
+    },
+    /// Sets the TWAP window, of at most a week, bids and payouts are priced
+    /// at. None goes back to the spot price
+    SetPriceTwapWindow {
+        window_secs: Option<u64>,
     },
     CancelGame {},
-    LockGame {},
+    /// Closes the game for bidding and swaps the FURY of the pools which
+    /// reached the min teams. Pools short of teams are left for refund.
+    LockGame {
+        max_spread: Option<Decimal>,
+    },
     CreatePool {
         pool_type: String
     },
     ClaimReward {
-        gamer: String
+        gamer: String,
+        pool_ids: Option<Vec<String>>,
     },
     ClaimRefund {
         gamer: String,
-        max_spread: Option<Decimal>,
+        pool_ids: Option<Vec<String>>,
     },
     GamePoolRewardDistribute {
         pool_id: String,
@@ -66,16 +83,114 @@ pub enum ExecuteMsg {
         game_id: String,
 
     },
+    /// Places a bid in the given pool, or in an open pool of the
+    /// pool type when no pool id is given
     GamePoolBidSubmitCommand {
         gamer: String,
         pool_type: String,
-        pool_id: String,
+        pool_id: Option<String>,
         team_id: String,
         amount: Uint128,
+    },
+    /// Replaces the lineup and the lineup hash of a team while the game is open
+    UpdateTeam {
+        gamer: String,
+        pool_id: String,
+        team_id: String,
+        lineup: Vec<LineupPlayer>,
+        lineup_hash: Option<String>,
+    },
+    /// Takes a team out of the pool while the game is open for a full refund
+    WithdrawTeam {
+        gamer: String,
+        pool_id: String,
+        team_id: String,
         max_spread: Option<Decimal>,
-
+    },
+    /// Reveals the lineup committed with the lineup hash once the game is locked
+    RevealLineup {
+        gamer: String,
+        pool_id: String,
+        team_id: String,
+        lineup: Vec<LineupPlayer>,
+        salt: String,
+    },
+    SetChallengeParams {
+        min_stake: Uint128,
+        max_stake: Uint128,
+        rake_percentage: Uint128,
+        wallet_percentages: Vec<WalletPercentage>,
+    },
+    /// Pays the pot minus rake to the winner, a draw gives back both stakes
+    SettleChallenge {
+        challenge_id: String,
+        winner: Option<String>,
+    },
+    /// Takes back the stake of a challenge which was never accepted
+    /// before the game got locked, or of any challenge in a cancelled game
+    ReclaimChallengeStake {
+        challenge_id: String,
+    },
+    /// Opens a season, the points posted with the game results and a
+    /// slice of the rake go to it till it is closed
+    StartSeason {
+        season_id: String,
+        rake_percentage: u32,
+        payout_percentages: Vec<u32>,
+    },
+    /// Pays the prize pot of the current season to the top gamers
+    CloseSeason {},
+    SetReferralPercentage {
+        percentage: u32,
+    },
+    /// Registers the referrer of the sender, can only be done once
+    RegisterReferrer {
+        referrer: String,
+    },
+    /// Pays the sender the UST earned from the rake of referred gamers
+    ClaimReferralRewards {},
+    /// Pauses or resumes bids and pool creation. Claims and refunds stay
+    /// open unless paused on their own through the claims status.
+    SetActivationStatus {
+        activation_status: bool,
+        claims_activation_status: Option<bool>,
+    },
+    /// Turns the whitelist restriction check of the cw20 token on or off
+    SetWhitelistCheck {
+        enabled: bool,
     },
     Sweep { funds: Vec<Coin> },
+    /// Sets the FURY caps of every wallet, the rolling window length and
+    /// the cooldown before self imposed limits can be loosened
+    SetWageringLimits {
+        limits: WageringLimits,
+        window_seconds: u64,
+        cooldown_seconds: u64,
+    },
+    /// Sets the limits of the sender on their own bids
+    SetSelfImposedLimits {
+        limits: WageringLimits,
+    },
+    /// Blocks the bids of the sender for the given duration
+    SelfExclude {
+        duration_seconds: u64,
+    },
+    /// Places a bid with FURY sent through a CW20 Send, the message
+    /// must be a [`ReceivedMsg`]
+    Receive(Cw20ReceiveMsg),
+    /// Lets the spender bid with a budget of FURY, claim refunds and
+    /// claim rewards on behalf of the sender till the expiry
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Lowers the budget of the spender, removing it when it hits zero
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
     Swap {
         amount: Uint128,
         pool_id: String,
@@ -130,6 +245,58 @@ pub enum QueryMsg {
     GetTotalFees {
         amount: Uint128
     },
+    GamerPools {
+        gamer: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    ChallengeDetails {
+        challenge_id: String,
+    },
+    ChallengeParams {},
+    SeasonDetails {
+        season_id: String,
+    },
+    /// Returns the standings of the season, highest points first
+    SeasonLeaderboard {
+        season_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    Referrer {
+        gamer: String,
+    },
+    ReferralStats {
+        referrer: String,
+    },
+    ActivationStatus {},
+    /// Returns the limits of the gamer and the FURY left under each of them
+    WageringLimits {
+        gamer: String,
+    },
+    /// Returns the logged events of the game, oldest first
+    GameEvents {
+        game_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the logged events of the pool, oldest first
+    PoolEvents {
+        pool_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the budget and the expiry of the manager for the gamer
+    Allowance {
+        owner: String,
+        spender: String,
+    },
+    /// Returns the managers of the gamer with their budgets
+    AllAllowances {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 
@@ -137,13 +304,29 @@ pub enum QueryMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ReceivedMsg {
     GamePoolBidSubmit(GamePoolBidSubmitCommand),
+    CreateChallenge(CreateChallengeCommand),
+    AcceptChallenge(AcceptChallengeCommand),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GamePoolBidSubmitCommand {
     pub gamer: String,
     pub pool_type: String,
-    pub pool_id: String,
+    pub pool_id: Option<String>,
+    pub team_id: String,
+}
+
+/// Opens a head to head challenge, the FURY sent is the stake
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreateChallengeCommand {
+    pub opponent: String,
+    pub team_id: String,
+}
+
+/// Accepts a head to head challenge, the FURY sent must match the stake
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AcceptChallengeCommand {
+    pub challenge_id: String,
     pub team_id: String,
 }
 
@@ -151,9 +334,11 @@ pub struct GamePoolBidSubmitCommand {
 pub enum ProxyQueryMsgs {
     get_fury_equivalent_to_ust {
         ust_count: Uint128,
+        twap_window_secs: Option<u64>,
     },
     get_ust_equivalent_to_fury {
         fury_count: Uint128,
+        twap_window_secs: Option<u64>,
     },
 }
 
@@ -206,3 +391,21 @@ pub enum QueryMsgSimulation {
 pub struct BalanceResponse {
     pub balance: Uint128,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ActivationStatusResponse {
+    pub activation_status: bool,
+    pub claims_activation_status: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WageringLimitsResponse {
+    /// The stricter of the compliance and the self imposed limits
+    pub limits: WageringLimits,
+    /// FURY left for the current game, none when not capped
+    pub remaining_for_game: Option<Uint128>,
+    pub remaining_for_day: Option<Uint128>,
+    pub remaining_for_window: Option<Uint128>,
+    pub excluded_until: u64,
+    pub loosen_allowed_at: u64,
+}
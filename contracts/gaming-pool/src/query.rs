@@ -1,9 +1,15 @@
 use cosmwasm_std::{Deps, Order, StdError, StdResult, Storage, Uint128};
+use cw_storage_plus::{Bound, U64Key};
 
 use crate::contract::{DUMMY_WALLET, INITIAL_TEAM_POINTS, INITIAL_TEAM_RANK,
                       UNCLAIMED_REFUND, UNCLAIMED_REWARD};
-use crate::execute::query_platform_fees;
-use crate::state::{CONFIG, FeeDetails, GAME_DETAILS, GAME_RESULT_DUMMY, GameDetails, GameResult, POOL_DETAILS, POOL_TEAM_DETAILS, POOL_TYPE_DETAILS, PoolDetails, PoolTeamDetails, PoolTypeDetails, SWAP_BALANCE_INFO, SwapBalanceDetails};
+use crate::execute::{query_platform_fees, season_rank_key};
+use crate::msg::ActivationStatusResponse;
+use crate::state::{ACTIVATION_STATUS, CHALLENGE_DETAILS, CHALLENGE_PARAMS, ChallengeDetails, ChallengeParams, CLAIMS_ACTIVATION_STATUS, CONFIG, FeeDetails, GAME_DETAILS, GAME_EVENTS, GAME_RESULT_DUMMY, GAMER_POOLS, GameDetails, GameEventRecord, GameResult, POOL_DETAILS, POOL_EVENTS, POOL_TEAM_DETAILS, POOL_TYPE_DETAILS, PoolDetails, PoolTeamDetails, PoolTypeDetails, REFERRAL_STATS, ReferralStats, REFERRERS, SEASON_DETAILS, SEASON_LEADERBOARD, SEASON_POINTS, SeasonDetails, SeasonStanding, SWAP_BALANCE_INFO, SwapBalanceDetails};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
 
 pub fn query_pool_type_details(
     storage: &dyn Storage,
@@ -76,11 +82,8 @@ pub fn query_all_teams(storage: &dyn Storage, users: Vec<String>) -> StdResult<V
 
 pub fn query_reward(storage: &dyn Storage, gamer: String) -> StdResult<Uint128> {
     let mut user_reward = Uint128::zero();
-    // Get all pools
-    let all_pools: Vec<String> = POOL_DETAILS
-        .keys(storage, None, None, Order::Ascending)
-        .map(|k| String::from_utf8(k).unwrap())
-        .collect();
+    // Get all pools joined by the gamer
+    let all_pools = query_gamer_pool_ids(storage, &gamer, None)?;
     for pool_id in all_pools {
         // Get the existing teams for this pool
         let mut teams = Vec::new();
@@ -102,11 +105,8 @@ pub fn query_reward(storage: &dyn Storage, gamer: String) -> StdResult<Uint128>
 
 pub fn query_refund(storage: &dyn Storage, gamer: String) -> StdResult<Uint128> {
     let mut user_refund = Uint128::zero();
-    // Get all pools
-    let all_pools: Vec<String> = POOL_DETAILS
-        .keys(storage, None, None, Order::Ascending)
-        .map(|k| String::from_utf8(k).unwrap())
-        .collect();
+    // Get all pools joined by the gamer
+    let all_pools = query_gamer_pool_ids(storage, &gamer, None)?;
     for pool_id in all_pools {
         let mut pool_details: PoolDetails = Default::default();
         let pd = POOL_DETAILS.load(storage, pool_id.clone());
@@ -300,3 +300,143 @@ pub fn query_swap_data_for_pool(
     let info = SWAP_BALANCE_INFO.load(storage, pool_id)?;
     return Ok(info)
 }
+
+/// Returns the pool ids to look at for a gamer. When no pool ids are given
+/// all the pools joined by the gamer are returned.
+pub fn query_gamer_pool_ids(
+    storage: &dyn Storage,
+    gamer: &str,
+    pool_ids: Option<Vec<String>>,
+) -> StdResult<Vec<String>> {
+    match pool_ids {
+        Some(mut pool_ids) => {
+            pool_ids.sort();
+            pool_ids.dedup();
+            Ok(pool_ids)
+        }
+        None => GAMER_POOLS
+            .prefix(gamer)
+            .keys(storage, None, None, Order::Ascending)
+            .map(|k| Ok(String::from_utf8(k)?))
+            .collect(),
+    }
+}
+
+pub fn query_gamer_pools(
+    storage: &dyn Storage,
+    gamer: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PoolDetails>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let pool_ids: StdResult<Vec<String>> = GAMER_POOLS
+        .prefix(&gamer)
+        .keys(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|k| Ok(String::from_utf8(k)?))
+        .collect();
+    let mut all_pool_details = Vec::new();
+    for pool_id in pool_ids? {
+        all_pool_details.push(POOL_DETAILS.load(storage, pool_id)?);
+    }
+    return Ok(all_pool_details);
+}
+
+pub fn query_challenge_details(storage: &dyn Storage, challenge_id: String) -> StdResult<ChallengeDetails> {
+    let cd = CHALLENGE_DETAILS.may_load(storage, challenge_id)?;
+    match cd {
+        Some(cd) => return Ok(cd),
+        None => return Err(StdError::generic_err("No challenge details found")),
+    };
+}
+
+pub fn query_challenge_params(storage: &dyn Storage) -> StdResult<ChallengeParams> {
+    let cp = CHALLENGE_PARAMS.may_load(storage)?;
+    match cp {
+        Some(cp) => return Ok(cp),
+        None => return Err(StdError::generic_err("Challenges are not enabled")),
+    };
+}
+
+pub fn query_season_details(storage: &dyn Storage, season_id: String) -> StdResult<SeasonDetails> {
+    let sd = SEASON_DETAILS.may_load(storage, season_id)?;
+    match sd {
+        Some(sd) => return Ok(sd),
+        None => return Err(StdError::generic_err("No season details found")),
+    };
+}
+
+pub fn query_season_leaderboard(
+    storage: &dyn Storage,
+    season_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<SeasonStanding>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut start = None;
+    if let Some(gamer) = start_after {
+        let points = SEASON_POINTS.load(storage, (&season_id, &gamer))?;
+        start = Some(Bound::exclusive(season_rank_key(points, &gamer)));
+    }
+
+    SEASON_LEADERBOARD
+        .prefix(&season_id)
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| Ok(item?.1))
+        .collect()
+}
+
+pub fn query_referrer(storage: &dyn Storage, gamer: String) -> StdResult<Option<String>> {
+    REFERRERS.may_load(storage, &gamer)
+}
+
+pub fn query_referral_stats(storage: &dyn Storage, referrer: String) -> StdResult<ReferralStats> {
+    Ok(REFERRAL_STATS.may_load(storage, &referrer)?.unwrap_or_default())
+}
+
+pub fn query_activation_status(storage: &dyn Storage) -> StdResult<ActivationStatusResponse> {
+    Ok(ActivationStatusResponse {
+        activation_status: ACTIVATION_STATUS.may_load(storage)?.unwrap_or(true),
+        claims_activation_status: CLAIMS_ACTIVATION_STATUS.may_load(storage)?.unwrap_or(true),
+    })
+}
+
+pub fn query_game_events(
+    storage: &dyn Storage,
+    game_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<GameEventRecord>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    GAME_EVENTS
+        .prefix(&game_id)
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| Ok(item?.1))
+        .collect()
+}
+
+pub fn query_pool_events(
+    storage: &dyn Storage,
+    pool_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<GameEventRecord>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    POOL_EVENTS
+        .prefix(&pool_id)
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (event_id, game_id) = item?;
+            GAME_EVENTS.load(storage, (&game_id, U64Key::from(event_id)))
+        })
+        .collect()
+}
@@ -0,0 +1,233 @@
+//! Responsible gaming. Compliance caps the FURY a wallet can enter per game,
+//! per day and per rolling window. Gamers can put stricter limits on
+//! themselves or exclude themselves for a while, and can only loosen their
+//! own limits once the cooldown since their last change has passed.
+
+use cosmwasm_std::{attr, BlockInfo, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128};
+
+use crate::error::ContractError;
+use crate::msg::WageringLimitsResponse;
+use crate::state::{CONFIG, GAME_WAGERED, RECENT_WAGERS, RESPONSIBLE_GAMING_CONFIG, ResponsibleGamingConfig,
+                   SELF_IMPOSED_LIMITS, WagerEntry, WageringLimits};
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+pub fn execute_set_wagering_limits(
+    deps: DepsMut,
+    info: MessageInfo,
+    limits: WageringLimits,
+    window_seconds: u64,
+    cooldown_seconds: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {
+            invoker: info.sender.to_string(),
+        });
+    }
+    RESPONSIBLE_GAMING_CONFIG.save(
+        deps.storage,
+        &ResponsibleGamingConfig {
+            limits,
+            window_seconds,
+            cooldown_seconds,
+        },
+    )?;
+    Ok(Response::new().add_attribute("action", "set_wagering_limits"))
+}
+
+pub fn execute_set_self_imposed_limits(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limits: WageringLimits,
+) -> Result<Response, ContractError> {
+    let gamer = info.sender.to_string();
+    let now = env.block.time.seconds();
+    let config = RESPONSIBLE_GAMING_CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    let mut self_limits = SELF_IMPOSED_LIMITS.may_load(deps.storage, &gamer)?.unwrap_or_default();
+    if loosens(&self_limits.limits, &limits) && now < self_limits.loosen_allowed_at {
+        return Err(ContractError::LimitCooldownActive {
+            until: self_limits.loosen_allowed_at,
+        });
+    }
+    self_limits.limits = limits;
+    self_limits.loosen_allowed_at = now.saturating_add(config.cooldown_seconds);
+    SELF_IMPOSED_LIMITS.save(deps.storage, &gamer, &self_limits)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_self_imposed_limits"),
+        attr("gamer", gamer),
+        attr("loosen_allowed_at", self_limits.loosen_allowed_at.to_string()),
+    ]))
+}
+
+// An exclusion can only be extended, never shortened
+pub fn execute_self_exclude(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    duration_seconds: u64,
+) -> Result<Response, ContractError> {
+    let gamer = info.sender.to_string();
+    let mut self_limits = SELF_IMPOSED_LIMITS.may_load(deps.storage, &gamer)?.unwrap_or_default();
+    let excluded_until = env.block.time.seconds().saturating_add(duration_seconds);
+    if excluded_until > self_limits.excluded_until {
+        self_limits.excluded_until = excluded_until;
+    }
+    SELF_IMPOSED_LIMITS.save(deps.storage, &gamer, &self_limits)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "self_exclude"),
+        attr("gamer", gamer),
+        attr("excluded_until", self_limits.excluded_until.to_string()),
+    ]))
+}
+
+/// Rejects the bid if the gamer is excluded or the FURY entered goes over
+/// one of the limits, else books it against the limits and returns the
+/// booked entry. Bids entering no FURY are only checked for exclusion.
+pub fn check_and_record_wager(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    gamer: &str,
+    game_id: &str,
+    amount: Uint128,
+) -> Result<Option<WagerEntry>, ContractError> {
+    let usage = query_wagering_limits(storage, block, gamer.to_string())?;
+    let now = block.time.seconds();
+    if now < usage.excluded_until {
+        return Err(ContractError::SelfExcluded {
+            until: usage.excluded_until,
+        });
+    }
+    if amount.is_zero() {
+        return Ok(None);
+    }
+    for (limit, remaining) in [
+        ("game", usage.remaining_for_game),
+        ("day", usage.remaining_for_day),
+        ("window", usage.remaining_for_window),
+    ] {
+        if let Some(remaining) = remaining {
+            if amount > remaining {
+                return Err(ContractError::WageringLimitExceeded {
+                    limit: limit.to_string(),
+                    remaining,
+                });
+            }
+        }
+    }
+
+    let wagered = GAME_WAGERED.may_load(storage, (gamer, game_id))?.unwrap_or_default();
+    GAME_WAGERED.save(storage, (gamer, game_id), &(wagered + amount))?;
+    let mut wagers = recent_wagers(storage, block, gamer)?;
+    let wager = WagerEntry { time: now, amount };
+    wagers.push(wager.clone());
+    RECENT_WAGERS.save(storage, gamer, &wagers)?;
+    Ok(Some(wager))
+}
+
+/// Takes a booked bid off the limits of the gamer once its team is
+/// withdrawn or refunded. Entries which already left the day and the
+/// window are gone and need no change.
+pub fn release_wager(
+    storage: &mut dyn Storage,
+    gamer: &str,
+    game_id: &str,
+    wager: &WagerEntry,
+) -> StdResult<()> {
+    if let Some(wagered) = GAME_WAGERED.may_load(storage, (gamer, game_id))? {
+        GAME_WAGERED.save(storage, (gamer, game_id), &wagered.saturating_sub(wager.amount))?;
+    }
+    let mut wagers = RECENT_WAGERS.may_load(storage, gamer)?.unwrap_or_default();
+    if let Some(position) = wagers
+        .iter()
+        .position(|entry| entry.time == wager.time && entry.amount >= wager.amount)
+    {
+        wagers[position].amount -= wager.amount;
+        if wagers[position].amount.is_zero() {
+            wagers.remove(position);
+        }
+        RECENT_WAGERS.save(storage, gamer, &wagers)?;
+    }
+    Ok(())
+}
+
+/// Returns the stricter of the compliance and the self imposed limits of the
+/// gamer along with the FURY the gamer can still enter under each of them
+pub fn query_wagering_limits(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    gamer: String,
+) -> StdResult<WageringLimitsResponse> {
+    let game_id = CONFIG.load(storage)?.game_id;
+    let config = RESPONSIBLE_GAMING_CONFIG.may_load(storage)?.unwrap_or_default();
+    let self_limits = SELF_IMPOSED_LIMITS.may_load(storage, &gamer)?.unwrap_or_default();
+    let limits = WageringLimits {
+        per_game: stricter(config.limits.per_game, self_limits.limits.per_game),
+        per_day: stricter(config.limits.per_day, self_limits.limits.per_day),
+        per_window: stricter(config.limits.per_window, self_limits.limits.per_window),
+    };
+
+    let now = block.time.seconds();
+    let day_start = now - now % SECONDS_PER_DAY;
+    let mut day_wagered = Uint128::zero();
+    let mut window_wagered = Uint128::zero();
+    for wager in recent_wagers(storage, block, &gamer)? {
+        if wager.time >= day_start {
+            day_wagered += wager.amount;
+        }
+        if wager.time + config.window_seconds > now {
+            window_wagered += wager.amount;
+        }
+    }
+    let game_wagered = GAME_WAGERED.may_load(storage, (&gamer, &game_id))?.unwrap_or_default();
+
+    Ok(WageringLimitsResponse {
+        remaining_for_game: remaining(limits.per_game, game_wagered),
+        remaining_for_day: remaining(limits.per_day, day_wagered),
+        remaining_for_window: remaining(limits.per_window, window_wagered),
+        limits,
+        excluded_until: self_limits.excluded_until,
+        loosen_allowed_at: self_limits.loosen_allowed_at,
+    })
+}
+
+// The bids of the gamer which still count towards the day or the window
+fn recent_wagers(storage: &dyn Storage, block: &BlockInfo, gamer: &str) -> StdResult<Vec<WagerEntry>> {
+    let now = block.time.seconds();
+    let window_seconds = RESPONSIBLE_GAMING_CONFIG.may_load(storage)?.unwrap_or_default().window_seconds;
+    let oldest = (now - now % SECONDS_PER_DAY).min(now.saturating_sub(window_seconds));
+    Ok(RECENT_WAGERS
+        .may_load(storage, gamer)?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|wager| wager.time >= oldest)
+        .collect())
+}
+
+// Zero stands for no limit
+fn stricter(a: Uint128, b: Uint128) -> Uint128 {
+    if a.is_zero() {
+        return b;
+    }
+    if b.is_zero() {
+        return a;
+    }
+    a.min(b)
+}
+
+fn remaining(limit: Uint128, used: Uint128) -> Option<Uint128> {
+    if limit.is_zero() {
+        return None;
+    }
+    Some(limit.saturating_sub(used))
+}
+
+fn loosens(current: &WageringLimits, new: &WageringLimits) -> bool {
+    let looser = |current: Uint128, new: Uint128| !current.is_zero() && (new.is_zero() || new > current);
+    looser(current.per_game, new.per_game)
+        || looser(current.per_day, new.per_day)
+        || looser(current.per_window, new.per_window)
+}
@@ -1,9 +1,10 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Timestamp, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use terraswap::asset::{Asset, AssetInfo};
 
 use cw20::AllowanceResponse;
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Item, Map, U64Key};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -15,12 +16,17 @@ pub struct Config {
     pub transaction_fee: Uint128,
     pub game_id: String,
     pub usdc_ibc_symbol:String,
+    /// Whether the sender is checked against the whitelist restriction of the cw20 token
+    #[serde(default)]
+    pub whitelist_check_enabled: bool,
 }
 
 pub const CONFIG_KEY: &str = "config";
 pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
 // This param will enable or disable contract completely as a fail safe
 pub const ACTIVATION_STATUS: Item<bool> = Item::new("activation_status");
+// This param will enable or disable claims and refunds on their own
+pub const CLAIMS_ACTIVATION_STATUS: Item<bool> = Item::new("claims_activation_status");
 
 /// This is used for saving various vesting details
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
@@ -74,6 +80,12 @@ pub struct PoolTypeDetails {
 
     /// Rake distribution 
     pub rake_list: Vec<WalletPercentage>,
+
+    /// The asset the pool fee is priced and paid in, rewards are paid in it
+    /// as well. When not set the fee is priced in USDC and paid in FURY
+    /// which gets swapped at lock
+    #[serde(default)]
+    pub entry_asset: Option<AssetInfo>,
 }
 
 /// This is used for saving various vesting details
@@ -138,6 +150,54 @@ pub struct PoolTeamDetails {
 
     /// team rank in the pool updated after each game
     pub team_rank: u64,
+
+    /// lineup of the team, empty until set or revealed by the gamer
+    #[serde(default)]
+    pub lineup: Vec<LineupPlayer>,
+
+    /// hex encoded sha256 of the lineup and a salt, committed while the
+    /// game is open and checked against the revealed lineup at settlement
+    #[serde(default)]
+    pub lineup_hash: Option<String>,
+
+    /// whether the lineup was revealed against the committed lineup hash
+    #[serde(default)]
+    pub revealed: bool,
+
+    /// whether the team points went to the season standing of the gamer
+    #[serde(default)]
+    pub season_points_added: bool,
+
+    /// FURY held by the contract for the team from the bid, given back
+    /// as is on refund unless the pool got swapped at lock
+    #[serde(default)]
+    pub fury_deposited: Uint128,
+
+    /// pool fee held by the contract for the team in the entry asset of
+    /// the pool type, for pool types which have one
+    #[serde(default)]
+    pub entry_asset_deposited: Uint128,
+
+    /// platform and transaction fee paid with the bid and held by the
+    /// contract, in the asset it was paid in, given back in that asset on
+    /// refund. Fees forwarded to the platform fees collector at bid are
+    /// booked at zero. Unset for teams saved before it was recorded, their
+    /// fee is refunded in UST
+    #[serde(default)]
+    pub fee_paid: Option<Asset>,
+
+    /// FURY of the bid booked against the responsible gaming limits,
+    /// taken off them again when the team is withdrawn or refunded
+    #[serde(default)]
+    pub wager: Option<WagerEntry>,
+}
+
+/// This is used for saving a player picked in a team lineup
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct LineupPlayer {
+    pub player_id: String,
+    pub position: String,
 }
 
 /// This is used for saving game result details
@@ -147,6 +207,9 @@ pub struct GameResult {
     pub gamer_address: String,
     pub team_id: String,
     pub reward_amount: Uint128,
+    /// points scored by the team, added to the standings of the open season
+    #[serde(default)]
+    pub team_points: u64,
 }
 
 /// This is used for transferring tokens to multiple wallets
@@ -158,6 +221,8 @@ pub struct WalletTransferDetails {
 }
 
 
+/// Map of FURY budgets given by gamers to managers playing on their behalf.
+/// The key is (gamer, manager).
 pub const ALLOWANCES: Map<(&Addr, &Addr), AllowanceResponse> = Map::new("allowance");
 
 /// Map of games. The key is game id and the
@@ -170,6 +235,14 @@ pub const GAME_DETAILS: Map<String, GameDetails> =
 pub const POOL_TYPE_DETAILS: Map<String, PoolTypeDetails> =
     Map::new("pool_type_details");
 
+/// Assets besides FURY and USDC which pool types can take as entry asset.
+/// The key is the denom or the token contract address
+pub const ENTRY_ASSET_WHITELIST: Map<String, bool> = Map::new("entry_asset_whitelist");
+
+/// Window in seconds of the FURY TWAP used for the pool fees, the bid fees
+/// and the payouts. Unset for the spot price of the pair
+pub const PRICE_TWAP_WINDOW: Item<u64> = Item::new("price_twap_window");
+
 /// Map of pools. The key is pool id and the
 /// PoolDetails will contain information about the pool 
 pub const POOL_DETAILS: Map<String, PoolDetails> =
@@ -180,6 +253,15 @@ pub const POOL_DETAILS: Map<String, PoolDetails> =
 pub const POOL_TEAM_DETAILS: Map<(&str, &str), Vec<PoolTeamDetails>> =
     Map::new("pool_team_details");
 
+/// Index of the pools a gamer has joined. The key is (gamer, pool id) so that
+/// claims and queries only walk the pools of that gamer
+pub const GAMER_POOLS: Map<(&str, &str), bool> = Map::new("gamer_pools");
+
+/// Index of the pools still taking teams. The key is (game id, pool type,
+/// pool id) and the value is the pool id, so that bids without a pool id
+/// find the lowest open pool of their type without walking all the pools
+pub const OPEN_POOLS: Map<(&str, &str, U64Key), String> = Map::new("open_pools");
+
 pub const CONTRACT_POOL_COUNT: Map<&Addr, Uint128> = Map::new("contract_pool_count");
 
 pub const GAME_RESULT_DUMMY: Map<&Addr, GameResult> = Map::new("game_result");
@@ -196,9 +278,328 @@ pub struct SwapBalanceDetails {
     pub exchange_rate: Uint128,
     pub ust_amount_swapped: Uint128,
     pub ust_for_rake: Uint128,
+    /// FURY the swap has to give at least, from the simulation and the max spread
+    #[serde(default)]
+    pub min_fury_expected: Uint128,
+    /// Set when the swap submessage failed, the swap can be tried again
+    /// or the game cancelled for refunds
+    #[serde(default)]
+    pub swap_failed: bool,
 }
 
 // This is a simple store we use to save the balance of the contact
 // pre swap and use it to compute the amount of CW20 tokens gained
 // In the swap
 pub const SWAP_BALANCE_INFO: Map<String, SwapBalanceDetails> = Map::new("current_reward_for_pool");
+
+/// FURY of the teams swapped to UST when the game got locked, keyed by
+/// pool id. Pools missing here still hold the FURY deposited at bid.
+pub const POOL_SWAPPED_FURY: Map<String, Uint128> = Map::new("pool_swapped_fury");
+
+/// UST received for the FURY of each pool swapped at lock, keyed by pool
+/// id. Refunds of the pool pay out its teams' shares of it.
+pub const POOL_SWAPPED_UST: Map<String, Uint128> = Map::new("pool_swapped_ust");
+
+/// The swap of the pools' FURY sent at lock, kept till its reply
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct LockSwapDetails {
+    /// UST held by the contract when the game got locked
+    pub ust_balance_pre_swap: Uint128,
+    /// UST sent along with the swap as the proxy platform fee
+    pub ust_fee_paid: Uint128,
+    /// UST the swap has to give at least, from the simulation and the max spread
+    pub min_ust_expected: Uint128,
+}
+
+pub const LOCK_SWAP_INFO: Item<LockSwapDetails> = Item::new("lock_swap_info");
+
+/// This is used for saving the limits and the rake of head to head challenges
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ChallengeParams {
+    /// The min stake in FURY each side has to put in
+    pub min_stake: Uint128,
+
+    /// The max stake in FURY each side can put in
+    pub max_stake: Uint128,
+
+    /// Rake taken from the pot, 100% = 10000
+    pub rake_percentage: Uint128,
+
+    /// Rake distribution
+    pub rake_list: Vec<WalletPercentage>,
+}
+
+pub const CHALLENGE_PARAMS: Item<ChallengeParams> = Item::new("challenge_params");
+
+/// This is used for saving a head to head challenge between two gamers
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ChallengeDetails {
+    /// The challenge id
+    pub challenge_id: String,
+
+    /// The game id
+    pub game_id: String,
+
+    /// The gamer who opened the challenge
+    pub creator: String,
+
+    /// The team selected by the creator
+    pub creator_team_id: String,
+
+    /// The only gamer who can accept the challenge
+    pub opponent: String,
+
+    /// The team selected by the opponent once accepted
+    pub opponent_team_id: Option<String>,
+
+    /// The stake in FURY put in by each side
+    pub stake: Uint128,
+
+    /// Current status of the challenge - open, accepted, settled
+    pub challenge_status: u64,
+
+    /// The winner once settled, none for a draw
+    pub winner: Option<String>,
+
+    /// Whether the creator has taken back the stake
+    pub creator_reclaimed: bool,
+
+    /// Whether the opponent has taken back the stake
+    pub opponent_reclaimed: bool,
+
+    /// Stake of the creator booked against the responsible gaming limits,
+    /// taken off them again when the stake is reclaimed
+    #[serde(default)]
+    pub creator_wager: Option<WagerEntry>,
+
+    /// Stake of the opponent booked against the responsible gaming limits
+    #[serde(default)]
+    pub opponent_wager: Option<WagerEntry>,
+}
+
+/// Map of challenges. The key is challenge id and the
+/// ChallengeDetails will contain information about the challenge
+pub const CHALLENGE_DETAILS: Map<String, ChallengeDetails> = Map::new("challenge_details");
+
+pub const CHALLENGE_COUNT: Item<u64> = Item::new("challenge_count");
+
+/// This is used for saving a season across which the points of the
+/// gamers are added up
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct SeasonDetails {
+    /// The season id
+    pub season_id: String,
+
+    /// Slice of the rake of each pool which goes to the prize pot, 100 = all
+    pub rake_percentage: u32,
+
+    /// Share of the prize pot for each place in the leaderboard, the
+    /// first entry is for the top gamer
+    pub payout_percentages: Vec<u32>,
+
+    /// UST collected for the prize pot
+    pub prize_pot: Uint128,
+
+    /// Prize pot collected from the rake of pools paid in an entry asset,
+    /// one entry per asset
+    #[serde(default)]
+    pub prize_pot_assets: Vec<Asset>,
+
+    /// Whether the season got closed and paid out
+    pub closed: bool,
+}
+
+/// This is used for saving the points of a gamer in a season
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct SeasonStanding {
+    pub gamer_address: String,
+    pub points: u64,
+}
+
+/// Map of seasons. The key is season id
+pub const SEASON_DETAILS: Map<String, SeasonDetails> = Map::new("season_details");
+
+/// The season to which points and rake currently go
+pub const CURRENT_SEASON: Item<String> = Item::new("current_season");
+
+/// Map of points. The key is (season id, gamer)
+pub const SEASON_POINTS: Map<(&str, &str), u64> = Map::new("season_points");
+
+/// Leaderboard of a season ordered by points. The key is (season id,
+/// rank key) where the rank key sorts the highest points first.
+pub const SEASON_LEADERBOARD: Map<(&str, &[u8]), SeasonStanding> = Map::new("season_leaderboard");
+
+/// This is used for saving the referral earnings of a referrer
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ReferralStats {
+    /// Number of gamers who registered the referrer
+    pub referred_gamers: u32,
+
+    /// UST earned from the rake of the referred gamers so far
+    pub total_earned: Uint128,
+
+    /// UST earned but not claimed yet
+    pub unclaimed: Uint128,
+
+    /// Earned from the rake of pools paid in an entry asset but not
+    /// claimed yet, one entry per asset
+    #[serde(default)]
+    pub unclaimed_assets: Vec<Asset>,
+}
+
+/// Share of the rake of a referred gamer's team which goes to the referrer, 100 = all
+pub const REFERRAL_PERCENTAGE: Item<u32> = Item::new("referral_percentage");
+
+/// Map of referrers. The key is the gamer, set once by the gamer
+pub const REFERRERS: Map<&str, String> = Map::new("referrers");
+
+/// Map of referral earnings. The key is the referrer
+pub const REFERRAL_STATS: Map<&str, ReferralStats> = Map::new("referral_stats");
+
+/// What happened in a game, kept so that payouts can be checked later
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum GameEvent {
+    BidSubmitted {
+        pool_id: String,
+        gamer: String,
+        team_id: String,
+        amount: Uint128,
+    },
+    TeamWithdrawn {
+        pool_id: String,
+        gamer: String,
+        team_id: String,
+    },
+    GameLocked {
+        fury_swapped: Uint128,
+    },
+    GameCancelled {},
+    RewardAssigned {
+        pool_id: String,
+        gamer: String,
+        team_id: String,
+        amount: Uint128,
+    },
+    RakeDistributed {
+        pool_id: String,
+        amount: Uint128,
+    },
+    RewardClaimed {
+        pool_id: String,
+        gamer: String,
+        team_id: String,
+        amount: Uint128,
+    },
+    RefundClaimed {
+        pool_id: String,
+        gamer: String,
+        team_id: String,
+        amount: Uint128,
+    },
+}
+
+impl GameEvent {
+    /// The pool the event is about, if any
+    pub fn pool_id(&self) -> Option<&str> {
+        match self {
+            GameEvent::BidSubmitted { pool_id, .. }
+            | GameEvent::TeamWithdrawn { pool_id, .. }
+            | GameEvent::RewardAssigned { pool_id, .. }
+            | GameEvent::RakeDistributed { pool_id, .. }
+            | GameEvent::RewardClaimed { pool_id, .. }
+            | GameEvent::RefundClaimed { pool_id, .. } => Some(pool_id),
+            GameEvent::GameLocked { .. } | GameEvent::GameCancelled {} => None,
+        }
+    }
+}
+
+/// This is used for saving a game event along with when it happened
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct GameEventRecord {
+    /// Sequence number of the event in the game, starting from 1
+    pub event_id: u64,
+    pub block_height: u64,
+    pub block_time: Timestamp,
+    pub event: GameEvent,
+}
+
+/// Append only log of the events of a game. The key is (game id, event id)
+pub const GAME_EVENTS: Map<(&str, U64Key), GameEventRecord> = Map::new("game_events");
+
+/// Number of events logged per game, the last event id given out
+pub const GAME_EVENT_COUNT: Map<&str, u64> = Map::new("game_event_count");
+
+/// Index of the events of a pool. The key is (pool id, event id) and the
+/// value is the game id the event is logged under
+pub const POOL_EVENTS: Map<(&str, U64Key), String> = Map::new("pool_events");
+
+/// Caps on the FURY a wallet can enter, a zero amount means no cap
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct WageringLimits {
+    /// FURY entered in a single game
+    pub per_game: Uint128,
+
+    /// FURY entered in a calendar day (UTC)
+    pub per_day: Uint128,
+
+    /// FURY entered in the rolling window of the responsible gaming config
+    pub per_window: Uint128,
+}
+
+/// This is used for saving the wallet limits set by compliance
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ResponsibleGamingConfig {
+    /// Limits applying to every wallet
+    pub limits: WageringLimits,
+
+    /// Length of the rolling window in seconds
+    pub window_seconds: u64,
+
+    /// Seconds a gamer has to wait after changing their own limits
+    /// before they can loosen them
+    pub cooldown_seconds: u64,
+}
+
+/// This is used for saving the limits a gamer put on themselves
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct SelfImposedLimits {
+    pub limits: WageringLimits,
+
+    /// No bids are accepted till this time, in seconds
+    pub excluded_until: u64,
+
+    /// The limits can only be loosened from this time on, in seconds
+    pub loosen_allowed_at: u64,
+}
+
+/// This is used for saving the FURY of a bid entered by a gamer
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct WagerEntry {
+    /// Block time of the bid, in seconds
+    pub time: u64,
+    pub amount: Uint128,
+}
+
+pub const RESPONSIBLE_GAMING_CONFIG: Item<ResponsibleGamingConfig> = Item::new("responsible_gaming_config");
+
+/// Map of the limits gamers put on themselves. The key is the gamer
+pub const SELF_IMPOSED_LIMITS: Map<&str, SelfImposedLimits> = Map::new("self_imposed_limits");
+
+/// FURY entered per gamer in a game. The key is (gamer, game id)
+pub const GAME_WAGERED: Map<(&str, &str), Uint128> = Map::new("game_wagered");
+
+/// Bids of the gamer still within the current day or the rolling window.
+/// The key is the gamer
+pub const RECENT_WAGERS: Map<&str, Vec<WagerEntry>> = Map::new("recent_wagers");
@@ -2,15 +2,23 @@
 
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{coin, Uint128};
+    use cosmwasm_std::{attr, BankMsg, coin, ContractResult, CosmosMsg, Decimal, Env, from_binary, Order, QuerierResult, Reply, ReplyOn, StdError, SubMsgResponse, SubMsgResult, SystemResult, to_binary, Uint128, WasmMsg, WasmQuery};
     use cosmwasm_std::Addr;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-
-    use crate::contract::{CLAIMED_REWARD, GAME_CANCELLED, GAME_COMPLETED, GAME_POOL_OPEN, INITIAL_REFUND_AMOUNT, instantiate};
-    use crate::execute::{cancel_game, claim_refund, claim_reward, create_pool, game_pool_bid_submit, game_pool_reward_distribute, lock_game, save_team_details, set_platform_fee_wallets, set_pool_type_params};
-    use crate::msg::InstantiateMsg;
-    use crate::query::{get_team_count_for_user_in_pool_type, query_game_details, query_pool_details, query_team_details};
-    use crate::state::{GameResult, PLATFORM_WALLET_PERCENTAGES, POOL_TEAM_DETAILS, WalletPercentage};
+    use terraswap::asset::{Asset, AssetInfo};
+    use terraswap::pair::{ExecuteMsg as AstroPortExecute, SimulationResponse};
+    use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, Expiration};
+    use cw_storage_plus::U64Key;
+
+    use crate::allowances::execute_increase_allowance;
+    use crate::contract::{CHALLENGE_ACCEPTED, CHALLENGE_OPEN, CLAIMED_REWARD, CONTRACT_NAME, CONTRACT_VERSION, migrate, GAME_CANCELLED, GAME_COMPLETED, GAME_POOL_OPEN, INITIAL_REFUND_AMOUNT, INITIAL_REWARD_AMOUNT, INITIAL_TEAM_POINTS, INITIAL_TEAM_RANK, LOCK_SWAP_REPLY_ID, execute, instantiate, reply};
+    use crate::enumerable::query_all_allowances;
+    use crate::execute::{fury_equivalent_to_ust, ust_equivalent_to_fury, cancel_game, claim_referral_rewards, claim_refund, claim_reward, close_season, create_pool, game_pool_bid_submit, game_pool_reward_distribute, lineup_hash_hex, lock_game, received_message, reclaim_challenge_stake, register_referrer, reveal_lineup, save_team_details, set_activation_status, set_challenge_params, set_entry_asset_whitelist, set_platform_fee_wallets, set_pool_type_params, set_price_twap_window, set_referral_percentage, set_whitelist_check, settle_challenge, start_season, swap, update_team, withdraw_team};
+    use crate::msg::{AcceptChallengeCommand, BalanceResponse, CreateChallengeCommand, ExecuteMsg, GamePoolBidSubmitCommand, InstantiateMsg, MigrateMsg, ProxyQueryMsgs, QueryMsgSimulation, ReceivedMsg};
+    use crate::query::{get_team_count_for_user_in_pool_type, query_activation_status, query_challenge_details, query_game_details, query_game_events, query_gamer_pool_ids, query_gamer_pools, query_pool_details, query_pool_events, query_referral_stats, query_referrer, query_season_details, query_season_leaderboard, query_swap_data_for_pool, query_team_details};
+    use crate::responsible_gaming::{execute_self_exclude, execute_set_self_imposed_limits, execute_set_wagering_limits, query_wagering_limits};
+    use crate::ContractError;
+    use crate::state::{CONFIG, GAME_DETAILS, GAMER_POOLS, LOCK_SWAP_INFO, GameDetails, GameEvent, GameResult, LineupPlayer, OPEN_POOLS, PLATFORM_WALLET_PERCENTAGES, POOL_SWAPPED_UST, POOL_TEAM_DETAILS, POOL_TYPE_DETAILS, SWAP_BALANCE_INFO, WageringLimits, WalletPercentage};
 
     #[test]
     fn test_create_and_query_game() {
@@ -162,6 +170,10 @@ mod tests {
             false,
             100,
             2,
+            Uint128::from(144262u128),
+            Uint128::zero(),
+            None,
+            None,
         );
 
         let mut teamId = String::new();
@@ -247,6 +259,7 @@ mod tests {
             10,
             2,
             rake_list,
+            None,
         );
 
         let rsp = create_pool(
@@ -274,11 +287,10 @@ mod tests {
             owner1_info.clone(),
             "gamer001".to_string(),
             "oneToOne".to_string(),
-            poolId.to_string(),
+            Some(poolId.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         let queryRes = query_pool_details(&mut deps.storage, "1".to_string());
         match queryRes {
@@ -350,6 +362,7 @@ mod tests {
             1,
             1,
             rake_list,
+            None,
         );
 
         let rsp = create_pool(
@@ -377,11 +390,10 @@ mod tests {
             owner1_info.clone(),
             "gamer001".to_string(),
             "oneToOne".to_string(),
-            poolId.to_string(),
+            Some(poolId.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -389,11 +401,10 @@ mod tests {
             owner1_info.clone(),
             "gamer001".to_string(),
             "oneToOne".to_string(),
-            poolId.to_string(),
+            Some(poolId.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         let queryRes = query_pool_details(&mut deps.storage, "2".to_string());
         match queryRes {
@@ -466,6 +477,7 @@ mod tests {
             10,
             10,
             rake_list.clone(),
+            None,
         );
         set_pool_type_params(
             deps.as_mut(),
@@ -477,6 +489,7 @@ mod tests {
             10,
             10,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -542,11 +555,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer001".to_string(),
             "oneToOne".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -554,11 +566,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer001".to_string(),
             "oneToOne".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -566,11 +577,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer001".to_string(),
             "oneToOne".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team002".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -578,11 +588,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer001".to_string(),
             "oneToOne".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_1 = query_pool_details(&mut deps.storage, pool_id_1.to_string());
@@ -603,11 +612,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer001".to_string(),
             "multiple".to_string(),
-            pool_id_2.to_string(),
+            Some(pool_id_2.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -615,11 +623,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer001".to_string(),
             "multiple".to_string(),
-            pool_id_2.to_string(),
+            Some(pool_id_2.to_string()),
             "Team004".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -627,11 +634,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer001".to_string(),
             "multiple".to_string(),
-            pool_id_2.to_string(),
+            Some(pool_id_2.to_string()),
             "Team005".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_2 = query_pool_details(&mut deps.storage, pool_id_2.to_string());
@@ -652,23 +658,21 @@ mod tests {
             ownerXInfo.clone(),
             "gamer001".to_string(),
             "oneToOne".to_string(),
-            pool_id_3.to_string(),
+            Some(pool_id_3.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
             mock_env(),
             ownerXInfo.clone(),
             "gamer001".to_string(),
-            "multiple".to_string(),
-            pool_id_3.to_string(),
+            "oneToOne".to_string(),
+            Some(pool_id_3.to_string()),
             "Team004".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         let query_pool_details_3 = query_pool_details(&mut deps.storage, pool_id_3.to_string());
         match query_pool_details_3 {
@@ -739,6 +743,7 @@ mod tests {
             10,
             2,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -767,11 +772,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -779,11 +783,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team002".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -791,11 +794,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_1 = query_pool_details(&mut deps.storage, pool_id_1.to_string());
@@ -868,6 +870,7 @@ mod tests {
             10,
             5,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -896,11 +899,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -908,11 +910,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team002".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -920,11 +921,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_1 = query_pool_details(&mut deps.storage, pool_id_1.to_string());
@@ -943,23 +943,26 @@ mod tests {
             gamer_address: "gamer002".to_string(),
             team_id: "Team001".to_string(),
             reward_amount: Uint128::from(100u128),
+            team_points: 0,
         };
         let game_result_2 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team002".to_string(),
             reward_amount: Uint128::from(200u128),
+            team_points: 0,
         };
         let game_result_3 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team003".to_string(),
             reward_amount: Uint128::from(300u128),
+            team_points: 0,
         };
         let mut game_results: Vec<GameResult> = Vec::new();
         game_results.push(game_result_1);
         game_results.push(game_result_2);
         game_results.push(game_result_3);
 
-        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone());
+        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None);
         match lock_game_rsp {
             Ok(lock_game_rsp) => {
                 //Since max allowed team for gamer under this pooltype is 2 so it will not allow 3rd team creation under this pooltype.
@@ -1069,6 +1072,7 @@ mod tests {
             10,
             5,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -1097,11 +1101,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let cancelInfo = mock_info("cancelInfo", &[]);
@@ -1184,6 +1187,7 @@ mod tests {
             10,
             5,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -1212,11 +1216,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -1224,11 +1227,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team002".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -1236,11 +1238,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_1 = query_pool_details(&mut deps.storage, pool_id_1.to_string());
@@ -1259,23 +1260,26 @@ mod tests {
             gamer_address: "gamer002".to_string(),
             team_id: "Team001".to_string(),
             reward_amount: Uint128::from(100u128),
+            team_points: 0,
         };
         let game_result_2 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team002".to_string(),
             reward_amount: Uint128::from(200u128),
+            team_points: 0,
         };
         let game_result_3 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team003".to_string(),
             reward_amount: Uint128::from(300u128),
+            team_points: 0,
         };
         let mut game_results: Vec<GameResult> = Vec::new();
         game_results.push(game_result_1);
         game_results.push(game_result_2);
         game_results.push(game_result_3);
 
-        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone());
+        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None);
         match lock_game_rsp {
             Ok(lock_game_rsp) => {
                 //Since max allowed team for gamer under this pooltype is 2 so it will not allow 3rd team creation under this pooltype.
@@ -1378,6 +1382,7 @@ mod tests {
             10,
             5,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -1425,11 +1430,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -1437,11 +1441,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team002".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -1449,11 +1452,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_1 = query_pool_details(&mut deps.storage, pool_id_1.to_string());
@@ -1472,23 +1474,26 @@ mod tests {
             gamer_address: "gamer002".to_string(),
             team_id: "Team001".to_string(),
             reward_amount: Uint128::from(500u128),
+            team_points: 0,
         };
         let game_result_2 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team002".to_string(),
             reward_amount: Uint128::from(200u128),
+            team_points: 0,
         };
         let game_result_3 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team003".to_string(),
             reward_amount: Uint128::from(300u128),
+            team_points: 0,
         };
         let mut game_results: Vec<GameResult> = Vec::new();
         game_results.push(game_result_1);
         game_results.push(game_result_2);
         game_results.push(game_result_3);
 
-        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone());
+        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None);
         match lock_game_rsp {
             Ok(lock_game_rsp) => {
                 //Since max allowed team for gamer under this pooltype is 2 so it will not allow 3rd team creation under this pooltype.
@@ -1647,6 +1652,7 @@ mod tests {
             10,
             5,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -1675,11 +1681,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -1687,11 +1692,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team002".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -1699,11 +1703,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_1 = query_pool_details(&mut deps.storage, pool_id_1.to_string());
@@ -1722,23 +1725,26 @@ mod tests {
             gamer_address: "gamer002".to_string(),
             team_id: "Team001".to_string(),
             reward_amount: Uint128::from(100u128),
+            team_points: 0,
         };
         let game_result_2 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team002".to_string(),
             reward_amount: Uint128::from(200u128),
+            team_points: 0,
         };
         let game_result_3 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team003".to_string(),
             reward_amount: Uint128::from(300u128),
+            team_points: 0,
         };
         let mut game_results: Vec<GameResult> = Vec::new();
         game_results.push(game_result_1);
         game_results.push(game_result_2);
         game_results.push(game_result_3);
 
-        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone());
+        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None);
         match lock_game_rsp {
             Ok(lock_game_rsp) => {
                 //Since max allowed team for gamer under this pooltype is 2 so it will not allow 3rd team creation under this pooltype.
@@ -1915,6 +1921,7 @@ mod tests {
             20,
             5,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -1956,11 +1963,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -1968,11 +1974,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team002".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -1980,11 +1985,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_1 = query_pool_details(&mut deps.storage, pool_id_1.to_string());
@@ -2003,23 +2007,26 @@ mod tests {
             gamer_address: "gamer002".to_string(),
             team_id: "Team001".to_string(),
             reward_amount: Uint128::from(100u128),
+            team_points: 0,
         };
         let game_result_2 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team002".to_string(),
             reward_amount: Uint128::from(200u128),
+            team_points: 0,
         };
         let game_result_3 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team003".to_string(),
             reward_amount: Uint128::from(300u128),
+            team_points: 0,
         };
         let mut game_results: Vec<GameResult> = Vec::new();
         game_results.push(game_result_1);
         game_results.push(game_result_2);
         game_results.push(game_result_3);
 
-        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone());
+        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None);
         match lock_game_rsp {
             Ok(lock_game_rsp) => {
                 //Since max allowed team for gamer under this pooltype is 2 so it will not allow 3rd team creation under this pooltype.
@@ -2107,6 +2114,7 @@ mod tests {
             10,
             5,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -2136,11 +2144,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -2148,11 +2155,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team002".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -2160,11 +2166,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_1 = query_pool_details(&mut deps.storage, pool_id_1.to_string());
@@ -2183,23 +2188,26 @@ mod tests {
             gamer_address: "gamer002".to_string(),
             team_id: "Team001".to_string(),
             reward_amount: Uint128::from(100u128),
+            team_points: 0,
         };
         let game_result_2 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team002".to_string(),
             reward_amount: Uint128::from(200u128),
+            team_points: 0,
         };
         let game_result_3 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team003".to_string(),
             reward_amount: Uint128::from(300u128),
+            team_points: 0,
         };
         let mut game_results: Vec<GameResult> = Vec::new();
         game_results.push(game_result_1);
         game_results.push(game_result_2);
         game_results.push(game_result_3);
 
-        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone());
+        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None);
         match lock_game_rsp {
             Ok(lock_game_rsp) => {
                 //Since max allowed team for gamer under this pooltype is 2 so it will not allow 3rd team creation under this pooltype.
@@ -2319,6 +2327,7 @@ mod tests {
             10,
             5,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -2343,16 +2352,19 @@ mod tests {
             gamer_address: "gamer002".to_string(),
             team_id: "Team001".to_string(),
             reward_amount: Uint128::from(100u128),
+            team_points: 0,
         };
         let game_result_2 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team002".to_string(),
             reward_amount: Uint128::from(200u128),
+            team_points: 0,
         };
         let game_result_3 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team003".to_string(),
             reward_amount: Uint128::from(300u128),
+            team_points: 0,
         };
         let mut game_results: Vec<GameResult> = Vec::new();
         game_results.push(game_result_1);
@@ -2395,11 +2407,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -2407,11 +2418,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team002".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -2419,11 +2429,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_1 = query_pool_details(&mut deps.storage, pool_id_1.to_string());
@@ -2523,6 +2532,7 @@ mod tests {
             10,
             5,
             rake_list.clone(),
+            None,
         );
 
         // create multiple pool
@@ -2564,11 +2574,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team001".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -2576,11 +2585,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team002".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
         game_pool_bid_submit(
             deps.as_mut(),
@@ -2588,11 +2596,10 @@ mod tests {
             ownerXInfo.clone(),
             "gamer002".to_string(),
             "oneToTwo".to_string(),
-            pool_id_1.to_string(),
+            Some(pool_id_1.to_string()),
             "Team003".to_string(),
             Uint128::from(144262u128) + platform_fee,
             true,
-            None,
         );
 
         let query_pool_details_1 = query_pool_details(&mut deps.storage, pool_id_1.to_string());
@@ -2611,23 +2618,26 @@ mod tests {
             gamer_address: "gamer002".to_string(),
             team_id: "Team001".to_string(),
             reward_amount: Uint128::from(100u128),
+            team_points: 0,
         };
         let game_result_2 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team002".to_string(),
             reward_amount: Uint128::from(200u128),
+            team_points: 0,
         };
         let game_result_3 = GameResult {
             gamer_address: "gamer002".to_string(),
             team_id: "Team003".to_string(),
             reward_amount: Uint128::from(300u128),
+            team_points: 0,
         };
         let mut game_results: Vec<GameResult> = Vec::new();
         game_results.push(game_result_1);
         game_results.push(game_result_2);
         game_results.push(game_result_3);
 
-        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone());
+        let lock_game_rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None);
         match lock_game_rsp {
             Ok(lock_game_rsp) => {
                 //Since max allowed team for gamer under this pooltype is 2 so it will not allow 3rd team creation under this pooltype.
@@ -2708,6 +2718,140 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_migrate_backfills_pool_indexes_from_old_versions_only() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(1000u128),
+            2,
+            10,
+            1,
+            vec![],
+            None,
+        ).unwrap();
+        game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[]),
+            "gamer001".to_string(),
+            "oneToTwo".to_string(),
+            None,
+            "Team001".to_string(),
+            Uint128::from(1000u128),
+            true,
+        ).unwrap();
+        // Teams and pools saved before the indexes existed
+        GAMER_POOLS.remove(&mut deps.storage, ("gamer001", "1"));
+        OPEN_POOLS.remove(&mut deps.storage, ("Game001", "oneToTwo", U64Key::from(1)));
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(query_gamer_pool_ids(&deps.storage, "gamer001", None).unwrap().len(), 0);
+
+        cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.9.1").unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(query_gamer_pool_ids(&deps.storage, "gamer001", None).unwrap(), vec!["1".to_string()]);
+        assert_eq!(OPEN_POOLS.prefix(("Game001", "oneToTwo")).range(&deps.storage, None, None, Order::Ascending).count(), 1);
+    }
+
+    #[test]
+    fn test_migrate_books_entry_asset_deposits_once() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        let usdc = Some(AssetInfo::NativeToken { denom: "uusd".to_string() });
+        for (pool_type, entry_asset) in [("usdcPool", usdc.clone()), ("furyPool", None)] {
+            set_pool_type_params(
+                deps.as_mut(),
+                mock_env(),
+                adminInfo.clone(),
+                pool_type.to_string(),
+                Uint128::from(1000u128),
+                2,
+                10,
+                1,
+                vec![],
+                entry_asset,
+            ).unwrap();
+            create_pool(deps.as_mut(), mock_env(), adminInfo.clone(), pool_type.to_string()).unwrap();
+        }
+        for (pool_id, pool_type) in [("1", "usdcPool"), ("2", "furyPool")] {
+            save_team_details(
+                &mut deps.storage,
+                mock_env(),
+                "gamer001".to_string(),
+                pool_id.to_string(),
+                "Team001".to_string(),
+                "Game001".to_string(),
+                pool_type.to_string(),
+                Uint128::zero(),
+                false,
+                Uint128::zero(),
+                false,
+                0,
+                0,
+                Uint128::from(1000u128),
+                Uint128::zero(),
+                None,
+                None,
+            ).unwrap();
+        }
+
+        // Deployments older than the split book the deposit of entry asset
+        // pools in the entry asset
+        cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.9.1").unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer001".to_string()).unwrap();
+        assert_eq!(team.fury_deposited, Uint128::zero());
+        assert_eq!(team.entry_asset_deposited, Uint128::from(1000u128));
+        assert_eq!(cw2::get_contract_version(&deps.storage).unwrap().version, CONTRACT_VERSION.to_string());
+
+        // A pool type switched to an entry asset afterwards keeps the FURY
+        // deposited of its teams
+        let mut fury_pool_type = POOL_TYPE_DETAILS.load(&deps.storage, "furyPool".to_string()).unwrap();
+        fury_pool_type.entry_asset = usdc;
+        POOL_TYPE_DETAILS.save(&mut deps.storage, "furyPool".to_string(), &fury_pool_type).unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let team = query_team_details(&deps.storage, "2".to_string(), "Team001".to_string(), "gamer001".to_string()).unwrap();
+        assert_eq!(team.fury_deposited, Uint128::from(1000u128));
+        assert_eq!(team.entry_asset_deposited, Uint128::zero());
+    }
+
     #[test]
     fn test_set_platform_fee_wallets() {
         let mut deps = mock_dependencies();
@@ -2758,5 +2902,2464 @@ mod tests {
             assert_eq!(wallet.wallet_name, "rake_3".to_string());
         }
     }
-}
 
+    #[test]
+    fn test_gamer_pools_index_and_pagination() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+
+        // create three pools and join two of them
+        for _ in 0..3 {
+            create_pool(
+                deps.as_mut(),
+                mock_env(),
+                adminInfo.clone(),
+                "oneToTwo".to_string(),
+            ).unwrap();
+        }
+        for pool_id in ["1", "3"] {
+            save_team_details(
+                &mut deps.storage,
+                mock_env(),
+                "gamer001".to_string(),
+                pool_id.to_string(),
+                "Team001".to_string(),
+                "Game001".to_string(),
+                "oneToTwo".to_string(),
+                Uint128::from(INITIAL_REWARD_AMOUNT),
+                false,
+                Uint128::from(INITIAL_REFUND_AMOUNT),
+                false,
+                INITIAL_TEAM_POINTS,
+                INITIAL_TEAM_RANK,
+                Uint128::zero(),
+                Uint128::zero(),
+                None,
+                None,
+            ).unwrap();
+        }
+
+        let all_pool_ids = query_gamer_pool_ids(&deps.storage, "gamer001", None).unwrap();
+        assert_eq!(all_pool_ids, vec!["1".to_string(), "3".to_string()]);
+        let given_pool_ids = query_gamer_pool_ids(
+            &deps.storage,
+            "gamer001",
+            Some(vec!["3".to_string(), "3".to_string()]),
+        ).unwrap();
+        assert_eq!(given_pool_ids, vec!["3".to_string()]);
+
+        let first_page = query_gamer_pools(&deps.storage, "gamer001".to_string(), None, Some(1)).unwrap();
+        assert_eq!(first_page.len(), 1);
+        assert_eq!(first_page[0].pool_id, "1".to_string());
+        let second_page = query_gamer_pools(
+            &deps.storage,
+            "gamer001".to_string(),
+            Some(first_page[0].pool_id.clone()),
+            Some(1),
+        ).unwrap();
+        assert_eq!(second_page.len(), 1);
+        assert_eq!(second_page[0].pool_id, "3".to_string());
+
+        let other_gamer = query_gamer_pools(&deps.storage, "gamer002".to_string(), None, None).unwrap();
+        assert!(other_gamer.is_empty());
+    }
+
+    #[test]
+    fn test_game_pool_bid_submit_by_cw20_send() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            1,
+            vec![],
+            None,
+        ).unwrap();
+        create_pool(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+        ).unwrap();
+
+        let bid = Cw20ReceiveMsg {
+            sender: "gamer001".to_string(),
+            amount: Uint128::from(144262u128),
+            msg: to_binary(&ReceivedMsg::GamePoolBidSubmit(GamePoolBidSubmitCommand {
+                gamer: "gamer001".to_string(),
+                pool_type: "oneToTwo".to_string(),
+                pool_id: Some("1".to_string()),
+                team_id: "Team001".to_string(),
+            })).unwrap(),
+        };
+
+        // Only the FURY token contract can send the bid
+        let err = received_message(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("othertoken", &[]),
+            bid.clone(),
+            true,
+        ).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized { invoker: "othertoken".to_string() });
+
+        // The gamer in the bid must be the sender of the tokens
+        let mut bid_for_other_gamer = bid.clone();
+        bid_for_other_gamer.sender = "gamer002".to_string();
+        let err = received_message(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("cwtoken11111", &[]),
+            bid_for_other_gamer,
+            true,
+        ).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized { invoker: "gamer002".to_string() });
+
+        let rsp = received_message(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("cwtoken11111", &[]),
+            bid.clone(),
+            true,
+        ).unwrap();
+        assert_eq!(rsp.attributes[0].value, "1".to_string());
+        // No TransferFrom as the tokens are already with the contract and
+        // the swap waits for the game to be locked
+        assert_eq!(rsp.messages.len(), 0);
+        let pool_detail = query_pool_details(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(pool_detail.current_teams_count, 1u32);
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer001".to_string()).unwrap();
+        assert_eq!(team.gamer_address, "gamer001".to_string());
+
+        // Same team limits as a bid through allowance
+        let err = received_message(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("cwtoken11111", &[]),
+            bid,
+            true,
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: User max team limit reached ".to_string());
+    }
+
+    #[test]
+    fn test_game_pool_bid_submit_creates_pool_when_all_pools_are_full() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToOne".to_string(),
+            Uint128::from(144262u128),
+            2,
+            2,
+            2,
+            vec![],
+            None,
+        ).unwrap();
+
+        let mut assigned_pools = Vec::new();
+        for (gamer, team) in [("gamer001", "Team001"), ("gamer002", "Team002"), ("gamer003", "Team003")] {
+            let rsp = game_pool_bid_submit(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(gamer, &[]),
+                gamer.to_string(),
+                "oneToOne".to_string(),
+                None,
+                team.to_string(),
+                Uint128::from(144262u128),
+                true,
+            ).unwrap();
+            let pool_id: String = from_binary(&rsp.data.unwrap()).unwrap();
+            assert_eq!(rsp.attributes[0].value, pool_id);
+            assigned_pools.push(pool_id);
+        }
+        // The first pool is created on the first bid and the third team
+        // overflows into a fresh pool
+        assert_eq!(assigned_pools, vec!["1".to_string(), "1".to_string(), "2".to_string()]);
+        assert_eq!(query_pool_details(&deps.storage, "1".to_string()).unwrap().current_teams_count, 2u32);
+        assert_eq!(query_pool_details(&deps.storage, "2".to_string()).unwrap().current_teams_count, 1u32);
+
+        // The next bid fills the open pool instead of creating another one
+        let rsp = game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer004", &[]),
+            "gamer004".to_string(),
+            "oneToOne".to_string(),
+            None,
+            "Team004".to_string(),
+            Uint128::from(144262u128),
+            true,
+        ).unwrap();
+        assert_eq!(rsp.attributes[0].value, "2".to_string());
+        assert!(query_pool_details(&deps.storage, "3".to_string()).is_err());
+        assert_eq!(OPEN_POOLS.prefix(("Game001", "oneToOne")).range(&deps.storage, None, None, Order::Ascending).count(), 0);
+
+        // A team leaving a full pool opens it up again for the next bid
+        withdraw_team(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[]),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team001".to_string(),
+            true,
+            None,
+        ).unwrap();
+        let rsp = game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer005", &[]),
+            "gamer005".to_string(),
+            "oneToOne".to_string(),
+            None,
+            "Team005".to_string(),
+            Uint128::from(144262u128),
+            true,
+        ).unwrap();
+        assert_eq!(rsp.attributes[0].value, "1".to_string());
+        assert!(query_pool_details(&deps.storage, "3".to_string()).is_err());
+
+        // Locked games take no more teams
+        create_pool(deps.as_mut(), mock_env(), adminInfo.clone(), "oneToOne".to_string()).unwrap();
+        assert_eq!(OPEN_POOLS.prefix(("Game001", "oneToOne")).range(&deps.storage, None, None, Order::Ascending).count(), 1);
+        lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None).unwrap();
+        assert_eq!(OPEN_POOLS.prefix(("Game001", "oneToOne")).range(&deps.storage, None, None, Order::Ascending).count(), 0);
+    }
+
+    #[test]
+    fn test_game_pool_bid_submit_rejects_pool_of_other_pool_type() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        for (pool_type, pool_fee) in [("cheap", 1000u128), ("premium", 144262u128)] {
+            set_pool_type_params(
+                deps.as_mut(),
+                mock_env(),
+                adminInfo.clone(),
+                pool_type.to_string(),
+                Uint128::from(pool_fee),
+                2,
+                10,
+                2,
+                vec![],
+                None,
+            ).unwrap();
+        }
+        create_pool(deps.as_mut(), mock_env(), adminInfo.clone(), "premium".to_string()).unwrap();
+
+        // Paying for the cheap pool type does not get a team into the premium pool
+        let err = game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[]),
+            "gamer001".to_string(),
+            "cheap".to_string(),
+            Some("1".to_string()),
+            "Team001".to_string(),
+            Uint128::from(1000u128),
+            true,
+        ).unwrap_err();
+        assert_eq!(err, ContractError::PoolTypeMismatch {
+            pool_id: "1".to_string(),
+            pool_type: "premium".to_string(),
+        });
+        let pool = query_pool_details(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(pool.pool_type, "premium".to_string());
+        assert_eq!(pool.current_teams_count, 0u32);
+        assert_eq!(OPEN_POOLS.prefix(("Game001", "premium")).range(&deps.storage, None, None, Order::Ascending).count(), 1);
+        assert_eq!(OPEN_POOLS.prefix(("Game001", "cheap")).range(&deps.storage, None, None, Order::Ascending).count(), 0);
+
+        // Type-only bids of the cheap pool type get a pool of their own
+        let rsp = game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[]),
+            "gamer001".to_string(),
+            "cheap".to_string(),
+            None,
+            "Team001".to_string(),
+            Uint128::from(1000u128),
+            true,
+        ).unwrap();
+        assert_eq!(rsp.attributes[0].value, "2".to_string());
+    }
+
+    #[test]
+    fn test_update_withdraw_and_reveal_team_lineup() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        let gamerInfo = mock_info("gamer001", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            1,
+            10,
+            2,
+            vec![],
+            None,
+        ).unwrap();
+        for team_id in ["Team001", "Team002"] {
+            game_pool_bid_submit(
+                deps.as_mut(),
+                mock_env(),
+                gamerInfo.clone(),
+                "gamer001".to_string(),
+                "oneToTwo".to_string(),
+                None,
+                team_id.to_string(),
+                Uint128::from(144262u128),
+                true,
+            ).unwrap();
+        }
+
+        let lineup = vec![
+            LineupPlayer { player_id: "player01".to_string(), position: "GK".to_string() },
+            LineupPlayer { player_id: "player07".to_string(), position: "FW".to_string() },
+        ];
+        let lineup_hash = lineup_hash_hex(&lineup, "salt").unwrap();
+
+        let err = update_team(
+            deps.as_mut(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team001".to_string(),
+            vec![],
+            Some("not a hash".to_string()),
+        ).unwrap_err();
+        assert_eq!(err, ContractError::InvalidLineupHash {});
+        // A plaintext lineup set earlier is dropped once a hash is committed
+        update_team(
+            deps.as_mut(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team001".to_string(),
+            lineup.clone(),
+            None,
+        ).unwrap();
+        update_team(
+            deps.as_mut(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team001".to_string(),
+            vec![],
+            Some(lineup_hash.clone()),
+        ).unwrap();
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer001".to_string()).unwrap();
+        assert_eq!(team.lineup_hash, Some(lineup_hash.clone()));
+        assert!(team.lineup.is_empty());
+
+        // The committed lineup cannot be pushed in plaintext
+        let err = update_team(
+            deps.as_mut(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team001".to_string(),
+            lineup.clone(),
+            Some(lineup_hash.clone()),
+        ).unwrap_err();
+        assert_eq!(err, ContractError::LineupWithHash {});
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer001".to_string()).unwrap();
+        assert!(team.lineup.is_empty());
+        assert!(!team.revealed);
+
+        // Withdrawing the second team frees its place in the pool
+        let rsp = withdraw_team(
+            deps.as_mut(),
+            mock_env(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team002".to_string(),
+            true,
+            None,
+        ).unwrap();
+        assert_eq!(rsp.messages.len(), 2);
+        assert_eq!(query_pool_details(&deps.storage, "1".to_string()).unwrap().current_teams_count, 1u32);
+        let err = withdraw_team(
+            deps.as_mut(),
+            mock_env(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team002".to_string(),
+            true,
+            None,
+        ).unwrap_err();
+        assert_eq!(err, ContractError::TeamNotFound { pool_id: "1".to_string(), team_id: "Team002".to_string() });
+
+        lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None).unwrap();
+        let err = update_team(
+            deps.as_mut(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team001".to_string(),
+            lineup.clone(),
+            None,
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: Team cant be updated as the game is not open".to_string());
+
+        // The committed lineup must be revealed before the team is paid
+        let game_results = vec![GameResult {
+            gamer_address: "gamer001".to_string(),
+            team_id: "Team001".to_string(),
+            reward_amount: Uint128::from(500u128),
+            team_points: 0,
+        }];
+        let err = game_pool_reward_distribute(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "Game001".to_string(),
+            "1".to_string(),
+            game_results.clone(),
+            true,
+            true,
+            Uint128::zero(),
+        ).unwrap_err();
+        assert_eq!(err, ContractError::LineupNotRevealed { team_id: "Team001".to_string() });
+
+        let err = reveal_lineup(
+            deps.as_mut(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team001".to_string(),
+            lineup.clone(),
+            "other salt".to_string(),
+        ).unwrap_err();
+        assert_eq!(err, ContractError::LineupMismatch {});
+        reveal_lineup(
+            deps.as_mut(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team001".to_string(),
+            lineup.clone(),
+            "salt".to_string(),
+        ).unwrap();
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer001".to_string()).unwrap();
+        assert_eq!(team.lineup, lineup);
+        assert!(team.revealed);
+        game_pool_reward_distribute(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "Game001".to_string(),
+            "1".to_string(),
+            game_results,
+            true,
+            true,
+            Uint128::zero(),
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_head_to_head_challenge() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        let furyInfo = mock_info("cwtoken11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+
+        let challenge = |sender: &str, amount: u128, msg: ReceivedMsg| Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&msg).unwrap(),
+        };
+        let create = ReceivedMsg::CreateChallenge(CreateChallengeCommand {
+            opponent: "gamer002".to_string(),
+            team_id: "Team001".to_string(),
+        });
+
+        // Challenges are not possible till the stake range is set
+        received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer001", 500, create.clone()), true).unwrap_err();
+
+        // The rake wallets have to take the whole rake
+        let err = set_challenge_params(
+            deps.as_mut(),
+            adminInfo.clone(),
+            Uint128::from(100u128),
+            Uint128::from(1000u128),
+            Uint128::from(1000u128),
+            vec![WalletPercentage {
+                wallet_address: "rake_1".to_string(),
+                wallet_name: "rake_1".to_string(),
+                percentage: 90,
+            }],
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: Rake wallet percentages must add up to 100".to_string());
+
+        set_challenge_params(
+            deps.as_mut(),
+            adminInfo.clone(),
+            Uint128::from(100u128),
+            Uint128::from(1000u128),
+            Uint128::from(1000u128),
+            vec![WalletPercentage {
+                wallet_address: "rake_1".to_string(),
+                wallet_name: "rake_1".to_string(),
+                percentage: 100,
+            }],
+        ).unwrap();
+
+        let err = received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer001", 5000, create.clone()), true).unwrap_err();
+        assert_eq!(err, ContractError::InvalidStake { min_stake: Uint128::from(100u128), max_stake: Uint128::from(1000u128) });
+
+        let rsp = received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer001", 500, create.clone()), true).unwrap();
+        let challenge_id: String = from_binary(&rsp.data.unwrap()).unwrap();
+        assert_eq!(challenge_id, "1".to_string());
+        received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer001", 300, create.clone()), true).unwrap();
+
+        let accept = ReceivedMsg::AcceptChallenge(AcceptChallengeCommand {
+            challenge_id: "1".to_string(),
+            team_id: "Team002".to_string(),
+        });
+        // Only the named opponent can accept and only with a matching stake
+        let err = received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer003", 500, accept.clone()), true).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized { invoker: "gamer003".to_string() });
+        received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer002", 400, accept.clone()), true).unwrap_err();
+        received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer002", 500, accept.clone()), true).unwrap();
+        let details = query_challenge_details(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(details.challenge_status, CHALLENGE_ACCEPTED);
+        assert_eq!(details.opponent_team_id, Some("Team002".to_string()));
+
+        // Nothing can be settled or reclaimed while the game is open
+        settle_challenge(deps.as_mut(), adminInfo.clone(), "1".to_string(), Some("gamer002".to_string())).unwrap_err();
+        reclaim_challenge_stake(deps.as_mut(), mock_info("gamer001", &[]), "2".to_string()).unwrap_err();
+
+        lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None).unwrap();
+
+        // The game results have to be in before settling
+        let err = settle_challenge(deps.as_mut(), adminInfo.clone(), "1".to_string(), Some("gamer002".to_string())).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: Challenges are settled once the game results are in".to_string());
+        GAME_DETAILS.save(&mut deps.storage, "Game001".to_string(), &GameDetails {
+            game_id: "Game001".to_string(),
+            game_status: GAME_COMPLETED,
+        }).unwrap();
+
+        // Pot of 1000 with 10% rake
+        let rsp = settle_challenge(deps.as_mut(), adminInfo.clone(), "1".to_string(), Some("gamer002".to_string())).unwrap();
+        assert_eq!(rsp.messages.len(), 2);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "cwtoken11111".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient: "rake_1".to_string(), amount: Uint128::from(100u128) }).unwrap(),
+            funds: vec![],
+        }));
+        assert_eq!(rsp.messages[1].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "cwtoken11111".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient: "gamer002".to_string(), amount: Uint128::from(900u128) }).unwrap(),
+            funds: vec![],
+        }));
+        settle_challenge(deps.as_mut(), adminInfo.clone(), "1".to_string(), Some("gamer002".to_string())).unwrap_err();
+
+        // The challenge which was never accepted goes back to its creator once
+        let err = reclaim_challenge_stake(deps.as_mut(), mock_info("gamer002", &[]), "2".to_string()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized { invoker: "gamer002".to_string() });
+        let rsp = reclaim_challenge_stake(deps.as_mut(), mock_info("gamer001", &[]), "2".to_string()).unwrap();
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "cwtoken11111".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient: "gamer001".to_string(), amount: Uint128::from(300u128) }).unwrap(),
+            funds: vec![],
+        }));
+        reclaim_challenge_stake(deps.as_mut(), mock_info("gamer001", &[]), "2".to_string()).unwrap_err();
+    }
+
+    #[test]
+    fn test_refund_returns_fury_deposited_and_lock_swaps_filled_pools() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            1,
+            vec![],
+            None,
+        ).unwrap();
+        for _ in 0..2 {
+            create_pool(
+                deps.as_mut(),
+                mock_env(),
+                adminInfo.clone(),
+                "oneToTwo".to_string(),
+            ).unwrap();
+        }
+        for (gamer, pool_id, amount) in [("gamer001", "1", 150000u128), ("gamer002", "1", 160000u128), ("gamer003", "2", 170000u128)] {
+            let rsp = game_pool_bid_submit(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(gamer, &[]),
+                gamer.to_string(),
+                "oneToTwo".to_string(),
+                Some(pool_id.to_string()),
+                "Team001".to_string(),
+                Uint128::from(amount),
+                true,
+            ).unwrap();
+            // Only the FURY transfer to the contract, no swap at bid time
+            assert_eq!(rsp.messages.len(), 1);
+            let team = query_team_details(&deps.storage, pool_id.to_string(), "Team001".to_string(), gamer.to_string()).unwrap();
+            assert_eq!(team.fury_deposited, Uint128::from(amount));
+        }
+
+        // Only the FURY of the filled pool gets swapped
+        let rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None).unwrap();
+        assert_eq!(rsp.messages.len(), 2);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "cwtoken11111".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                spender: "astroport".to_string(),
+                amount: Uint128::from(310000u128),
+                expires: None,
+            }).unwrap(),
+            funds: vec![],
+        }));
+        assert_eq!(rsp.messages[1].id, LOCK_SWAP_REPLY_ID);
+        assert_eq!(rsp.messages[1].reply_on, ReplyOn::Always);
+        assert!(!query_pool_details(&deps.storage, "1".to_string()).unwrap().pool_refund_status);
+        assert!(query_pool_details(&deps.storage, "2".to_string()).unwrap().pool_refund_status);
+
+        // The reply books the UST received against the swapped pool
+        let env = mock_env();
+        deps.querier.update_balance(env.contract.address.clone(), vec![coin(31000, "uusd")]);
+        reply(deps.as_mut(), env, Reply {
+            id: LOCK_SWAP_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse { events: vec![], data: None }),
+        }).unwrap();
+        assert_eq!(POOL_SWAPPED_UST.load(&deps.storage, "1".to_string()).unwrap(), Uint128::from(31000u128));
+
+        // The pool which never filled gives back the exact FURY deposited
+        let rsp = claim_refund(deps.as_mut(), mock_info("gamer003", &[]), "gamer003".to_string(), None, mock_env(), Some(true)).unwrap();
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "cwtoken11111".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "gamer003".to_string(),
+                amount: Uint128::from(170000u128),
+            }).unwrap(),
+            funds: vec![],
+        }));
+        let team = query_team_details(&deps.storage, "2".to_string(), "Team001".to_string(), "gamer003".to_string()).unwrap();
+        assert_eq!(team.refund_amount, Uint128::from(170000u128));
+
+        // Once swapped the refund of a cancelled game swaps the share of the
+        // UST received back to FURY for the gamer, the fee paid with the bid
+        // goes back in UST
+        cancel_game(deps.as_mut(), mock_env(), adminInfo.clone()).unwrap();
+        let rsp = claim_refund(deps.as_mut(), mock_info("gamer001", &[]), "gamer001".to_string(), None, mock_env(), Some(true)).unwrap();
+        assert_eq!(rsp.messages.len(), 2);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "astroport".to_string(),
+            msg: to_binary(&AstroPortExecute::Swap {
+                offer_asset: Asset {
+                    info: AssetInfo::NativeToken { denom: "uusd".to_string() },
+                    amount: Uint128::from(15000u128),
+                },
+                belief_price: None,
+                max_spread: None,
+                to: Some("gamer001".to_string()),
+            }).unwrap(),
+            funds: vec![coin(15000, "uusd")],
+        }));
+        assert_eq!(rsp.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "gamer001".to_string(),
+            amount: vec![coin(5770480, "uusd")],
+        }));
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer001".to_string()).unwrap();
+        assert_eq!(team.refund_amount, Uint128::from(15000u128));
+        claim_refund(deps.as_mut(), mock_info("gamer002", &[]), "gamer002".to_string(), None, mock_env(), Some(true)).unwrap();
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer002".to_string()).unwrap();
+        assert_eq!(team.refund_amount, Uint128::from(16000u128));
+    }
+
+    #[test]
+    fn test_failed_lock_swap_refunds_the_fury_deposited() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            1,
+            vec![],
+            None,
+        ).unwrap();
+        for gamer in ["gamer001", "gamer002"] {
+            game_pool_bid_submit(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(gamer, &[]),
+                gamer.to_string(),
+                "oneToTwo".to_string(),
+                None,
+                "Team001".to_string(),
+                Uint128::from(150000u128),
+                true,
+            ).unwrap();
+        }
+        lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None).unwrap();
+        let rsp = reply(deps.as_mut(), mock_env(), Reply {
+            id: LOCK_SWAP_REPLY_ID,
+            result: SubMsgResult::Err("max spread assertion".to_string()),
+        }).unwrap();
+        assert_eq!(rsp.attributes[1].value, "max spread assertion".to_string());
+
+        // The FURY stayed with the contract and goes back as deposited
+        assert!(query_pool_details(&deps.storage, "1".to_string()).unwrap().pool_refund_status);
+        let rsp = claim_refund(deps.as_mut(), mock_info("gamer001", &[]), "gamer001".to_string(), None, mock_env(), Some(true)).unwrap();
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "cwtoken11111".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "gamer001".to_string(),
+                amount: Uint128::from(150000u128),
+            }).unwrap(),
+            funds: vec![],
+        }));
+    }
+
+    #[test]
+    fn test_lock_swap_below_min_receive_refunds_the_ust_received() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            1,
+            vec![],
+            None,
+        ).unwrap();
+        for gamer in ["gamer001", "gamer002"] {
+            game_pool_bid_submit(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(gamer, &[]),
+                gamer.to_string(),
+                "oneToTwo".to_string(),
+                None,
+                "Team001".to_string(),
+                Uint128::from(150000u128),
+                true,
+            ).unwrap();
+        }
+        lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None).unwrap();
+        let mut lock_swap = LOCK_SWAP_INFO.load(&deps.storage).unwrap();
+        lock_swap.min_ust_expected = Uint128::from(30000u128);
+        LOCK_SWAP_INFO.save(&mut deps.storage, &lock_swap).unwrap();
+
+        let env = mock_env();
+        deps.querier.update_balance(env.contract.address.clone(), vec![coin(20000, "uusd")]);
+        let rsp = reply(deps.as_mut(), env, Reply {
+            id: LOCK_SWAP_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse { events: vec![], data: None }),
+        }).unwrap();
+        assert_eq!(rsp.attributes[2].value, "min receive not met".to_string());
+
+        // The pool is refunded out of the UST it was swapped for
+        assert!(query_pool_details(&deps.storage, "1".to_string()).unwrap().pool_refund_status);
+        claim_refund(deps.as_mut(), mock_info("gamer001", &[]), "gamer001".to_string(), None, mock_env(), Some(true)).unwrap();
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer001".to_string()).unwrap();
+        assert_eq!(team.refund_amount, Uint128::from(10000u128));
+    }
+
+    #[test]
+    fn test_refund_gives_back_only_the_fee_held_by_the_contract() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(100u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(deps.as_mut(), mock_env(), adminInfo.clone(), instantiate_msg).unwrap();
+        // The mocked proxy prices FURY at 10 UST
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let response = match from_binary(msg).unwrap() {
+                    ProxyQueryMsgs::get_fury_equivalent_to_ust { ust_count, .. } => ust_count.multiply_ratio(1u128, 10u128),
+                    ProxyQueryMsgs::get_ust_equivalent_to_fury { fury_count, .. } => fury_count * Uint128::from(10u128),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&response).unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        });
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(1000u128),
+            2,
+            10,
+            1,
+            vec![],
+            None,
+        ).unwrap();
+        create_pool(deps.as_mut(), mock_env(), adminInfo.clone(), "oneToTwo".to_string()).unwrap();
+
+        // The UST fee of a bid placed with an allowance stays with the contract
+        game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[coin(20, "uusd")]),
+            "gamer001".to_string(),
+            "oneToTwo".to_string(),
+            Some("1".to_string()),
+            "Team001".to_string(),
+            Uint128::from(100u128),
+            false,
+        ).unwrap();
+        // The FURY fee of a CW20 Send bid goes on to the collector at once
+        let rsp = received_message(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("cwtoken11111", &[]),
+            Cw20ReceiveMsg {
+                sender: "gamer002".to_string(),
+                amount: Uint128::from(102u128),
+                msg: to_binary(&ReceivedMsg::GamePoolBidSubmit(GamePoolBidSubmitCommand {
+                    gamer: "gamer002".to_string(),
+                    pool_type: "oneToTwo".to_string(),
+                    pool_id: Some("1".to_string()),
+                    team_id: "Team001".to_string(),
+                })).unwrap(),
+            },
+            false,
+        ).unwrap();
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "cwtoken11111".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "feewallet".to_string(),
+                amount: Uint128::from(2u128),
+            }).unwrap(),
+            funds: vec![],
+        }));
+        cancel_game(deps.as_mut(), mock_env(), adminInfo.clone()).unwrap();
+
+        let fury_refund = |gamer: &str| CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "cwtoken11111".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: gamer.to_string(),
+                amount: Uint128::from(100u128),
+            }).unwrap(),
+            funds: vec![],
+        });
+        let rsp = claim_refund(deps.as_mut(), mock_info("gamer001", &[]), "gamer001".to_string(), None, mock_env(), Some(true)).unwrap();
+        let msgs: Vec<CosmosMsg> = rsp.messages.into_iter().map(|sub_msg| sub_msg.msg).collect();
+        assert_eq!(msgs.len(), 2);
+        assert!(msgs.contains(&fury_refund("gamer001")));
+        assert!(msgs.contains(&CosmosMsg::Bank(BankMsg::Send {
+            to_address: "gamer001".to_string(),
+            amount: vec![coin(20, "uusd")],
+        })));
+
+        // Only the deposit comes back of the CW20 Send bid
+        let rsp = claim_refund(deps.as_mut(), mock_info("gamer002", &[]), "gamer002".to_string(), None, mock_env(), Some(true)).unwrap();
+        assert_eq!(rsp.messages.len(), 1);
+        assert_eq!(rsp.messages[0].msg, fury_refund("gamer002"));
+    }
+
+    #[test]
+    fn test_manager_bids_and_claims_refund_for_gamer() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        let managerInfo = mock_info("manager001", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            2,
+            vec![],
+            None,
+        ).unwrap();
+        create_pool(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+        ).unwrap();
+        let bid = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, team_id: &str| game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("manager001", &[]),
+            "gamer001".to_string(),
+            "oneToTwo".to_string(),
+            Some("1".to_string()),
+            team_id.to_string(),
+            Uint128::from(150000u128),
+            true,
+        );
+
+        // No budget given yet
+        let err = bid(&mut deps, "Team001").unwrap_err();
+        assert_eq!(err, ContractError::NoAllowance {});
+
+        let expires = Expiration::AtHeight(mock_env().block.height + 10);
+        execute_increase_allowance(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[]),
+            "manager001".to_string(),
+            Uint128::from(200000u128),
+            Some(expires),
+        ).unwrap();
+
+        // The FURY comes from the gamer and the budget is drawn down
+        let rsp = bid(&mut deps, "Team001").unwrap();
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "cwtoken11111".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: "gamer001".to_string(),
+                recipient: mock_env().contract.address.to_string(),
+                amount: Uint128::from(150000u128),
+            }).unwrap(),
+            funds: vec![],
+        }));
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer001".to_string()).unwrap();
+        assert_eq!(team.gamer_address, "gamer001".to_string());
+        let allowances = query_all_allowances(deps.as_ref(), "gamer001".to_string(), None, None).unwrap();
+        assert_eq!(allowances.allowances.len(), 1);
+        assert_eq!(allowances.allowances[0].spender, "manager001".to_string());
+        assert_eq!(allowances.allowances[0].allowance, Uint128::from(50000u128));
+        assert_eq!(allowances.allowances[0].expires, expires);
+
+        // Not enough budget left for another bid
+        bid(&mut deps, "Team002").unwrap_err();
+
+        // Refunds of the manager are paid to the gamer
+        cancel_game(deps.as_mut(), mock_env(), adminInfo.clone()).unwrap();
+        let err = claim_refund(deps.as_mut(), mock_info("stranger", &[]), "gamer001".to_string(), None, mock_env(), Some(true)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized { invoker: "stranger".to_string() });
+        let mut expired_env = mock_env();
+        expired_env.block.height += 10;
+        let err = claim_refund(deps.as_mut(), managerInfo.clone(), "gamer001".to_string(), None, expired_env, Some(true)).unwrap_err();
+        assert_eq!(err, ContractError::Expired {});
+        let rsp = claim_refund(deps.as_mut(), managerInfo.clone(), "gamer001".to_string(), None, mock_env(), Some(true)).unwrap();
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "cwtoken11111".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "gamer001".to_string(),
+                amount: Uint128::from(150000u128),
+            }).unwrap(),
+            funds: vec![],
+        }));
+    }
+
+    #[test]
+    fn test_season_leaderboard_and_close_season() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            1,
+            vec![WalletPercentage {
+                wallet_address: "rake_1".to_string(),
+                wallet_name: "rake_1".to_string(),
+                percentage: 100,
+            }],
+            None,
+        ).unwrap();
+        create_pool(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+        ).unwrap();
+        for gamer in ["gamer001", "gamer002", "gamer003"] {
+            game_pool_bid_submit(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(gamer, &[]),
+                gamer.to_string(),
+                "oneToTwo".to_string(),
+                Some("1".to_string()),
+                "Team001".to_string(),
+                Uint128::from(144262u128),
+                true,
+            ).unwrap();
+        }
+        start_season(deps.as_mut(), adminInfo.clone(), "Season001".to_string(), 50, vec![60, 40]).unwrap();
+        start_season(deps.as_mut(), adminInfo.clone(), "Season002".to_string(), 50, vec![100]).unwrap_err();
+        lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None).unwrap();
+
+        // Results of a team which is not in the pool earn no points and a
+        // team sent again in a later batch only counts once
+        let first_batch = vec![
+            GameResult {
+                gamer_address: "gamer001".to_string(),
+                team_id: "Team001".to_string(),
+                reward_amount: Uint128::from(100u128),
+                team_points: 10,
+            },
+            GameResult {
+                gamer_address: "gamer004".to_string(),
+                team_id: "Team009".to_string(),
+                reward_amount: Uint128::from(100u128),
+                team_points: 99,
+            },
+        ];
+        game_pool_reward_distribute(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "Game001".to_string(),
+            "1".to_string(),
+            first_batch,
+            false,
+            true,
+            Uint128::zero(),
+        ).unwrap();
+
+        let mut game_results = Vec::new();
+        for (gamer, points) in [("gamer001", 10u64), ("gamer002", 30u64), ("gamer003", 20u64)] {
+            game_results.push(GameResult {
+                gamer_address: gamer.to_string(),
+                team_id: "Team001".to_string(),
+                reward_amount: Uint128::from(100u128),
+                team_points: points,
+            });
+        }
+        game_pool_reward_distribute(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "Game001".to_string(),
+            "1".to_string(),
+            game_results,
+            true,
+            true,
+            Uint128::from(1000u128),
+        ).unwrap();
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer002".to_string()).unwrap();
+        assert_eq!(team.team_points, 30u64);
+
+        // Half of the rake goes to the prize pot
+        let season = query_season_details(&deps.storage, "Season001".to_string()).unwrap();
+        assert_eq!(season.prize_pot, Uint128::from(500u128));
+
+        let leaderboard = query_season_leaderboard(&deps.storage, "Season001".to_string(), None, None).unwrap();
+        let order: Vec<(String, u64)> = leaderboard.into_iter().map(|s| (s.gamer_address, s.points)).collect();
+        assert_eq!(order, vec![
+            ("gamer002".to_string(), 30u64),
+            ("gamer003".to_string(), 20u64),
+            ("gamer001".to_string(), 10u64),
+        ]);
+        let page = query_season_leaderboard(&deps.storage, "Season001".to_string(), Some("gamer002".to_string()), Some(1)).unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].gamer_address, "gamer003".to_string());
+
+        close_season(deps.as_mut(), mock_info("gamer001", &[])).unwrap_err();
+        let rsp = close_season(deps.as_mut(), adminInfo.clone()).unwrap();
+        assert_eq!(rsp.messages.len(), 2);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "gamer002".to_string(),
+            amount: vec![coin(300, "uusd")],
+        }));
+        assert_eq!(rsp.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "gamer003".to_string(),
+            amount: vec![coin(200, "uusd")],
+        }));
+        assert!(query_season_details(&deps.storage, "Season001".to_string()).unwrap().closed);
+        close_season(deps.as_mut(), adminInfo.clone()).unwrap_err();
+    }
+
+    #[test]
+    fn test_referrer_gets_share_of_rake() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        let gamerInfo = mock_info("gamer001", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            1,
+            vec![WalletPercentage {
+                wallet_address: "rake_1".to_string(),
+                wallet_name: "rake_1".to_string(),
+                percentage: 100,
+            }],
+            None,
+        ).unwrap();
+        create_pool(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+        ).unwrap();
+        set_referral_percentage(deps.as_mut(), gamerInfo.clone(), 50).unwrap_err();
+        set_referral_percentage(deps.as_mut(), adminInfo.clone(), 50).unwrap();
+
+        let err = register_referrer(deps.as_mut(), gamerInfo.clone(), "gamer001".to_string()).unwrap_err();
+        assert_eq!(err, ContractError::CannotSetOwnAccount {});
+        register_referrer(deps.as_mut(), gamerInfo.clone(), "referrer01".to_string()).unwrap();
+        register_referrer(deps.as_mut(), gamerInfo.clone(), "referrer02".to_string()).unwrap_err();
+        assert_eq!(query_referrer(&deps.storage, "gamer001".to_string()).unwrap(), Some("referrer01".to_string()));
+
+        for gamer in ["gamer001", "gamer002"] {
+            game_pool_bid_submit(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(gamer, &[]),
+                gamer.to_string(),
+                "oneToTwo".to_string(),
+                Some("1".to_string()),
+                "Team001".to_string(),
+                Uint128::from(144262u128),
+                true,
+            ).unwrap();
+        }
+        lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None).unwrap();
+        let rsp = game_pool_reward_distribute(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "Game001".to_string(),
+            "1".to_string(),
+            vec![GameResult {
+                gamer_address: "gamer002".to_string(),
+                team_id: "Team001".to_string(),
+                reward_amount: Uint128::from(100u128),
+                team_points: 0,
+            }],
+            true,
+            false,
+            Uint128::from(1000u128),
+        ).unwrap();
+        // Half of the 500 rake share of the referred team is kept for the referrer
+        assert_eq!(rsp.messages.len(), 1);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "rake_1".to_string(),
+            amount: vec![coin(750, "uusd")],
+        }));
+        let stats = query_referral_stats(&deps.storage, "referrer01".to_string()).unwrap();
+        assert_eq!(stats.referred_gamers, 1);
+        assert_eq!(stats.total_earned, Uint128::from(250u128));
+        assert_eq!(stats.unclaimed, Uint128::from(250u128));
+
+        let rsp = claim_referral_rewards(deps.as_mut(), mock_info("referrer01", &[])).unwrap();
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "referrer01".to_string(),
+            amount: vec![coin(250, "uusd")],
+        }));
+        assert_eq!(query_referral_stats(&deps.storage, "referrer01".to_string()).unwrap().unclaimed, Uint128::zero());
+        claim_referral_rewards(deps.as_mut(), mock_info("referrer01", &[])).unwrap_err();
+    }
+
+    #[test]
+    fn test_activation_status_pauses_bids_and_claims() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        let gamerInfo = mock_info("gamer001", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            1,
+            vec![],
+            None,
+        ).unwrap();
+        create_pool(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+        ).unwrap();
+        game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "oneToTwo".to_string(),
+            Some("1".to_string()),
+            "Team001".to_string(),
+            Uint128::from(144262u128),
+            true,
+        ).unwrap();
+
+        set_activation_status(deps.as_mut(), gamerInfo.clone(), false, None).unwrap_err();
+        set_activation_status(deps.as_mut(), adminInfo.clone(), false, None).unwrap();
+        let status = query_activation_status(&deps.storage).unwrap();
+        assert!(!status.activation_status);
+        assert!(status.claims_activation_status);
+
+        let err = create_pool(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+        ).unwrap_err();
+        assert_eq!(err, ContractError::ContractPaused {});
+        let err = game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer002", &[]),
+            "gamer002".to_string(),
+            "oneToTwo".to_string(),
+            None,
+            "Team001".to_string(),
+            Uint128::from(144262u128),
+            true,
+        ).unwrap_err();
+        assert_eq!(err, ContractError::ContractPaused {});
+        let err = update_team(
+            deps.as_mut(),
+            gamerInfo.clone(),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team001".to_string(),
+            vec![],
+            None,
+        ).unwrap_err();
+        assert_eq!(err, ContractError::ContractPaused {});
+
+        // Refunds stay open till paused on their own
+        cancel_game(deps.as_mut(), mock_env(), adminInfo.clone()).unwrap();
+        set_activation_status(deps.as_mut(), adminInfo.clone(), false, Some(false)).unwrap();
+        let err = claim_refund(deps.as_mut(), gamerInfo.clone(), "gamer001".to_string(), None, mock_env(), Some(true)).unwrap_err();
+        assert_eq!(err, ContractError::ClaimsPaused {});
+        set_activation_status(deps.as_mut(), adminInfo.clone(), false, Some(true)).unwrap();
+        claim_refund(deps.as_mut(), gamerInfo.clone(), "gamer001".to_string(), None, mock_env(), Some(true)).unwrap();
+
+        // The whitelist restriction is only queried once turned on, and
+        // never for the messages of the admin
+        set_whitelist_check(deps.as_mut(), adminInfo.clone(), true).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            gamerInfo.clone(),
+            ExecuteMsg::RegisterReferrer { referrer: "gamer002".to_string() },
+        ).unwrap_err();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            ExecuteMsg::SetActivationStatus { activation_status: true, claims_activation_status: None },
+        ).unwrap();
+        assert!(query_activation_status(&deps.storage).unwrap().activation_status);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            ExecuteMsg::SetWhitelistCheck { enabled: false },
+        ).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            gamerInfo.clone(),
+            ExecuteMsg::RegisterReferrer { referrer: "gamer002".to_string() },
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_whitelist_check_covers_cw20_senders_and_gamers() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            1,
+            vec![],
+            None,
+        ).unwrap();
+        set_whitelist_check(deps.as_mut(), adminInfo.clone(), true).unwrap();
+        // Only gamer666 is restricted by the FURY token
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "cwtoken11111" => {
+                match from_binary(msg).unwrap() {
+                    cw20_base::msg::QueryMsg::WhitelistRestriction { wallet_address, .. } => {
+                        SystemResult::Ok(ContractResult::Ok(to_binary(&(wallet_address == "gamer666")).unwrap()))
+                    }
+                    _ => panic!("unexpected query"),
+                }
+            }
+            _ => SystemResult::Ok(ContractResult::Err("no proxy".to_string())),
+        });
+
+        let send_bid = |gamer: &str| ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: gamer.to_string(),
+            amount: Uint128::from(144262u128),
+            msg: to_binary(&ReceivedMsg::GamePoolBidSubmit(GamePoolBidSubmitCommand {
+                gamer: gamer.to_string(),
+                pool_type: "oneToTwo".to_string(),
+                pool_id: None,
+                team_id: "Team001".to_string(),
+            })).unwrap(),
+        });
+        // The CW20 sender is checked rather than the token contract
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("cwtoken11111", &[]),
+            send_bid("gamer666"),
+        ).unwrap_err();
+        assert_eq!(err, ContractError::UserIsRestricted {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("cwtoken11111", &[]),
+            send_bid("gamer001"),
+        ).unwrap_err();
+        assert_ne!(err, ContractError::UserIsRestricted {});
+
+        // Bids and claims made by a manager check the gamer too
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("manager001", &[]),
+            ExecuteMsg::GamePoolBidSubmitCommand {
+                gamer: "gamer666".to_string(),
+                pool_type: "oneToTwo".to_string(),
+                pool_id: None,
+                team_id: "Team001".to_string(),
+                amount: Uint128::from(144262u128),
+            },
+        ).unwrap_err();
+        assert_eq!(err, ContractError::UserIsRestricted {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("manager001", &[]),
+            ExecuteMsg::ClaimRefund {
+                gamer: "gamer666".to_string(),
+                pool_ids: None,
+            },
+        ).unwrap_err();
+        assert_eq!(err, ContractError::UserIsRestricted {});
+    }
+
+    // Answers the FURY balance of the contract and the proxy simulation
+    // and swap fee queries
+    fn mock_swap_queries(fury_balance: u128, return_amount: u128) -> impl Fn(&WasmQuery) -> QuerierResult {
+        move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } => {
+                let response = if contract_addr == "cwtoken11111" {
+                    to_binary(&BalanceResponse { balance: Uint128::from(fury_balance) })
+                } else {
+                    match from_binary(msg).unwrap() {
+                        QueryMsgSimulation::Simulation { .. } => to_binary(&SimulationResponse {
+                            return_amount: Uint128::from(return_amount),
+                            spread_amount: Uint128::zero(),
+                            commission_amount: Uint128::zero(),
+                        }),
+                        _ => to_binary(&Uint128::from(10u128)),
+                    }
+                };
+                SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        }
+    }
+
+    #[test]
+    fn test_swap_stores_min_receive_and_reply_handles_failures() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            1,
+            vec![],
+            None,
+        ).unwrap();
+        create_pool(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+        ).unwrap();
+
+        swap(deps.as_mut(), mock_env(), adminInfo.clone(), Uint128::zero(), "1".to_string(), None).unwrap_err();
+
+        deps.querier.update_wasm(mock_swap_queries(5000, 1000));
+        let rsp = swap(deps.as_mut(), mock_env(), adminInfo.clone(), Uint128::from(100u128), "1".to_string(), Some(Decimal::percent(2))).unwrap();
+        assert_eq!(rsp.messages[0].reply_on, ReplyOn::Always);
+        let swap_info = query_swap_data_for_pool(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(swap_info.min_fury_expected, Uint128::from(980u128));
+        assert_eq!(swap_info.balance_pre_swap, Uint128::from(5000u128));
+
+        // A failed swap marks the pool for a retry
+        reply(deps.as_mut(), mock_env(), Reply {
+            id: 1,
+            result: SubMsgResult::Err("max spread assertion".to_string()),
+        }).unwrap();
+        assert!(query_swap_data_for_pool(&deps.storage, "1".to_string()).unwrap().swap_failed);
+
+        // Too little FURY received marks the pool as well, without a rate
+        deps.querier.update_wasm(mock_swap_queries(5500, 1000));
+        let rsp = reply(deps.as_mut(), mock_env(), Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse { events: vec![], data: None }),
+        }).unwrap();
+        assert_eq!(rsp.attributes[0].value, "min receive not met".to_string());
+        let swap_info = query_swap_data_for_pool(&deps.storage, "1".to_string()).unwrap();
+        assert!(swap_info.swap_failed);
+        assert_eq!(swap_info.exchange_rate, Uint128::zero());
+
+        deps.querier.update_wasm(mock_swap_queries(5990, 1000));
+        reply(deps.as_mut(), mock_env(), Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse { events: vec![], data: None }),
+        }).unwrap();
+        let swap_info = query_swap_data_for_pool(&deps.storage, "1".to_string()).unwrap();
+        assert!(!swap_info.swap_failed);
+        assert_eq!(swap_info.exchange_rate, Uint128::from(99000u128));
+
+        // No rate can be worked out without the UST swapped
+        let mut swap_info = swap_info;
+        swap_info.ust_amount_swapped = Uint128::zero();
+        SWAP_BALANCE_INFO.save(&mut deps.storage, "1".to_string(), &swap_info).unwrap();
+        let err = reply(deps.as_mut(), mock_env(), Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse { events: vec![], data: None }),
+        }).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::DivideByZero { .. })));
+        assert_eq!(query_swap_data_for_pool(&deps.storage, "1".to_string()).unwrap().exchange_rate, Uint128::from(99000u128));
+    }
+
+    #[test]
+    fn test_pools_paid_in_entry_asset_skip_the_swap() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(100u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        let rake_list = vec![WalletPercentage {
+            wallet_address: "rake_1".to_string(),
+            wallet_name: "rake_1".to_string(),
+            percentage: 100,
+        }];
+        let other_token = AssetInfo::Token { contract_addr: "othertoken".to_string() };
+
+        // Tokens other than FURY and USDC have to be whitelisted first
+        let err = set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "tokenPool".to_string(),
+            Uint128::from(1000u128),
+            2,
+            10,
+            1,
+            rake_list.clone(),
+            Some(other_token.clone()),
+        ).unwrap_err();
+        assert_eq!(err, ContractError::EntryAssetNotWhitelisted { asset: "othertoken".to_string() });
+        set_entry_asset_whitelist(deps.as_mut(), adminInfo.clone(), other_token.clone(), true).unwrap();
+        for (pool_type, entry_asset) in [("usdcPool", AssetInfo::NativeToken { denom: "uusd".to_string() }), ("tokenPool", other_token.clone())] {
+            set_pool_type_params(
+                deps.as_mut(),
+                mock_env(),
+                adminInfo.clone(),
+                pool_type.to_string(),
+                Uint128::from(1000u128),
+                2,
+                10,
+                1,
+                rake_list.clone(),
+                Some(entry_asset),
+            ).unwrap();
+            create_pool(
+                deps.as_mut(),
+                mock_env(),
+                adminInfo.clone(),
+                pool_type.to_string(),
+            ).unwrap();
+        }
+
+        // USDC bids bring the pool fee and the 2% fees along
+        let usdc_bid = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, gamer: &str, funds: u128| game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(gamer, &[coin(funds, "uusd")]),
+            gamer.to_string(),
+            "usdcPool".to_string(),
+            Some("1".to_string()),
+            "Team001".to_string(),
+            Uint128::from(1000u128),
+            false,
+        );
+        let err = usdc_bid(&mut deps, "gamer001", 1019).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientEntryFunds { asset: "uusd".to_string() });
+        // The fees go on to the platform fees collector and anything sent
+        // above the bid comes back
+        let rsp = usdc_bid(&mut deps, "gamer001", 1020).unwrap();
+        assert_eq!(rsp.messages.len(), 1);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "feewallet".to_string(),
+            amount: vec![coin(20, "uusd")],
+        }));
+        let rsp = usdc_bid(&mut deps, "gamer002", 1025).unwrap();
+        assert_eq!(rsp.messages.len(), 2);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "gamer002".to_string(),
+            amount: vec![coin(5, "uusd")],
+        }));
+        let team = query_team_details(&deps.storage, "1".to_string(), "Team001".to_string(), "gamer002".to_string()).unwrap();
+        assert_eq!(team.entry_asset_deposited, Uint128::from(1000u128));
+        assert_eq!(team.fury_deposited, Uint128::zero());
+
+        // CW20 bids come in the entry asset token only
+        let token_bid = Cw20ReceiveMsg {
+            sender: "gamer003".to_string(),
+            amount: Uint128::from(1020u128),
+            msg: to_binary(&ReceivedMsg::GamePoolBidSubmit(GamePoolBidSubmitCommand {
+                gamer: "gamer003".to_string(),
+                pool_type: "tokenPool".to_string(),
+                pool_id: Some("2".to_string()),
+                team_id: "Team001".to_string(),
+            })).unwrap(),
+        };
+        let err = received_message(deps.as_mut(), mock_env(), mock_info("cwtoken11111", &[]), token_bid.clone(), false).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized { invoker: "cwtoken11111".to_string() });
+        let rsp = received_message(deps.as_mut(), mock_env(), mock_info("othertoken", &[]), token_bid, false).unwrap();
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "othertoken".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "feewallet".to_string(),
+                amount: Uint128::from(20u128),
+            }).unwrap(),
+            funds: vec![],
+        }));
+        let team = query_team_details(&deps.storage, "2".to_string(), "Team001".to_string(), "gamer003".to_string()).unwrap();
+        assert_eq!(team.entry_asset_deposited, Uint128::from(1000u128));
+
+        // Nothing to swap at lock
+        let rsp = lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), false, None).unwrap();
+        assert_eq!(rsp.messages.len(), 0);
+        assert!(query_pool_details(&deps.storage, "2".to_string()).unwrap().pool_refund_status);
+
+        // The refund gives back the deposit in the token, the fees went on to the collector
+        let rsp = claim_refund(deps.as_mut(), mock_info("gamer003", &[]), "gamer003".to_string(), None, mock_env(), None).unwrap();
+        assert_eq!(rsp.messages.len(), 1);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "othertoken".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "gamer003".to_string(),
+                amount: Uint128::from(1000u128),
+            }).unwrap(),
+            funds: vec![],
+        }));
+
+        // Rake and rewards are paid in USDC, with the same slice of the
+        // rake for the season as other pools
+        start_season(deps.as_mut(), adminInfo.clone(), "Season001".to_string(), 50, vec![100]).unwrap();
+        let rsp = game_pool_reward_distribute(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "Game001".to_string(),
+            "1".to_string(),
+            vec![GameResult {
+                gamer_address: "gamer001".to_string(),
+                team_id: "Team001".to_string(),
+                reward_amount: Uint128::from(1800u128),
+                team_points: 5,
+            }],
+            true,
+            false,
+            Uint128::from(200u128),
+        ).unwrap();
+        assert_eq!(rsp.messages.len(), 1);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "rake_1".to_string(),
+            amount: vec![coin(100, "uusd")],
+        }));
+        let season = query_season_details(&deps.storage, "Season001".to_string()).unwrap();
+        assert_eq!(season.prize_pot, Uint128::zero());
+        assert_eq!(season.prize_pot_assets, vec![Asset {
+            info: AssetInfo::NativeToken { denom: "uusd".to_string() },
+            amount: Uint128::from(100u128),
+        }]);
+        let rsp = close_season(deps.as_mut(), adminInfo.clone()).unwrap();
+        assert_eq!(rsp.messages.len(), 1);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "gamer001".to_string(),
+            amount: vec![coin(100, "uusd")],
+        }));
+        let rsp = claim_reward(deps.as_mut(), mock_info("gamer001", &[]), "gamer001".to_string(), None, mock_env()).unwrap();
+        assert_eq!(rsp.messages.len(), 1);
+        assert_eq!(rsp.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: "gamer001".to_string(),
+            amount: vec![coin(1800, "uusd")],
+        }));
+    }
+
+    #[test]
+    fn test_game_events_log_bids_locks_and_payouts() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(144262u128),
+            2,
+            10,
+            2,
+            vec![],
+            None,
+        ).unwrap();
+        for _ in 0..2 {
+            create_pool(
+                deps.as_mut(),
+                mock_env(),
+                adminInfo.clone(),
+                "oneToTwo".to_string(),
+            ).unwrap();
+        }
+        for (gamer, pool_id, team_id) in [("gamer001", "1", "Team001"), ("gamer002", "1", "Team001"), ("gamer003", "2", "Team001"), ("gamer001", "1", "Team002")] {
+            game_pool_bid_submit(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(gamer, &[]),
+                gamer.to_string(),
+                "oneToTwo".to_string(),
+                Some(pool_id.to_string()),
+                team_id.to_string(),
+                Uint128::from(144262u128),
+                true,
+            ).unwrap();
+        }
+        withdraw_team(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[]),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team002".to_string(),
+            true,
+            None,
+        ).unwrap();
+        lock_game(deps.as_mut(), mock_env(), adminInfo.clone(), true, None).unwrap();
+        claim_refund(deps.as_mut(), mock_info("gamer003", &[]), "gamer003".to_string(), None, mock_env(), Some(true)).unwrap();
+        game_pool_reward_distribute(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "Game001".to_string(),
+            "1".to_string(),
+            vec![GameResult {
+                gamer_address: "gamer001".to_string(),
+                team_id: "Team001".to_string(),
+                reward_amount: Uint128::from(250000u128),
+                team_points: 0,
+            }],
+            true,
+            true,
+            Uint128::from(30000u128),
+        ).unwrap();
+
+        let events = query_game_events(&deps.storage, "Game001".to_string(), None, None).unwrap();
+        assert_eq!(events.len(), 9);
+        assert_eq!(events[0].event_id, 1);
+        assert_eq!(events[0].block_time, mock_env().block.time);
+        assert_eq!(events[0].event, GameEvent::BidSubmitted {
+            pool_id: "1".to_string(),
+            gamer: "gamer001".to_string(),
+            team_id: "Team001".to_string(),
+            amount: Uint128::from(144262u128),
+        });
+        assert_eq!(events[4].event, GameEvent::TeamWithdrawn {
+            pool_id: "1".to_string(),
+            gamer: "gamer001".to_string(),
+            team_id: "Team002".to_string(),
+        });
+        assert_eq!(events[5].event, GameEvent::GameLocked { fury_swapped: Uint128::from(288524u128) });
+
+        // Paging continues after the last event seen
+        let events = query_game_events(&deps.storage, "Game001".to_string(), Some(7), Some(5)).unwrap();
+        assert_eq!(events.iter().map(|e| e.event.clone()).collect::<Vec<_>>(), vec![
+            GameEvent::RewardAssigned {
+                pool_id: "1".to_string(),
+                gamer: "gamer001".to_string(),
+                team_id: "Team001".to_string(),
+                amount: Uint128::from(250000u128),
+            },
+            GameEvent::RakeDistributed {
+                pool_id: "1".to_string(),
+                amount: Uint128::from(30000u128),
+            },
+        ]);
+
+        let events = query_pool_events(&deps.storage, "2".to_string(), None, None).unwrap();
+        assert_eq!(events.iter().map(|e| e.event_id).collect::<Vec<_>>(), vec![3, 7]);
+        assert_eq!(events[1].event, GameEvent::RefundClaimed {
+            pool_id: "2".to_string(),
+            gamer: "gamer003".to_string(),
+            team_id: "Team001".to_string(),
+            amount: Uint128::from(144262u128),
+        });
+    }
+
+    #[test]
+    fn test_wagering_limits_and_self_exclusion() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100000u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(300000u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            instantiate_msg,
+        ).unwrap();
+        set_pool_type_params(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+            Uint128::from(100000u128),
+            1,
+            10,
+            5,
+            vec![],
+            None,
+        ).unwrap();
+        create_pool(
+            deps.as_mut(),
+            mock_env(),
+            adminInfo.clone(),
+            "oneToTwo".to_string(),
+        ).unwrap();
+        execute_set_wagering_limits(
+            deps.as_mut(),
+            adminInfo.clone(),
+            WageringLimits {
+                per_game: Uint128::zero(),
+                per_day: Uint128::from(300000u128),
+                per_window: Uint128::zero(),
+            },
+            7200,
+            86400,
+        ).unwrap();
+        let bid = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, env: Env, gamer: &str, team_id: &str| game_pool_bid_submit(
+            deps.as_mut(),
+            env,
+            mock_info(gamer, &[]),
+            gamer.to_string(),
+            "oneToTwo".to_string(),
+            Some("1".to_string()),
+            team_id.to_string(),
+            Uint128::from(100000u128),
+            true,
+        );
+        let mut later = mock_env();
+        later.block.time = later.block.time.plus_seconds(7200);
+
+        // A stricter self imposed window limit
+        execute_set_self_imposed_limits(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[]),
+            WageringLimits {
+                per_game: Uint128::zero(),
+                per_day: Uint128::zero(),
+                per_window: Uint128::from(150000u128),
+            },
+        ).unwrap();
+        bid(&mut deps, mock_env(), "gamer001", "Team001").unwrap();
+        let err = bid(&mut deps, mock_env(), "gamer001", "Team002").unwrap_err();
+        assert_eq!(err, ContractError::WageringLimitExceeded {
+            limit: "window".to_string(),
+            remaining: Uint128::from(50000u128),
+        });
+
+        // The limit cannot be loosened during the cooldown
+        let err = execute_set_self_imposed_limits(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("gamer001", &[]),
+            WageringLimits::default(),
+        ).unwrap_err();
+        assert_eq!(err, ContractError::LimitCooldownActive {
+            until: mock_env().block.time.seconds() + 86400,
+        });
+
+        // Once out of the window only the daily limit is left to watch
+        bid(&mut deps, later.clone(), "gamer001", "Team002").unwrap();
+        let limits = query_wagering_limits(&deps.storage, &later.block, "gamer001".to_string()).unwrap();
+        assert_eq!(limits.remaining_for_game, None);
+        assert_eq!(limits.remaining_for_day, Some(Uint128::from(100000u128)));
+        assert_eq!(limits.remaining_for_window, Some(Uint128::from(50000u128)));
+
+        // Self excluded wallets cannot bid till the exclusion is over
+        execute_self_exclude(deps.as_mut(), mock_env(), mock_info("gamer002", &[]), 3600).unwrap();
+        let err = bid(&mut deps, mock_env(), "gamer002", "Team001").unwrap_err();
+        assert_eq!(err, ContractError::SelfExcluded {
+            until: mock_env().block.time.seconds() + 3600,
+        });
+        bid(&mut deps, later, "gamer002", "Team001").unwrap();
+    }
+
+    #[test]
+    fn test_wagering_limits_count_the_fury_entered() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(100u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(deps.as_mut(), mock_env(), adminInfo.clone(), instantiate_msg).unwrap();
+        // The mocked proxy prices FURY at 10 UST
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let response = match from_binary(msg).unwrap() {
+                    ProxyQueryMsgs::get_fury_equivalent_to_ust { ust_count, .. } => ust_count.multiply_ratio(1u128, 10u128),
+                    ProxyQueryMsgs::get_ust_equivalent_to_fury { fury_count, .. } => fury_count * Uint128::from(10u128),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&response).unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        });
+        let other_token = AssetInfo::Token { contract_addr: "othertoken".to_string() };
+        set_entry_asset_whitelist(deps.as_mut(), adminInfo.clone(), other_token.clone(), true).unwrap();
+        for (pool_type, entry_asset) in [
+            ("furyPool", None),
+            ("usdcPool", Some(AssetInfo::NativeToken { denom: "uusd".to_string() })),
+            ("tokenPool", Some(other_token)),
+        ] {
+            set_pool_type_params(
+                deps.as_mut(),
+                mock_env(),
+                adminInfo.clone(),
+                pool_type.to_string(),
+                Uint128::from(1000u128),
+                2,
+                10,
+                5,
+                vec![],
+                entry_asset,
+            ).unwrap();
+            create_pool(deps.as_mut(), mock_env(), adminInfo.clone(), pool_type.to_string()).unwrap();
+        }
+        execute_set_wagering_limits(
+            deps.as_mut(),
+            adminInfo.clone(),
+            WageringLimits {
+                per_game: Uint128::from(150u128),
+                per_day: Uint128::zero(),
+                per_window: Uint128::zero(),
+            },
+            7200,
+            86400,
+        ).unwrap();
+        let remaining_for_game = |deps: &cosmwasm_std::OwnedDeps<_, _, _>| query_wagering_limits(
+            &deps.storage,
+            &mock_env().block,
+            "gamer001".to_string(),
+        ).unwrap().remaining_for_game;
+        let usdc_bid = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, gamer: &str, team_id: &str| game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(gamer, &[coin(1020, "uusd")]),
+            gamer.to_string(),
+            "usdcPool".to_string(),
+            Some("2".to_string()),
+            team_id.to_string(),
+            Uint128::from(1000u128),
+            false,
+        );
+        let token_bid = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, gamer: &str| received_message(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("othertoken", &[]),
+            Cw20ReceiveMsg {
+                sender: gamer.to_string(),
+                amount: Uint128::from(1020u128),
+                msg: to_binary(&ReceivedMsg::GamePoolBidSubmit(GamePoolBidSubmitCommand {
+                    gamer: gamer.to_string(),
+                    pool_type: "tokenPool".to_string(),
+                    pool_id: Some("3".to_string()),
+                    team_id: "Team001".to_string(),
+                })).unwrap(),
+            },
+            false,
+        );
+
+        let fury_bid = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, team_id: &str| game_pool_bid_submit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[coin(20, "uusd")]),
+            "gamer001".to_string(),
+            "furyPool".to_string(),
+            Some("1".to_string()),
+            team_id.to_string(),
+            Uint128::from(100u128),
+            false,
+        );
+
+        // Only the 100 FURY entered are booked, bids in other assets enter none
+        fury_bid(&mut deps, "Team001").unwrap();
+        assert_eq!(remaining_for_game(&deps), Some(Uint128::from(50u128)));
+        usdc_bid(&mut deps, "gamer001", "Team001").unwrap();
+        token_bid(&mut deps, "gamer001").unwrap();
+        assert_eq!(remaining_for_game(&deps), Some(Uint128::from(50u128)));
+        let err = fury_bid(&mut deps, "Team002").unwrap_err();
+        assert_eq!(err, ContractError::WageringLimitExceeded {
+            limit: "game".to_string(),
+            remaining: Uint128::from(50u128),
+        });
+
+        // A withdrawn team gives its room back
+        withdraw_team(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[]),
+            "gamer001".to_string(),
+            "1".to_string(),
+            "Team001".to_string(),
+            false,
+            None,
+        ).unwrap();
+        assert_eq!(remaining_for_game(&deps), Some(Uint128::from(150u128)));
+        fury_bid(&mut deps, "Team002").unwrap();
+        assert_eq!(remaining_for_game(&deps), Some(Uint128::from(50u128)));
+
+        // Self excluded wallets cannot bid in any asset
+        execute_self_exclude(deps.as_mut(), mock_env(), mock_info("gamer002", &[]), 3600).unwrap();
+        let excluded = ContractError::SelfExcluded {
+            until: mock_env().block.time.seconds() + 3600,
+        };
+        assert_eq!(usdc_bid(&mut deps, "gamer002", "Team001").unwrap_err(), excluded);
+        assert_eq!(token_bid(&mut deps, "gamer002").unwrap_err(), excluded);
+
+        // So do refunded teams
+        cancel_game(deps.as_mut(), mock_env(), adminInfo.clone()).unwrap();
+        claim_refund(
+            deps.as_mut(),
+            mock_info("gamer001", &[]),
+            "gamer001".to_string(),
+            None,
+            mock_env(),
+            Some(false),
+        ).unwrap();
+        assert_eq!(remaining_for_game(&deps), Some(Uint128::from(150u128)));
+    }
+
+    #[test]
+    fn test_challenge_stakes_count_against_wagering_limits() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(100u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        let furyInfo = mock_info("cwtoken11111", &[]);
+        instantiate(deps.as_mut(), mock_env(), adminInfo.clone(), instantiate_msg).unwrap();
+        set_challenge_params(
+            deps.as_mut(),
+            adminInfo.clone(),
+            Uint128::from(100u128),
+            Uint128::from(1000u128),
+            Uint128::from(1000u128),
+            vec![WalletPercentage {
+                wallet_address: "rake_1".to_string(),
+                wallet_name: "rake_1".to_string(),
+                percentage: 100,
+            }],
+        ).unwrap();
+        execute_set_wagering_limits(
+            deps.as_mut(),
+            adminInfo.clone(),
+            WageringLimits {
+                per_game: Uint128::zero(),
+                per_day: Uint128::from(800u128),
+                per_window: Uint128::zero(),
+            },
+            7200,
+            86400,
+        ).unwrap();
+        let challenge = |sender: &str, amount: u128, msg: ReceivedMsg| Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&msg).unwrap(),
+        };
+        let create = |opponent: &str| ReceivedMsg::CreateChallenge(CreateChallengeCommand {
+            opponent: opponent.to_string(),
+            team_id: "Team001".to_string(),
+        });
+        let accept = ReceivedMsg::AcceptChallenge(AcceptChallengeCommand {
+            challenge_id: "1".to_string(),
+            team_id: "Team002".to_string(),
+        });
+        let remaining_for_day = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, gamer: &str| query_wagering_limits(
+            &deps.storage,
+            &mock_env().block,
+            gamer.to_string(),
+        ).unwrap().remaining_for_day;
+
+        // The stake of the creator is booked against the limits
+        received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer001", 500, create("gamer002")), true).unwrap();
+        assert_eq!(remaining_for_day(&deps, "gamer001"), Some(Uint128::from(300u128)));
+        let err = received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer001", 500, create("gamer003")), true).unwrap_err();
+        assert_eq!(err, ContractError::WageringLimitExceeded {
+            limit: "day".to_string(),
+            remaining: Uint128::from(300u128),
+        });
+
+        // Self excluded wallets can neither accept nor open a challenge
+        execute_self_exclude(deps.as_mut(), mock_env(), mock_info("gamer002", &[]), 3600).unwrap();
+        let excluded = ContractError::SelfExcluded {
+            until: mock_env().block.time.seconds() + 3600,
+        };
+        let err = received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer002", 500, accept.clone()), true).unwrap_err();
+        assert_eq!(err, excluded);
+        let err = received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer002", 500, create("gamer001")), true).unwrap_err();
+        assert_eq!(err, excluded);
+        assert_eq!(query_challenge_details(&deps.storage, "1".to_string()).unwrap().challenge_status, CHALLENGE_OPEN);
+
+        // The stake of the opponent is booked once accepted
+        received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer003", 200, create("gamer004")), true).unwrap();
+        let accept_second = ReceivedMsg::AcceptChallenge(AcceptChallengeCommand {
+            challenge_id: "2".to_string(),
+            team_id: "Team002".to_string(),
+        });
+        received_message(deps.as_mut(), mock_env(), furyInfo.clone(), challenge("gamer004", 200, accept_second), true).unwrap();
+        assert_eq!(remaining_for_day(&deps, "gamer004"), Some(Uint128::from(600u128)));
+
+        // A reclaimed stake no longer counts against the limits
+        cancel_game(deps.as_mut(), mock_env(), adminInfo.clone()).unwrap();
+        reclaim_challenge_stake(deps.as_mut(), mock_info("gamer001", &[]), "1".to_string()).unwrap();
+        assert_eq!(remaining_for_day(&deps, "gamer001"), Some(Uint128::from(800u128)));
+        reclaim_challenge_stake(deps.as_mut(), mock_info("gamer004", &[]), "2".to_string()).unwrap();
+        assert_eq!(remaining_for_day(&deps, "gamer004"), Some(Uint128::from(800u128)));
+    }
+
+    #[test]
+    fn test_long_exclusions_and_cooldowns_do_not_overflow() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(100u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(deps.as_mut(), mock_env(), adminInfo.clone(), instantiate_msg).unwrap();
+        execute_set_wagering_limits(
+            deps.as_mut(),
+            adminInfo,
+            WageringLimits::default(),
+            7200,
+            u64::MAX,
+        ).unwrap();
+
+        let res = execute_set_self_imposed_limits(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gamer001", &[]),
+            WageringLimits::default(),
+        ).unwrap();
+        assert!(res.attributes.contains(&attr("loosen_allowed_at", u64::MAX.to_string())));
+        let res = execute_self_exclude(deps.as_mut(), mock_env(), mock_info("gamer001", &[]), u64::MAX).unwrap();
+        assert!(res.attributes.contains(&attr("excluded_until", u64::MAX.to_string())));
+    }
+
+    #[test]
+    fn test_price_twap_window_is_passed_to_the_proxy() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            transaction_fee: Uint128::from(100u128),
+            minting_contract_address: "cwtoken11111".to_string(),
+            admin_address: "admin11111".to_string(),
+            platform_fee: Uint128::from(100u128),
+            game_id: "Game001".to_string(),
+            platform_fees_collector_wallet: "feewallet".to_string(),
+            astro_proxy_address: "astroport".to_string(),
+            usdc_ibc_symbol: "uusd".to_string(),
+        };
+        let adminInfo = mock_info("admin11111", &[]);
+        instantiate(deps.as_mut(), mock_env(), adminInfo.clone(), instantiate_msg).unwrap();
+
+        // The mocked proxy prices FURY at 10 UST spot and at 8 UST over a 1 hour TWAP
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                let response = match from_binary(msg).unwrap() {
+                    ProxyQueryMsgs::get_fury_equivalent_to_ust { ust_count, twap_window_secs } => match twap_window_secs {
+                        Some(3600) => ust_count.multiply_ratio(1u128, 8u128),
+                        None => ust_count.multiply_ratio(1u128, 10u128),
+                        Some(_) => panic!("unexpected window"),
+                    },
+                    ProxyQueryMsgs::get_ust_equivalent_to_fury { fury_count, twap_window_secs } => match twap_window_secs {
+                        Some(3600) => fury_count * Uint128::from(8u128),
+                        None => fury_count * Uint128::from(10u128),
+                        Some(_) => panic!("unexpected window"),
+                    },
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&response).unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        });
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(fury_equivalent_to_ust(deps.as_ref(), &config, Uint128::from(800u128)).unwrap(), Uint128::from(80u128));
+        assert_eq!(ust_equivalent_to_fury(deps.as_ref(), &config, Uint128::from(100u128)).unwrap(), Uint128::from(1000u128));
+
+        let err = set_price_twap_window(deps.as_mut(), mock_info("gamer001", &[]), Some(3600)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized { invoker: "gamer001".to_string() });
+        // The proxy keeps a week of observations
+        for window_secs in [0, 7 * 24 * 60 * 60 + 1] {
+            let err = set_price_twap_window(deps.as_mut(), adminInfo.clone(), Some(window_secs)).unwrap_err();
+            assert_eq!(err, ContractError::InvalidTwapWindow { max_secs: 604800 });
+        }
+
+        set_price_twap_window(deps.as_mut(), adminInfo.clone(), Some(3600)).unwrap();
+        assert_eq!(fury_equivalent_to_ust(deps.as_ref(), &config, Uint128::from(800u128)).unwrap(), Uint128::from(100u128));
+        assert_eq!(ust_equivalent_to_fury(deps.as_ref(), &config, Uint128::from(100u128)).unwrap(), Uint128::from(800u128));
+
+        // Back to the spot price
+        set_price_twap_window(deps.as_mut(), adminInfo.clone(), None).unwrap();
+        assert_eq!(fury_equivalent_to_ust(deps.as_ref(), &config, Uint128::from(800u128)).unwrap(), Uint128::from(80u128));
+    }
+}
@@ -0,0 +1,295 @@
+//! Bond market. A bond series sells a capped amount of discounted FURY for
+//! one kind of investment on one pair. Every sale adds to the debt of the
+//! series, which lowers the discount of the next bond, and the debt pays
+//! itself off over time so the discount recovers. Bonds of a series vest linearly. Without
+//! an open series bonds are sold at the discount rates of the pair and vest
+//! all at once at the end of its bonding period.
+
+use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Timestamp, Uint128};
+
+use terraswap::asset::AssetInfo;
+
+use crate::contract::fury_equivalent_in_pair;
+use crate::error::ContractError;
+use crate::msg::{BondKind, BondQuoteResponse};
+use crate::pairs::{load_pair_config, pair_key, same_pair};
+use crate::state::{ACTIVE_BOND_SERIES, BOND_SERIES, BOND_SERIES_COUNT, BONDED_REWARDS_DETAILS, BondSeries,
+                   BondedRewardsDetails, Config, CONFIG};
+
+const HUNDRED_PERCENT: u16 = 10000;
+
+/// Terms a bond is sold at
+#[derive(Debug)]
+pub struct BondTerms {
+    pub series_id: Option<u64>,
+    pub discount_rate: u16,
+    /// FURY bonded, discount included
+    pub fury_amount: Uint128,
+    pub vesting_period_in_sec: u64,
+    pub linear_vesting: bool,
+}
+
+/// Key of the open series of the kind on the pair. The FURY/UST pair keeps
+/// the bare [`BondKind::as_str`] key.
+pub fn active_series_key(kind: BondKind, pair: &Option<[AssetInfo; 2]>) -> String {
+    match pair {
+        Some(asset_infos) => format!("{}:{}", pair_key(asset_infos), kind.as_str()),
+        None => kind.as_str().to_string(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_bond_series(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    kind: BondKind,
+    capacity: Uint128,
+    max_discount_rate: u16,
+    min_discount_rate: u16,
+    debt_decay_period_in_sec: u64,
+    vesting_period_in_sec: u64,
+    pair: Option<[AssetInfo; 2]>,
+) -> Result<Response, ContractError> {
+    // Fails on pairs which are not registered
+    let config = load_pair_config(deps.as_ref(), &pair)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {});
+    }
+    if capacity.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err("Bond series needs some capacity")));
+    }
+    if min_discount_rate > max_discount_rate || max_discount_rate > HUNDRED_PERCENT {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Discount rates must satisfy min <= max <= 100 percent",
+        )));
+    }
+
+    let mut resp = Response::new().add_attribute("action", "create_bond_series");
+    let active_key = active_series_key(kind, &pair);
+    if let Some(previous) = ACTIVE_BOND_SERIES.may_load(deps.storage, &active_key)? {
+        BOND_SERIES.update(deps.storage, previous, |series| -> StdResult<_> {
+            let mut series = series.ok_or_else(|| StdError::not_found("BondSeries"))?;
+            series.closed = true;
+            Ok(series)
+        })?;
+        resp = resp.add_attribute("closed_series_id", previous.to_string());
+    }
+
+    let series_id = BOND_SERIES_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    BOND_SERIES_COUNT.save(deps.storage, &series_id)?;
+    BOND_SERIES.save(
+        deps.storage,
+        series_id,
+        &BondSeries {
+            series_id,
+            kind,
+            pair: pair.clone(),
+            capacity,
+            sold: Uint128::zero(),
+            max_discount_rate,
+            min_discount_rate,
+            debt_decay_period_in_sec,
+            vesting_period_in_sec,
+            debt: Uint128::zero(),
+            debt_updated_at: env.block.time.seconds(),
+            closed: false,
+        },
+    )?;
+    ACTIVE_BOND_SERIES.save(deps.storage, &active_key, &series_id)?;
+
+    Ok(resp
+        .add_attribute("series_id", series_id.to_string())
+        .add_attribute("kind", kind.as_str())
+        .add_attribute("pair", pair.as_ref().map(pair_key).unwrap_or_default()))
+}
+
+pub fn close_bond_series(deps: DepsMut, info: MessageInfo, series_id: u64) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut series = BOND_SERIES.load(deps.storage, series_id)?;
+    series.closed = true;
+    BOND_SERIES.save(deps.storage, series_id, &series)?;
+    let active_key = active_series_key(series.kind, &series.pair);
+    if ACTIVE_BOND_SERIES.may_load(deps.storage, &active_key)? == Some(series_id) {
+        ACTIVE_BOND_SERIES.remove(deps.storage, &active_key);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "close_bond_series")
+        .add_attribute("series_id", series_id.to_string()))
+}
+
+/// Works out the terms of a bond on the pair for `fury_pre_discount` and
+/// books it against the open series of the kind on the pair, if there is
+/// one. `config` is the config of the pair.
+pub fn sell_bond(
+    storage: &mut dyn Storage,
+    config: &Config,
+    pair: &Option<[AssetInfo; 2]>,
+    kind: BondKind,
+    now: u64,
+    fury_pre_discount: Uint128,
+) -> Result<BondTerms, ContractError> {
+    let terms = bond_terms(storage, config, pair, kind, now, fury_pre_discount)?;
+    if let Some(series_id) = terms.series_id {
+        let mut series = BOND_SERIES.load(storage, series_id)?;
+        let remaining = series.capacity.saturating_sub(series.sold);
+        if terms.fury_amount > remaining {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Bond series {} has only {} FURY left",
+                series_id, remaining
+            ))));
+        }
+        series.sold += terms.fury_amount;
+        series.debt = current_debt(&series, now) + terms.fury_amount;
+        series.debt_updated_at = now;
+        BOND_SERIES.save(storage, series_id, &series)?;
+    }
+    Ok(terms)
+}
+
+fn bond_terms(
+    storage: &dyn Storage,
+    config: &Config,
+    pair: &Option<[AssetInfo; 2]>,
+    kind: BondKind,
+    now: u64,
+    fury_pre_discount: Uint128,
+) -> StdResult<BondTerms> {
+    let series = match ACTIVE_BOND_SERIES.may_load(storage, &active_series_key(kind, pair))? {
+        Some(series_id) => Some(BOND_SERIES.load(storage, series_id)?),
+        None => None,
+    };
+    let (series_id, discount_rate, vesting_period_in_sec, linear_vesting) = match &series {
+        Some(series) => (
+            Some(series.series_id),
+            discount_rate(series, now),
+            series.vesting_period_in_sec,
+            true,
+        ),
+        None => match kind {
+            BondKind::Pair => (None, config.pair_discount_rate, config.pair_bonding_period_in_sec, false),
+            BondKind::Native => (None, config.native_discount_rate, config.native_bonding_period_in_sec, false),
+        },
+    };
+    // The discount is paid on top of the FURY equivalent
+    let fury_amount = fury_pre_discount.multiply_ratio(
+        u128::from(HUNDRED_PERCENT) + u128::from(discount_rate),
+        HUNDRED_PERCENT,
+    );
+    Ok(BondTerms {
+        series_id,
+        discount_rate,
+        fury_amount,
+        vesting_period_in_sec,
+        linear_vesting,
+    })
+}
+
+// The debt left of the last sale after paying itself off since
+fn current_debt(series: &BondSeries, now: u64) -> Uint128 {
+    if series.debt_decay_period_in_sec == 0 {
+        return Uint128::zero();
+    }
+    let elapsed = now
+        .saturating_sub(series.debt_updated_at)
+        .min(series.debt_decay_period_in_sec);
+    series
+        .debt
+        .multiply_ratio(series.debt_decay_period_in_sec - elapsed, series.debt_decay_period_in_sec)
+}
+
+fn discount_rate(series: &BondSeries, now: u64) -> u16 {
+    let debt = current_debt(series, now).min(series.capacity);
+    let spread = Uint128::from(series.max_discount_rate - series.min_discount_rate);
+    let reduction = spread.multiply_ratio(debt, series.capacity);
+    series.max_discount_rate - reduction.u128() as u16
+}
+
+/// FURY of the bond vested by `now`. Bonds taken before the swap opening
+/// start vesting at the swap opening.
+pub fn vested_amount(bond: &BondedRewardsDetails, swap_opening_date: Timestamp, now: u64) -> Uint128 {
+    let start = if bond.bonding_start_timestamp.seconds() == 0 {
+        swap_opening_date.seconds()
+    } else {
+        bond.bonding_start_timestamp.seconds()
+    };
+    let end = start + bond.bonding_period;
+    if now >= end {
+        return bond.bonded_amount;
+    }
+    if !bond.linear_vesting || now <= start {
+        return Uint128::zero();
+    }
+    bond.bonded_amount.multiply_ratio(now - start, bond.bonding_period)
+}
+
+/// FURY of the bonds vested by `now` and not claimed yet
+pub fn claimable_amount(bonds: &[BondedRewardsDetails], swap_opening_date: Timestamp, now: u64) -> Uint128 {
+    bonds
+        .iter()
+        .map(|bond| vested_amount(bond, swap_opening_date, now).saturating_sub(bond.claimed_amount))
+        .sum()
+}
+
+/// Bonds of the user bought on the pair
+pub fn bonds_on_pair(
+    storage: &dyn Storage,
+    user_address: String,
+    pair: &Option<[AssetInfo; 2]>,
+) -> StdResult<Vec<BondedRewardsDetails>> {
+    Ok(BONDED_REWARDS_DETAILS
+        .may_load(storage, user_address)?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|bond| same_pair(&bond.pair, pair))
+        .collect())
+}
+
+pub fn query_claimable_reward(
+    deps: Deps,
+    env: &Env,
+    user_address: String,
+    pair: Option<[AssetInfo; 2]>,
+) -> StdResult<Uint128> {
+    let config = load_pair_config(deps, &pair)?;
+    let bonds = bonds_on_pair(deps.storage, user_address, &pair)?;
+    Ok(claimable_amount(&bonds, config.swap_opening_date, env.block.time.seconds()))
+}
+
+pub fn query_bond_series(deps: Deps, series_id: u64) -> StdResult<BondSeries> {
+    BOND_SERIES.load(deps.storage, series_id)
+}
+
+pub fn query_bond_quote(
+    deps: Deps,
+    env: &Env,
+    kind: BondKind,
+    ust_amount: Uint128,
+    pair: Option<[AssetInfo; 2]>,
+) -> StdResult<BondQuoteResponse> {
+    let config = load_pair_config(deps, &pair)?;
+    let mut fury_pre_discount = fury_equivalent_in_pair(deps, &config.pool_pair_address, ust_amount)?;
+    if kind == BondKind::Pair {
+        fury_pre_discount *= Uint128::from(2u128);
+    }
+    let now = env.block.time.seconds();
+    let terms = bond_terms(deps.storage, &config, &pair, kind, now, fury_pre_discount)?;
+    let capacity_remaining = match terms.series_id {
+        Some(series_id) => {
+            let series = BOND_SERIES.load(deps.storage, series_id)?;
+            Some(series.capacity.saturating_sub(series.sold))
+        }
+        None => None,
+    };
+    Ok(BondQuoteResponse {
+        series_id: terms.series_id,
+        discount_rate: terms.discount_rate,
+        fury_amount: terms.fury_amount,
+        capacity_remaining,
+        vesting_period_in_sec: terms.vesting_period_in_sec,
+        linear_vesting: terms.linear_vesting,
+    })
+}
@@ -1,8 +1,8 @@
-use cosmwasm_std::{Addr, BankMsg, Binary, Coin,
+use cosmwasm_std::{Addr, attr, Attribute, BankMsg, Binary, Coin,
                    ContractResult, CosmosMsg, Decimal, Deps,
                    DepsMut, entry_point, Env, from_binary,
                    MessageInfo, Reply, ReplyOn, Response,
-                   StdError, StdResult, Storage, SubMsg, SubMsgResult,
+                   StdError, StdResult, Storage, SubMsg,
                    Timestamp, to_binary, Uint128, Uint64, WasmMsg};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
@@ -14,11 +14,25 @@ use terraswap::pair::{
 use terraswap::pair::ExecuteMsg as PairExecuteMsg;
 use terraswap::pair::QueryMsg::{Pair, Pool, ReverseSimulation, Simulation};
 
+use crate::bonds::{bonds_on_pair, claimable_amount, close_bond_series, create_bond_series, query_bond_quote,
+                   query_bond_series, query_claimable_reward, sell_bond, vested_amount};
+use crate::cw20_hooks::{deposit_funds, query_deposit, receive_provide_liquidity, receive_swap, withdraw_funds};
 use crate::error::ContractError;
-use crate::msg::{CW20Custom, ExecuteMsg, InstantiateMsg, ProxyCw20HookMsg, QueryMsg};
+use crate::fees::{FeeSettlement, settle_platform_fees};
+use crate::flow::{advance, next_sub_req_id, query_stale_requests, recover_stale_request};
+use crate::lp_staking::{check_lp_token_change, check_lp_token_current, claim_lp_rewards, configure_lp_staking,
+                        query_lp_staker, query_lp_stakers, query_lp_unbonding, receive_lp, unstake_lp,
+                        withdraw_unbonded_lp};
+use crate::msg::{BondKind, CW20Custom, Cw20FeePayment, ExecuteMsg, InstantiateMsg, ProxyCw20HookMsg, QueryMsg, UpdateConfigMsg};
+use crate::oracle::{clear_price_observations, observe_price, query_cumulative_prices, query_twap_fury_to_ust,
+                    record_price_observation, twap_fury_equivalent_to_ust, twap_ust_equivalent_to_fury};
+use crate::pairs::{deregister_pair, load_pair_config, native_spent, pair_of_liquidity_token, query_managed_pair,
+                   query_managed_pairs, register_pair, same_pair};
+use crate::router::{continue_swap_operations, execute_swap_operations, query_reverse_simulate_swap_operations,
+                    query_simulate_swap_operations, route_fee_base};
 use crate::state::{
     BONDED_REWARDS_DETAILS, BondedRewardsDetails, Config, CONFIG, CONTRACT,
-    ContractVersion, SUB_MESSAGE_DETAILS, SUB_REQ_ID,
+    ContractVersion, CW20_FEE_PAYMENT, LP_STAKING, PENDING_ADMIN, SUB_MESSAGE_DETAILS, TERRASWAP_FACTORY,
     SubMessageDetails, SubMessageNextAction, SubMessageType,
 };
 
@@ -112,7 +126,7 @@ fn hello_sub(
     });
     //TESTING AS SUB
     let mut send: SubMsg = SubMsg::new(exec.clone());
-    let mut sub_req_id = 1;
+    let sub_req_id = next_sub_req_id(deps.storage)?;
     send.id = sub_req_id;
     send.reply_on = ReplyOn::Always;
     let mut resp = Response::new();
@@ -124,13 +138,35 @@ fn hello_sub(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // Observed before the message moves the reserves. Admin and continue
+    // messages take no observation, so a failing pair cannot hold them up
+    let observed = match &msg {
+        ExecuteMsg::RecordPriceObservation { pair } => record_price_observation(deps.branch(), &env, pair)?,
+        ExecuteMsg::Configure { .. }
+        | ExecuteMsg::UpdateConfig(_)
+        | ExecuteMsg::ProposeNewAdmin { .. }
+        | ExecuteMsg::AcceptAdmin {}
+        | ExecuteMsg::CreateBondSeries { .. }
+        | ExecuteMsg::CloseBondSeries { .. }
+        | ExecuteMsg::RegisterPair { .. }
+        | ExecuteMsg::DeregisterPair { .. }
+        | ExecuteMsg::ConfigureLpStaking { .. }
+        | ExecuteMsg::RecoverStaleRequest { .. }
+        | ExecuteMsg::ContinueSwapOperations { .. }
+        | ExecuteMsg::ContinueWithdrawLiquidity { .. }
+        | ExecuteMsg::HelloSub {} => false,
+        _ => observe_price(deps.branch(), &env)?,
+    };
     match msg {
         ExecuteMsg::HelloSub {} => hello_sub(deps),
+        ExecuteMsg::RecordPriceObservation { .. } => Ok(Response::new()
+            .add_attribute("action", "record_price_observation")
+            .add_attribute("observed", observed.to_string())),
         ExecuteMsg::Configure {
             pool_pair_address,
             liquidity_token,
@@ -143,6 +179,81 @@ pub fn execute(
             liquidity_token,
             swap_opening_date,
         ),
+        ExecuteMsg::UpdateConfig(update) => update_config(deps, info, update),
+        ExecuteMsg::ProposeNewAdmin { admin_address } => propose_new_admin(deps, info, admin_address),
+        ExecuteMsg::AcceptAdmin {} => accept_admin(deps, info),
+        ExecuteMsg::CreateBondSeries {
+            kind,
+            capacity,
+            max_discount_rate,
+            min_discount_rate,
+            debt_decay_period_in_sec,
+            vesting_period_in_sec,
+            pair,
+        } => create_bond_series(
+            deps,
+            env,
+            info,
+            kind,
+            capacity,
+            max_discount_rate,
+            min_discount_rate,
+            debt_decay_period_in_sec,
+            vesting_period_in_sec,
+            pair,
+        ),
+        ExecuteMsg::CloseBondSeries { series_id } => close_bond_series(deps, info, series_id),
+        ExecuteMsg::RegisterPair {
+            asset_infos,
+            swap_opening_date,
+            settings,
+        } => register_pair(deps, info, asset_infos, swap_opening_date, settings),
+        ExecuteMsg::DeregisterPair { asset_infos } => deregister_pair(deps, info, asset_infos),
+        ExecuteMsg::ConfigureLpStaking {
+            fury_per_second,
+            fury_reward_wallet,
+            fee_share_rate,
+            unbonding_period,
+        } => configure_lp_staking(
+            deps,
+            env,
+            info,
+            fury_per_second,
+            fury_reward_wallet,
+            fee_share_rate,
+            unbonding_period,
+        ),
+        ExecuteMsg::UnstakeLp { amount } => unstake_lp(deps, env, info, amount),
+        ExecuteMsg::WithdrawUnbondedLp {} => withdraw_unbonded_lp(deps, env, info),
+        ExecuteMsg::ClaimLpRewards {} => claim_lp_rewards(deps, env, info),
+        ExecuteMsg::DepositFunds {} => deposit_funds(deps, info),
+        ExecuteMsg::WithdrawFunds {} => withdraw_funds(deps, info),
+        ExecuteMsg::RecoverStaleRequest { id, refund } => recover_stale_request(deps, env, info, id, refund),
+        ExecuteMsg::ExecuteSwapOperations {
+            offer_asset,
+            operations,
+            minimum_receive,
+            to,
+        } => execute_swap_operations(deps, env, info, offer_asset, operations, minimum_receive, to),
+        ExecuteMsg::ContinueSwapOperations {
+            operations,
+            asset_info,
+            balance_before,
+            minimum_receive,
+            to,
+        } => continue_swap_operations(
+            deps,
+            env,
+            info,
+            operations,
+            asset_info,
+            balance_before,
+            minimum_receive,
+            to,
+        ),
+        ExecuteMsg::ContinueWithdrawLiquidity { balances_before, to } => {
+            continue_withdraw_liquidity(deps, env, info, balances_before, to)
+        }
         ExecuteMsg::Receive(received_message) => {
             process_received_message(deps, env, info, received_message)
         }
@@ -150,12 +261,14 @@ pub fn execute(
             assets,
             slippage_tolerance,
             auto_stake,
+            pair,
         } => {
-            let config = CONFIG.load(deps.storage)?;
+            let config = load_pair_config(deps.as_ref(), &pair)?;
             if info.sender != config.authorized_liquidity_provider {
                 return Err(ContractError::Unauthorized {});
             }
 
+            check_auto_stake(deps.as_ref(), auto_stake, &pair)?;
             let receiver = Some(config.authorized_liquidity_provider.to_string());
             provide_liquidity(
                 deps,
@@ -166,72 +279,43 @@ pub fn execute(
                 auto_stake,
                 receiver,
                 SubMessageNextAction::IncreaseAllowance,
-                None,
+                pair,
             )
         }
         ExecuteMsg::ProvidePairForReward {
             assets,
             slippage_tolerance,
             auto_stake,
+            pair,
         } => {
-            let required_ust_fees: Uint128;
-            required_ust_fees = query_platform_fees(
-                deps.as_ref(),
-                to_binary(&ExecuteMsg::ProvidePairForReward {
+            let config = load_pair_config(deps.as_ref(), &pair)?;
+            check_auto_stake(deps.as_ref(), auto_stake, &pair)?;
+            let ust_spent = native_spent(&config, &info, &assets)?;
+            let settlement = settle_platform_fees(
+                deps.branch(),
+                &env,
+                &info,
+                &ExecuteMsg::ProvidePairForReward {
                     assets: assets.clone(),
                     slippage_tolerance: slippage_tolerance.clone(),
                     auto_stake: auto_stake.clone(),
-                })?,
+                    pair: pair.clone(),
+                },
+                ust_spent,
             )?;
-            let mut fees = Uint128::zero();
-            let uusd = get_symbol(&deps)?;
-            for fund in info.funds.clone() {
-                if fund.denom == uusd {
-                    fees = fees.checked_add(fund.amount).unwrap();
-                }
-            }
-            // let mut native_tax = Uint128::zero();
-            for asset in assets.clone() {
-                if asset.is_native_token() {
-                    fees = fees.checked_sub(asset.amount).unwrap();
-                    // native_tax = native_tax
-                    //     .checked_add(asset.compute_tax(&deps.querier)?)
-                    //     .unwrap();
-                }
-            }
-            // fees = fees.checked_sub(native_tax).unwrap();
-            if fees < required_ust_fees {
-                return Err(ContractError::InsufficientFees {
-                    required: required_ust_fees,
-                    received: fees,
-                });
-            }
+            // Only the native tokens provided travel on with the liquidity
             let mut info_to_send = info.clone();
-            let uusd = get_symbol(&deps)?;
-
-            let mut platform_fee_funds = Coin::new(0, format!("{}", uusd.to_string()));
-            if !fees.is_zero() {
-                //Received the platform fees, remove it from funds
-                let mut coin_to_set_in_funds = Coin::new(0, format!("{}", uusd.to_string()));
-                for coin in info.funds.clone() {
-                    if coin.denom == uusd {
-                        coin_to_set_in_funds = Coin {
-                            amount: coin.amount - required_ust_fees,
-                            denom: coin.denom.clone(),
-                        };
-                        platform_fee_funds = Coin {
-                            amount: required_ust_fees,
-                            denom: coin.denom.clone(),
-                        };
+            info_to_send.funds = vec![];
+            for asset in assets.iter() {
+                if let AssetInfo::NativeToken { denom } = &asset.info {
+                    if !asset.amount.is_zero() {
+                        info_to_send.funds.push(Coin::new(asset.amount.u128(), denom));
                     }
                 }
-                info_to_send.funds = vec![coin_to_set_in_funds];
-                // and transfer them to
             }
-            let config = CONFIG.load(deps.storage)?;
             let receiver: Option<String>;
             receiver = Some(config.pair_lp_tokens_holder.to_string());
-            provide_liquidity(
+            let resp = provide_liquidity(
                 deps,
                 env,
                 info_to_send,
@@ -240,24 +324,29 @@ pub fn execute(
                 auto_stake,
                 receiver,
                 SubMessageNextAction::TransferCustomAssetsFromFundsOwner,
-                Some(platform_fee_funds),
-            )
+                pair,
+            )?;
+            Ok(resp
+                .add_attributes(settlement.attributes())
+                .add_messages(settlement.messages))
         }
         ExecuteMsg::ProvideNativeForReward {
             asset,
             slippage_tolerance,
             auto_stake,
+            pair,
         } => {
             if !asset.is_native_token() {
                 return Err(ContractError::Unauthorized {});
             }
-            provide_native_liquidity(deps, env, info, asset, slippage_tolerance, auto_stake)
+            provide_native_liquidity(deps, env, info, asset, slippage_tolerance, auto_stake, pair)
         }
         ExecuteMsg::Swap {
             offer_asset,
             belief_price,
             max_spread,
             to,
+            pair,
         } => {
             //offer_asset.info.check(deps.api)?;
             // if !offer_asset.is_native_token() {
@@ -277,12 +366,14 @@ pub fn execute(
                 belief_price,
                 max_spread,
                 to_addr,
+                pair,
             )
         }
         ExecuteMsg::RewardClaim {
             receiver,
             withdrawal_amount,
-        } => claim_investment_reward(deps, env, info, receiver, withdrawal_amount),
+            pair,
+        } => claim_investment_reward(deps, env, info, receiver, withdrawal_amount, pair),
     }
 }
 
@@ -310,16 +401,166 @@ fn configure_proxy(
     }
 
     if let Some(pool_pair_addr) = pool_pair_address {
+        if pool_pair_addr != config.pool_pair_address {
+            clear_price_observations(deps.storage, &None)?;
+        }
         config.pool_pair_address = pool_pair_addr;
     }
     if let Some(liquidity_token) = liquidity_token {
         config.liquidity_token = deps.api.addr_validate(&liquidity_token)?;
+        if let Some(staking) = LP_STAKING.may_load(deps.storage)? {
+            check_lp_token_change(&staking, &config.liquidity_token)?;
+        }
     }
     config.swap_opening_date = Timestamp::from_nanos(swap_opening_date.u64());
     CONFIG.save(deps.storage, &config)?;
     Ok(Response::default())
 }
 
+fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    update: UpdateConfigMsg,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let validate = |address: Option<String>| address.map(|a| deps.api.addr_validate(&a)).transpose();
+    let mut attrs = vec![attr("action", "update_config")];
+    set_field(&mut config.pair_discount_rate, update.pair_discount_rate, "pair_discount_rate", &mut attrs);
+    set_field(&mut config.pair_bonding_period_in_sec, update.pair_bonding_period_in_sec,
+              "pair_bonding_period_in_sec", &mut attrs);
+    set_field(&mut config.pair_fury_reward_wallet, validate(update.pair_fury_reward_wallet)?,
+              "pair_fury_reward_wallet", &mut attrs);
+    set_field(&mut config.pair_lp_tokens_holder, validate(update.pair_lp_tokens_holder)?,
+              "pair_lp_tokens_holder", &mut attrs);
+    set_field(&mut config.native_discount_rate, update.native_discount_rate, "native_discount_rate", &mut attrs);
+    set_field(&mut config.native_bonding_period_in_sec, update.native_bonding_period_in_sec,
+              "native_bonding_period_in_sec", &mut attrs);
+    set_field(&mut config.native_investment_reward_wallet, validate(update.native_investment_reward_wallet)?,
+              "native_investment_reward_wallet", &mut attrs);
+    set_field(&mut config.native_investment_receive_wallet, validate(update.native_investment_receive_wallet)?,
+              "native_investment_receive_wallet", &mut attrs);
+    set_field(&mut config.authorized_liquidity_provider, validate(update.authorized_liquidity_provider)?,
+              "authorized_liquidity_provider", &mut attrs);
+    set_field(&mut config.platform_fees_collector_wallet, validate(update.platform_fees_collector_wallet)?,
+              "platform_fees_collector_wallet", &mut attrs);
+    set_field(&mut config.platform_fees, update.platform_fees, "platform_fees", &mut attrs);
+    set_field(&mut config.transaction_fees, update.transaction_fees, "transaction_fees", &mut attrs);
+    set_field(&mut config.swap_fees, update.swap_fees, "swap_fees", &mut attrs);
+    set_field(&mut config.max_bonding_limit_per_user, update.max_bonding_limit_per_user,
+              "max_bonding_limit_per_user", &mut attrs);
+    if let Some(terraswap_factory) = validate(update.terraswap_factory)? {
+        if TERRASWAP_FACTORY.may_load(deps.storage)?.as_ref() != Some(&terraswap_factory) {
+            TERRASWAP_FACTORY.save(deps.storage, &terraswap_factory)?;
+            attrs.push(attr("terraswap_factory", terraswap_factory));
+        }
+    }
+    if let Some(cw20_fee_payment) = update.cw20_fee_payment {
+        if CW20_FEE_PAYMENT.may_load(deps.storage)? != Some(cw20_fee_payment) {
+            CW20_FEE_PAYMENT.save(deps.storage, &cw20_fee_payment)?;
+            attrs.push(attr("cw20_fee_payment", cw20_fee_payment.to_string()));
+        }
+    }
+
+    assert_config(&config)?;
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(attrs))
+}
+
+/// Checks the rates, fees and limits of the config make sense together
+pub(crate) fn assert_config(config: &Config) -> Result<(), ContractError> {
+    // The rates are added on top of 100 percent of the FURY equivalent
+    if u128::from(config.pair_discount_rate) > HUNDRED_PERCENT
+        || u128::from(config.native_discount_rate) > HUNDRED_PERCENT
+    {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Discount rate can not be more than 100 percent",
+        )));
+    }
+    // A swap is charged all three fees
+    let total_fees = config.platform_fees
+        .checked_add(config.transaction_fees)
+        .and_then(|fees| fees.checked_add(config.swap_fees))
+        .map_err(StdError::from)?;
+    if total_fees >= Uint128::from(HUNDRED_PERCENT) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Fees together must be less than 100 percent",
+        )));
+    }
+    if config.max_bonding_limit_per_user == 0 {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Bonding limit per user must be at least 1",
+        )));
+    }
+    Ok(())
+}
+
+// Sets the field and records it in the attributes when it changes
+fn set_field<T: PartialEq + ToString>(field: &mut T, value: Option<T>, name: &str, attrs: &mut Vec<Attribute>) {
+    if let Some(value) = value {
+        if *field != value {
+            attrs.push(attr(name, value.to_string()));
+            *field = value;
+        }
+    }
+}
+
+fn propose_new_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin_address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let pending_admin = deps.api.addr_validate(&admin_address)?;
+    PENDING_ADMIN.save(deps.storage, &pending_admin)?;
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_admin")
+        .add_attribute("pending_admin", pending_admin))
+}
+
+fn accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending_admin = PENDING_ADMIN.may_load(deps.storage)?;
+    if pending_admin != Some(info.sender.clone()) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    let previous_admin = config.admin_address;
+    config.admin_address = info.sender;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("previous_admin", previous_admin)
+        .add_attribute("admin_address", config.admin_address))
+}
+
+/// Checks the LP tokens of a provision can be staked when asked to. They go
+/// to the proxy then and are staked for the receiver the provision names.
+pub(crate) fn check_auto_stake(
+    deps: Deps,
+    auto_stake: Option<bool>,
+    pair: &Option<[AssetInfo; 2]>,
+) -> Result<(), ContractError> {
+    if auto_stake != Some(true) {
+        return Ok(());
+    }
+    let staking = match (pair, LP_STAKING.may_load(deps.storage)?) {
+        (None, Some(staking)) => staking,
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Only the LP token of the FURY/UST pair can be staked, once LP staking is set up",
+            )));
+        }
+    };
+    check_lp_token_current(deps.storage, &staking)
+}
+
 fn process_received_message(
     deps: DepsMut,
     env: Env,
@@ -338,31 +579,50 @@ fn process_received_message(
     //     ))));
     // }
     match from_binary(&received_message.msg) {
-        // Ok(ProxyCw20HookMsg::Swap {
-        //     belief_price,
-        //     max_spread,
-        //     to,
-        // }) => {
-        //     let to_address: Option<String>;
-        //     match to {
-        //         Some(to_addr) => to_address = Some(to_addr),
-        //         None => to_address = Some(received_message.sender),
-        //     }
-        //     let swap_msg_to_send = ProxyCw20HookMsg::Swap {
-        //         belief_price: belief_price,
-        //         max_spread: max_spread,
-        //         to: to_address,
-        //     };
-        //     forward_swap_to_astro(deps, info, swap_msg_to_send, received_message.amount)
-        // }
+        Ok(ProxyCw20HookMsg::Swap {
+            belief_price,
+            max_spread,
+            to,
+            pair,
+        }) => receive_swap(
+            deps,
+            env,
+            info,
+            received_message.sender,
+            received_message.amount,
+            belief_price,
+            max_spread,
+            to,
+            pair,
+        ),
+        Ok(ProxyCw20HookMsg::ProvideLiquidity {
+            assets,
+            slippage_tolerance,
+            auto_stake,
+            pair,
+        }) => receive_provide_liquidity(
+            deps,
+            env,
+            info,
+            received_message.sender,
+            received_message.amount,
+            assets,
+            slippage_tolerance,
+            auto_stake,
+            pair,
+        ),
         Ok(ProxyCw20HookMsg::WithdrawLiquidity {}) => {
             withdraw_liquidity(deps, env, info, received_message)
         }
+        Ok(ProxyCw20HookMsg::StakeLp {}) => {
+            receive_lp(deps, env, info, received_message.sender, received_message.amount)
+        }
         Err(err) => Err(ContractError::Std(err)),
     }
     // Ok(Response::default())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn incr_allow_for_provide_liquidity(
     deps: DepsMut,
     env: Env,
@@ -373,9 +633,10 @@ pub fn incr_allow_for_provide_liquidity(
     funds: Vec<Coin>,
     user_address: String,
     is_fury_provided: bool,
+    pair: Option<[AssetInfo; 2]>,
 ) -> Result<Response, ContractError> {
     let mut resp = Response::new();
-    let config: Config = CONFIG.load(deps.storage)?;
+    let config = load_pair_config(deps.as_ref(), &pair)?;
 
     // Get the amount of Fury tokens to be specified in transfer_from and increase_allowance
     let mut amount = Uint128::zero();
@@ -397,14 +658,7 @@ pub fn incr_allow_for_provide_liquidity(
         funds: vec![],
     };
     let mut send_incr_allow: SubMsg = SubMsg::new(exec_incr_allow);
-    let mut sub_req_id = 1;
-    if let Some(mut req_id) = SUB_REQ_ID.may_load(deps.storage)? {
-        req_id += 1;
-        SUB_REQ_ID.save(deps.storage, &req_id)?;
-        sub_req_id = req_id;
-    } else {
-        SUB_REQ_ID.save(deps.storage, &sub_req_id)?;
-    }
+    let sub_req_id = next_sub_req_id(deps.storage)?;
     send_incr_allow.reply_on = ReplyOn::Always;
     send_incr_allow.id = sub_req_id;
 
@@ -428,9 +682,13 @@ pub fn incr_allow_for_provide_liquidity(
             request_type: SubMessageType::ProvideLiquiditySubMsg,
             next_action: SubMessageNextAction::ProvideLiquidity,
             sub_message_payload: to_binary(&pl_msg)?,
-            funds: funds,
+            funds: funds.clone(),
             user_address: user_address,
             is_fury_provided: is_fury_provided,
+            pair,
+            auto_stake: auto_stake == Some(true),
+            created_at_height: env.block.height,
+            escrowed_funds: funds,
         },
     )?;
 
@@ -440,6 +698,7 @@ pub fn incr_allow_for_provide_liquidity(
     ))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn forward_provide_liquidity_to_astro(
     deps: DepsMut,
     env: Env,
@@ -448,8 +707,19 @@ pub fn forward_provide_liquidity_to_astro(
     auto_stake: Option<bool>,
     receiver: Option<String>,
     funds: Vec<Coin>,
+    user_address: String,
+    pair: Option<[AssetInfo; 2]>,
 ) -> Result<Response, ContractError> {
-    let config: Config = CONFIG.load(deps.storage)?;
+    let config = load_pair_config(deps.as_ref(), &pair)?;
+    // Staked LP tokens go to the proxy and are staked for the receiver
+    let stake_for = match auto_stake {
+        Some(true) => receiver.clone(),
+        _ => None,
+    };
+    let receiver = match stake_for {
+        Some(_) => Some(env.contract.address.to_string()),
+        None => receiver,
+    };
 
     let mut funds_to_pass: Vec<Coin> = Vec::new();
     for fund in funds {
@@ -481,16 +751,28 @@ pub fn forward_provide_liquidity_to_astro(
         funds: funds_to_pass,
     };
     let mut send: SubMsg = SubMsg::new(exec);
-    let mut sub_req_id = 1;
-    if let Some(mut req_id) = SUB_REQ_ID.may_load(deps.storage)? {
-        req_id += 1;
-        SUB_REQ_ID.save(deps.storage, &req_id)?;
-        sub_req_id = req_id;
-    } else {
-        SUB_REQ_ID.save(deps.storage, &sub_req_id)?;
-    }
+    let sub_req_id = next_sub_req_id(deps.storage)?;
     send.id = sub_req_id;
     send.reply_on = ReplyOn::Always;
+    if let Some(staker) = stake_for {
+        SUB_MESSAGE_DETAILS.save(
+            deps.storage,
+            sub_req_id.to_string(),
+            &SubMessageDetails {
+                sub_req_id: sub_req_id.to_string(),
+                request_type: SubMessageType::ProvideLiquiditySubMsg,
+                next_action: SubMessageNextAction::StakeLiquidity,
+                sub_message_payload: to_binary(&pl_msg)?,
+                funds: vec![],
+                user_address: staker,
+                is_fury_provided: FURY_PROVIDED,
+                pair,
+                auto_stake: false,
+                created_at_height: env.block.height,
+                escrowed_funds: vec![],
+            },
+        )?;
+    }
 
     let mut resp = Response::new();
     resp = resp.add_submessage(send);
@@ -507,9 +789,10 @@ pub fn forward_swap_to_astro(
     received_message: Cw20HookMsg,
     amount: Uint128,
     funds_to_send: Vec<Coin>,
-    platform_fees: Coin,
+    settlement: FeeSettlement,
+    pair: Option<[AssetInfo; 2]>,
 ) -> Result<Response, ContractError> {
-    let config: Config = CONFIG.load(deps.storage)?;
+    let config = load_pair_config(deps.as_ref(), &pair)?;
     let send_msg = Cw20ExecuteMsg::Send {
         contract: config.pool_pair_address,
         amount: amount,
@@ -545,72 +828,51 @@ pub fn forward_swap_to_astro(
         funds: vec![],
     }));
     // resp = resp.add_submessage(send);
-    let uusd = get_symbol(&deps)?;
     resp = resp.add_message(CosmosMsg::Wasm(exec));
-    //Add bank message to transfer platform fees to platform fee collector wallet
-    let pf_asset = Asset {
-        info: AssetInfo::NativeToken {
-            denom: String::from(uusd),
-        },
-        amount: platform_fees.amount,
-    };
-    // let taxed_platform_fees = pf_asset.deduct_tax(&deps.querier)?;
-    resp = resp.add_message(CosmosMsg::Bank(BankMsg::Send {
-        to_address: config.platform_fees_collector_wallet.into_string(),
-        amount: vec![],
-    }));
+    //Add bank messages settling the platform fees
+    resp = resp
+        .add_attributes(settlement.attributes())
+        .add_messages(settlement.messages);
     Ok(resp.add_attribute("action", "Forwarding swap message to pool pair address"))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn provide_native_liquidity(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     asset: Asset,
     slippage_tolerance: Option<Decimal>,
     auto_stake: Option<bool>,
+    pair: Option<[AssetInfo; 2]>,
 ) -> Result<Response, ContractError> {
     //Check if assets provided are native tokens
     //asset.info.check(deps.api)?;
     if !asset.is_native_token() {
         return Err(ContractError::Unauthorized {});
     }
+    if auto_stake == Some(true) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "A native investment provides no LP tokens to stake",
+        )));
+    }
 
-    //First check if platform fees are provided
-    let required_ust_fees: Uint128;
-    required_ust_fees = query_platform_fees(
-        deps.as_ref(),
-        to_binary(&ExecuteMsg::ProvideNativeForReward {
+    let config = load_pair_config(deps.as_ref(), &pair)?;
+    let ust_spent = native_spent(&config, &info, std::slice::from_ref(&asset))?;
+    let settlement = settle_platform_fees(
+        deps.branch(),
+        &env,
+        &info,
+        &ExecuteMsg::ProvideNativeForReward {
             asset: asset.clone(),
             slippage_tolerance: slippage_tolerance.clone(),
             auto_stake: auto_stake.clone(),
-        })?,
+            pair: pair.clone(),
+        },
+        ust_spent,
     )?;
-    let mut fees = Uint128::zero();
-    let uusd = get_symbol(&deps)?;
-    for fund in info.funds.clone() {
-        if fund.denom == uusd {
-            fees = fees.checked_add(fund.amount).unwrap();
-        }
-    }
-    // let native_tax = asset.compute_tax(&deps.querier)?;
-    //Remove platform fees from funds
-    let funds_to_pass = fees.checked_sub(required_ust_fees).unwrap();
-    fees = fees.checked_sub(asset.amount).unwrap();
-    // fees = fees.checked_sub(native_tax).unwrap();
-    if fees < required_ust_fees {
-        return Err(ContractError::InsufficientFees {
-            required: required_ust_fees,
-            received: fees,
-        });
-    }
-    // Platform fees received is good, now proceed
-    //transfer it to platform_fees_collector_wallet
-    let uusd = get_symbol(&deps)?;
-
-    let mut funds_to_send = vec![Coin::new(funds_to_pass.u128(), String::from(uusd.to_string()))];
-    let platform_fees = Coin::new(required_ust_fees.u128(), format!("{}", uusd));
 
+    let mut funds_to_send = vec![];
     if let AssetInfo::NativeToken { denom, .. } = &asset.info {
         funds_to_send = vec![Coin {
             denom: denom.to_string(),
@@ -618,12 +880,9 @@ pub fn provide_native_liquidity(
         }];
     }
 
-    let config = CONFIG.load(deps.storage)?;
     let assets = [
         Asset {
-            info: AssetInfo::NativeToken {
-                denom: uusd.to_string(),
-            },
+            info: asset.info.clone(),
             amount: asset.amount,
         },
         Asset {
@@ -638,7 +897,7 @@ pub fn provide_native_liquidity(
     receiver = Some(config.native_investment_receive_wallet.to_string());
 
     let user_address = info.sender.into_string();
-    transfer_custom_assets_from_funds_owner_to_proxy(
+    let resp = transfer_custom_assets_from_funds_owner_to_proxy(
         deps,
         env,
         assets,
@@ -648,8 +907,11 @@ pub fn provide_native_liquidity(
         funds_to_send,
         user_address,
         NO_FURY_PROVIDED,
-        Some(platform_fees),
-    )
+        pair,
+    )?;
+    Ok(resp
+        .add_attributes(settlement.attributes())
+        .add_messages(settlement.messages))
 }
 
 pub fn transfer_native_assets_to_native_investment_receive_wallet(
@@ -681,6 +943,7 @@ pub fn transfer_native_assets_to_native_investment_receive_wallet(
     })))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn transfer_custom_assets_from_funds_owner_to_proxy(
     deps: DepsMut,
     env: Env,
@@ -691,7 +954,7 @@ pub fn transfer_custom_assets_from_funds_owner_to_proxy(
     funds: Vec<Coin>,
     user_address: String,
     is_fury_provided: bool,
-    platform_fees: Option<Coin>,
+    pair: Option<[AssetInfo; 2]>,
 ) -> Result<Response, ContractError> {
     let mut fury_amount_provided = Uint128::zero();
     let mut ust_amount_provided = Uint128::zero();
@@ -713,32 +976,33 @@ pub fn transfer_custom_assets_from_funds_owner_to_proxy(
 
     let mut resp = Response::new();
 
-    let config = CONFIG.load(deps.storage)?;
+    let config = load_pair_config(deps.as_ref(), &pair)?;
     let mut fury_equiv_for_ust =
-        get_fury_equivalent_to_ust(deps.as_ref(), ust_amount_provided).unwrap();
+        fury_equivalent_in_pair(deps.as_ref(), &config.pool_pair_address, ust_amount_provided)?;
     let fury_pre_discount;
     let funds_owner;
-    let bonding_period;
-    let mut discounted_rate = 10000u16; // 100 percent
+    let bond_kind;
     if is_fury_provided {
         if fury_equiv_for_ust > fury_amount_provided {
             fury_equiv_for_ust = fury_amount_provided;
         }
         fury_pre_discount = Uint128::from(2u128) * fury_equiv_for_ust;
-        discounted_rate += config.pair_discount_rate;
         funds_owner = config.pair_fury_reward_wallet.to_string();
-        bonding_period = config.pair_bonding_period_in_sec;
+        bond_kind = BondKind::Pair;
     } else {
         fury_pre_discount = fury_equiv_for_ust;
-        discounted_rate += config.native_discount_rate;
         funds_owner = config.native_investment_reward_wallet.to_string();
-        bonding_period = config.native_bonding_period_in_sec;
+        bond_kind = BondKind::Native;
     }
-    let total_fury_amount = fury_pre_discount
-        .checked_mul(Uint128::from(discounted_rate))
-        .unwrap_or_default()
-        .checked_div(Uint128::from(10000u128))
-        .unwrap_or_default();
+    let bond_terms = sell_bond(
+        deps.storage,
+        &config,
+        &pair,
+        bond_kind,
+        env.block.time.seconds(),
+        fury_pre_discount,
+    )?;
+    let total_fury_amount = bond_terms.fury_amount;
 
     // Get the existing bonded_rewards_details for this user
     let mut bonded_rewards_details = Vec::new();
@@ -761,13 +1025,20 @@ pub fn transfer_custom_assets_from_funds_owner_to_proxy(
     let mut bonding_start_timestamp = Timestamp::from_seconds(0u64);
     if config.swap_opening_date < env.block.time {
         bonding_start_timestamp = env.block.time;
+    } else if pair.is_some() {
+        // Zero stands for the opening of the FURY/UST pair
+        bonding_start_timestamp = config.swap_opening_date;
     }
 
     bonded_rewards_details.push(BondedRewardsDetails {
         user_address: user_address.to_string(),
         bonded_amount: total_fury_amount,
-        bonding_period: bonding_period,
+        bonding_period: bond_terms.vesting_period_in_sec,
         bonding_start_timestamp: bonding_start_timestamp,
+        series_id: bond_terms.series_id,
+        linear_vesting: bond_terms.linear_vesting,
+        claimed_amount: Uint128::zero(),
+        pair: pair.clone(),
     });
     BONDED_REWARDS_DETAILS.save(
         deps.storage,
@@ -787,14 +1058,7 @@ pub fn transfer_custom_assets_from_funds_owner_to_proxy(
         funds: vec![],
     };
     let mut send_transfer_from: SubMsg = SubMsg::new(exec_transfer_from);
-    let mut sub_req_id = 1;
-    if let Some(mut req_id) = SUB_REQ_ID.may_load(deps.storage)? {
-        req_id += 1;
-        SUB_REQ_ID.save(deps.storage, &req_id)?;
-        sub_req_id = req_id;
-    } else {
-        SUB_REQ_ID.save(deps.storage, &sub_req_id)?;
-    }
+    let sub_req_id = next_sub_req_id(deps.storage)?;
     send_transfer_from.reply_on = ReplyOn::Always;
     send_transfer_from.id = sub_req_id;
     resp = resp.add_submessage(send_transfer_from);
@@ -815,9 +1079,13 @@ pub fn transfer_custom_assets_from_funds_owner_to_proxy(
                 request_type: SubMessageType::ProvideLiquiditySubMsg,
                 next_action: SubMessageNextAction::IncreaseAllowance,
                 sub_message_payload: to_binary(&pl_msg)?,
-                funds: funds,
+                funds: funds.clone(),
                 user_address: user_address,
                 is_fury_provided: is_fury_provided,
+                pair: pair.clone(),
+                auto_stake: auto_stake == Some(true),
+                created_at_height: env.block.height,
+                escrowed_funds: funds,
             },
         )?;
     } else {
@@ -830,35 +1098,23 @@ pub fn transfer_custom_assets_from_funds_owner_to_proxy(
                 request_type: SubMessageType::ProvideLiquiditySubMsg,
                 next_action: SubMessageNextAction::TransferToNativeInvestmentReceiveWallet,
                 sub_message_payload: to_binary(&pl_msg)?,
-                funds: funds,
+                funds: funds.clone(),
                 user_address: user_address,
                 is_fury_provided: is_fury_provided,
+                pair: pair.clone(),
+                auto_stake: auto_stake == Some(true),
+                created_at_height: env.block.height,
+                escrowed_funds: funds,
             },
         )?;
     }
-    match platform_fees {
-        Some(pf) => {
-            let pf_asset = Asset {
-                info: AssetInfo::NativeToken {
-                    denom: String::from(get_symbol(&deps)?),
-                },
-                amount: pf.amount,
-            };
-            // let taxed_platform_fees = pf_asset.deduct_tax(&deps.querier)?;
-            resp = resp.add_message(CosmosMsg::Bank(BankMsg::Send {
-                to_address: config.platform_fees_collector_wallet.into_string(),
-                amount: vec![],
-            }));
-        }
-        None => {}
-    };
-
     Ok(resp.add_attribute(
         "action",
         "Transferring fury from treasury funds owner to proxy",
     ))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn provide_liquidity(
     deps: DepsMut,
     env: Env,
@@ -868,10 +1124,10 @@ pub fn provide_liquidity(
     auto_stake: Option<bool>,
     receiver: Option<String>,
     next_action: SubMessageNextAction,
-    platform_fee_funds: Option<Coin>,
+    pair: Option<[AssetInfo; 2]>,
 ) -> Result<Response, ContractError> {
     let mut resp = Response::new();
-    let config: Config = CONFIG.load(deps.storage)?;
+    let config = load_pair_config(deps.as_ref(), &pair)?;
     // Get the amount of Fury tokens to be specified in transfer_from and increase_allowance
     let mut amount = Uint128::zero();
     if !assets[0].info.is_native_token() {
@@ -894,14 +1150,7 @@ pub fn provide_liquidity(
         funds: vec![],
     };
     let mut send_transfer_from: SubMsg = SubMsg::new(exec_transfer_from);
-    let mut sub_req_id = 1;
-    if let Some(mut req_id) = SUB_REQ_ID.may_load(deps.storage)? {
-        req_id += 1;
-        SUB_REQ_ID.save(deps.storage, &req_id)?;
-        sub_req_id = req_id;
-    } else {
-        SUB_REQ_ID.save(deps.storage, &sub_req_id)?;
-    }
+    let sub_req_id = next_sub_req_id(deps.storage)?;
     send_transfer_from.reply_on = ReplyOn::Always;
     send_transfer_from.id = sub_req_id;
     resp = resp.add_submessage(send_transfer_from);
@@ -920,26 +1169,15 @@ pub fn provide_liquidity(
             request_type: SubMessageType::ProvideLiquiditySubMsg,
             next_action: next_action,
             sub_message_payload: to_binary(&pl_msg)?,
-            funds: info.funds,
+            funds: info.funds.clone(),
             user_address: user_address.clone(),
             is_fury_provided: FURY_PROVIDED,
+            pair,
+            auto_stake: auto_stake == Some(true),
+            created_at_height: env.block.height,
+            escrowed_funds: info.funds,
         },
     )?;
-    if let Some(platform_fees) = platform_fee_funds {
-        if platform_fees.amount > Uint128::zero() {
-            let pf_asset = Asset {
-                info: AssetInfo::NativeToken {
-                    denom: String::from(get_symbol(&deps)?),
-                },
-                amount: platform_fees.amount,
-            };
-            // let taxed_platform_fees = pf_asset.deduct_tax(&deps.querier)?;
-            resp = resp.add_message(CosmosMsg::Bank(BankMsg::Send {
-                to_address: config.platform_fees_collector_wallet.into_string(),
-                amount: vec![],
-            }));
-        }
-    }
     Ok(resp.add_attribute("action", "Transferring tokens for Provide Liquidity"))
 }
 
@@ -949,11 +1187,22 @@ pub fn withdraw_liquidity(
     info: MessageInfo,
     received_message: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    let config: Config = CONFIG.load(deps.storage)?;
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let mut pair = None;
 
     if info.sender != config.liquidity_token {
-        return Err(ContractError::Unauthorized {});
+        // The LP token of a registered pair goes back to that pair
+        let managed = pair_of_liquidity_token(deps.as_ref(), &info.sender)?.ok_or(ContractError::Unauthorized {})?;
+        pair = Some(managed.asset_infos);
+        config = load_pair_config(deps.as_ref(), &pair)?;
     }
+    // The pair refunds the proxy, which sends on what its balances grew by
+    let pool = query_pool(deps.as_ref(), pair)?;
+    let balances_before = [
+        balance_of(deps.as_ref(), &env, pool.assets[0].info.clone())?,
+        balance_of(deps.as_ref(), &env, pool.assets[1].info.clone())?,
+    ];
+    let provider = deps.api.addr_validate(&received_message.sender)?;
     let wl_msg = Cw20ExecuteMsg::Send {
         contract: config.pool_pair_address.to_string(),
         amount: received_message.amount,
@@ -964,46 +1213,80 @@ pub fn withdraw_liquidity(
         msg: to_binary(&wl_msg).unwrap(),
         funds: info.funds,
     };
+    let next = WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::ContinueWithdrawLiquidity {
+            balances_before,
+            to: provider.to_string(),
+        })?,
+        funds: vec![],
+    };
 
     let send: SubMsg = SubMsg::new(exec);
     let resp = Response::new();
     let data_msg = format!("Withdraw {:?}", wl_msg).into_bytes();
     Ok(resp
         .add_submessage(send)
+        .add_message(next)
         .add_attribute("action", "Forwarding withdraw message to lptoken address")
         .set_data(data_msg))
 }
 
-fn claim_investment_reward(
+// The asset at the balance of the proxy in it
+fn balance_of(deps: Deps, env: &Env, info: AssetInfo) -> StdResult<Asset> {
+    let amount = info.query_pool(&deps.querier, deps.api, env.contract.address.clone())?;
+    Ok(Asset { info, amount })
+}
+
+fn continue_withdraw_liquidity(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    balances_before: [Asset; 2],
+    to: String,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let provider = deps.api.addr_validate(&to)?;
+    let mut refunds = vec![];
+    for before in balances_before {
+        let balance = balance_of(deps.as_ref(), &env, before.info)?;
+        let refund = Asset {
+            amount: balance.amount.checked_sub(before.amount).map_err(StdError::from)?,
+            info: balance.info,
+        };
+        if !refund.amount.is_zero() {
+            refunds.push(refund.into_msg(provider.clone())?);
+        }
+    }
+    Ok(Response::new()
+        .add_messages(refunds)
+        .add_attribute("action", "continue_withdraw_liquidity"))
+}
+
+fn claim_investment_reward(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     receiver: String,
-    withdrawal_amount: Uint128,
+    withdrawal_amount: Option<Uint128>,
+    pair: Option<[AssetInfo; 2]>,
 ) -> Result<Response, ContractError> {
     //Check if platform fees provided is sufficient
-    let required_ust_fees: Uint128;
-    required_ust_fees = query_platform_fees(
-        deps.as_ref(),
-        to_binary(&ExecuteMsg::RewardClaim {
+    let settlement = settle_platform_fees(
+        deps.branch(),
+        &env,
+        &info,
+        &ExecuteMsg::RewardClaim {
             receiver: receiver.clone(),
             withdrawal_amount: withdrawal_amount.clone(),
-        })?,
+            pair: pair.clone(),
+        },
+        Uint128::zero(),
     )?;
-    let mut fees = Uint128::zero();
-    for fund in info.funds.clone() {
-        if fund.denom == get_symbol(&deps)? {
-            fees = fees.checked_add(fund.amount).unwrap();
-        }
-    }
-    if fees < required_ust_fees {
-        return Err(ContractError::InsufficientFees {
-            required: required_ust_fees,
-            received: fees,
-        });
-    }
 
-    let config = CONFIG.load(deps.storage)?;
+    let config = load_pair_config(deps.as_ref(), &pair)?;
     let receiver_addr = deps.api.addr_validate(&receiver)?;
     //Check if withdrawer is same as invoker
     if receiver_addr != info.sender {
@@ -1016,107 +1299,44 @@ fn claim_investment_reward(
         ))));
     }
 
-    let FAR_IN_FUTURE = env.block.time.plus_seconds(2000 * 24 * 60 * 60).seconds();
-
     let action = "claim_investment_reward".to_string();
-    let mut unbonded_amount = Uint128::zero();
-    let mut amount_remaining = withdrawal_amount.clone();
-
-    // parse bonding to check maturity and sort with descending order of timestamp
-    let mut bonds = Vec::new();
-    let mut updated_bonds = Vec::new();
-    let mut earliest = FAR_IN_FUTURE;
-    let mut earliest_amount = Uint128::zero();
-
-    let all_bonds = BONDED_REWARDS_DETAILS.may_load(deps.storage, receiver.clone())?;
-    match all_bonds {
-        Some(some_bonds) => {
-            bonds = some_bonds;
-            let mut updated_bond;
-            for bond in bonds {
-                println!(
-                    "receiver {:?} timestamp  {:?} duration  {:?} amount {:?}",
-                    receiver_addr,
-                    bond.bonding_start_timestamp,
-                    bond.bonding_period,
-                    bond.bonded_amount
-                );
-
-                updated_bond = bond.clone();
-                let _bond_timestamp;
-                if bond.bonding_start_timestamp.seconds() == Timestamp::from_seconds(0u64).seconds()
-                {
-                    _bond_timestamp = config.swap_opening_date;
-                } else {
-                    _bond_timestamp = bond.bonding_start_timestamp;
-                }
-                if _bond_timestamp.plus_seconds(bond.bonding_period).seconds() < earliest {
-                    earliest = _bond_timestamp.plus_seconds(bond.bonding_period).seconds();
-                    earliest_amount = bond.bonded_amount.clone();
-                }
-                if _bond_timestamp.plus_seconds(bond.bonding_period).seconds()
-                    < env.block.time.seconds()
-                {
-                    if amount_remaining > Uint128::zero() {
-                        if bond.bonded_amount > amount_remaining {
-                            unbonded_amount = amount_remaining;
-                            updated_bond.bonded_amount -= amount_remaining;
-                            amount_remaining = Uint128::zero();
-                            updated_bonds.push(updated_bond);
-                        } else {
-                            unbonded_amount += bond.bonded_amount;
-                            amount_remaining -= bond.bonded_amount;
-                        }
-                    } else {
-                        updated_bonds.push(updated_bond);
-                    }
-                } else {
-                    updated_bonds.push(updated_bond);
-                }
-            }
-        }
-        None => {}
+    let now = env.block.time.seconds();
+    let pair_bonds = bonds_on_pair(deps.storage, receiver.clone(), &pair)?;
+    if pair_bonds.is_empty() {
+        return Err(ContractError::Std(StdError::generic_err("No Bonded Rewards")));
     }
-
-    if unbonded_amount == Uint128::zero() {
-        let message;
-        if earliest < FAR_IN_FUTURE {
-            message = format!(
-                "Earliest Withdrawal Amount {:?} at {:?}",
-                earliest_amount, earliest
-            );
-        } else {
-            message = format!("No Bonded Rewards");
-        }
-        return Err(ContractError::Std(StdError::generic_err(message)));
-    } else {
-        if amount_remaining > Uint128::zero() {
-            return Err(ContractError::Std(StdError::generic_err(format!(
-                "Withdraw Amount requested is more than Claimable {:?}",
-                unbonded_amount
-            ))));
-        }
+    let claimable = claimable_amount(&pair_bonds, config.swap_opening_date, now);
+    if claimable.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Nothing vested yet, check the bonding details for the vesting times",
+        )));
+    }
+    let withdrawal_amount = withdrawal_amount.unwrap_or(claimable);
+    if withdrawal_amount > claimable {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Withdraw Amount requested is more than Claimable {:?}",
+            claimable
+        ))));
     }
 
-    BONDED_REWARDS_DETAILS.save(deps.storage, receiver.clone(), &updated_bonds)?;
+    // Claim from the oldest bonds of the pair first and drop the bonds
+    // claimed in full
+    let mut bonds = BONDED_REWARDS_DETAILS.load(deps.storage, receiver.clone())?;
+    let mut amount_remaining = withdrawal_amount;
+    for bond in bonds.iter_mut().filter(|bond| same_pair(&bond.pair, &pair)) {
+        let vested = vested_amount(bond, config.swap_opening_date, now);
+        let claimed = vested.saturating_sub(bond.claimed_amount).min(amount_remaining);
+        bond.claimed_amount += claimed;
+        amount_remaining -= claimed;
+    }
+    bonds.retain(|bond| bond.claimed_amount < bond.bonded_amount);
+    BONDED_REWARDS_DETAILS.save(deps.storage, receiver.clone(), &bonds)?;
 
     let mut rsp = Response::new();
     //Send the platform fees to platform fee collector wallet
-    let mut funds_to_send = vec![];
-    for fund in info.funds {
-        let pf_asset = Asset {
-            info: AssetInfo::NativeToken {
-                denom: String::from(get_symbol(&deps)?),
-            },
-            amount: fund.amount,
-        };
-        // let taxed_platform_fees = pf_asset.deduct_tax(&deps.querier)?;
-        // funds_to_send.push(taxed_platform_fees);
-    }
-    rsp = rsp.add_message(CosmosMsg::Bank(BankMsg::Send {
-        to_address: config.platform_fees_collector_wallet.into_string(),
-        amount: funds_to_send,
-    }));
+    rsp = rsp
+        .add_attributes(settlement.attributes())
+        .add_messages(settlement.messages);
     let transfer_msg = Cw20ExecuteMsg::Transfer {
         recipient: receiver,
         amount: withdrawal_amount,
@@ -1144,15 +1364,16 @@ fn claim_investment_reward(
 
 #[allow(clippy::too_many_arguments)]
 pub fn swap(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     offer_asset: Asset,
     belief_price: Option<Decimal>,
     max_spread: Option<Decimal>,
     to: Option<Addr>,
+    pair: Option<[AssetInfo; 2]>,
 ) -> Result<Response, ContractError> {
-    let config: Config = CONFIG.load(deps.storage)?;
+    let config = load_pair_config(deps.as_ref(), &pair)?;
     // Check if the swap_enable_date is passed
     if config.swap_opening_date.nanos() > env.block.time.nanos() {
         //return error
@@ -1162,33 +1383,20 @@ pub fn swap(
     }
     // Swap is enabled so proceed
     // Check if platform fees is provided
-    let required_ust_fees: Uint128;
-    required_ust_fees = query_platform_fees(
-        deps.as_ref(),
-        to_binary(&ExecuteMsg::Swap {
+    let ust_spent = native_spent(&config, &info, std::slice::from_ref(&offer_asset))?;
+    let settlement = settle_platform_fees(
+        deps.branch(),
+        &env,
+        &info,
+        &ExecuteMsg::Swap {
             offer_asset: offer_asset.clone(),
             belief_price: belief_price.clone(),
             max_spread: max_spread.clone(),
-            to: Some(to.clone().unwrap().into_string()),
-        })?,
+            to: to.clone().map(String::from),
+            pair: pair.clone(),
+        },
+        ust_spent,
     )?;
-    let mut fees = Uint128::zero();
-    for fund in info.funds.clone() {
-        if fund.denom == get_symbol(&deps)? {
-            fees = fees.checked_add(fund.amount).unwrap();
-        }
-    }
-    if offer_asset.is_native_token() {
-        fees = fees.checked_sub(offer_asset.amount).unwrap();
-    }
-    // let native_tax = offer_asset.compute_tax(&deps.querier)?;
-    // fees = fees.checked_sub(native_tax).unwrap();
-    if fees < required_ust_fees {
-        return Err(ContractError::InsufficientFees {
-            required: required_ust_fees,
-            received: fees,
-        });
-    }
     //Platform fees provided is good
     //If offer asset is custom token (sell fury),
     // transfer the asset amount of fury tokens to this contract
@@ -1197,9 +1405,8 @@ pub fn swap(
         Some(to_addr) => to_address = Some(to_addr.into_string()),
         None => to_address = Some(info.sender.clone().into_string()),
     }
-    //Remove platform fees from funds and transfer it to platform_fees_collector_wallet
+    //Only the offer travels on to the pair, the fees are settled separately
     let mut funds_to_send = vec![];
-    let platform_fees = Coin::new(required_ust_fees.u128(), String::from(get_symbol(&deps)?));
     if offer_asset.is_native_token() {
         if let AssetInfo::NativeToken { denom, .. } = &offer_asset.info {
             funds_to_send = vec![Coin {
@@ -1220,7 +1427,8 @@ pub fn swap(
             },
             offer_asset.amount,
             funds_to_send,
-            platform_fees,
+            settlement,
+            pair,
         );
     }
     //Check if assets provided are native tokens
@@ -1237,14 +1445,7 @@ pub fn swap(
         funds: funds_to_send,
     };
     let mut send: SubMsg = SubMsg::new(exec);
-    let mut sub_req_id = 1;
-    if let Some(mut req_id) = SUB_REQ_ID.may_load(deps.storage)? {
-        req_id += 1;
-        SUB_REQ_ID.save(deps.storage, &req_id)?;
-        sub_req_id = req_id;
-    } else {
-        SUB_REQ_ID.save(deps.storage, &sub_req_id)?;
-    }
+    let sub_req_id = next_sub_req_id(deps.storage)?;
     send.id = sub_req_id;
     send.reply_on = ReplyOn::Always;
 
@@ -1252,14 +1453,10 @@ pub fn swap(
     resp = resp.add_submessage(send);
     let data_msg = format!("Swapping {:?}", swap_msg).into_bytes();
 
-    //Add bank message to transfer platform fees to platform fee collector wallet
-    let pf_asset = Asset { info: AssetInfo::NativeToken { denom: String::from(get_symbol(&deps)?) }, amount: platform_fees.amount };
-    // let taxed_platform_fees = pf_asset.deduct_tax(&deps.querier)?;
-
-    resp = resp.add_message(CosmosMsg::Bank(BankMsg::Send {
-        to_address: config.platform_fees_collector_wallet.into_string(),
-        amount: vec![],
-    }));
+    //Add bank messages settling the platform fees
+    resp = resp
+        .add_attributes(settlement.attributes())
+        .add_messages(settlement.messages);
 
     Ok(resp
         .add_attribute("action", "Sending swap message")
@@ -1286,156 +1483,93 @@ pub fn get_symbol(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
-    let result = msg.result;
-    match result {
-        SubMsgResult::Ok(sub_msg) => {
-            let sub_msg_id = msg.id;
-            let sub_message_details =
-                SUB_MESSAGE_DETAILS.may_load(deps.storage, sub_msg_id.to_string())?;
-            match sub_message_details {
-                Some(smd) => {
-                    match smd.request_type {
-                        SubMessageType::TransferFromSubMsg => {
-                            // Remove the saved submessage from storage
-                            SUB_MESSAGE_DETAILS.remove(deps.storage, msg.id.to_string());
-                        }
-                        SubMessageType::IncreaseAlowanceSubMsg => {
-                            // Remove the saved submessage from storage
-                            SUB_MESSAGE_DETAILS.remove(deps.storage, msg.id.to_string());
-                        }
-                        SubMessageType::ProvideLiquiditySubMsg => {
-                            // Remove the saved submessage from storage
-                            SUB_MESSAGE_DETAILS.remove(deps.storage, msg.id.to_string());
-                            match from_binary(&smd.sub_message_payload).unwrap() {
-                                PairExecuteMsg::ProvideLiquidity {
-                                    assets,
-                                    slippage_tolerance,
-                                    //auto_stake,
-                                    receiver,
-                                } => {
-                                    let auto_stake = Some(true);
-                                    if smd.next_action
-                                        == SubMessageNextAction::TransferCustomAssetsFromFundsOwner
-                                    {
-                                        return transfer_custom_assets_from_funds_owner_to_proxy(
-                                            deps,
-                                            env,
-                                            assets,
-                                            slippage_tolerance,
-                                            auto_stake,
-                                            receiver,
-                                            smd.funds,
-                                            smd.user_address,
-                                            smd.is_fury_provided,
-                                            None,
-                                        );
-                                    } else if smd.next_action == SubMessageNextAction::TransferToNativeInvestmentReceiveWallet {
-                                        return transfer_native_assets_to_native_investment_receive_wallet(
-                                            deps,
-                                            receiver,
-                                            smd.funds,
-                                        );
-                                    } else if smd.next_action == SubMessageNextAction::IncreaseAllowance
-                                    {
-                                        return incr_allow_for_provide_liquidity(
-                                            deps,
-                                            env,
-                                            assets,
-                                            slippage_tolerance,
-                                            auto_stake,
-                                            receiver,
-                                            smd.funds,
-                                            smd.user_address,
-                                            smd.is_fury_provided,
-                                        );
-                                    } else if smd.next_action == SubMessageNextAction::ProvideLiquidity
-                                    {
-                                        return forward_provide_liquidity_to_astro(
-                                            deps,
-                                            env,
-                                            assets,
-                                            slippage_tolerance,
-                                            auto_stake,
-                                            receiver,
-                                            smd.funds,
-                                        );
-                                    }
-                                }
-                                _ => {
-                                    return Err(ContractError::Std(StdError::generic_err(
-                                        format!("Should never reach here!!!", ),
-                                    )));
-                                }
-                            }
-                        }
-                    }
-                }
-                None => {}
-            }
-            // For all fall-through messages respond with success
-            let mut resp = Response::new();
-            for event in sub_msg.events {
-                //resp = resp.add_attributes(event.attributes);
-            }
-            match sub_msg.data {
-                Some(d) => resp = resp.set_data(d),
-                None => {}
-            }
-            return Ok(resp);
-        }
-        SubMsgResult::Err(error) => {
-            return Err(ContractError::Std(StdError::generic_err(format!(
-                "Received error: {:?}",
-                error
-            ))));
-        }
-    }
+    advance(deps, env, msg)
 }
 
-
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::Configuration {} => to_binary(&query_configuration(deps)?),
-        QueryMsg::Pair {} => to_binary(&query_pair(deps)?),
-        QueryMsg::Pool {} => to_binary(&query_pool(deps)?),
-        QueryMsg::Simulation { offer_asset } => to_binary(&query_simulation(deps, offer_asset)?),
-        QueryMsg::ReverseSimulation { ask_asset } => {
-            to_binary(&query_reverse_simulation(deps, ask_asset)?)
+        QueryMsg::Configuration { pair } => to_binary(&query_configuration(deps, pair)?),
+        QueryMsg::Pair { pair } => to_binary(&query_pair(deps, pair)?),
+        QueryMsg::Pool { pair } => to_binary(&query_pool(deps, pair)?),
+        QueryMsg::Simulation { offer_asset, pair } => to_binary(&query_simulation(deps, offer_asset, pair)?),
+        QueryMsg::ReverseSimulation { ask_asset, pair } => {
+            to_binary(&query_reverse_simulation(deps, ask_asset, pair)?)
+        }
+        QueryMsg::ManagedPair { asset_infos } => to_binary(&query_managed_pair(deps, asset_infos)?),
+        QueryMsg::ManagedPairs {} => to_binary(&query_managed_pairs(deps)?),
+        QueryMsg::PendingAdmin {} => to_binary(&PENDING_ADMIN.may_load(deps.storage)?),
+        QueryMsg::LpStaking {} => to_binary(&LP_STAKING.may_load(deps.storage)?),
+        QueryMsg::LpStaker { address } => to_binary(&query_lp_staker(deps, &env, address)?),
+        QueryMsg::LpStakers { start_after, limit } => {
+            to_binary(&query_lp_stakers(deps, &env, start_after, limit)?)
+        }
+        QueryMsg::LpUnbonding { address } => to_binary(&query_lp_unbonding(deps, address)?),
+        QueryMsg::BondSeries { series_id } => to_binary(&query_bond_series(deps, series_id)?),
+        QueryMsg::BondQuote { kind, ust_amount, pair } => {
+            to_binary(&query_bond_quote(deps, &env, kind, ust_amount, pair)?)
+        }
+        QueryMsg::GetClaimableReward { user_address, pair } => {
+            to_binary(&query_claimable_reward(deps, &env, user_address, pair)?)
+        }
+        QueryMsg::GetTerraswapFactory {} => to_binary(&TERRASWAP_FACTORY.may_load(deps.storage)?),
+        QueryMsg::GetCw20FeePayment {} => to_binary(
+            &CW20_FEE_PAYMENT.may_load(deps.storage)?.unwrap_or(Cw20FeePayment::Native),
+        ),
+        QueryMsg::Deposit { address } => to_binary(&query_deposit(deps, address)?),
+        QueryMsg::StaleRequests { start_after, limit } => {
+            to_binary(&query_stale_requests(deps, &env, start_after, limit)?)
+        }
+        QueryMsg::SimulateSwapOperations { offer_amount, operations } => {
+            to_binary(&query_simulate_swap_operations(deps, offer_amount, operations)?)
         }
-        QueryMsg::GetSwapOpeningDate {} => to_binary(&query_swap_opening_date(deps)?),
-        QueryMsg::GetBondingDetails { user_address } => {
-            to_binary(&query_bonding_details(deps, user_address)?)
+        QueryMsg::ReverseSimulateSwapOperations { ask_amount, operations } => {
+            to_binary(&query_reverse_simulate_swap_operations(deps, ask_amount, operations)?)
         }
-        QueryMsg::GetUstEquivalentToFury { fury_count } => {
-            to_binary(&get_ust_equivalent_to_fury(deps, fury_count)?)
+        QueryMsg::GetSwapOpeningDate { pair } => to_binary(&query_swap_opening_date(deps, pair)?),
+        QueryMsg::GetBondingDetails { user_address, pair } => {
+            to_binary(&query_bonding_details(deps, user_address, pair)?)
         }
-        QueryMsg::GetFuryEquivalentToUst { ust_count } => {
-            to_binary(&get_fury_equivalent_to_ust(deps, ust_count)?)
+        QueryMsg::CumulativePrices { pair } => to_binary(&query_cumulative_prices(deps, &pair)?),
+        QueryMsg::TwapFuryToUst { window_secs, pair } => {
+            to_binary(&query_twap_fury_to_ust(deps, &env, window_secs, &pair)?)
         }
-        QueryMsg::QueryPlatformFees { msg } => to_binary(&query_platform_fees(deps, msg)?),
+        QueryMsg::GetUstEquivalentToFury { fury_count, twap_window_secs, pair } => match twap_window_secs {
+            Some(window_secs) => to_binary(&twap_ust_equivalent_to_fury(deps, &env, fury_count, window_secs, &pair)?),
+            None => {
+                let config = load_pair_config(deps, &pair)?;
+                to_binary(&native_equivalent_in_pair(deps, &config.pool_pair_address, fury_count)?)
+            }
+        },
+        QueryMsg::GetFuryEquivalentToUst { ust_count, twap_window_secs, pair } => match twap_window_secs {
+            Some(window_secs) => to_binary(&twap_fury_equivalent_to_ust(deps, &env, ust_count, window_secs, &pair)?),
+            None => {
+                let config = load_pair_config(deps, &pair)?;
+                to_binary(&fury_equivalent_in_pair(deps, &config.pool_pair_address, ust_count)?)
+            }
+        },
+        QueryMsg::QueryPlatformFees { msg } => to_binary(&query_platform_fees(deps, &env, msg)?),
     }
 }
 
-fn query_configuration(deps: Deps) -> StdResult<Config> {
-    let config: Config = CONFIG.load(deps.storage)?;
-    Ok(config)
+fn query_configuration(deps: Deps, pair: Option<[AssetInfo; 2]>) -> StdResult<Config> {
+    load_pair_config(deps, &pair)
 }
 
-fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
-    let config: Config = CONFIG.load(deps.storage)?;
+fn query_pool(deps: Deps, pair: Option<[AssetInfo; 2]>) -> StdResult<PoolResponse> {
+    let config = load_pair_config(deps, &pair)?;
     deps.querier
         .query_wasm_smart(config.pool_pair_address, &Pool {})
 }
 
-fn query_pair(deps: Deps) -> StdResult<PairInfo> {
-    let config: Config = CONFIG.load(deps.storage)?;
+fn query_pair(deps: Deps, pair: Option<[AssetInfo; 2]>) -> StdResult<PairInfo> {
+    let config = load_pair_config(deps, &pair)?;
     deps.querier
         .query_wasm_smart(config.pool_pair_address, &Pair {})
 }
 
-fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationResponse> {
-    let config: Config = CONFIG.load(deps.storage)?;
+fn query_simulation(deps: Deps, offer_asset: Asset, pair: Option<[AssetInfo; 2]>) -> StdResult<SimulationResponse> {
+    let config = load_pair_config(deps, &pair)?;
     deps.querier.query_wasm_smart(
         config.pool_pair_address,
         &Simulation {
@@ -1444,8 +1578,12 @@ fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationRespo
     )
 }
 
-fn query_reverse_simulation(deps: Deps, ask_asset: Asset) -> StdResult<ReverseSimulationResponse> {
-    let config: Config = CONFIG.load(deps.storage)?;
+fn query_reverse_simulation(
+    deps: Deps,
+    ask_asset: Asset,
+    pair: Option<[AssetInfo; 2]>,
+) -> StdResult<ReverseSimulationResponse> {
+    let config = load_pair_config(deps, &pair)?;
     deps.querier.query_wasm_smart(
         config.pool_pair_address,
         &ReverseSimulation {
@@ -1454,24 +1592,32 @@ fn query_reverse_simulation(deps: Deps, ask_asset: Asset) -> StdResult<ReverseSi
     )
 }
 
-fn query_swap_opening_date(deps: Deps) -> StdResult<Timestamp> {
-    let config: Config = CONFIG.load(deps.storage)?;
+fn query_swap_opening_date(deps: Deps, pair: Option<[AssetInfo; 2]>) -> StdResult<Timestamp> {
+    let config = load_pair_config(deps, &pair)?;
     Ok(config.swap_opening_date)
 }
 
 fn query_bonding_details(
     deps: Deps,
     user_address: String,
+    pair: Option<[AssetInfo; 2]>,
 ) -> StdResult<Option<Vec<BondedRewardsDetails>>> {
-    let bonding_details = BONDED_REWARDS_DETAILS.may_load(deps.storage, user_address.clone())?;
-    Ok(bonding_details)
+    if !BONDED_REWARDS_DETAILS.has(deps.storage, user_address.clone()) {
+        return Ok(None);
+    }
+    Ok(Some(bonds_on_pair(deps.storage, user_address, &pair)?))
 }
 
 fn get_ust_equivalent_to_fury(deps: Deps, fury_count: Uint128) -> StdResult<Uint128> {
     let config: Config = CONFIG.load(deps.storage)?;
+    native_equivalent_in_pair(deps, &config.pool_pair_address, fury_count)
+}
+
+/// Native token of the pair the FURY is worth at its reserves
+fn native_equivalent_in_pair(deps: Deps, pool_pair_address: &str, fury_count: Uint128) -> StdResult<Uint128> {
     let pool_rsp: PoolResponse = deps
         .querier
-        .query_wasm_smart(config.pool_pair_address, &Pool {})?;
+        .query_wasm_smart(pool_pair_address, &Pool {})?;
 
     let mut uust_count = Uint128::zero();
     let mut ufury_count = Uint128::zero();
@@ -1492,11 +1638,11 @@ fn get_ust_equivalent_to_fury(deps: Deps, fury_count: Uint128) -> StdResult<Uint
     return Ok(ust_equiv_for_fury);
 }
 
-fn get_fury_equivalent_to_ust(deps: Deps, ust_count: Uint128) -> StdResult<Uint128> {
-    let config: Config = CONFIG.load(deps.storage)?;
+/// FURY the native token is worth at the reserves of the pair
+pub(crate) fn fury_equivalent_in_pair(deps: Deps, pool_pair_address: &str, ust_count: Uint128) -> StdResult<Uint128> {
     let pool_rsp: PoolResponse = deps
         .querier
-        .query_wasm_smart(config.pool_pair_address, &Pool {})?;
+        .query_wasm_smart(pool_pair_address, &Pool {})?;
 
     let mut uust_count = Uint128::zero();
     let mut ufury_count = Uint128::zero();
@@ -1519,37 +1665,113 @@ fn get_fury_equivalent_to_ust(deps: Deps, ust_count: Uint128) -> StdResult<Uint1
     return Ok(fury_equiv_for_ust);
 }
 
-pub fn query_platform_fees(deps: Deps, msg: Binary) -> StdResult<Uint128> {
+struct FeeBase {
+    rate: Uint128,
+    ust_amount: Uint128,
+    fury_amount: Uint128,
+}
+
+// Rate, UST and FURY the platform fee of the message is charged on, none for
+// messages paying no fee
+fn platform_fee_base(deps: Deps, env: &Env, msg: Binary) -> StdResult<Option<FeeBase>> {
     let config = CONFIG.load(deps.storage)?;
     let platform_fees_percentage;
     let mut fury_amount_provided = Uint128::zero();
     let mut ust_amount_provided = Uint128::zero();
     match from_binary(&msg) {
         Ok(ExecuteMsg::HelloSub {}) => {
-            return Ok(Uint128::zero());
+            return Ok(None);
         },
         Ok(ExecuteMsg::Configure {
                pool_pair_address: _,
                liquidity_token: _,
                swap_opening_date: _,
            }) => {
-            return Ok(Uint128::zero());
+            return Ok(None);
         }
         Ok(ExecuteMsg::Receive(_)) => {
-            return Ok(Uint128::zero());
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::RecordPriceObservation { .. }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::UpdateConfig(_)) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::ProposeNewAdmin { admin_address: _ }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::AcceptAdmin {}) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::CreateBondSeries { .. }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::CloseBondSeries { series_id: _ }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::ContinueSwapOperations { .. }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::ContinueWithdrawLiquidity { .. }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::RegisterPair { .. }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::DeregisterPair { asset_infos: _ }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::ConfigureLpStaking { .. }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::UnstakeLp { amount: _ }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::WithdrawUnbondedLp {}) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::ClaimLpRewards {}) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::DepositFunds {}) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::WithdrawFunds {}) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::RecoverStaleRequest { .. }) => {
+            return Ok(None);
+        }
+        Ok(ExecuteMsg::ExecuteSwapOperations {
+               offer_asset,
+               operations: _,
+               minimum_receive: _,
+               to: _,
+           }) => {
+            // Charged once for the whole route, on the value of the offer
+            platform_fees_percentage =
+                config.platform_fees + config.transaction_fees + config.swap_fees;
+            let (ust_amount, fury_amount) = route_fee_base(deps, &offer_asset)?;
+            ust_amount_provided = ust_amount;
+            fury_amount_provided = fury_amount;
         }
         Ok(ExecuteMsg::ProvidePairForReward {
                assets,
                slippage_tolerance: _,
                auto_stake: _,
+               pair,
            }) => {
-            platform_fees_percentage = config.platform_fees + config.transaction_fees;
+            let pair_config = load_pair_config(deps, &pair)?;
+            platform_fees_percentage = pair_config.platform_fees + pair_config.transaction_fees;
             for asset in assets {
                 if asset.info.is_native_token() {
-                    ust_amount_provided = asset.amount;
+                    let (ust_amount, fury_amount) = native_fee_base(deps, &pair_config, &asset)?;
+                    ust_amount_provided = ust_amount;
+                    fury_amount_provided += fury_amount;
                 }
                 if !asset.info.is_native_token() {
-                    fury_amount_provided = asset.amount;
+                    fury_amount_provided += asset.amount;
                 }
             }
         }
@@ -1557,49 +1779,79 @@ pub fn query_platform_fees(deps: Deps, msg: Binary) -> StdResult<Uint128> {
                asset,
                slippage_tolerance: _,
                auto_stake: _,
+               pair,
            }) => {
-            platform_fees_percentage = config.platform_fees + config.transaction_fees;
+            let pair_config = load_pair_config(deps, &pair)?;
+            platform_fees_percentage = pair_config.platform_fees + pair_config.transaction_fees;
             if asset.info.is_native_token() {
-                ust_amount_provided = asset.amount;
+                let (ust_amount, fury_amount) = native_fee_base(deps, &pair_config, &asset)?;
+                ust_amount_provided = ust_amount;
+                fury_amount_provided = fury_amount;
             }
         }
         Ok(ExecuteMsg::ProvideLiquidity {
                assets: _,
                slippage_tolerance: _,
                auto_stake: _,
+               pair: _,
            }) => {
-            return Ok(Uint128::zero());
+            return Ok(None);
         }
         Ok(ExecuteMsg::Swap {
                offer_asset,
                belief_price: _,
                max_spread: _,
                to: _,
+               pair,
            }) => {
+            let pair_config = load_pair_config(deps, &pair)?;
             platform_fees_percentage =
-                config.platform_fees + config.transaction_fees + config.swap_fees;
+                pair_config.platform_fees + pair_config.transaction_fees + pair_config.swap_fees;
             if offer_asset.info.is_native_token() {
-                ust_amount_provided = offer_asset.amount;
+                let (ust_amount, fury_amount) = native_fee_base(deps, &pair_config, &offer_asset)?;
+                ust_amount_provided = ust_amount;
+                fury_amount_provided = fury_amount;
             }
             if !offer_asset.info.is_native_token() {
                 fury_amount_provided = offer_asset.amount;
             }
         }
         Ok(ExecuteMsg::RewardClaim {
-               receiver: _,
+               receiver,
                withdrawal_amount,
+               pair,
            }) => {
-            platform_fees_percentage = config.platform_fees + config.transaction_fees;
-            fury_amount_provided = withdrawal_amount;
+            let pair_config = load_pair_config(deps, &pair)?;
+            platform_fees_percentage = pair_config.platform_fees + pair_config.transaction_fees;
+            fury_amount_provided = match withdrawal_amount {
+                Some(withdrawal_amount) => withdrawal_amount,
+                None => {
+                    let bonds = bonds_on_pair(deps.storage, receiver, &pair)?;
+                    claimable_amount(&bonds, pair_config.swap_opening_date, env.block.time.seconds())
+                }
+            };
         }
         Err(err) => {
             return Err(StdError::generic_err(format!("{:?}", err)));
         }
     }
-    let ust_equiv_for_fury = get_ust_equivalent_to_fury(deps, fury_amount_provided)?;
+    Ok(Some(FeeBase {
+        rate: platform_fees_percentage,
+        ust_amount: ust_amount_provided,
+        fury_amount: fury_amount_provided,
+    }))
+}
+
+pub fn query_platform_fees(deps: Deps, env: &Env, msg: Binary) -> StdResult<Uint128> {
+    let config = CONFIG.load(deps.storage)?;
+    let base = match platform_fee_base(deps, env, msg)? {
+        Some(base) => base,
+        None => return Ok(Uint128::zero()),
+    };
+    let ust_equiv_for_fury = get_ust_equivalent_to_fury(deps, base.fury_amount)?;
 
-    let platform_fee = (ust_equiv_for_fury.checked_add(ust_amount_provided)?)
-        .checked_mul(platform_fees_percentage)?
+    let platform_fee = (ust_equiv_for_fury.checked_add(base.ust_amount)?)
+        .checked_mul(base.rate)?
         .checked_div(Uint128::from(HUNDRED_PERCENT))?;
     let pf_asset = Asset {
         info: AssetInfo::NativeToken {
@@ -1611,3 +1863,29 @@ pub fn query_platform_fees(deps: Deps, msg: Binary) -> StdResult<Uint128> {
     // let tax_on_pf = pf_asset.compute_tax(&deps.querier)?;
     return Ok(platform_fee);
 }
+
+/// Platform fee of the message in FURY, the UST it is charged on being
+/// valued on the pair rather than on the FURY/UST pair
+pub(crate) fn platform_fee_in_fury(deps: Deps, env: &Env, msg: Binary, pair_config: &Config) -> StdResult<Uint128> {
+    let base = match platform_fee_base(deps, env, msg)? {
+        Some(base) => base,
+        None => return Ok(Uint128::zero()),
+    };
+    let mut fury_amount = base.fury_amount;
+    if !base.ust_amount.is_zero() {
+        fury_amount += fury_equivalent_in_pair(deps, &pair_config.pool_pair_address, base.ust_amount)?;
+    }
+    Ok(fury_amount.multiply_ratio(base.rate, HUNDRED_PERCENT))
+}
+
+// UST and FURY the fee on a native asset of the pair is charged on. A native
+// token other than UST is valued at its FURY equivalent in the pair.
+fn native_fee_base(deps: Deps, pair_config: &Config, asset: &Asset) -> StdResult<(Uint128, Uint128)> {
+    match &asset.info {
+        AssetInfo::NativeToken { denom } if *denom != pair_config.usdc_ibc_symbol => Ok((
+            Uint128::zero(),
+            fury_equivalent_in_pair(deps, &pair_config.pool_pair_address, asset.amount)?,
+        )),
+        _ => Ok((asset.amount, Uint128::zero())),
+    }
+}
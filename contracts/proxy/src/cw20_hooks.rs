@@ -0,0 +1,307 @@
+//! CW20 hooks. FURY sent to the proxy with a `Swap` or `ProvideLiquidity`
+//! hook is swapped or provided without an allowance. A `Send` carries no
+//! native tokens, so the native assets provided come out of the funds the
+//! sender deposited beforehand, as does the platform fee unless it is set
+//! to be deducted from the FURY. The hook refunds whatever it leaves of the
+//! deposit.
+
+use cosmwasm_std::{attr, Addr, Attribute, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo,
+                   Response, StdError, StdResult, to_binary, Uint128, WasmMsg};
+use cw20::Cw20ExecuteMsg;
+use terraswap::asset::{Asset, AssetInfo};
+use terraswap::pair::Cw20HookMsg;
+
+use crate::contract::{check_auto_stake, incr_allow_for_provide_liquidity, platform_fee_in_fury, query_platform_fees,
+                      transfer_custom_assets_from_funds_owner_to_proxy};
+use crate::error::ContractError;
+use crate::fees::collect_platform_fee;
+use crate::lp_staking::share_platform_fee_in_fury;
+use crate::msg::{Cw20FeePayment, ExecuteMsg};
+use crate::pairs::load_pair_config;
+use crate::state::{Config, CW20_FEE_PAYMENT, DEPOSITS};
+
+pub fn deposit_funds(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if info.funds.is_empty() {
+        return Err(ContractError::Std(StdError::generic_err("No funds to deposit")));
+    }
+    let mut deposit = DEPOSITS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    for coin in info.funds {
+        match deposit.iter_mut().find(|deposited| deposited.denom == coin.denom) {
+            Some(deposited) => deposited.amount += coin.amount,
+            None => deposit.push(coin),
+        }
+    }
+    DEPOSITS.save(deps.storage, &info.sender, &deposit)?;
+    Ok(Response::new()
+        .add_attribute("action", "deposit_funds")
+        .add_attribute("depositor", info.sender))
+}
+
+pub fn withdraw_funds(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let deposit = DEPOSITS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    if deposit.is_empty() {
+        return Err(ContractError::Std(StdError::generic_err("No funds deposited")));
+    }
+    DEPOSITS.remove(deps.storage, &info.sender);
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: deposit,
+        })
+        .add_attribute("action", "withdraw_funds")
+        .add_attribute("depositor", info.sender))
+}
+
+pub fn query_deposit(deps: Deps, address: String) -> StdResult<Vec<Coin>> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(DEPOSITS.may_load(deps.storage, &address)?.unwrap_or_default())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn receive_swap(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: String,
+    amount: Uint128,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<String>,
+    pair: Option<[AssetInfo; 2]>,
+) -> Result<Response, ContractError> {
+    let config = load_pair_config(deps.as_ref(), &pair)?;
+    if info.sender != config.custom_token_address {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.swap_opening_date.nanos() > env.block.time.nanos() {
+        return Err(ContractError::Std(StdError::generic_err("Swap is not enabled yet!!!")));
+    }
+    let user = deps.api.addr_validate(&sender)?;
+    let to = match to {
+        Some(to) => deps.api.addr_validate(&to)?,
+        None => user.clone(),
+    };
+    let funds = take_deposit(deps.branch(), &user)?;
+    let fee_msg = ExecuteMsg::Swap {
+        offer_asset: fury(&config, amount),
+        belief_price,
+        max_spread,
+        to: Some(to.to_string()),
+        pair,
+    };
+    let (offer_amount, resp) = settle_hook_fees(deps, &env, &config, &user, &fee_msg, amount, funds)?;
+
+    let swap = Cw20ExecuteMsg::Send {
+        contract: config.pool_pair_address,
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            belief_price,
+            max_spread,
+            to: Some(to.to_string()),
+        })?,
+    };
+    Ok(resp
+        .add_message(WasmMsg::Execute {
+            contract_addr: config.custom_token_address.to_string(),
+            msg: to_binary(&swap)?,
+            funds: vec![],
+        })
+        .add_attribute("action", "Forwarding swap message to pool pair address"))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn receive_provide_liquidity(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: String,
+    amount: Uint128,
+    assets: [Asset; 2],
+    slippage_tolerance: Option<Decimal>,
+    auto_stake: Option<bool>,
+    pair: Option<[AssetInfo; 2]>,
+) -> Result<Response, ContractError> {
+    let config = load_pair_config(deps.as_ref(), &pair)?;
+    if info.sender != config.custom_token_address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let user = deps.api.addr_validate(&sender)?;
+    check_auto_stake(deps.as_ref(), auto_stake, &pair)?;
+    let fury_provided = match assets.iter().find(|asset| !asset.is_native_token()) {
+        Some(asset) if asset.info == fury(&config, Uint128::zero()).info => asset.amount,
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "The FURY sent must be one of the assets provided",
+            )))
+        }
+    };
+
+    // The native assets go with the liquidity, out of the deposit
+    let mut funds = take_deposit(deps.branch(), &user)?;
+    let mut liquidity_funds = vec![];
+    for asset in assets.iter() {
+        if let AssetInfo::NativeToken { denom } = &asset.info {
+            spend(&mut funds, denom, asset.amount).map_err(|_| {
+                StdError::generic_err(format!("Deposit falls short of the {}{} provided", asset.amount, denom))
+            })?;
+            if !asset.amount.is_zero() {
+                liquidity_funds.push(Coin::new(asset.amount.u128(), denom));
+            }
+        }
+    }
+
+    // The authorized liquidity provider provides for no fee and for no reward
+    let authorized = user == config.authorized_liquidity_provider;
+    let fee_msg = if authorized {
+        ExecuteMsg::ProvideLiquidity {
+            assets: assets.clone(),
+            slippage_tolerance,
+            auto_stake,
+            pair: pair.clone(),
+        }
+    } else {
+        ExecuteMsg::ProvidePairForReward {
+            assets: assets.clone(),
+            slippage_tolerance,
+            auto_stake,
+            pair: pair.clone(),
+        }
+    };
+    let (fury_left, resp) = settle_hook_fees(deps.branch(), &env, &config, &user, &fee_msg, amount, funds)?;
+    if fury_left != fury_provided {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "The FURY sent must be the {} provided and its fee of {}",
+            fury_provided,
+            amount.saturating_sub(fury_left)
+        ))));
+    }
+
+    // The FURY is with the proxy already, so the provision skips fetching it
+    let provision = if authorized {
+        let receiver = Some(config.authorized_liquidity_provider.to_string());
+        incr_allow_for_provide_liquidity(
+            deps,
+            env,
+            assets,
+            slippage_tolerance,
+            auto_stake,
+            receiver,
+            liquidity_funds,
+            user.to_string(),
+            true,
+            pair,
+        )?
+    } else {
+        transfer_custom_assets_from_funds_owner_to_proxy(
+            deps,
+            env,
+            assets,
+            slippage_tolerance,
+            auto_stake,
+            Some(config.pair_lp_tokens_holder.to_string()),
+            liquidity_funds,
+            user.to_string(),
+            true,
+            pair,
+        )?
+    };
+    Ok(provision
+        .add_attributes(resp.attributes)
+        .add_submessages(resp.messages))
+}
+
+fn fury(config: &Config, amount: Uint128) -> Asset {
+    Asset {
+        info: AssetInfo::Token {
+            contract_addr: config.custom_token_address.to_string(),
+        },
+        amount,
+    }
+}
+
+fn take_deposit(deps: DepsMut, user: &Addr) -> StdResult<Vec<Coin>> {
+    let deposit = DEPOSITS.may_load(deps.storage, user)?.unwrap_or_default();
+    DEPOSITS.remove(deps.storage, user);
+    Ok(deposit)
+}
+
+// Takes `amount` of `denom` out of the funds
+fn spend(funds: &mut [Coin], denom: &str, amount: Uint128) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    match funds.iter_mut().find(|coin| coin.denom == denom) {
+        Some(coin) if coin.amount >= amount => {
+            coin.amount -= amount;
+            Ok(())
+        }
+        _ => Err(StdError::generic_err(format!("Funds fall short of {}{}", amount, denom))),
+    }
+}
+
+// Charges the platform fee of `msg`, in UST the same a native message pays
+// or in FURY worked out on the pair of the hook, and returns the FURY left of `fury_sent` with a response settling the fee and
+// refunding the funds left
+fn settle_hook_fees(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    user: &Addr,
+    msg: &ExecuteMsg,
+    fury_sent: Uint128,
+    mut funds: Vec<Coin>,
+) -> Result<(Uint128, Response), ContractError> {
+    let payment = CW20_FEE_PAYMENT.may_load(deps.storage)?.unwrap_or(Cw20FeePayment::Native);
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut attributes: Vec<Attribute> = vec![];
+    let mut fury_left = fury_sent;
+    match payment {
+        Cw20FeePayment::Native => {
+            let fee = query_platform_fees(deps.as_ref(), env, to_binary(msg)?)?;
+            attributes.push(attr("platform_fees", fee));
+            let denom = &config.usdc_ibc_symbol;
+            if spend(&mut funds, denom, fee).is_err() {
+                let received = funds
+                    .iter()
+                    .filter(|coin| coin.denom == *denom)
+                    .map(|coin| coin.amount)
+                    .sum();
+                return Err(ContractError::InsufficientFees {
+                    received,
+                    required: fee,
+                });
+            }
+            let (shared, collected) = collect_platform_fee(deps.storage, config, fee)?;
+            messages.extend(collected);
+            attributes.push(attr("platform_fees_shared", shared));
+        }
+        Cw20FeePayment::Fury => {
+            // Worked out on the pair of the hook
+            let fee_in_fury = platform_fee_in_fury(deps.as_ref(), env, to_binary(msg)?, config)?;
+            fury_left = fury_sent.checked_sub(fee_in_fury).map_err(StdError::from)?;
+            // The stakers' share stays with the proxy like a fee paid in UST
+            let shared = share_platform_fee_in_fury(deps.storage, fee_in_fury)?;
+            if fee_in_fury > shared {
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: config.custom_token_address.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: config.platform_fees_collector_wallet.to_string(),
+                        amount: fee_in_fury - shared,
+                    })?,
+                    funds: vec![],
+                }));
+            }
+            attributes.push(attr("platform_fees_in_fury", fee_in_fury));
+            attributes.push(attr("platform_fees_shared_in_fury", shared));
+        }
+    }
+
+    funds.retain(|coin| !coin.amount.is_zero());
+    if !funds.is_empty() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: user.to_string(),
+            amount: funds,
+        }));
+    }
+    Ok((fury_left, Response::new().add_messages(messages).add_attributes(attributes)))
+}
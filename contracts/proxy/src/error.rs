@@ -1,6 +1,8 @@
 use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
+use crate::state::SubMessageNextAction;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
@@ -16,4 +18,11 @@ pub enum ContractError {
         #[cfg(feature = "backtraces")]
         backtrace: Backtrace,
     },
+
+    #[error("Request {id} failed on its way to {next_action:?}: {error}")]
+    StepFailed {
+        id: u64,
+        next_action: SubMessageNextAction,
+        error: String,
+    },
 }
@@ -0,0 +1,104 @@
+//! Fee settlement. A message charging a platform fee carries the fee in UST
+//! next to any UST it spends itself. The LP stakers' share of the fee stays
+//! with the proxy, the rest goes on to the platform fees collector and
+//! whatever UST is left over goes back to the sender.
+
+use cosmwasm_std::{to_binary, Attribute, attr, BankMsg, Coin, CosmosMsg, DepsMut, Env, MessageInfo, StdResult, Storage,
+                   Uint128};
+
+use crate::contract::query_platform_fees;
+use crate::error::ContractError;
+use crate::lp_staking::share_platform_fee;
+use crate::msg::ExecuteMsg;
+use crate::state::{Config, CONFIG};
+
+/// Outcome of settling the fee of a message
+pub struct FeeSettlement {
+    /// Fee charged
+    pub fee: Uint128,
+    /// Part of the fee kept for the LP stakers, the rest is sent to the platform fees collector
+    pub shared: Uint128,
+    /// UST sent back to the sender
+    pub refund: Uint128,
+    /// Bank messages carrying the fee and the refund
+    pub messages: Vec<CosmosMsg>,
+}
+
+impl FeeSettlement {
+    pub fn attributes(&self) -> Vec<Attribute> {
+        vec![
+            attr("platform_fees", self.fee),
+            attr("platform_fees_shared", self.shared),
+            attr("platform_fees_refund", self.refund),
+        ]
+    }
+}
+
+/// Checks the UST sent covers `ust_spent` plus the platform fee of `msg` and
+/// returns the messages settling the fee and refunding any overpayment
+pub fn settle_platform_fees(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msg: &ExecuteMsg,
+    ust_spent: Uint128,
+) -> Result<FeeSettlement, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let fee = query_platform_fees(deps.as_ref(), env, to_binary(msg)?)?;
+    let denom = config.usdc_ibc_symbol.clone();
+    let sent: Uint128 = info
+        .funds
+        .iter()
+        .filter(|coin| coin.denom == denom)
+        .map(|coin| coin.amount)
+        .sum();
+    let received = sent.saturating_sub(ust_spent);
+    if sent < ust_spent || received < fee {
+        return Err(ContractError::InsufficientFees {
+            required: fee,
+            received,
+        });
+    }
+
+    let refund = received - fee;
+    let (shared, collected) = collect_platform_fee(deps.storage, &config, fee)?;
+    let mut messages: Vec<CosmosMsg> = collected.into_iter().collect();
+    if !refund.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom,
+                amount: refund,
+            }],
+        }));
+    }
+    Ok(FeeSettlement {
+        fee,
+        shared,
+        refund,
+        messages,
+    })
+}
+
+/// Keeps the LP stakers' share of a fee in UST and returns it along with the
+/// message sending the rest on to the platform fees collector
+pub(crate) fn collect_platform_fee(
+    storage: &mut dyn Storage,
+    config: &Config,
+    fee: Uint128,
+) -> StdResult<(Uint128, Option<CosmosMsg>)> {
+    let shared = share_platform_fee(storage, fee)?;
+    if fee == shared {
+        return Ok((shared, None));
+    }
+    Ok((
+        shared,
+        Some(CosmosMsg::Bank(BankMsg::Send {
+            to_address: config.platform_fees_collector_wallet.to_string(),
+            amount: vec![Coin {
+                denom: config.usdc_ibc_symbol.clone(),
+                amount: fee - shared,
+            }],
+        })),
+    ))
+}
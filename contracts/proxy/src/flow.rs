@@ -0,0 +1,215 @@
+//! Provide liquidity flow. Providing liquidity takes a chain of submessages,
+//! fetching the user's FURY, fetching the FURY of the reward, allowing the
+//! pair the FURY, providing to the pair and staking the LP tokens. Each step
+//! is recorded in [`SUB_MESSAGE_DETAILS`] under the id of its submessage with
+//! the step the reply moves on to.
+//!
+//! A step that fails fails the message that started the flow, which rolls
+//! back every step before it and leaves the user their funds. Replies to
+//! submessages recording no step, such as those of swaps, pass through.
+
+use cosmwasm_std::{BankMsg, Deps, DepsMut, Env, Event, from_binary, MessageInfo, Reply, Response, StdError, StdResult,
+                   Storage, SubMsgResult};
+use terraswap::pair::ExecuteMsg as PairExecuteMsg;
+
+use crate::contract::{forward_provide_liquidity_to_astro, incr_allow_for_provide_liquidity,
+                      transfer_custom_assets_from_funds_owner_to_proxy,
+                      transfer_native_assets_to_native_investment_receive_wallet};
+use crate::error::ContractError;
+use crate::lp_staking::stake_provided_liquidity;
+use crate::msg::StaleRequestsResponse;
+use crate::state::{CONFIG, SUB_MESSAGE_DETAILS, SUB_REQ_ID, SubMessageDetails, SubMessageNextAction,
+                   SubMessageType};
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_SCAN: u64 = 300;
+
+/// Id of the next submessage the proxy replies to
+pub(crate) fn next_sub_req_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let sub_req_id = SUB_REQ_ID.may_load(storage)?.unwrap_or_default() + 1;
+    SUB_REQ_ID.save(storage, &sub_req_id)?;
+    Ok(sub_req_id)
+}
+
+/// Moves the flow the replying submessage is a step of on to its next step
+pub fn advance(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let step = SUB_MESSAGE_DETAILS.may_load(deps.storage, msg.id.to_string())?;
+    let (step, events) = match (step, msg.result) {
+        (Some(step), SubMsgResult::Ok(response)) => (step, response.events),
+        (Some(step), SubMsgResult::Err(error)) => {
+            return Err(ContractError::StepFailed {
+                id: msg.id,
+                next_action: step.next_action,
+                error,
+            })
+        }
+        (None, SubMsgResult::Ok(response)) => {
+            let mut resp = Response::new();
+            if let Some(data) = response.data {
+                resp = resp.set_data(data);
+            }
+            return Ok(resp);
+        }
+        (None, SubMsgResult::Err(error)) => {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Received error: {:?}",
+                error
+            ))))
+        }
+    };
+    SUB_MESSAGE_DETAILS.remove(deps.storage, msg.id.to_string());
+    match step.request_type {
+        SubMessageType::TransferFromSubMsg | SubMessageType::IncreaseAlowanceSubMsg => Ok(Response::new()),
+        SubMessageType::ProvideLiquiditySubMsg => transition(deps, env, msg.id, step, &events),
+    }
+}
+
+fn transition(
+    deps: DepsMut,
+    env: Env,
+    id: u64,
+    step: SubMessageDetails,
+    events: &[Event],
+) -> Result<Response, ContractError> {
+    let (assets, slippage_tolerance, receiver) = match from_binary(&step.sub_message_payload)? {
+        PairExecuteMsg::ProvideLiquidity {
+            assets,
+            slippage_tolerance,
+            receiver,
+        } => (assets, slippage_tolerance, receiver),
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Request {} does not provide liquidity",
+                id
+            ))))
+        }
+    };
+    match step.next_action {
+        SubMessageNextAction::TransferCustomAssetsFromFundsOwner => transfer_custom_assets_from_funds_owner_to_proxy(
+            deps,
+            env,
+            assets,
+            slippage_tolerance,
+            Some(step.auto_stake),
+            receiver,
+            step.funds,
+            step.user_address,
+            step.is_fury_provided,
+            step.pair,
+        ),
+        SubMessageNextAction::TransferToNativeInvestmentReceiveWallet => {
+            transfer_native_assets_to_native_investment_receive_wallet(deps, receiver, step.funds)
+        }
+        SubMessageNextAction::IncreaseAllowance => incr_allow_for_provide_liquidity(
+            deps,
+            env,
+            assets,
+            slippage_tolerance,
+            Some(step.auto_stake),
+            receiver,
+            step.funds,
+            step.user_address,
+            step.is_fury_provided,
+            step.pair,
+        ),
+        SubMessageNextAction::ProvideLiquidity => forward_provide_liquidity_to_astro(
+            deps,
+            env,
+            assets,
+            slippage_tolerance,
+            Some(step.auto_stake),
+            receiver,
+            step.funds,
+            step.user_address,
+            step.pair,
+        ),
+        SubMessageNextAction::StakeLiquidity => stake_provided_liquidity(deps, &env, events, step.user_address),
+        SubMessageNextAction::TransferNativeAssets | SubMessageNextAction::TransferCustomAssets => {
+            Err(ContractError::Std(StdError::generic_err(format!(
+                "Request {} has no step for {:?}",
+                id, step.next_action
+            ))))
+        }
+    }
+}
+
+/// Drops a stale step. A flow runs its course within the message that starts
+/// it and every step is removed by its reply or rolled back with the message,
+/// so only steps left by earlier versions of the proxy can be stale. With
+/// `refund`, the user gets back the funds the step escrowed, which steps of
+/// those versions did not record.
+pub fn recover_stale_request(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    refund: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let step = SUB_MESSAGE_DETAILS
+        .may_load(deps.storage, id.to_string())?
+        .filter(|step| is_stale(&env, step))
+        .ok_or_else(|| StdError::generic_err(format!("Request {} is not stale", id)))?;
+    if refund {
+        for coin in step.escrowed_funds.iter() {
+            let balance = deps.querier.query_balance(&env.contract.address, &coin.denom)?;
+            if balance.amount < coin.amount {
+                return Err(ContractError::Std(StdError::generic_err(format!(
+                    "Proxy holds less than the {} escrowed",
+                    coin
+                ))));
+            }
+        }
+    }
+    SUB_MESSAGE_DETAILS.remove(deps.storage, id.to_string());
+
+    let mut resp = Response::new()
+        .add_attribute("action", "recover_stale_request")
+        .add_attribute("id", id.to_string())
+        .add_attribute("user_address", step.user_address.clone());
+    if refund && !step.escrowed_funds.is_empty() {
+        resp = resp.add_message(BankMsg::Send {
+            to_address: deps.api.addr_validate(&step.user_address)?.to_string(),
+            amount: step.escrowed_funds,
+        });
+    }
+    Ok(resp)
+}
+
+/// Stale steps in the order of their ids. A page looks at up to
+/// [`MAX_SCAN`] ids, as the ids of steps long gone far outnumber the stale.
+pub fn query_stale_requests(
+    deps: Deps,
+    env: &Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<StaleRequestsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let last_id = SUB_REQ_ID.may_load(deps.storage)?.unwrap_or_default();
+    let first_id = start_after.unwrap_or_default() + 1;
+    let mut requests = vec![];
+    let mut scanned = start_after.unwrap_or_default();
+    for id in first_id..=last_id.min(first_id + MAX_SCAN - 1) {
+        scanned = id;
+        if let Some(step) = SUB_MESSAGE_DETAILS.may_load(deps.storage, id.to_string())? {
+            if is_stale(env, &step) {
+                requests.push(step);
+                if requests.len() == limit {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(StaleRequestsResponse {
+        requests,
+        next_start_after: if scanned < last_id { Some(scanned) } else { None },
+    })
+}
+
+fn is_stale(env: &Env, step: &SubMessageDetails) -> bool {
+    step.created_at_height < env.block.height
+}
@@ -1,5 +1,16 @@
 pub mod contract;
+mod bonds;
+mod cw20_hooks;
 mod error;
+mod fees;
+mod flow;
+mod lp_staking;
 pub mod msg;
+mod oracle;
+mod pairs;
 mod query;
+mod router;
 mod state;
+
+#[cfg(test)]
+mod testing;
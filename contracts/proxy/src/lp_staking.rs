@@ -0,0 +1,380 @@
+//! LP staking. Liquidity providers stake the LP token of the FURY/UST pair
+//! with the proxy. Stakers earn FURY emitted at a configured rate and a share
+//! of the platform fees, both split by stake. Fees paid in FURY are shared in
+//! FURY. Unstaked LP tokens wait out the unbonding period, if there is one,
+//! before they can be withdrawn.
+
+use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, Event, MessageInfo, Order,
+                   Response, StdError, StdResult, Storage, to_binary, Uint128, WasmMsg};
+use cw20::Cw20ExecuteMsg;
+use cw_controllers::ClaimsResponse;
+use cw_storage_plus::Bound;
+use cw_utils::Duration;
+
+use crate::error::ContractError;
+use crate::msg::LpStakerResponse;
+use crate::state::{CONFIG, LP_STAKERS, LP_STAKING, LP_UNBONDING, LpStaker, LpStaking};
+
+const HUNDRED_PERCENT: u16 = 10000;
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn configure_lp_staking(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fury_per_second: Uint128,
+    fury_reward_wallet: String,
+    fee_share_rate: u16,
+    unbonding_period: Option<Duration>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {});
+    }
+    if fee_share_rate > HUNDRED_PERCENT {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Fee share rate can not be more than 100 percent",
+        )));
+    }
+    if config.liquidity_token.as_str().is_empty() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The LP token of the FURY/UST pair is not configured",
+        )));
+    }
+    let fury_reward_wallet = deps.api.addr_validate(&fury_reward_wallet)?;
+    let now = env.block.time.seconds();
+    let mut staking = match LP_STAKING.may_load(deps.storage)? {
+        Some(mut staking) => {
+            check_lp_token_change(&staking, &config.liquidity_token)?;
+            // What was emitted at the old rate stays earned
+            emit(&mut staking, now);
+            staking.liquidity_token = config.liquidity_token.clone();
+            staking
+        }
+        None => LpStaking {
+            liquidity_token: config.liquidity_token.clone(),
+            fury_per_second,
+            fury_reward_wallet: fury_reward_wallet.clone(),
+            fee_share_rate,
+            unbonding_period,
+            total_staked: Uint128::zero(),
+            total_unbonding: Uint128::zero(),
+            fury_per_share: Decimal::zero(),
+            ust_per_share: Decimal::zero(),
+            fury_fees_per_share: Decimal::zero(),
+            emitted_until: now,
+        },
+    };
+    staking.fury_per_second = fury_per_second;
+    staking.fury_reward_wallet = fury_reward_wallet;
+    staking.fee_share_rate = fee_share_rate;
+    staking.unbonding_period = unbonding_period;
+    LP_STAKING.save(deps.storage, &staking)?;
+    Ok(Response::new()
+        .add_attribute("action", "configure_lp_staking")
+        .add_attribute("fury_per_second", fury_per_second)
+        .add_attribute("fee_share_rate", fee_share_rate.to_string()))
+}
+
+/// Staked and unbonding LP tokens are paid back in the token they were
+/// staked in, so the LP token can only change while none are held
+pub(crate) fn check_lp_token_change(staking: &LpStaking, liquidity_token: &Addr) -> Result<(), ContractError> {
+    if staking.liquidity_token != *liquidity_token
+        && (!staking.total_staked.is_zero() || !staking.total_unbonding.is_zero()) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The LP token can not change while LP tokens are staked or unbonding",
+        )));
+    }
+    Ok(())
+}
+
+/// Only the LP token staking was configured with can be staked, till LP
+/// staking is configured again for the LP token of the proxy
+pub(crate) fn check_lp_token_current(storage: &dyn Storage, staking: &LpStaking) -> Result<(), ContractError> {
+    if staking.liquidity_token != CONFIG.load(storage)?.liquidity_token {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The LP token of the FURY/UST pair changed, LP staking has to be configured again",
+        )));
+    }
+    Ok(())
+}
+
+// Emits the FURY of the seconds since the last emission. Nothing is emitted
+// while nothing is staked.
+fn emit(staking: &mut LpStaking, now: u64) {
+    if now <= staking.emitted_until {
+        return;
+    }
+    if !staking.total_staked.is_zero() {
+        let emitted = staking.fury_per_second * Uint128::from(now - staking.emitted_until);
+        staking.fury_per_share += Decimal::from_ratio(emitted, staking.total_staked);
+    }
+    staking.emitted_until = now;
+}
+
+// Moves what the stake earned since it last changed into the pending rewards
+fn settle_staker(staking: &LpStaking, staker: &mut LpStaker) {
+    staker.pending_fury += staker.staked * (staking.fury_per_share - staker.fury_per_share);
+    staker.pending_ust += staker.staked * (staking.ust_per_share - staker.ust_per_share);
+    staker.pending_fury_fees += staker.staked * (staking.fury_fees_per_share - staker.fury_fees_per_share);
+    staker.fury_per_share = staking.fury_per_share;
+    staker.ust_per_share = staking.ust_per_share;
+    staker.fury_fees_per_share = staking.fury_fees_per_share;
+}
+
+pub fn stake_lp(storage: &mut dyn Storage, env: &Env, address: &Addr, amount: Uint128) -> Result<Response, ContractError> {
+    let mut staking = LP_STAKING
+        .may_load(storage)?
+        .ok_or_else(|| StdError::generic_err("LP staking is not set up"))?;
+    if amount.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err("Nothing to stake")));
+    }
+    check_lp_token_current(storage, &staking)?;
+    emit(&mut staking, env.block.time.seconds());
+    let mut staker = LP_STAKERS.may_load(storage, address)?.unwrap_or_default();
+    settle_staker(&staking, &mut staker);
+    staker.staked += amount;
+    staking.total_staked += amount;
+    LP_STAKERS.save(storage, address, &staker)?;
+    LP_STAKING.save(storage, &staking)?;
+    Ok(Response::new()
+        .add_attribute("action", "stake_lp")
+        .add_attribute("staker", address)
+        .add_attribute("amount", amount))
+}
+
+/// Stakes the LP tokens sent with the hook for their sender
+pub fn receive_lp(deps: DepsMut, env: Env, info: MessageInfo, sender: String, amount: Uint128) -> Result<Response, ContractError> {
+    let staking = LP_STAKING
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("LP staking is not set up"))?;
+    if info.sender != staking.liquidity_token {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = deps.api.addr_validate(&sender)?;
+    stake_lp(deps.storage, &env, &address, amount)
+}
+
+/// Stakes the LP tokens a provide liquidity sent to the proxy for `staker`,
+/// which the pair reports as its share in its own wasm event
+pub fn stake_provided_liquidity(
+    deps: DepsMut,
+    env: &Env,
+    events: &[Event],
+    staker: String,
+) -> Result<Response, ContractError> {
+    // Only the LP token of the FURY/UST pair is staked
+    let pair_address = CONFIG.load(deps.storage)?.pool_pair_address;
+    let share = events
+        .iter()
+        .filter(|event| {
+            event.ty == "wasm"
+                && event
+                    .attributes
+                    .iter()
+                    .any(|attribute| attribute.key == "_contract_address" && attribute.value == pair_address)
+        })
+        .flat_map(|event| event.attributes.iter())
+        .find(|attribute| attribute.key == "share")
+        .ok_or_else(|| StdError::generic_err("Provide liquidity did not report its share"))?;
+    let share = share
+        .value
+        .parse::<u128>()
+        .map_err(|_| StdError::generic_err(format!("Invalid share {}", share.value)))?;
+    let address = deps.api.addr_validate(&staker)?;
+    // The LP token is checked against the staking setup when staked
+    stake_lp(deps.storage, env, &address, Uint128::from(share))
+}
+
+pub fn unstake_lp(deps: DepsMut, env: Env, info: MessageInfo, amount: Uint128) -> Result<Response, ContractError> {
+    let mut staking = LP_STAKING.load(deps.storage)?;
+    let mut staker = LP_STAKERS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    if amount.is_zero() || amount > staker.staked {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Can unstake up to {} LP tokens",
+            staker.staked
+        ))));
+    }
+    emit(&mut staking, env.block.time.seconds());
+    settle_staker(&staking, &mut staker);
+    staker.staked -= amount;
+    staking.total_staked -= amount;
+    if staking.unbonding_period.is_some() {
+        staking.total_unbonding += amount;
+    }
+    LP_STAKERS.save(deps.storage, &info.sender, &staker)?;
+    LP_STAKING.save(deps.storage, &staking)?;
+
+    let resp = Response::new()
+        .add_attribute("action", "unstake_lp")
+        .add_attribute("staker", info.sender.clone())
+        .add_attribute("amount", amount);
+    match staking.unbonding_period {
+        Some(unbonding_period) => {
+            let release_at = unbonding_period.after(&env.block);
+            LP_UNBONDING.create_claim(deps.storage, &info.sender, amount, release_at)?;
+            Ok(resp.add_attribute("release_at", release_at.to_string()))
+        }
+        None => Ok(resp.add_message(lp_transfer(&staking.liquidity_token, &info.sender, amount)?)),
+    }
+}
+
+pub fn withdraw_unbonded_lp(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut staking = LP_STAKING.load(deps.storage)?;
+    let amount = LP_UNBONDING.claim_tokens(deps.storage, &info.sender, &env.block, None)?;
+    if amount.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err("No LP tokens done unbonding")));
+    }
+    staking.total_unbonding = staking.total_unbonding.saturating_sub(amount);
+    LP_STAKING.save(deps.storage, &staking)?;
+    Ok(Response::new()
+        .add_message(lp_transfer(&staking.liquidity_token, &info.sender, amount)?)
+        .add_attribute("action", "withdraw_unbonded_lp")
+        .add_attribute("amount", amount))
+}
+
+fn lp_transfer(liquidity_token: &Addr, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: liquidity_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    }))
+}
+
+pub fn claim_lp_rewards(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut staking = LP_STAKING.load(deps.storage)?;
+    let mut staker = LP_STAKERS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    emit(&mut staking, env.block.time.seconds());
+    settle_staker(&staking, &mut staker);
+    let fury = staker.pending_fury;
+    let ust = staker.pending_ust;
+    let fury_fees = staker.pending_fury_fees;
+    if fury.is_zero() && ust.is_zero() && fury_fees.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err("No LP staking rewards to claim")));
+    }
+    staker.pending_fury = Uint128::zero();
+    staker.pending_ust = Uint128::zero();
+    staker.pending_fury_fees = Uint128::zero();
+    LP_STAKERS.save(deps.storage, &info.sender, &staker)?;
+    LP_STAKING.save(deps.storage, &staking)?;
+
+    let mut resp = Response::new();
+    if !fury.is_zero() {
+        resp = resp.add_message(WasmMsg::Execute {
+            contract_addr: config.custom_token_address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: staking.fury_reward_wallet.to_string(),
+                recipient: info.sender.to_string(),
+                amount: fury,
+            })?,
+            funds: vec![],
+        });
+    }
+    // The FURY fees were kept by the proxy
+    if !fury_fees.is_zero() {
+        resp = resp.add_message(WasmMsg::Execute {
+            contract_addr: config.custom_token_address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: fury_fees,
+            })?,
+            funds: vec![],
+        });
+    }
+    if !ust.is_zero() {
+        resp = resp.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: config.usdc_ibc_symbol,
+                amount: ust,
+            }],
+        });
+    }
+    Ok(resp
+        .add_attribute("action", "claim_lp_rewards")
+        .add_attribute("fury", fury)
+        .add_attribute("fury_fees", fury_fees)
+        .add_attribute("ust", ust))
+}
+
+/// Keeps the stakers' share of a platform fee with the proxy and returns it.
+/// Nothing is shared while nothing is staked.
+pub(crate) fn share_platform_fee(storage: &mut dyn Storage, fee: Uint128) -> StdResult<Uint128> {
+    share_fee(storage, fee, |staking| &mut staking.ust_per_share)
+}
+
+/// [`share_platform_fee`] for a fee paid in FURY
+pub(crate) fn share_platform_fee_in_fury(storage: &mut dyn Storage, fee_in_fury: Uint128) -> StdResult<Uint128> {
+    share_fee(storage, fee_in_fury, |staking| &mut staking.fury_fees_per_share)
+}
+
+fn share_fee(
+    storage: &mut dyn Storage,
+    fee: Uint128,
+    per_share: fn(&mut LpStaking) -> &mut Decimal,
+) -> StdResult<Uint128> {
+    let mut staking = match LP_STAKING.may_load(storage)? {
+        Some(staking) => staking,
+        None => return Ok(Uint128::zero()),
+    };
+    if staking.total_staked.is_zero() {
+        return Ok(Uint128::zero());
+    }
+    let shared = fee.multiply_ratio(staking.fee_share_rate, HUNDRED_PERCENT);
+    if shared.is_zero() {
+        return Ok(shared);
+    }
+    let added = Decimal::from_ratio(shared, staking.total_staked);
+    *per_share(&mut staking) += added;
+    LP_STAKING.save(storage, &staking)?;
+    Ok(shared)
+}
+
+pub fn query_lp_staker(deps: Deps, env: &Env, address: String) -> StdResult<LpStakerResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let staker = LP_STAKERS.may_load(deps.storage, &address)?.unwrap_or_default();
+    lp_staker_response(deps, env, address, staker)
+}
+
+pub fn query_lp_stakers(
+    deps: Deps,
+    env: &Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<LpStakerResponse>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(|address| deps.api.addr_validate(&address)).transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    LP_STAKERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (address, staker) = item?;
+            lp_staker_response(deps, env, address, staker)
+        })
+        .collect()
+}
+
+fn lp_staker_response(deps: Deps, env: &Env, address: Addr, mut staker: LpStaker) -> StdResult<LpStakerResponse> {
+    if let Some(mut staking) = LP_STAKING.may_load(deps.storage)? {
+        emit(&mut staking, env.block.time.seconds());
+        settle_staker(&staking, &mut staker);
+    }
+    Ok(LpStakerResponse {
+        address: address.to_string(),
+        staked: staker.staked,
+        pending_fury: staker.pending_fury,
+        pending_ust: staker.pending_ust,
+        pending_fury_fees: staker.pending_fury_fees,
+    })
+}
+
+pub fn query_lp_unbonding(deps: Deps, address: String) -> StdResult<ClaimsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    LP_UNBONDING.query_claims(deps, &address)
+}
@@ -1,9 +1,15 @@
+use std::fmt;
+
 use cosmwasm_std::{Binary, Decimal, Timestamp, Uint128, Uint64};
 use cw20::Cw20ReceiveMsg;
+use cw_utils::Duration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use terraswap::asset::Asset;
+use terraswap::asset::{Asset, AssetInfo};
+use terraswap::router::SwapOperation;
+
+use crate::state::SubMessageDetails;
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMsg {
@@ -80,6 +86,8 @@ pub enum ExecuteMsg {
         slippage_tolerance: Option<Decimal>,
         /// Determines whether an autostake will be performed on the generator
         auto_stake: Option<bool>,
+        /// Pair to provide to, the FURY/UST pair when left out
+        pair: Option<[AssetInfo; 2]>,
     },
     /// ProvideNativeForReward a user provides native liquidity (UST only) and gets Fury rewards
     ProvideNativeForReward {
@@ -89,6 +97,8 @@ pub enum ExecuteMsg {
         slippage_tolerance: Option<Decimal>,
         /// Determines whether an autostake will be performed on the generator
         auto_stake: Option<bool>,
+        /// Pair to provide to, the FURY/UST pair when left out
+        pair: Option<[AssetInfo; 2]>,
     },
     /// ProvideLiquidity an Authorized user provides pair liquidity and gets lp_tokens
     ProvideLiquidity {
@@ -98,6 +108,8 @@ pub enum ExecuteMsg {
         slippage_tolerance: Option<Decimal>,
         /// Determines whether an autostake will be performed on the generator
         auto_stake: Option<bool>,
+        /// Pair to provide to, the FURY/UST pair when left out
+        pair: Option<[AssetInfo; 2]>,
     },
     /// Swap an offer asset to the other
     Swap {
@@ -105,49 +117,338 @@ pub enum ExecuteMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// Pair to swap on, the FURY/UST pair when left out
+        pair: Option<[AssetInfo; 2]>,
     },
-    /// Claim the Discounted Reward Fury after bond maturity
+    /// Claim the Discounted Reward Fury vested so far
     RewardClaim {
         receiver: String,
-        withdrawal_amount: Uint128,
+        /// Claims everything vested when left out
+        withdrawal_amount: Option<Uint128>,
+        /// Claims the bonds bought on this pair, those of the FURY/UST pair
+        /// when left out
+        pair: Option<[AssetInfo; 2]>,
     },
     HelloSub {},
+    /// Changes the given settings of the proxy, only for the admin
+    UpdateConfig(UpdateConfigMsg),
+    /// Proposes a new admin, who takes over once they accept
+    ProposeNewAdmin {
+        admin_address: String,
+    },
+    /// Accepts the admin role proposed to the sender
+    AcceptAdmin {},
+    /// Opens a bond series for the kind of investment on the pair, closing
+    /// the one open before it
+    CreateBondSeries {
+        kind: BondKind,
+        /// Total FURY, discount included, the series can sell
+        capacity: Uint128,
+        /// Discount when no debt is outstanding, in percentage multiplied by 100
+        max_discount_rate: u16,
+        /// Discount when the debt takes up the whole capacity, in percentage multiplied by 100
+        min_discount_rate: u16,
+        /// Seconds the debt of a sale takes to pay itself off
+        debt_decay_period_in_sec: u64,
+        /// Seconds the bonds of the series vest linearly over
+        vesting_period_in_sec: u64,
+        /// Registered pair the series sells bonds on, the FURY/UST pair when left out
+        pair: Option<[AssetInfo; 2]>,
+    },
+    /// Stops a bond series from selling any more bonds
+    CloseBondSeries {
+        series_id: u64,
+    },
+    /// Swaps the offer asset hop by hop through the pairs the terraswap
+    /// factory has for each operation. The platform fee is charged once for
+    /// the whole route.
+    ExecuteSwapOperations {
+        offer_asset: Asset,
+        operations: Vec<SwapOperation>,
+        minimum_receive: Option<Uint128>,
+        to: Option<String>,
+    },
+    /// Internal use
+    /// Swaps what the previous hop returned along the rest of the route
+    ContinueSwapOperations {
+        operations: Vec<SwapOperation>,
+        /// Asset the previous hop returned
+        asset_info: AssetInfo,
+        /// Balance of the proxy in the asset before the previous hop
+        balance_before: Uint128,
+        minimum_receive: Option<Uint128>,
+        to: String,
+    },
+    /// Internal use
+    /// Sends the provider what withdrawing the liquidity returned
+    ContinueWithdrawLiquidity {
+        /// Assets of the pair, each at the balance of the proxy before the withdraw
+        balances_before: [Asset; 2],
+        to: String,
+    },
+    /// Registers the pair the terraswap factory has for FURY and a native
+    /// token, or replaces its settings when it is already registered
+    RegisterPair {
+        asset_infos: [AssetInfo; 2],
+        /// Time in nano seconds since EPOC when swapping on the pair will be enabled
+        swap_opening_date: Uint64,
+        settings: PairSettings,
+    },
+    /// Removes a pair from the registry
+    DeregisterPair {
+        asset_infos: [AssetInfo; 2],
+    },
+    /// Sets up staking of the LP token of the FURY/UST pair or changes its
+    /// settings, only for the admin
+    ConfigureLpStaking {
+        /// FURY emitted to the stakers every second, split by stake
+        fury_per_second: Uint128,
+        /// The emitted FURY is fetched from this wallet
+        fury_reward_wallet: String,
+        /// Part of the platform fees shared with the stakers, in percentage multiplied by 100
+        fee_share_rate: u16,
+        /// Unstaked LP tokens can be withdrawn after this long, right away when left out
+        unbonding_period: Option<Duration>,
+    },
+    /// Unstakes LP tokens, which go back to the sender after the unbonding period
+    UnstakeLp {
+        amount: Uint128,
+    },
+    /// Sends the sender the LP tokens done unbonding
+    WithdrawUnbondedLp {},
+    /// Sends the sender the FURY and UST earned by staking LP tokens
+    ClaimLpRewards {},
+    /// Holds the native funds sent for the next CW20 hook of the sender,
+    /// which spends them on native assets and fees and refunds the rest
+    DepositFunds {},
+    /// Sends the sender back the native funds deposited
+    WithdrawFunds {},
+    /// Drops a step of a provide liquidity flow left stale in an earlier
+    /// block, which only steps recorded by earlier versions of the proxy can
+    /// be, and, with `refund`, sends its user the native funds it escrowed
+    RecoverStaleRequest {
+        id: u64,
+        refund: bool,
+    },
+    /// Takes an observation of the cumulative FURY price of the pair for the
+    /// TWAP. The first one starts the observations of the pair, the proxy
+    /// then also takes them on its own whenever it is used. Keepers should
+    /// send it at least once per TWAP window while the proxy is idle
+    RecordPriceObservation {
+        /// Observes this pair, the FURY/UST pair when left out
+        pair: Option<[AssetInfo; 2]>,
+    },
+}
+
+/// Settings left out are kept as they are
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct UpdateConfigMsg {
+    /// discount_rate when fury and UST are both provided
+    pub pair_discount_rate: Option<u16>,
+    /// bonding period when fury and UST are both provided
+    pub pair_bonding_period_in_sec: Option<u64>,
+    /// Fury tokens for balanced investment will be fetched from this wallet
+    pub pair_fury_reward_wallet: Option<String>,
+    /// The LP tokens generated at time of Pair investment for discounted Fury Rewards will be assigned to this wallet
+    pub pair_lp_tokens_holder: Option<String>,
+    /// discount_rate when only UST is provided
+    pub native_discount_rate: Option<u16>,
+    /// bonding period when only UST is provided
+    pub native_bonding_period_in_sec: Option<u64>,
+    /// Fury tokens for native(UST only) investment will be fetched from this wallet
+    pub native_investment_reward_wallet: Option<String>,
+    /// The native(UST only) investment will be stored into this wallet
+    pub native_investment_receive_wallet: Option<String>,
+    /// This address has the authority to provide liquidity (balanced UST + Fury) and in return shall get the LP tokens
+    pub authorized_liquidity_provider: Option<String>,
+    /// The wallet to which various fees that is collected shall be transferred
+    pub platform_fees_collector_wallet: Option<String>,
+    /// Platform Fee Specified in percentage multiplied by 100, i.e. 100% = 10000 and 0.01% = 1
+    pub platform_fees: Option<Uint128>,
+    /// Transaction Fee Specified in percentage multiplied by 100, i.e. 100% = 10000 and 0.01% = 1
+    pub transaction_fees: Option<Uint128>,
+    /// Swap Fees Specified in percentage multiplied by 100, i.e. 100% = 10000 and 0.01% = 1
+    pub swap_fees: Option<Uint128>,
+    /// Maximum number of simultaneous outstanding Bonds of discounted Reward Fury Tokens permitted per user
+    pub max_bonding_limit_per_user: Option<u64>,
+    /// The terraswap factory multi-hop swaps look their pairs up in
+    pub terraswap_factory: Option<String>,
+    /// How the platform fee of FURY sent with a CW20 hook is paid
+    pub cw20_fee_payment: Option<Cw20FeePayment>,
+}
+
+/// How the platform fee of FURY sent with a CW20 hook is paid, a `Send`
+/// carrying no native tokens
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20FeePayment {
+    /// In UST, out of the funds the sender deposited
+    Native,
+    /// In FURY, deducted from the FURY sent and worked out on the pair of
+    /// the hook
+    Fury,
+}
+
+impl fmt::Display for Cw20FeePayment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cw20FeePayment::Native => write!(f, "native"),
+            Cw20FeePayment::Fury => write!(f, "fury"),
+        }
+    }
+}
+
+/// Settings of a registered pair, those left out are taken from the config
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PairSettings {
+    /// Platform Fee Specified in percentage multiplied by 100, i.e. 100% = 10000 and 0.01% = 1
+    pub platform_fees: Option<Uint128>,
+    /// Transaction Fee Specified in percentage multiplied by 100, i.e. 100% = 10000 and 0.01% = 1
+    pub transaction_fees: Option<Uint128>,
+    /// Swap Fees Specified in percentage multiplied by 100, i.e. 100% = 10000 and 0.01% = 1
+    pub swap_fees: Option<Uint128>,
+    /// discount_rate when fury and the native token are both provided
+    pub pair_discount_rate: Option<u16>,
+    /// bonding period when fury and the native token are both provided
+    pub pair_bonding_period_in_sec: Option<u64>,
+    /// discount_rate when only the native token is provided
+    pub native_discount_rate: Option<u16>,
+    /// bonding period when only the native token is provided
+    pub native_bonding_period_in_sec: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    /// Returns information about Proxy Configuration
-    Configuration {},
+    /// Returns information about Proxy Configuration, with the settings of
+    /// the pair applied when one is given
+    Configuration {
+        pair: Option<[AssetInfo; 2]>,
+    },
     /// Returns counts of Tokens related to the Liquidity Pool
-    Pool {},
+    Pool {
+        pair: Option<[AssetInfo; 2]>,
+    },
     /// Returns information about Tokens Paired in the Liquidity Pool
-    Pair {},
+    Pair {
+        pair: Option<[AssetInfo; 2]>,
+    },
     /// Returns information about the simulation of the swap in a [`SimulationResponse`] object.
     Simulation {
         offer_asset: Asset,
+        pair: Option<[AssetInfo; 2]>,
     },
     /// Returns information about the reverse simulation in a [`ReverseSimulationResponse`] object.
     ReverseSimulation {
         ask_asset: Asset,
+        pair: Option<[AssetInfo; 2]>,
+    },
+    /// Returns a registered pair in a [`ManagedPair`] object
+    ManagedPair {
+        asset_infos: [AssetInfo; 2],
     },
+    /// Returns all registered pairs
+    ManagedPairs {},
 
     /// Returns information about the cumulative prices in a [`CumulativePricesResponse`] object
-    // CumulativePrices {},
+    CumulativePrices {
+        pair: Option<[AssetInfo; 2]>,
+    },
+    /// Returns the time weighted UST price of FURY over at least the last
+    /// `window_secs` in a [`TwapResponse`] object
+    TwapFuryToUst {
+        window_secs: u64,
+        /// Averages the price on this pair, in its native token
+        pair: Option<[AssetInfo; 2]>,
+    },
 
+    /// Returns the admin proposed and not yet accepted, if any
+    PendingAdmin {},
+    /// Returns the LP staking settings and totals in a [`LpStaking`] object, if set up
+    LpStaking {},
+    /// Returns the stake and rewards of a liquidity provider in a [`LpStakerResponse`] object
+    LpStaker {
+        address: String,
+    },
+    /// Returns the stake and rewards of liquidity providers, ordered by address
+    LpStakers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the LP tokens of a liquidity provider still unbonding in a `cw_controllers::ClaimsResponse` object
+    LpUnbonding {
+        address: String,
+    },
+    /// Returns a bond series in a [`BondSeries`] object
+    BondSeries {
+        series_id: u64,
+    },
+    /// Returns the terms a bond for `ust_amount` gets right now in a
+    /// [`BondQuoteResponse`] object. Pair bonds assume the matching FURY is provided.
+    BondQuote {
+        kind: BondKind,
+        ust_amount: Uint128,
+        /// Quotes a bond on this pair, `ust_amount` being of its native token
+        pair: Option<[AssetInfo; 2]>,
+    },
+    /// Returns the Fury Reward vested and not yet claimed by the user
+    GetClaimableReward {
+        user_address: String,
+        pair: Option<[AssetInfo; 2]>,
+    },
+    /// Returns the terraswap factory multi-hop swaps use, if set
+    GetTerraswapFactory {},
+    /// Returns how the platform fee of a CW20 hook is paid in a [`Cw20FeePayment`] object
+    GetCw20FeePayment {},
+    /// Returns the native funds an address deposited for its next CW20 hook
+    Deposit {
+        address: String,
+    },
+    /// Returns the steps of provide liquidity flows left stale in earlier
+    /// blocks, for the admin to recover, in a [`StaleRequestsResponse`]
+    /// object. Pages go by id, `next_start_after` of a page starting the next.
+    StaleRequests {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns what a route returns for `offer_amount` in a
+    /// [`SimulateSwapOperationsResponse`] object, before the platform fee
+    SimulateSwapOperations {
+        offer_amount: Uint128,
+        operations: Vec<SwapOperation>,
+    },
+    /// Returns what a route needs to be offered to return `ask_amount` in a
+    /// [`SimulateSwapOperationsResponse`] object, before the platform fee
+    ReverseSimulateSwapOperations {
+        ask_amount: Uint128,
+        operations: Vec<SwapOperation>,
+    },
     /// Returns Timestamp after which Swap operations would be permitted in the Liquidity Pool
-    GetSwapOpeningDate {},
+    GetSwapOpeningDate {
+        pair: Option<[AssetInfo; 2]>,
+    },
     /// Returns status of Fury Reward Tokens Bonded or allocated at discounted rate against Native or Pair Investment
     GetBondingDetails {
         user_address: String,
+        /// Returns the bonds bought on this pair, those of the FURY/UST pair
+        /// when left out
+        pair: Option<[AssetInfo; 2]>,
     },
     /// Returns Fury Equivalent for some UST amount (without operational overheads of swap)
     GetFuryEquivalentToUst {
         ust_count: Uint128,
+        /// Prices at the TWAP over this window instead of the current reserves
+        twap_window_secs: Option<u64>,
+        /// Prices at this pair, `ust_count` being of its native token
+        pair: Option<[AssetInfo; 2]>,
     },
     /// Returns UST Equivalent for some Fury amount (without operational overheads of swap)
     GetUstEquivalentToFury {
         fury_count: Uint128,
+        /// Prices at the TWAP over this window instead of the current reserves
+        twap_window_secs: Option<u64>,
+        /// Prices at this pair, the result being of its native token
+        pair: Option<[AssetInfo; 2]>,
     },
     /// Returns Platform Fee required for specific ExecuteMsg
     QueryPlatformFees {
@@ -155,25 +456,95 @@ pub enum QueryMsg {
     },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TwapResponse {
+    /// Average UST, or the native token of the pair asked for, paid per FURY
+    pub ust_per_fury: Decimal,
+    /// Seconds the average is taken over, the window or at most a tenth more
+    pub observed_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LpStakerResponse {
+    pub address: String,
+    pub staked: Uint128,
+    /// FURY earned and not claimed yet
+    pub pending_fury: Uint128,
+    /// UST of platform fees earned and not claimed yet
+    pub pending_ust: Uint128,
+    /// FURY of platform fees paid in FURY earned and not claimed yet
+    pub pending_fury_fees: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StaleRequestsResponse {
+    pub requests: Vec<SubMessageDetails>,
+    /// Id the next page starts after, none once every id was looked at
+    pub next_start_after: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BondQuoteResponse {
+    /// Series the bond is sold from, none when bonds are sold at the configured discount rate
+    pub series_id: Option<u64>,
+    /// Specified in percentage multiplied by 100, i.e. 100% = 10000 and 0.01% = 1
+    pub discount_rate: u16,
+    /// FURY bonded, discount included
+    pub fury_amount: Uint128,
+    /// FURY the series can still sell
+    pub capacity_remaining: Option<Uint128>,
+    pub vesting_period_in_sec: u64,
+    /// Whether the bond vests linearly or all at the end of the period
+    pub linear_vesting: bool,
+}
+
+/// Hooks of FURY or LP tokens sent to the proxy. A `Send` carries no native
+/// tokens, so a `Swap` or `ProvideLiquidity` hook takes its native assets and
+/// UST fee out of the funds the sender deposited with
+/// [`ExecuteMsg::DepositFunds`] in a transaction of its own beforehand.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ProxyCw20HookMsg {
-    // ProvideLiquidity {
-    //     /// the type of asset available in [`Asset`]
-    //     assets: [Asset; 2],
-    //     /// the slippage tolerance for sets the maximum percent of price movement
-    //     slippage_tolerance: Option<Decimal>,
-    //     /// Determines whether an autostake will be performed on the generator
-    //     auto_stake: Option<bool>,
-    //     /// the receiver of provide liquidity
-    //     receiver: Option<String>,
-    // },
-    /// Sell a given amount of asset
-    // Swap {
-    //     belief_price: Option<Decimal>,
-    //     max_spread: Option<Decimal>,
-    //     to: Option<String>,
-    // },
+    /// Provides the FURY sent with the native assets deposited, for reward
+    /// or, for the authorized liquidity provider, as plain liquidity
+    ProvideLiquidity {
+        /// the type of asset available in [`Asset`]
+        assets: [Asset; 2],
+        /// the slippage tolerance for sets the maximum percent of price movement
+        slippage_tolerance: Option<Decimal>,
+        /// Determines whether an autostake will be performed on the generator
+        auto_stake: Option<bool>,
+        pair: Option<[AssetInfo; 2]>,
+    },
+    /// Sell the FURY sent, paying the fee out of the deposit unless it is
+    /// set to be deducted from the FURY
+    Swap {
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        pair: Option<[AssetInfo; 2]>,
+    },
     /// Withdrawing liquidity from the pool against the LP Tokens
     WithdrawLiquidity {},
+    /// Stakes the LP tokens of the FURY/UST pair sent for the sender
+    StakeLp {},
+}
+
+/// Which investment a bond series sells bonds for
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BondKind {
+    /// FURY and UST both provided
+    Pair,
+    /// Only UST provided
+    Native,
+}
+
+impl BondKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BondKind::Pair => "pair",
+            BondKind::Native => "native",
+        }
+    }
 }
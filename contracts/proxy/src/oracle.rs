@@ -0,0 +1,275 @@
+//! Time weighted FURY price. The proxy keeps observations of the cumulative
+//! FURY price of the pair as it gets used, and the average price over a
+//! window is the growth of the cumulative price since the newest observation
+//! taken before the window started. A swap pushing the reserves around
+//! inside one block does not move the cumulative price at all.
+//!
+//! Observations of a pair start with the first `RecordPriceObservation` of
+//! it and are taken along with the user messages after that. The FURY/UST pair
+//! and every registered pair keep their own observations, priced in the
+//! native token of the pair. A proxy left idle has gaps between
+//! its observations, so an average stretching well past the window asked
+//! for is refused rather than served as the price of the window.
+
+use cosmwasm_std::{Decimal, Deps, DepsMut, Env, Order, StdError, StdResult, Storage, Uint128};
+use cw_storage_plus::Bound;
+use terraswap::asset::AssetInfo;
+use terraswap::pair::{CUMULATIVE_PRICE_PRECISION, CumulativePricesResponse, QueryMsg as PairQueryMsg};
+
+use crate::msg::TwapResponse;
+use crate::pairs::{load_pair_config, pair_key};
+use crate::state::{CONFIG, FURY_PRICE_OBSERVATIONS, MANAGED_PAIRS, OBSERVED_PAIRS, PAIR_PRICE_OBSERVATIONS};
+
+/// Least seconds between two observations
+const OBSERVATION_INTERVAL_SECS: u64 = 60;
+/// Most pairs observed along with a single message
+const MAX_OBSERVATIONS_PER_MESSAGE: usize = 3;
+/// Longest window a TWAP can be asked for, older observations are dropped
+pub const MAX_TWAP_WINDOW_SECS: u64 = 7 * 24 * 60 * 60;
+/// Most the observed seconds can overrun the window by, in percent of the
+/// window. The observation interval is always allowed
+const MAX_WINDOW_OVERRUN_PERCENT: u64 = 10;
+
+// A pair the price is observed of. Observations of the FURY/UST pair have
+// no key, those of a registered pair are kept under its pair key.
+struct ObservedPair {
+    key: Option<String>,
+    pair_address: String,
+}
+
+fn observed_pair(deps: Deps, pair: &Option<[AssetInfo; 2]>) -> StdResult<ObservedPair> {
+    let config = load_pair_config(deps, pair)?;
+    Ok(ObservedPair {
+        key: pair.as_ref().map(pair_key),
+        pair_address: config.pool_pair_address,
+    })
+}
+
+pub fn query_cumulative_prices(deps: Deps, pair: &Option<[AssetInfo; 2]>) -> StdResult<CumulativePricesResponse> {
+    let config = load_pair_config(deps, pair)?;
+    deps.querier
+        .query_wasm_smart(config.pool_pair_address, &PairQueryMsg::CumulativePrices {})
+}
+
+/// Observes the prices along with the user messages of the proxy. Nothing
+/// is queried of a pair before its first observation is recorded, so proxies
+/// on pairs without cumulative prices make no extra query. At most
+/// `MAX_OBSERVATIONS_PER_MESSAGE` pairs are queried, those observed longest
+/// ago first, and a pair failing its query is left for a later message.
+pub fn observe_price(mut deps: DepsMut, env: &Env) -> StdResult<bool> {
+    let now = env.block.time.seconds();
+    let mut due: Vec<(u64, Option<String>)> = vec![];
+    if let Some(last) = last_observation_time(deps.storage, &None)? {
+        due.push((last, None));
+    }
+    for item in OBSERVED_PAIRS.range(deps.storage, None, None, Order::Ascending) {
+        let (key, last) = item?;
+        due.push((last, Some(key)));
+    }
+    due.retain(|(last, _)| now >= last + OBSERVATION_INTERVAL_SECS);
+    due.sort_by_key(|(last, _)| *last);
+
+    let mut observed = false;
+    for (_, key) in due.into_iter().take(MAX_OBSERVATIONS_PER_MESSAGE) {
+        let pair = match &key {
+            None => observed_pair(deps.as_ref(), &None).ok(),
+            Some(key) => MANAGED_PAIRS
+                .may_load(deps.storage, key)?
+                .map(|managed| ObservedPair {
+                    key: Some(key.clone()),
+                    pair_address: managed.pair_address.to_string(),
+                }),
+        };
+        if let Some(pair) = pair {
+            observed |= observe(deps.branch(), env, &pair).unwrap_or(false);
+        }
+    }
+    Ok(observed)
+}
+
+/// Stores the cumulative FURY price of the pair unless its last observation
+/// is younger than the interval
+pub fn record_price_observation(deps: DepsMut, env: &Env, pair: &Option<[AssetInfo; 2]>) -> StdResult<bool> {
+    let pair = observed_pair(deps.as_ref(), pair)?;
+    observe(deps, env, &pair)
+}
+
+fn observe(deps: DepsMut, env: &Env, pair: &ObservedPair) -> StdResult<bool> {
+    let now = env.block.time.seconds();
+    if let Some(last) = last_observation_time(deps.storage, &pair.key)? {
+        if now < last + OBSERVATION_INTERVAL_SECS {
+            return Ok(false);
+        }
+    }
+    let prices = deps
+        .querier
+        .query_wasm_smart(&pair.pair_address, &PairQueryMsg::CumulativePrices {})?;
+    let cumulative = fury_price_cumulative(deps.as_ref(), &prices)?;
+
+    // The newest observation before the longest window is kept to open it
+    let mut stale = observations_until(deps.storage, &pair.key, now.saturating_sub(MAX_TWAP_WINDOW_SECS))
+        .map(|item| item.map(|(time, _)| time))
+        .collect::<StdResult<Vec<u64>>>()?;
+    if !stale.is_empty() {
+        stale.remove(0);
+    }
+    match &pair.key {
+        None => {
+            FURY_PRICE_OBSERVATIONS.save(deps.storage, now, &cumulative)?;
+            for time in stale {
+                FURY_PRICE_OBSERVATIONS.remove(deps.storage, time);
+            }
+        }
+        Some(key) => {
+            PAIR_PRICE_OBSERVATIONS.save(deps.storage, (key.as_str(), now), &cumulative)?;
+            OBSERVED_PAIRS.save(deps.storage, key, &now)?;
+            for time in stale {
+                PAIR_PRICE_OBSERVATIONS.remove(deps.storage, (key.as_str(), time));
+            }
+        }
+    }
+    Ok(true)
+}
+
+pub fn query_twap_fury_to_ust(
+    deps: Deps,
+    env: &Env,
+    window_secs: u64,
+    pair: &Option<[AssetInfo; 2]>,
+) -> StdResult<TwapResponse> {
+    let (average, observed_secs) = average_fury_price(deps, env, window_secs, pair)?;
+    Ok(TwapResponse {
+        ust_per_fury: Decimal::from_ratio(average, CUMULATIVE_PRICE_PRECISION),
+        observed_secs,
+    })
+}
+
+pub fn twap_ust_equivalent_to_fury(
+    deps: Deps,
+    env: &Env,
+    fury_count: Uint128,
+    window_secs: u64,
+    pair: &Option<[AssetInfo; 2]>,
+) -> StdResult<Uint128> {
+    let (average, _) = average_fury_price(deps, env, window_secs, pair)?;
+    Ok(fury_count.multiply_ratio(average, CUMULATIVE_PRICE_PRECISION))
+}
+
+pub fn twap_fury_equivalent_to_ust(
+    deps: Deps,
+    env: &Env,
+    ust_count: Uint128,
+    window_secs: u64,
+    pair: &Option<[AssetInfo; 2]>,
+) -> StdResult<Uint128> {
+    let (average, _) = average_fury_price(deps, env, window_secs, pair)?;
+    if average.is_zero() {
+        return Err(StdError::generic_err("FURY has no price over the window"));
+    }
+    Ok(ust_count.multiply_ratio(CUMULATIVE_PRICE_PRECISION, average))
+}
+
+/// Drops the observations of the pair, to be called whenever its address
+/// changes. Cumulative prices of two pair contracts cannot be averaged.
+pub fn clear_price_observations(storage: &mut dyn Storage, key: &Option<String>) -> StdResult<()> {
+    match key {
+        None => {
+            let times = FURY_PRICE_OBSERVATIONS
+                .keys(storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<u64>>>()?;
+            for time in times {
+                FURY_PRICE_OBSERVATIONS.remove(storage, time);
+            }
+        }
+        Some(key) => {
+            let times = PAIR_PRICE_OBSERVATIONS
+                .prefix(key)
+                .keys(storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<u64>>>()?;
+            for time in times {
+                PAIR_PRICE_OBSERVATIONS.remove(storage, (key.as_str(), time));
+            }
+            OBSERVED_PAIRS.remove(storage, key);
+        }
+    }
+    Ok(())
+}
+
+fn last_observation_time(storage: &dyn Storage, key: &Option<String>) -> StdResult<Option<u64>> {
+    match key {
+        None => FURY_PRICE_OBSERVATIONS
+            .keys(storage, None, None, Order::Descending)
+            .next()
+            .transpose(),
+        Some(key) => OBSERVED_PAIRS.may_load(storage, key),
+    }
+}
+
+// Observations of the pair taken up to `until`, the newest first
+fn observations_until<'a>(
+    storage: &'a dyn Storage,
+    key: &Option<String>,
+    until: u64,
+) -> Box<dyn Iterator<Item = StdResult<(u64, Uint128)>> + 'a> {
+    match key {
+        None => FURY_PRICE_OBSERVATIONS.range(storage, None, Some(Bound::inclusive(until)), Order::Descending),
+        Some(key) => PAIR_PRICE_OBSERVATIONS
+            .prefix(key)
+            .range(storage, None, Some(Bound::inclusive(until)), Order::Descending),
+    }
+}
+
+// Returns the scaled average native token per FURY and the seconds it was
+// taken over, which is the window or a little more
+fn average_fury_price(
+    deps: Deps,
+    env: &Env,
+    window_secs: u64,
+    pair: &Option<[AssetInfo; 2]>,
+) -> StdResult<(Uint128, u64)> {
+    if window_secs == 0 || window_secs > MAX_TWAP_WINDOW_SECS {
+        return Err(StdError::generic_err(format!(
+            "TWAP window must be between 1 and {} seconds",
+            MAX_TWAP_WINDOW_SECS
+        )));
+    }
+    let pair = observed_pair(deps, pair)?;
+    let now = env.block.time.seconds();
+    let window_start = now.saturating_sub(window_secs);
+    let (start, start_cumulative) = observations_until(deps.storage, &pair.key, window_start)
+        .next()
+        .transpose()?
+        .ok_or_else(|| StdError::generic_err("No price observation old enough for the window"))?;
+    let prices = deps
+        .querier
+        .query_wasm_smart(&pair.pair_address, &PairQueryMsg::CumulativePrices {})?;
+    let current = fury_price_cumulative(deps, &prices)?;
+    let observed_secs = now - start;
+    let max_overrun = OBSERVATION_INTERVAL_SECS.max(window_secs * MAX_WINDOW_OVERRUN_PERCENT / 100);
+    if observed_secs > window_secs + max_overrun {
+        return Err(StdError::generic_err(format!(
+            "Price observations cover {} seconds, too far off the {} seconds window",
+            observed_secs, window_secs
+        )));
+    }
+    let average = current
+        .wrapping_sub(start_cumulative)
+        .checked_div(Uint128::from(observed_secs))?;
+    Ok((average, observed_secs))
+}
+
+// Picks the cumulative price of FURY in UST out of the two of the pair
+fn fury_price_cumulative(deps: Deps, prices: &CumulativePricesResponse) -> StdResult<Uint128> {
+    let config = CONFIG.load(deps.storage)?;
+    let is_fury = |info: &AssetInfo| match info {
+        AssetInfo::Token { contract_addr } => *contract_addr == config.custom_token_address.as_str(),
+        AssetInfo::NativeToken { .. } => false,
+    };
+    if is_fury(&prices.assets[0].info) {
+        Ok(prices.price0_cumulative_last)
+    } else if is_fury(&prices.assets[1].info) {
+        Ok(prices.price1_cumulative_last)
+    } else {
+        Err(StdError::generic_err("The pair does not trade FURY"))
+    }
+}
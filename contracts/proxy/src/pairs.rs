@@ -0,0 +1,176 @@
+//! Pair registry. Besides the FURY/UST pair of the config the proxy manages
+//! pairs of FURY against other native tokens. A registered pair is looked up
+//! in the terraswap factory, has its own swap opening date and can override
+//! the fees and bond settings of the config. Messages select a pair by its
+//! two assets and go to the FURY/UST pair when they select none.
+
+use cosmwasm_std::{Addr, Deps, DepsMut, MessageInfo, Order, Response, StdError, StdResult, Timestamp, Uint128,
+                   Uint64};
+use terraswap::asset::{Asset, AssetInfo};
+use terraswap::querier::query_pair_info;
+
+use crate::contract::assert_config;
+use crate::error::ContractError;
+use crate::msg::PairSettings;
+use crate::oracle::clear_price_observations;
+use crate::state::{Config, CONFIG, MANAGED_PAIRS, ManagedPair, PAIR_OF_LIQUIDITY_TOKEN, TERRASWAP_FACTORY};
+
+/// Key of a pair in the registry, the same whichever way round its assets are given
+pub fn pair_key(asset_infos: &[AssetInfo; 2]) -> String {
+    let mut names = [asset_infos[0].to_string(), asset_infos[1].to_string()];
+    names.sort();
+    names.join("/")
+}
+
+/// Whether two pair selectors select the same pair, none being the FURY/UST pair
+pub fn same_pair(a: &Option<[AssetInfo; 2]>, b: &Option<[AssetInfo; 2]>) -> bool {
+    a.as_ref().map(pair_key) == b.as_ref().map(pair_key)
+}
+
+/// The config with the pair, its swap opening date and its overrides in
+/// place of those of the FURY/UST pair
+pub fn load_pair_config(deps: Deps, pair: &Option<[AssetInfo; 2]>) -> StdResult<Config> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if let Some(asset_infos) = pair {
+        let managed = MANAGED_PAIRS
+            .may_load(deps.storage, &pair_key(asset_infos))?
+            .ok_or_else(|| StdError::generic_err(format!("Pair {} is not managed", pair_key(asset_infos))))?;
+        apply_pair(&mut config, &managed);
+    }
+    Ok(config)
+}
+
+fn apply_pair(config: &mut Config, managed: &ManagedPair) {
+    config.pool_pair_address = managed.pair_address.to_string();
+    config.liquidity_token = managed.liquidity_token.clone();
+    config.swap_opening_date = managed.swap_opening_date;
+    let settings = &managed.settings;
+    if let Some(platform_fees) = settings.platform_fees {
+        config.platform_fees = platform_fees;
+    }
+    if let Some(transaction_fees) = settings.transaction_fees {
+        config.transaction_fees = transaction_fees;
+    }
+    if let Some(swap_fees) = settings.swap_fees {
+        config.swap_fees = swap_fees;
+    }
+    if let Some(pair_discount_rate) = settings.pair_discount_rate {
+        config.pair_discount_rate = pair_discount_rate;
+    }
+    if let Some(pair_bonding_period_in_sec) = settings.pair_bonding_period_in_sec {
+        config.pair_bonding_period_in_sec = pair_bonding_period_in_sec;
+    }
+    if let Some(native_discount_rate) = settings.native_discount_rate {
+        config.native_discount_rate = native_discount_rate;
+    }
+    if let Some(native_bonding_period_in_sec) = settings.native_bonding_period_in_sec {
+        config.native_bonding_period_in_sec = native_bonding_period_in_sec;
+    }
+}
+
+pub fn register_pair(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset_infos: [AssetInfo; 2],
+    swap_opening_date: Uint64,
+    settings: PairSettings,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let fury = AssetInfo::Token {
+        contract_addr: config.custom_token_address.to_string(),
+    };
+    if !asset_infos.contains(&fury) || !asset_infos.iter().any(|asset_info| asset_info.is_native_token()) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "A managed pair must be of FURY and a native token",
+        )));
+    }
+    let factory = TERRASWAP_FACTORY
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("Terraswap factory is not configured"))?;
+    let pair_info = query_pair_info(&deps.querier, factory, &asset_infos)?;
+
+    let managed = ManagedPair {
+        asset_infos: asset_infos.clone(),
+        pair_address: deps.api.addr_validate(&pair_info.contract_addr)?,
+        liquidity_token: deps.api.addr_validate(&pair_info.liquidity_token)?,
+        swap_opening_date: Timestamp::from_nanos(swap_opening_date.u64()),
+        settings,
+    };
+    // The overrides have to hold up on their own as much as the config does
+    apply_pair(&mut config, &managed);
+    assert_config(&config)?;
+
+    let key = pair_key(&asset_infos);
+    if let Some(previous) = MANAGED_PAIRS.may_load(deps.storage, &key)? {
+        PAIR_OF_LIQUIDITY_TOKEN.remove(deps.storage, &previous.liquidity_token);
+        if previous.pair_address != managed.pair_address {
+            clear_price_observations(deps.storage, &Some(key.clone()))?;
+        }
+    }
+    MANAGED_PAIRS.save(deps.storage, &key, &managed)?;
+    PAIR_OF_LIQUIDITY_TOKEN.save(deps.storage, &managed.liquidity_token, &key)?;
+    Ok(Response::new()
+        .add_attribute("action", "register_pair")
+        .add_attribute("pair", key)
+        .add_attribute("pair_address", managed.pair_address)
+        .add_attribute("liquidity_token", managed.liquidity_token))
+}
+
+pub fn deregister_pair(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset_infos: [AssetInfo; 2],
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin_address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let key = pair_key(&asset_infos);
+    let managed = MANAGED_PAIRS
+        .may_load(deps.storage, &key)?
+        .ok_or_else(|| StdError::generic_err(format!("Pair {} is not managed", key)))?;
+    MANAGED_PAIRS.remove(deps.storage, &key);
+    PAIR_OF_LIQUIDITY_TOKEN.remove(deps.storage, &managed.liquidity_token);
+    clear_price_observations(deps.storage, &Some(key.clone()))?;
+    Ok(Response::new()
+        .add_attribute("action", "deregister_pair")
+        .add_attribute("pair", key))
+}
+
+/// Registered pair the LP token belongs to, if any
+pub fn pair_of_liquidity_token(deps: Deps, liquidity_token: &Addr) -> StdResult<Option<ManagedPair>> {
+    match PAIR_OF_LIQUIDITY_TOKEN.may_load(deps.storage, liquidity_token)? {
+        Some(key) => MANAGED_PAIRS.may_load(deps.storage, &key),
+        None => Ok(None),
+    }
+}
+
+/// UST the native assets spend, which the fee settlement checks is sent
+/// along with the fee. Any other native token has to be sent as it is.
+pub fn native_spent(config: &Config, info: &MessageInfo, assets: &[Asset]) -> StdResult<Uint128> {
+    let mut ust_spent = Uint128::zero();
+    for asset in assets {
+        if let AssetInfo::NativeToken { denom } = &asset.info {
+            if *denom == config.usdc_ibc_symbol {
+                ust_spent = ust_spent.checked_add(asset.amount)?;
+            } else {
+                asset.assert_sent_native_token_balance(info)?;
+            }
+        }
+    }
+    Ok(ust_spent)
+}
+
+pub fn query_managed_pair(deps: Deps, asset_infos: [AssetInfo; 2]) -> StdResult<ManagedPair> {
+    MANAGED_PAIRS.load(deps.storage, &pair_key(&asset_infos))
+}
+
+pub fn query_managed_pairs(deps: Deps) -> StdResult<Vec<ManagedPair>> {
+    MANAGED_PAIRS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, managed)| managed))
+        .collect()
+}
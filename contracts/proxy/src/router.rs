@@ -0,0 +1,303 @@
+//! Multi-hop swaps. A route is a chain of swap operations, each done on the
+//! pair the terraswap factory has for its two assets. The proxy swaps hop by
+//! hop, sending itself a message after every hop which takes what the hop
+//! returned, as the growth of its balance in the asset, on to the next one.
+//! What the last hop returns is checked against the minimum receive and
+//! sent on to the receiver.
+
+use cosmwasm_std::{to_binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+                   Uint128, WasmMsg};
+use cw20::Cw20ExecuteMsg;
+use terraswap::asset::{Asset, AssetInfo};
+use terraswap::pair::{Cw20HookMsg, ExecuteMsg as PairExecuteMsg};
+use terraswap::querier::{query_pair_info, reverse_simulate, simulate};
+use terraswap::router::{SimulateSwapOperationsResponse, SwapOperation};
+
+use crate::error::ContractError;
+use crate::fees::settle_platform_fees;
+use crate::msg::ExecuteMsg;
+use crate::state::{CONFIG, TERRASWAP_FACTORY};
+
+/// Most hops a route can take
+const MAX_SWAP_OPERATIONS: usize = 10;
+
+pub fn execute_swap_operations(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_asset: Asset,
+    operations: Vec<SwapOperation>,
+    minimum_receive: Option<Uint128>,
+    to: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.swap_opening_date > env.block.time {
+        return Err(ContractError::Std(StdError::generic_err("Swap is not enabled yet!!!")));
+    }
+    assert_operations(&operations)?;
+    if operations[0].get_offer_asset_info() != offer_asset.info {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The route does not start with the offer asset",
+        )));
+    }
+    let to = match to {
+        Some(to) => deps.api.addr_validate(&to)?,
+        None => info.sender.clone(),
+    };
+
+    let mut resp = Response::new();
+    let mut ust_spent = Uint128::zero();
+    match &offer_asset.info {
+        AssetInfo::NativeToken { denom } if *denom == config.usdc_ibc_symbol => {
+            ust_spent = offer_asset.amount;
+        }
+        AssetInfo::NativeToken { .. } => offer_asset.assert_sent_native_token_balance(&info)?,
+        AssetInfo::Token { contract_addr } => {
+            resp = resp.add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: info.sender.to_string(),
+                    recipient: env.contract.address.to_string(),
+                    amount: offer_asset.amount,
+                })?,
+                funds: vec![],
+            }));
+        }
+    }
+    let settlement = settle_platform_fees(
+        deps.branch(),
+        &env,
+        &info,
+        &ExecuteMsg::ExecuteSwapOperations {
+            offer_asset: offer_asset.clone(),
+            operations: operations.clone(),
+            minimum_receive,
+            to: Some(to.to_string()),
+        },
+        ust_spent,
+    )?;
+
+    Ok(resp
+        .add_messages(hop_messages(
+            deps.as_ref(),
+            &env,
+            operations,
+            offer_asset.amount,
+            minimum_receive,
+            to.to_string(),
+        )?)
+        .add_attributes(settlement.attributes())
+        .add_messages(settlement.messages)
+        .add_attribute("action", "execute_swap_operations"))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn continue_swap_operations(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operations: Vec<SwapOperation>,
+    asset_info: AssetInfo,
+    balance_before: Uint128,
+    minimum_receive: Option<Uint128>,
+    to: String,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let balance = asset_info.query_pool(&deps.querier, deps.api, env.contract.address.clone())?;
+    let received = balance.checked_sub(balance_before).map_err(StdError::from)?;
+
+    if !operations.is_empty() {
+        return Ok(Response::new()
+            .add_messages(hop_messages(deps.as_ref(), &env, operations, received, minimum_receive, to)?)
+            .add_attribute("action", "continue_swap_operations"));
+    }
+    if let Some(minimum_receive) = minimum_receive {
+        if received < minimum_receive {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "assertion failed; minimum receive amount: {}, swap amount: {}",
+                minimum_receive, received
+            ))));
+        }
+    }
+    let receiver = deps.api.addr_validate(&to)?;
+    Ok(Response::new()
+        .add_message(
+            Asset {
+                info: asset_info,
+                amount: received,
+            }
+            .into_msg(receiver)?,
+        )
+        .add_attribute("action", "finish_swap_operations")
+        .add_attribute("return_amount", received))
+}
+
+/// Swaps `offer_amount` on the first hop and leaves the rest of the route
+/// to a message the proxy sends itself afterwards
+fn hop_messages(
+    deps: Deps,
+    env: &Env,
+    mut operations: Vec<SwapOperation>,
+    offer_amount: Uint128,
+    minimum_receive: Option<Uint128>,
+    to: String,
+) -> StdResult<Vec<CosmosMsg>> {
+    let operation = operations.remove(0);
+    let pair = pair_address(deps, &operation)?;
+    let offer_asset_info = operation.get_offer_asset_info();
+    let ask_asset_info = operation.get_target_asset_info();
+
+    let swap = match offer_asset_info {
+        AssetInfo::NativeToken { denom } => WasmMsg::Execute {
+            contract_addr: pair,
+            msg: to_binary(&PairExecuteMsg::Swap {
+                offer_asset: Asset {
+                    info: AssetInfo::NativeToken { denom: denom.clone() },
+                    amount: offer_amount,
+                },
+                belief_price: None,
+                max_spread: None,
+                to: None,
+            })?,
+            funds: vec![Coin {
+                denom,
+                amount: offer_amount,
+            }],
+        },
+        AssetInfo::Token { contract_addr } => WasmMsg::Execute {
+            contract_addr,
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: pair,
+                amount: offer_amount,
+                msg: to_binary(&Cw20HookMsg::Swap {
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                })?,
+            })?,
+            funds: vec![],
+        },
+    };
+    let balance_before = ask_asset_info.query_pool(&deps.querier, deps.api, env.contract.address.clone())?;
+    let next = WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::ContinueSwapOperations {
+            operations,
+            asset_info: ask_asset_info,
+            balance_before,
+            minimum_receive,
+            to,
+        })?,
+        funds: vec![],
+    };
+    Ok(vec![CosmosMsg::Wasm(swap), CosmosMsg::Wasm(next)])
+}
+
+fn pair_address(deps: Deps, operation: &SwapOperation) -> StdResult<String> {
+    let factory = TERRASWAP_FACTORY
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("Terraswap factory is not configured"))?;
+    let pair = query_pair_info(
+        &deps.querier,
+        factory,
+        &[operation.get_offer_asset_info(), operation.get_target_asset_info()],
+    )?;
+    Ok(pair.contract_addr)
+}
+
+// Every hop has to swap into something else and start with what the hop
+// before it returned
+fn assert_operations(operations: &[SwapOperation]) -> StdResult<()> {
+    if operations.is_empty() {
+        return Err(StdError::generic_err("Must provide swap operations to execute"));
+    }
+    if operations.len() > MAX_SWAP_OPERATIONS {
+        return Err(StdError::generic_err(format!(
+            "A route can have at most {} swap operations",
+            MAX_SWAP_OPERATIONS
+        )));
+    }
+    for operation in operations {
+        if operation.get_offer_asset_info() == operation.get_target_asset_info() {
+            return Err(StdError::generic_err("A swap operation must swap into another asset"));
+        }
+    }
+    for hops in operations.windows(2) {
+        if hops[0].get_target_asset_info() != hops[1].get_offer_asset_info() {
+            return Err(StdError::generic_err("Swap operations do not connect"));
+        }
+    }
+    Ok(())
+}
+
+/// UST and FURY the platform fee of a route offering `offer_asset` is
+/// charged on. Any other offer asset is valued at its pair with UST.
+pub fn route_fee_base(deps: Deps, offer_asset: &Asset) -> StdResult<(Uint128, Uint128)> {
+    let config = CONFIG.load(deps.storage)?;
+    let ust = AssetInfo::NativeToken {
+        denom: config.usdc_ibc_symbol,
+    };
+    if offer_asset.info == ust {
+        return Ok((offer_asset.amount, Uint128::zero()));
+    }
+    if let AssetInfo::Token { contract_addr } = &offer_asset.info {
+        if *contract_addr == config.custom_token_address {
+            return Ok((Uint128::zero(), offer_asset.amount));
+        }
+    }
+    let pair = pair_address(
+        deps,
+        &SwapOperation::TerraSwap {
+            offer_asset_info: offer_asset.info.clone(),
+            ask_asset_info: ust,
+        },
+    )?;
+    let simulation = simulate(&deps.querier, deps.api.addr_validate(&pair)?, offer_asset)?;
+    Ok((simulation.return_amount, Uint128::zero()))
+}
+
+pub fn query_simulate_swap_operations(
+    deps: Deps,
+    offer_amount: Uint128,
+    operations: Vec<SwapOperation>,
+) -> StdResult<SimulateSwapOperationsResponse> {
+    assert_operations(&operations)?;
+    let mut amount = offer_amount;
+    for operation in operations {
+        let pair = pair_address(deps, &operation)?;
+        let simulation = simulate(
+            &deps.querier,
+            deps.api.addr_validate(&pair)?,
+            &Asset {
+                info: operation.get_offer_asset_info(),
+                amount,
+            },
+        )?;
+        amount = simulation.return_amount;
+    }
+    Ok(SimulateSwapOperationsResponse { amount })
+}
+
+pub fn query_reverse_simulate_swap_operations(
+    deps: Deps,
+    ask_amount: Uint128,
+    operations: Vec<SwapOperation>,
+) -> StdResult<SimulateSwapOperationsResponse> {
+    assert_operations(&operations)?;
+    let mut amount = ask_amount;
+    for operation in operations.into_iter().rev() {
+        let pair = pair_address(deps, &operation)?;
+        let simulation = reverse_simulate(
+            &deps.querier,
+            deps.api.addr_validate(&pair)?,
+            &Asset {
+                info: operation.get_target_asset_info(),
+                amount,
+            },
+        )?;
+        amount = simulation.offer_amount;
+    }
+    Ok(SimulateSwapOperationsResponse { amount })
+}
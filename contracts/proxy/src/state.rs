@@ -1,8 +1,14 @@
-use cosmwasm_std::{Addr, Binary, Coin, Timestamp, Uint128};
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Timestamp, Uint128};
+use cw_controllers::Claims;
 use cw_storage_plus::{Bound, Item, Map};
+use cw_utils::Duration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use terraswap::asset::AssetInfo;
+
+use crate::msg::{BondKind, Cw20FeePayment, PairSettings};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     /// admin address for configuration activities
@@ -56,6 +62,36 @@ pub struct Config {
 // put the length bytes at the first for compatibility with legacy singleton store
 pub const CONFIG: Item<Config> = Item::new("\u{0}\u{6}config");
 
+/// Admin proposed by the current admin, who takes over on accepting
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
+
+/// Factory the pairs of multi-hop swaps are looked up in
+pub const TERRASWAP_FACTORY: Item<Addr> = Item::new("terraswap_factory");
+
+/// How the platform fee of a CW20 hook is paid, in native funds when not set
+pub const CW20_FEE_PAYMENT: Item<Cw20FeePayment> = Item::new("cw20_fee_payment");
+
+/// Native funds deposited for the next CW20 hook of an address
+pub const DEPOSITS: Map<&Addr, Vec<Coin>> = Map::new("deposits");
+
+/// A pair of FURY and a native token the proxy provides to and swaps on
+/// besides the FURY/UST pair of the config
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ManagedPair {
+    pub asset_infos: [AssetInfo; 2],
+    pub pair_address: Addr,
+    pub liquidity_token: Addr,
+    ///Time in nano seconds since EPOC when the swapping will be enabled
+    pub swap_opening_date: Timestamp,
+    pub settings: PairSettings,
+}
+
+/// Registered pairs, keyed by [`crate::pairs::pair_key`]
+pub const MANAGED_PAIRS: Map<&str, ManagedPair> = Map::new("managed_pairs");
+
+/// Key of the registered pair each LP token belongs to
+pub const PAIR_OF_LIQUIDITY_TOKEN: Map<&Addr, String> = Map::new("pair_of_liquidity_token");
+
 pub const CONTRACT: Item<ContractVersion> = Item::new("contract_info");
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -89,6 +125,7 @@ pub enum SubMessageNextAction {
     ProvideLiquidity,
     TransferCustomAssetsFromFundsOwner,
     TransferToNativeInvestmentReceiveWallet,
+    StakeLiquidity,
 }
 
 /// This is used for saving pending request details
@@ -110,10 +147,29 @@ pub struct SubMessageDetails {
     pub user_address: String,
 
     pub is_fury_provided: bool,
+
+    /// Pair the liquidity goes to, the FURY/UST pair when none
+    #[serde(default)]
+    pub pair: Option<[AssetInfo; 2]>,
+
+    /// The LP tokens are staked for their receiver once the liquidity is provided
+    #[serde(default)]
+    pub auto_stake: bool,
+
+    /// Height of the block the step was sent in. A flow runs its course
+    /// within the message that starts it, so a step recorded in an earlier
+    /// block is stale.
+    #[serde(default)]
+    pub created_at_height: u64,
+
+    /// Native funds of the user the proxy holds for the flow at this step,
+    /// none for steps recorded before escrows were
+    #[serde(default)]
+    pub escrowed_funds: Vec<Coin>,
 }
 
 /// Map of request and list of their bonds. the key is request id and the
-/// Value jsonified request
+/// Value jsonified request. See [`crate::flow`] for the steps.
 pub const SUB_MESSAGE_DETAILS: Map<String, SubMessageDetails> = Map::new("pending_request_details");
 
 
@@ -130,6 +186,23 @@ pub struct BondedRewardsDetails {
     pub bonding_period: u64,
 
     pub bonding_start_timestamp: Timestamp,
+
+    /// Bond series the bond was bought from, none for bonds at the configured
+    /// discount rates
+    #[serde(default)]
+    pub series_id: Option<u64>,
+
+    /// Vests linearly over the bonding period instead of all at its end
+    #[serde(default)]
+    pub linear_vesting: bool,
+
+    /// Part of the bonded amount already claimed
+    #[serde(default)]
+    pub claimed_amount: Uint128,
+
+    /// Registered pair the bond was bought on, none for the FURY/UST pair
+    #[serde(default)]
+    pub pair: Option<[AssetInfo; 2]>,
 }
 
 /// Map of users and list of their bonded rewards. the key is user name and the
@@ -139,3 +212,110 @@ pub const BONDED_REWARDS_DETAILS: Map<String, Vec<BondedRewardsDetails>> =
 
 
 pub const SUB_REQ_ID: Item<u64> = Item::new("sub_req_id");
+
+/// A bond program selling a capped amount of FURY. The discount starts at
+/// `max_discount_rate` and falls towards `min_discount_rate` as the recently
+/// sold FURY (the debt) takes up the capacity. The debt pays itself off
+/// linearly over `debt_decay_period_in_sec`, which lets the discount recover.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct BondSeries {
+    pub series_id: u64,
+    pub kind: BondKind,
+    /// Registered pair the series sells bonds on, none for the FURY/UST pair
+    #[serde(default)]
+    pub pair: Option<[AssetInfo; 2]>,
+    /// Total FURY, discount included, the series can sell
+    pub capacity: Uint128,
+    /// FURY sold so far
+    pub sold: Uint128,
+    ///Specified in percentage multiplied by 100, i.e. 100% = 10000 and 0.01% = 1
+    pub max_discount_rate: u16,
+    ///Specified in percentage multiplied by 100, i.e. 100% = 10000 and 0.01% = 1
+    pub min_discount_rate: u16,
+    pub debt_decay_period_in_sec: u64,
+    /// Bonds of the series vest linearly over this period
+    pub vesting_period_in_sec: u64,
+    /// Debt as of `debt_updated_at`
+    pub debt: Uint128,
+    /// Time in seconds since EPOC
+    pub debt_updated_at: u64,
+    pub closed: bool,
+}
+
+pub const BOND_SERIES: Map<u64, BondSeries> = Map::new("bond_series");
+
+pub const BOND_SERIES_COUNT: Item<u64> = Item::new("bond_series_count");
+
+/// The open series of each bond kind and pair, keyed by
+/// [`crate::bonds::active_series_key`]
+pub const ACTIVE_BOND_SERIES: Map<&str, u64> = Map::new("active_bond_series");
+
+/// Cumulative UST per FURY of the pair, keyed by the block time in seconds
+/// it was observed at
+pub const FURY_PRICE_OBSERVATIONS: Map<u64, Uint128> = Map::new("fury_price_observations");
+
+/// Cumulative native token per FURY of the registered pairs, keyed by
+/// [`crate::pairs::pair_key`] and the block time in seconds it was observed at
+pub const PAIR_PRICE_OBSERVATIONS: Map<(&str, u64), Uint128> = Map::new("pair_price_observations");
+
+/// Registered pairs whose price is observed, keyed by [`crate::pairs::pair_key`],
+/// with the block time in seconds of their last observation
+pub const OBSERVED_PAIRS: Map<&str, u64> = Map::new("observed_pairs");
+
+/// Staking of the LP token of the FURY/UST pair
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct LpStaking {
+    /// LP token of the FURY/UST pair when staking was set up
+    pub liquidity_token: Addr,
+    /// FURY emitted to the stakers every second, split by stake
+    pub fury_per_second: Uint128,
+    /// The emitted FURY is fetched from this wallet
+    pub fury_reward_wallet: Addr,
+    /// Part of the platform fees shared with the stakers
+    ///Specified in percentage multiplied by 100, i.e. 100% = 10000 and 0.01% = 1
+    pub fee_share_rate: u16,
+    /// Unstaked LP tokens can be withdrawn after this long, right away when none
+    pub unbonding_period: Option<Duration>,
+    pub total_staked: Uint128,
+    /// LP tokens unstaked and not yet withdrawn. Claims left from before it
+    /// was kept are not counted.
+    #[serde(default)]
+    pub total_unbonding: Uint128,
+    /// FURY emitted per LP token staked since staking started
+    pub fury_per_share: Decimal,
+    /// UST of platform fees shared per LP token staked since staking started
+    pub ust_per_share: Decimal,
+    /// FURY of platform fees paid in FURY shared per LP token staked since
+    /// staking started
+    #[serde(default)]
+    pub fury_fees_per_share: Decimal,
+    /// Time in seconds since EPOC up to which the FURY has been emitted
+    pub emitted_until: u64,
+}
+
+pub const LP_STAKING: Item<LpStaking> = Item::new("lp_staking");
+
+/// Stake of a liquidity provider
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct LpStaker {
+    pub staked: Uint128,
+    /// [`LpStaking::fury_per_share`] the pending FURY is worked out up to
+    pub fury_per_share: Decimal,
+    /// [`LpStaking::ust_per_share`] the pending UST is worked out up to
+    pub ust_per_share: Decimal,
+    pub pending_fury: Uint128,
+    pub pending_ust: Uint128,
+    /// [`LpStaking::fury_fees_per_share`] the pending FURY fees are worked out up to
+    #[serde(default)]
+    pub fury_fees_per_share: Decimal,
+    #[serde(default)]
+    pub pending_fury_fees: Uint128,
+}
+
+pub const LP_STAKERS: Map<&Addr, LpStaker> = Map::new("lp_stakers");
+
+/// LP tokens unstaked and waiting out the unbonding period, by staker
+pub const LP_UNBONDING: Claims = Claims::new("lp_unbonding");
@@ -0,0 +1,2005 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+                            MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{coins, Addr, from_binary, to_binary, BankMsg, Coin, ContractResult, CosmosMsg, Decimal, Env, Event,
+                   OwnedDeps, QuerierResult, Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResponse,
+                   SubMsgResult, SystemError, SystemResult, Timestamp, Uint128, Uint64, WasmMsg, WasmQuery};
+use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_controllers::{Claim, ClaimsResponse};
+use cw_utils::{Duration, Expiration};
+use terraswap::asset::{Asset, AssetInfo, PairInfo};
+use terraswap::factory::QueryMsg as FactoryQueryMsg;
+use terraswap::pair::{CumulativePricesResponse, Cw20HookMsg, ExecuteMsg as PairExecuteMsg, PoolResponse,
+                      QueryMsg as PairQueryMsg, ReverseSimulationResponse, SimulationResponse};
+use terraswap::router::{SimulateSwapOperationsResponse, SwapOperation};
+
+use crate::bonds::{claimable_amount, sell_bond, vested_amount, BondTerms};
+use crate::contract::{execute, instantiate, query, reply, swap};
+use crate::error::ContractError;
+use crate::msg::{BondKind, BondQuoteResponse, Cw20FeePayment, ExecuteMsg, InstantiateMsg, LpStakerResponse,
+                 PairSettings, ProxyCw20HookMsg, QueryMsg, StaleRequestsResponse, TwapResponse, UpdateConfigMsg};
+use crate::pairs::pair_of_liquidity_token;
+use crate::state::{BondSeries, BondedRewardsDetails, Config, SubMessageNextAction,
+                   BONDED_REWARDS_DETAILS, SUB_MESSAGE_DETAILS, SUB_REQ_ID};
+
+const ADMIN: &str = "admin";
+const GAMER: &str = "gamer";
+const FURY: &str = "fury";
+const PAIR: &str = "pair";
+const PAIR_X: &str = "pair_x";
+const PAIR_LUNA: &str = "pair_luna";
+const TOKEN_X: &str = "token_x";
+const FACTORY: &str = "factory";
+const FEE_COLLECTOR: &str = "fee_collector";
+const UUSD: &str = "uusd";
+const ULUNA: &str = "uluna";
+
+// 10 FURY per UST
+const POOL_UST: u128 = 1_000_000_000;
+const POOL_FURY: u128 = 10_000_000_000;
+const POOL_SHARE: u128 = 1_000_000_000;
+const PROXY_FURY: u128 = 7_000_000;
+
+// The FURY/UST pair at its reserves, FURY/TOKEN_X and LUNA/FURY pairs, a
+// factory listing them and the token balances of the proxy. Every simulation doubles the offer.
+fn wasm_query(query: &WasmQuery) -> QuerierResult {
+    let (contract_addr, msg) = match query {
+        WasmQuery::Smart { contract_addr, msg } => (contract_addr.as_str(), msg),
+        _ => {
+            return SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: String::from("wasm"),
+            })
+        }
+    };
+    let response = match contract_addr {
+        FACTORY => match from_binary(msg).unwrap() {
+            FactoryQueryMsg::Pair { asset_infos } => {
+                let contract_addr = if asset_infos == [ust(0).info, fury(0).info] {
+                    PAIR
+                } else if asset_infos == [fury(0).info, token_x(0).info] {
+                    PAIR_X
+                } else if asset_infos == [luna(0).info, fury(0).info] {
+                    PAIR_LUNA
+                } else {
+                    return SystemResult::Ok(ContractResult::Err(String::from("Pair not found")));
+                };
+                to_binary(&PairInfo {
+                    asset_infos,
+                    contract_addr: contract_addr.to_string(),
+                    liquidity_token: format!("{}_lp", contract_addr),
+                    asset_decimals: [6, 6],
+                })
+            }
+            _ => return SystemResult::Ok(ContractResult::Err(String::from("Query is not supported"))),
+        },
+        PAIR | PAIR_X | PAIR_LUNA => match from_binary(msg).unwrap() {
+            PairQueryMsg::Pool {} => to_binary(&PoolResponse {
+                assets: [ust(POOL_UST), fury(POOL_FURY)],
+                total_share: Uint128::from(POOL_SHARE),
+            }),
+            PairQueryMsg::Simulation { offer_asset } => to_binary(&SimulationResponse {
+                return_amount: offer_asset.amount * Uint128::from(2u128),
+                spread_amount: Uint128::zero(),
+                commission_amount: Uint128::zero(),
+            }),
+            PairQueryMsg::ReverseSimulation { ask_asset } => to_binary(&ReverseSimulationResponse {
+                offer_amount: ask_asset.amount.multiply_ratio(1u128, 2u128),
+                spread_amount: Uint128::zero(),
+                commission_amount: Uint128::zero(),
+            }),
+            _ => return SystemResult::Ok(ContractResult::Err(String::from("Query is not supported"))),
+        },
+        FURY => to_binary(&Cw20BalanceResponse {
+            balance: Uint128::from(PROXY_FURY),
+        }),
+        TOKEN_X => to_binary(&Cw20BalanceResponse {
+            balance: Uint128::zero(),
+        }),
+        _ => {
+            return SystemResult::Err(SystemError::NoSuchContract {
+                addr: contract_addr.to_string(),
+            })
+        }
+    };
+    SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+}
+
+fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    deps.querier.update_wasm(wasm_query);
+
+    let msg = InstantiateMsg {
+        admin_address: ADMIN.to_string(),
+        custom_token_address: FURY.to_string(),
+        pair_discount_rate: 0,
+        pair_bonding_period_in_sec: 0,
+        pair_fury_reward_wallet: ADMIN.to_string(),
+        pair_lp_tokens_holder: ADMIN.to_string(),
+        native_discount_rate: 0,
+        native_bonding_period_in_sec: 0,
+        native_investment_reward_wallet: ADMIN.to_string(),
+        native_investment_receive_wallet: ADMIN.to_string(),
+        authorized_liquidity_provider: ADMIN.to_string(),
+        swap_opening_date: Uint64::zero(),
+        pool_pair_address: Some(PAIR.to_string()),
+        platform_fees_collector_wallet: FEE_COLLECTOR.to_string(),
+        platform_fees: Uint128::from(100u128),
+        transaction_fees: Uint128::from(100u128),
+        swap_fees: Uint128::from(100u128),
+        max_bonding_limit_per_user: 10,
+        usdc_ibc_symbol: UUSD.to_string(),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    deps
+}
+
+fn ust(amount: u128) -> Asset {
+    Asset {
+        info: AssetInfo::NativeToken {
+            denom: UUSD.to_string(),
+        },
+        amount: Uint128::from(amount),
+    }
+}
+
+fn luna(amount: u128) -> Asset {
+    Asset {
+        info: AssetInfo::NativeToken {
+            denom: ULUNA.to_string(),
+        },
+        amount: Uint128::from(amount),
+    }
+}
+
+fn token_x(amount: u128) -> Asset {
+    Asset {
+        info: AssetInfo::Token {
+            contract_addr: TOKEN_X.to_string(),
+        },
+        amount: Uint128::from(amount),
+    }
+}
+
+fn fury(amount: u128) -> Asset {
+    Asset {
+        info: AssetInfo::Token {
+            contract_addr: FURY.to_string(),
+        },
+        amount: Uint128::from(amount),
+    }
+}
+
+// The UST sent to each wallet by the bank messages of the response
+fn bank_sends(res: &Response) -> Vec<(String, u128)> {
+    res.messages
+        .iter()
+        .filter_map(|sub_msg| match &sub_msg.msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                assert_eq!(amount.len(), 1);
+                assert_eq!(amount[0].denom, UUSD);
+                Some((to_address.clone(), amount[0].amount.u128()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn swap_of_ust_refunds_the_overpaid_fee() {
+    let mut deps = setup();
+    // 3 percent of 1 UST
+    let info = mock_info(GAMER, &[Coin::new(1_050_000, UUSD)]);
+    let msg = ExecuteMsg::Swap {
+        offer_asset: ust(1_000_000),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        pair: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![(FEE_COLLECTOR.to_string(), 30_000), (GAMER.to_string(), 20_000)]
+    );
+}
+
+#[test]
+fn swap_of_fury_sends_the_fee_and_refunds_the_rest() {
+    let mut deps = setup();
+    // 3 percent of the 999_001 uusd 10 FURY are worth
+    let info = mock_info(GAMER, &[Coin::new(30_000, UUSD)]);
+    let msg = ExecuteMsg::Swap {
+        offer_asset: fury(10_000_000),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        pair: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![(FEE_COLLECTOR.to_string(), 29_970), (GAMER.to_string(), 30)]
+    );
+}
+
+#[test]
+fn swap_without_a_recipient_settles_the_fee_and_pays_the_sender() {
+    let mut deps = setup();
+    let info = mock_info(GAMER, &[Coin::new(1_050_000, UUSD)]);
+    let res = swap(deps.as_mut(), mock_env(), info, ust(1_000_000), None, None, None, None).unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![(FEE_COLLECTOR.to_string(), 30_000), (GAMER.to_string(), 20_000)]
+    );
+    let pair_swap = res.messages.iter().find_map(|sub_msg| match &sub_msg.msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) if contract_addr == PAIR => {
+            Some(from_binary::<PairExecuteMsg>(msg).unwrap())
+        }
+        _ => None,
+    });
+    match pair_swap {
+        Some(PairExecuteMsg::Swap { to, .. }) => assert_eq!(to, Some(GAMER.to_string())),
+        _ => panic!("no swap sent to the pair"),
+    }
+}
+
+#[test]
+fn provide_native_for_reward_refunds_the_overpaid_fee() {
+    let mut deps = setup();
+    // 2 percent of 1 UST
+    let info = mock_info(GAMER, &[Coin::new(1_025_000, UUSD)]);
+    let msg = ExecuteMsg::ProvideNativeForReward {
+        asset: ust(1_000_000),
+        slippage_tolerance: None,
+        auto_stake: None,
+        pair: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![(FEE_COLLECTOR.to_string(), 20_000), (GAMER.to_string(), 5_000)]
+    );
+}
+
+#[test]
+fn provide_pair_for_reward_sends_the_exact_fee() {
+    let mut deps = setup();
+    // 2 percent of 1 UST and the 999_001 uusd 10 FURY are worth
+    let info = mock_info(GAMER, &[Coin::new(1_039_980, UUSD)]);
+    let msg = ExecuteMsg::ProvidePairForReward {
+        assets: [ust(1_000_000), fury(10_000_000)],
+        slippage_tolerance: None,
+        auto_stake: None,
+        pair: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+    assert_eq!(bank_sends(&res), vec![(FEE_COLLECTOR.to_string(), 39_980)]);
+
+    let info = mock_info(GAMER, &[Coin::new(1_039_979, UUSD)]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientFees {
+            received: Uint128::from(39_979u128),
+            required: Uint128::from(39_980u128),
+        }
+    );
+}
+
+#[test]
+fn reward_claim_sends_the_fee_and_refunds_the_rest() {
+    let mut deps = setup();
+    BONDED_REWARDS_DETAILS
+        .save(
+            deps.as_mut().storage,
+            GAMER.to_string(),
+            &vec![BondedRewardsDetails {
+                user_address: GAMER.to_string(),
+                bonded_amount: Uint128::from(5_000_000u128),
+                bonding_period: 0,
+                bonding_start_timestamp: Timestamp::from_seconds(1),
+                series_id: None,
+                linear_vesting: false,
+                claimed_amount: Uint128::zero(),
+                pair: None,
+            }],
+        )
+        .unwrap();
+
+    // 2 percent of the 499_751 uusd 5 FURY are worth
+    let info = mock_info(GAMER, &[Coin::new(10_000, UUSD)]);
+    let msg = ExecuteMsg::RewardClaim {
+        receiver: GAMER.to_string(),
+        withdrawal_amount: None,
+        pair: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![(FEE_COLLECTOR.to_string(), 9_995), (GAMER.to_string(), 5)]
+    );
+    assert!(BONDED_REWARDS_DETAILS
+        .load(&deps.storage, GAMER.to_string())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn fees_sent_short_of_the_swapped_ust_are_rejected() {
+    let mut deps = setup();
+    let info = mock_info(GAMER, &[Coin::new(500_000, UUSD)]);
+    let msg = ExecuteMsg::Swap {
+        offer_asset: ust(1_000_000),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        pair: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientFees {
+            received: Uint128::zero(),
+            required: Uint128::from(30_000u128),
+        }
+    );
+}
+
+fn set_factory(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>) {
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        terraswap_factory: Some(FACTORY.to_string()),
+        ..UpdateConfigMsg::default()
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    assert_eq!(res.attributes[1].key, "terraswap_factory");
+}
+
+fn route() -> Vec<SwapOperation> {
+    vec![
+        SwapOperation::TerraSwap {
+            offer_asset_info: ust(0).info,
+            ask_asset_info: fury(0).info,
+        },
+        SwapOperation::TerraSwap {
+            offer_asset_info: fury(0).info,
+            ask_asset_info: token_x(0).info,
+        },
+    ]
+}
+
+#[test]
+fn swap_operations_swap_the_first_hop_and_leave_the_rest_to_the_proxy() {
+    let mut deps = setup();
+    set_factory(&mut deps);
+
+    // 3 percent of 1 UST, once for the route
+    let info = mock_info(GAMER, &[Coin::new(1_040_000, UUSD)]);
+    let msg = ExecuteMsg::ExecuteSwapOperations {
+        offer_asset: ust(1_000_000),
+        operations: route(),
+        minimum_receive: Some(Uint128::from(3_000_000u128)),
+        to: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[..2],
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: PAIR.to_string(),
+                msg: to_binary(&PairExecuteMsg::Swap {
+                    offer_asset: ust(1_000_000),
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                })
+                .unwrap(),
+                funds: coins(1_000_000, UUSD),
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&ExecuteMsg::ContinueSwapOperations {
+                    operations: route()[1..].to_vec(),
+                    asset_info: fury(0).info,
+                    balance_before: Uint128::from(PROXY_FURY),
+                    minimum_receive: Some(Uint128::from(3_000_000u128)),
+                    to: GAMER.to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert_eq!(
+        bank_sends(&res),
+        vec![(FEE_COLLECTOR.to_string(), 30_000), (GAMER.to_string(), 10_000)]
+    );
+
+    // Only the proxy itself carries a route on
+    let msg = ExecuteMsg::ContinueSwapOperations {
+        operations: route()[1..].to_vec(),
+        asset_info: fury(0).info,
+        balance_before: Uint128::zero(),
+        minimum_receive: None,
+        to: GAMER.to_string(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // The FURY the first hop returned goes on to the next pair
+    let res = execute(deps.as_mut(), mock_env(), mock_info(MOCK_CONTRACT_ADDR, &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: FURY.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: PAIR_X.to_string(),
+                amount: Uint128::from(PROXY_FURY),
+                msg: to_binary(&Cw20HookMsg::Swap {
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                })
+                .unwrap(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+}
+
+#[test]
+fn last_hop_is_held_to_the_minimum_receive() {
+    let mut deps = setup();
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(1_500, UUSD));
+    let continue_msg = |minimum_receive: u128| ExecuteMsg::ContinueSwapOperations {
+        operations: vec![],
+        asset_info: ust(0).info,
+        balance_before: Uint128::from(1_000u128),
+        minimum_receive: Some(Uint128::from(minimum_receive)),
+        to: GAMER.to_string(),
+    };
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(MOCK_CONTRACT_ADDR, &[]), continue_msg(501)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "assertion failed; minimum receive amount: 501, swap amount: 500"
+        ))
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info(MOCK_CONTRACT_ADDR, &[]), continue_msg(500)).unwrap();
+    assert_eq!(bank_sends(&res), vec![(GAMER.to_string(), 500)]);
+}
+
+#[test]
+fn swap_operations_are_simulated_hop_by_hop() {
+    let mut deps = setup();
+    set_factory(&mut deps);
+
+    let msg = QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::from(1_000u128),
+        operations: route(),
+    };
+    let res: SimulateSwapOperationsResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::from(4_000u128));
+
+    let msg = QueryMsg::ReverseSimulateSwapOperations {
+        ask_amount: Uint128::from(4_000u128),
+        operations: route(),
+    };
+    let res: SimulateSwapOperationsResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::from(1_000u128));
+
+    // Hops have to connect
+    let mut operations = route();
+    operations.swap(0, 1);
+    let msg = QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::from(1_000u128),
+        operations,
+    };
+    assert_eq!(
+        query(deps.as_ref(), mock_env(), msg).unwrap_err(),
+        StdError::generic_err("Swap operations do not connect")
+    );
+}
+
+fn register_luna_pair(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, swap_opening_date: u64) {
+    let msg = ExecuteMsg::RegisterPair {
+        asset_infos: [luna(0).info, fury(0).info],
+        swap_opening_date: Uint64::from(swap_opening_date),
+        settings: PairSettings {
+            swap_fees: Some(Uint128::from(200u128)),
+            ..PairSettings::default()
+        },
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+}
+
+#[test]
+fn registered_pairs_override_the_config() {
+    let mut deps = setup();
+    set_factory(&mut deps);
+
+    let msg = ExecuteMsg::RegisterPair {
+        asset_infos: [luna(0).info, fury(0).info],
+        swap_opening_date: Uint64::zero(),
+        settings: PairSettings::default(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let msg = ExecuteMsg::RegisterPair {
+        asset_infos: [fury(0).info, token_x(0).info],
+        swap_opening_date: Uint64::zero(),
+        settings: PairSettings::default(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err("A managed pair must be of FURY and a native token"))
+    );
+
+    register_luna_pair(&mut deps, 1_000);
+    // Either way round selects the pair
+    let msg = QueryMsg::Configuration {
+        pair: Some([fury(0).info, luna(0).info]),
+    };
+    let config: Config = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(config.pool_pair_address, PAIR_LUNA);
+    assert_eq!(config.liquidity_token, Addr::unchecked("pair_luna_lp"));
+    assert_eq!(config.swap_opening_date, Timestamp::from_nanos(1_000));
+    assert_eq!(config.swap_fees, Uint128::from(200u128));
+    assert_eq!(config.platform_fees, Uint128::from(100u128));
+
+    let config: Config =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Configuration { pair: None }).unwrap()).unwrap();
+    assert_eq!(config.pool_pair_address, PAIR);
+
+    let msg = ExecuteMsg::DeregisterPair {
+        asset_infos: [luna(0).info, fury(0).info],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    let msg = QueryMsg::Pool {
+        pair: Some([luna(0).info, fury(0).info]),
+    };
+    assert_eq!(
+        query(deps.as_ref(), mock_env(), msg).unwrap_err(),
+        StdError::generic_err("Pair fury/uluna is not managed")
+    );
+}
+
+#[test]
+fn liquidity_tokens_are_indexed_to_their_pair() {
+    let mut deps = setup();
+    set_factory(&mut deps);
+    let lp_token = Addr::unchecked("pair_luna_lp");
+    assert_eq!(pair_of_liquidity_token(deps.as_ref(), &lp_token).unwrap(), None);
+
+    register_luna_pair(&mut deps, 0);
+    let managed = pair_of_liquidity_token(deps.as_ref(), &lp_token).unwrap().unwrap();
+    assert_eq!(managed.pair_address, Addr::unchecked(PAIR_LUNA));
+    // Registering again keeps the index
+    register_luna_pair(&mut deps, 1_000);
+    let managed = pair_of_liquidity_token(deps.as_ref(), &lp_token).unwrap().unwrap();
+    assert_eq!(managed.swap_opening_date, Timestamp::from_nanos(1_000));
+
+    let msg = ExecuteMsg::DeregisterPair {
+        asset_infos: [fury(0).info, luna(0).info],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    assert_eq!(pair_of_liquidity_token(deps.as_ref(), &lp_token).unwrap(), None);
+}
+
+#[test]
+fn swap_on_a_managed_pair_charges_its_fees_in_ust() {
+    let mut deps = setup();
+    set_factory(&mut deps);
+    register_luna_pair(&mut deps, mock_env().block.time.nanos() + 1);
+
+    let swap = ExecuteMsg::Swap {
+        offer_asset: luna(1_000_000),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        pair: Some([luna(0).info, fury(0).info]),
+    };
+    let info = mock_info(GAMER, &[Coin::new(1_000_000, ULUNA), Coin::new(40_000, UUSD)]);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), swap.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err("Swap is not enabled yet!!!")));
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(1);
+    let res = execute(deps.as_mut(), env, info, swap).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: PAIR_LUNA.to_string(),
+            msg: to_binary(&PairExecuteMsg::Swap {
+                offer_asset: luna(1_000_000),
+                belief_price: None,
+                max_spread: None,
+                to: Some(GAMER.to_string()),
+            })
+            .unwrap(),
+            funds: coins(1_000_000, ULUNA),
+        })
+    );
+    // 4 percent of the 998_004 uusd the 9_990_010 FURY 1 LUNA is worth fetch
+    assert_eq!(
+        bank_sends(&res),
+        vec![(FEE_COLLECTOR.to_string(), 39_920), (GAMER.to_string(), 80)]
+    );
+}
+
+const LP_TOKEN: &str = "pair_lp";
+const HOLDER: &str = "holder";
+
+// LP staking of the FURY/UST LP token at 10 FURY a second, with half the
+// platform fees going to the stakers
+fn setup_lp_staking(unbonding_period: Option<Duration>) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let mut deps = setup();
+    let msg = ExecuteMsg::Configure {
+        pool_pair_address: None,
+        liquidity_token: Some(LP_TOKEN.to_string()),
+        swap_opening_date: Uint64::zero(),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    let msg = ExecuteMsg::ConfigureLpStaking {
+        fury_per_second: Uint128::from(10u128),
+        fury_reward_wallet: ADMIN.to_string(),
+        fee_share_rate: 5000,
+        unbonding_period,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    deps
+}
+
+fn stake_lp(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    token: &str,
+    staker: &str,
+    amount: u128,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: staker.to_string(),
+        amount: Uint128::from(amount),
+        msg: to_binary(&ProxyCw20HookMsg::StakeLp {}).unwrap(),
+    });
+    execute(deps.as_mut(), mock_env(), mock_info(token, &[]), msg)
+}
+
+fn lp_staker(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, env: Env, staker: &str) -> LpStakerResponse {
+    let msg = QueryMsg::LpStaker {
+        address: staker.to_string(),
+    };
+    from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap()
+}
+
+#[test]
+fn lp_stakers_earn_emissions_and_a_share_of_the_platform_fees() {
+    let mut deps = setup_lp_staking(None);
+    let err = stake_lp(&mut deps, TOKEN_X, GAMER, 100).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    stake_lp(&mut deps, LP_TOKEN, GAMER, 100).unwrap();
+    stake_lp(&mut deps, LP_TOKEN, HOLDER, 300).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(10);
+    // A quarter of the 100 FURY of 10 seconds
+    assert_eq!(
+        lp_staker(&deps, env.clone(), GAMER),
+        LpStakerResponse {
+            address: GAMER.to_string(),
+            staked: Uint128::from(100u128),
+            pending_fury: Uint128::from(25u128),
+            pending_ust: Uint128::zero(),
+            pending_fury_fees: Uint128::zero(),
+        }
+    );
+
+    // Half the 30_000 uusd fee stays with the proxy for the stakers
+    let msg = ExecuteMsg::Swap {
+        offer_asset: ust(1_000_000),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        pair: None,
+    };
+    let info = mock_info(GAMER, &[Coin::new(1_050_000, UUSD)]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![(FEE_COLLECTOR.to_string(), 15_000), (GAMER.to_string(), 20_000)]
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info(GAMER, &[]), ExecuteMsg::ClaimLpRewards {}).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: FURY.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: ADMIN.to_string(),
+                recipient: GAMER.to_string(),
+                amount: Uint128::from(25u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(bank_sends(&res), vec![(GAMER.to_string(), 3_750)]);
+    let err = execute(deps.as_mut(), env.clone(), mock_info(GAMER, &[]), ExecuteMsg::ClaimLpRewards {}).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err("No LP staking rewards to claim")));
+
+    let msg = QueryMsg::LpStakers {
+        start_after: None,
+        limit: Some(1),
+    };
+    let stakers: Vec<LpStakerResponse> = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(stakers.len(), 1);
+    assert_eq!(stakers[0].address, GAMER);
+    let msg = QueryMsg::LpStakers {
+        start_after: Some(GAMER.to_string()),
+        limit: None,
+    };
+    let stakers: Vec<LpStakerResponse> = from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+    assert_eq!(stakers.len(), 1);
+    assert_eq!(stakers[0].address, HOLDER);
+    assert_eq!(stakers[0].pending_fury, Uint128::from(75u128));
+    assert_eq!(stakers[0].pending_ust, Uint128::from(11_250u128));
+}
+
+#[test]
+fn unstaked_lp_waits_out_the_unbonding_period() {
+    let mut deps = setup_lp_staking(Some(Duration::Time(100)));
+    stake_lp(&mut deps, LP_TOKEN, GAMER, 100).unwrap();
+
+    let unstake = |amount: u128| ExecuteMsg::UnstakeLp {
+        amount: Uint128::from(amount),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), unstake(101)).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err("Can unstake up to 100 LP tokens")));
+    let res = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), unstake(40)).unwrap();
+    assert!(res.messages.is_empty());
+
+    let release_at = Expiration::AtTime(mock_env().block.time.plus_seconds(100));
+    let msg = QueryMsg::LpUnbonding {
+        address: GAMER.to_string(),
+    };
+    let unbonding: ClaimsResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        unbonding.claims,
+        vec![Claim {
+            amount: Uint128::from(40u128),
+            release_at,
+        }]
+    );
+    assert_eq!(lp_staker(&deps, mock_env(), GAMER).staked, Uint128::from(60u128));
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), ExecuteMsg::WithdrawUnbondedLp {}).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err("No LP tokens done unbonding")));
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    let res = execute(deps.as_mut(), env, mock_info(GAMER, &[]), ExecuteMsg::WithdrawUnbondedLp {}).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: LP_TOKEN.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: GAMER.to_string(),
+                amount: Uint128::from(40u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+}
+
+#[test]
+fn lp_staking_follows_the_configured_lp_token() {
+    let mut deps = setup_lp_staking(None);
+    let configure = |liquidity_token: &str| ExecuteMsg::Configure {
+        pool_pair_address: None,
+        liquidity_token: Some(liquidity_token.to_string()),
+        swap_opening_date: Uint64::zero(),
+    };
+    let configure_lp_staking = ExecuteMsg::ConfigureLpStaking {
+        fury_per_second: Uint128::from(10u128),
+        fury_reward_wallet: ADMIN.to_string(),
+        fee_share_rate: 5000,
+        unbonding_period: Some(Duration::Time(100)),
+    };
+    let auto_stake = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+        let info = mock_info(GAMER, &[Coin::new(1_039_980, UUSD)]);
+        let msg = ExecuteMsg::ProvidePairForReward {
+            assets: [ust(1_000_000), fury(10_000_000)],
+            slippage_tolerance: None,
+            auto_stake: Some(true),
+            pair: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg)
+    };
+    let changed = ContractError::Std(StdError::generic_err(
+        "The LP token of the FURY/UST pair changed, LP staking has to be configured again",
+    ));
+    let held = ContractError::Std(StdError::generic_err(
+        "The LP token can not change while LP tokens are staked or unbonding",
+    ));
+
+    // Nothing staked yet, the new LP token takes over once staking is
+    // configured again
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), configure("new_lp")).unwrap();
+    assert_eq!(stake_lp(&mut deps, LP_TOKEN, GAMER, 100).unwrap_err(), changed);
+    assert_eq!(auto_stake(&mut deps).unwrap_err(), changed);
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), configure_lp_staking.clone()).unwrap();
+    let err = stake_lp(&mut deps, LP_TOKEN, GAMER, 100).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    stake_lp(&mut deps, "new_lp", GAMER, 100).unwrap();
+    auto_stake(&mut deps).unwrap();
+
+    // Staked LP tokens hold the LP token in place
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), configure(LP_TOKEN)).unwrap_err();
+    assert_eq!(err, held);
+
+    // So do unbonding ones, till they are withdrawn
+    let unstake = ExecuteMsg::UnstakeLp {
+        amount: Uint128::from(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), unstake).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), configure(LP_TOKEN)).unwrap_err();
+    assert_eq!(err, held);
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    execute(deps.as_mut(), env, mock_info(GAMER, &[]), ExecuteMsg::WithdrawUnbondedLp {}).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), configure(LP_TOKEN)).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), configure_lp_staking).unwrap();
+    stake_lp(&mut deps, LP_TOKEN, GAMER, 100).unwrap();
+}
+
+fn send_fury(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    token: &str,
+    amount: u128,
+    hook: ProxyCw20HookMsg,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: GAMER.to_string(),
+        amount: Uint128::from(amount),
+        msg: to_binary(&hook).unwrap(),
+    });
+    execute(deps.as_mut(), mock_env(), mock_info(token, &[]), msg)
+}
+
+fn fury_swap_hook() -> ProxyCw20HookMsg {
+    ProxyCw20HookMsg::Swap {
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        pair: None,
+    }
+}
+
+fn fury_sent_to_pair(pair: &str, amount: u128) -> CosmosMsg {
+    CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: FURY.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Send {
+            contract: pair.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&Cw20HookMsg::Swap {
+                belief_price: None,
+                max_spread: None,
+                to: Some(GAMER.to_string()),
+            })
+            .unwrap(),
+        })
+        .unwrap(),
+        funds: vec![],
+    })
+}
+
+#[test]
+fn cw20_swap_pays_the_fee_out_of_the_deposit() {
+    let mut deps = setup();
+    let err = send_fury(&mut deps, TOKEN_X, 10_000_000, fury_swap_hook()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = send_fury(&mut deps, FURY, 10_000_000, fury_swap_hook()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientFees {
+            received: Uint128::zero(),
+            required: Uint128::from(29_970u128),
+        }
+    );
+
+    // The deposit is a transaction of its own, of some funds
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), ExecuteMsg::DepositFunds {}).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err("No funds to deposit")));
+    let info = mock_info(GAMER, &[Coin::new(50_000, UUSD)]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::DepositFunds {}).unwrap();
+    // The same 3 percent of the 999_001 uusd 10 FURY are worth a native swap pays
+    let res = send_fury(&mut deps, FURY, 10_000_000, fury_swap_hook()).unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![(FEE_COLLECTOR.to_string(), 29_970), (GAMER.to_string(), 20_030)]
+    );
+    assert_eq!(res.messages[2].msg, fury_sent_to_pair(PAIR, 10_000_000));
+    let msg = QueryMsg::Deposit {
+        address: GAMER.to_string(),
+    };
+    let deposit: Vec<Coin> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert!(deposit.is_empty());
+}
+
+#[test]
+fn cw20_swap_can_deduct_the_fee_from_the_fury() {
+    let mut deps = setup();
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        cw20_fee_payment: Some(Cw20FeePayment::Fury),
+        ..UpdateConfigMsg::default()
+    });
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    // 3 percent of the 10 FURY
+    let res = send_fury(&mut deps, FURY, 10_000_000, fury_swap_hook()).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: FURY.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: FEE_COLLECTOR.to_string(),
+                amount: Uint128::from(300_000u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(res.messages[1].msg, fury_sent_to_pair(PAIR, 9_700_000));
+    assert!(bank_sends(&res).is_empty());
+
+    // The 4 percent of the LUNA/FURY pair
+    set_factory(&mut deps);
+    register_luna_pair(&mut deps, 0);
+    let hook = ProxyCw20HookMsg::Swap {
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        pair: Some([luna(0).info, fury(0).info]),
+    };
+    let res = send_fury(&mut deps, FURY, 10_000_000, hook).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: FURY.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: FEE_COLLECTOR.to_string(),
+                amount: Uint128::from(400_000u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(res.messages[1].msg, fury_sent_to_pair(PAIR_LUNA, 9_600_000));
+}
+
+#[test]
+fn fury_paid_fees_are_shared_with_lp_stakers() {
+    let mut deps = setup_lp_staking(None);
+    stake_lp(&mut deps, LP_TOKEN, HOLDER, 100).unwrap();
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        cw20_fee_payment: Some(Cw20FeePayment::Fury),
+        ..UpdateConfigMsg::default()
+    });
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    // Half the 300_000 FURY fee stays with the proxy for the stakers
+    let res = send_fury(&mut deps, FURY, 10_000_000, fury_swap_hook()).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: FURY.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: FEE_COLLECTOR.to_string(),
+                amount: Uint128::from(150_000u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(lp_staker(&deps, mock_env(), HOLDER).pending_fury_fees, Uint128::from(150_000u128));
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info(HOLDER, &[]), ExecuteMsg::ClaimLpRewards {}).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: FURY.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: HOLDER.to_string(),
+                amount: Uint128::from(150_000u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+}
+
+#[test]
+fn cw20_provide_liquidity_takes_the_ust_out_of_the_deposit() {
+    let mut deps = setup();
+    let hook = ProxyCw20HookMsg::ProvideLiquidity {
+        assets: [ust(1_000_000), fury(10_000_000)],
+        slippage_tolerance: None,
+        auto_stake: None,
+        pair: None,
+    };
+    let info = mock_info(GAMER, &[Coin::new(500_000, UUSD)]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::DepositFunds {}).unwrap();
+    let err = send_fury(&mut deps, FURY, 10_000_000, hook.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err("Deposit falls short of the 1000000uusd provided"))
+    );
+
+    // 2 percent of 1 UST and the 999_001 uusd 10 FURY are worth
+    let info = mock_info(GAMER, &[Coin::new(1_039_980, UUSD)]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::DepositFunds {}).unwrap();
+    let res = send_fury(&mut deps, FURY, 10_000_000, hook).unwrap();
+    assert_eq!(bank_sends(&res), vec![(FEE_COLLECTOR.to_string(), 39_980)]);
+    // The reward is fetched for the bond right away, the FURY being with the proxy
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) => {
+            assert_eq!(contract_addr, FURY);
+            match from_binary(msg).unwrap() {
+                Cw20ExecuteMsg::TransferFrom { owner, .. } => assert_eq!(owner, ADMIN),
+                msg => panic!("unexpected {:?}", msg),
+            }
+        }
+        msg => panic!("unexpected {:?}", msg),
+    }
+    let bonds = BONDED_REWARDS_DETAILS.load(&deps.storage, GAMER.to_string()).unwrap();
+    assert_eq!(bonds.len(), 1);
+}
+
+fn provide_pair_for_reward(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>) -> u64 {
+    let info = mock_info(GAMER, &[Coin::new(1_039_980, UUSD)]);
+    let msg = ExecuteMsg::ProvidePairForReward {
+        assets: [ust(1_000_000), fury(10_000_000)],
+        slippage_tolerance: None,
+        auto_stake: None,
+        pair: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    res.messages[0].id
+}
+
+fn reply_ok(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, id: u64) -> Response {
+    let msg = Reply {
+        id,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    reply(deps.as_mut(), mock_env(), msg).unwrap()
+}
+
+#[test]
+fn replies_move_a_provide_flow_on_step_by_step() {
+    let mut deps = setup();
+    let id = provide_pair_for_reward(&mut deps);
+
+    // The reward, the allowance and then the liquidity itself
+    let res = reply_ok(&mut deps, id);
+    let id = res.messages[0].id;
+    let res = reply_ok(&mut deps, id);
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+            Cw20ExecuteMsg::IncreaseAllowance { spender, .. } => assert_eq!(spender, PAIR),
+            msg => panic!("unexpected {:?}", msg),
+        },
+        msg => panic!("unexpected {:?}", msg),
+    }
+    let res = reply_ok(&mut deps, res.messages[0].id);
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: PAIR.to_string(),
+            msg: to_binary(&PairExecuteMsg::ProvideLiquidity {
+                assets: [ust(1_000_000), fury(10_000_000)],
+                slippage_tolerance: None,
+                receiver: Some(ADMIN.to_string()),
+            })
+            .unwrap(),
+            funds: coins(1_000_000, UUSD),
+        })
+    );
+
+    // The provision records no step, its reply passes through
+    let res = reply_ok(&mut deps, res.messages[0].id);
+    assert!(res.messages.is_empty());
+}
+
+fn share_event(contract_addr: &str, share: u128) -> Event {
+    Event::new("wasm")
+        .add_attribute("_contract_address", contract_addr)
+        .add_attribute("share", share.to_string())
+}
+
+#[test]
+fn auto_staked_liquidity_is_staked_for_the_lp_tokens_holder() {
+    let mut deps = setup_lp_staking(None);
+    let info = mock_info(GAMER, &[Coin::new(1_025_000, UUSD)]);
+    let msg = ExecuteMsg::ProvideNativeForReward {
+        asset: ust(1_000_000),
+        slippage_tolerance: None,
+        auto_stake: Some(true),
+        pair: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err("A native investment provides no LP tokens to stake"))
+    );
+
+    let info = mock_info(GAMER, &[Coin::new(1_039_980, UUSD)]);
+    let msg = ExecuteMsg::ProvidePairForReward {
+        assets: [ust(1_000_000), fury(10_000_000)],
+        slippage_tolerance: None,
+        auto_stake: Some(true),
+        pair: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = reply_ok(&mut deps, res.messages[0].id);
+    let res = reply_ok(&mut deps, res.messages[0].id);
+    let res = reply_ok(&mut deps, res.messages[0].id);
+    // The LP tokens go to the proxy
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) => {
+            assert_eq!(contract_addr, PAIR);
+            match from_binary(msg).unwrap() {
+                PairExecuteMsg::ProvideLiquidity { receiver, .. } => {
+                    assert_eq!(receiver, Some(MOCK_CONTRACT_ADDR.to_string()))
+                }
+                msg => panic!("unexpected {:?}", msg),
+            }
+        }
+        msg => panic!("unexpected {:?}", msg),
+    }
+
+    // Only the share the pair reports is staked
+    let msg = Reply {
+        id: res.messages[0].id,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![share_event(TOKEN_X, 7), share_event(PAIR, 1_000)],
+            data: None,
+        }),
+    };
+    reply(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(lp_staker(&deps, mock_env(), ADMIN).staked, Uint128::from(1_000u128));
+}
+
+#[test]
+fn a_failed_step_fails_the_message() {
+    let mut deps = setup();
+    let id = provide_pair_for_reward(&mut deps);
+    let msg = Reply {
+        id,
+        result: SubMsgResult::Err(String::from("allowance exceeded")),
+    };
+    let err = reply(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::StepFailed {
+            id,
+            next_action: SubMessageNextAction::TransferCustomAssetsFromFundsOwner,
+            error: String::from("allowance exceeded"),
+        }
+    );
+}
+
+fn stale_requests(
+    deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    env: Env,
+    start_after: Option<u64>,
+) -> StaleRequestsResponse {
+    let msg = QueryMsg::StaleRequests {
+        start_after,
+        limit: None,
+    };
+    from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap()
+}
+
+// A step as recorded before steps had a height or an escrow
+fn save_legacy_request(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, id: u64) {
+    let payload = to_binary(&PairExecuteMsg::ProvideLiquidity {
+        assets: [ust(1_000_000), fury(10_000_000)],
+        slippage_tolerance: None,
+        receiver: Some(ADMIN.to_string()),
+    })
+    .unwrap();
+    let step = format!(
+        r#"{{"sub_req_id":"{}","request_type":"provide_liquidity_sub_msg","next_action":"increase_allowance","sub_message_payload":"{}","funds":[{{"denom":"uusd","amount":"1000000"}}],"user_address":"{}","is_fury_provided":true}}"#,
+        id, payload, GAMER
+    );
+    deps.storage.set(&SUB_MESSAGE_DETAILS.key(id.to_string()), step.as_bytes());
+}
+
+#[test]
+fn stale_requests_are_listed_and_recovered() {
+    let mut deps = setup();
+    // Replies are not run here, so the step is left over
+    let id = provide_pair_for_reward(&mut deps);
+    assert!(stale_requests(&deps, mock_env(), None).requests.is_empty());
+    let recover = ExecuteMsg::RecoverStaleRequest { id, refund: true };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), recover.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err(format!("Request {} is not stale", id))));
+
+    let mut env = mock_env();
+    env.block.height += 1;
+    let stale = stale_requests(&deps, env.clone(), None);
+    assert_eq!(stale.requests.len(), 1);
+    assert_eq!(stale.requests[0].escrowed_funds, coins(1_000_000, UUSD));
+
+    let err = execute(deps.as_mut(), env.clone(), mock_info(GAMER, &[]), recover.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    // Only what the proxy still holds of the escrow is refunded
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(999_999, UUSD));
+    let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), recover.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err("Proxy holds less than the 1000000uusd escrowed"))
+    );
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(1_000_000, UUSD));
+    let res = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), recover).unwrap();
+    assert_eq!(bank_sends(&res), vec![(GAMER.to_string(), 1_000_000)]);
+    assert!(stale_requests(&deps, env, None).requests.is_empty());
+}
+
+#[test]
+fn legacy_stale_requests_are_paged_by_id_and_refund_nothing() {
+    let mut deps = setup();
+    SUB_REQ_ID.save(&mut deps.storage, &1_000).unwrap();
+    for id in [9, 10, 89, 900] {
+        save_legacy_request(&mut deps, id);
+    }
+
+    // In the order of the ids, a page looking at 300 of them
+    let ids = |page: &StaleRequestsResponse| -> Vec<String> {
+        page.requests.iter().map(|step| step.sub_req_id.clone()).collect()
+    };
+    let page = stale_requests(&deps, mock_env(), Some(9));
+    assert_eq!(ids(&page), vec!["10", "89"]);
+    assert_eq!(page.next_start_after, Some(309));
+    let page = stale_requests(&deps, mock_env(), Some(309));
+    assert!(page.requests.is_empty());
+    assert_eq!(page.next_start_after, Some(609));
+    let page = stale_requests(&deps, mock_env(), Some(609));
+    assert_eq!(ids(&page), vec!["900"]);
+    assert_eq!(page.next_start_after, Some(909));
+    let page = stale_requests(&deps, mock_env(), Some(909));
+    assert!(page.requests.is_empty());
+    assert_eq!(page.next_start_after, None);
+
+    // The funds of a legacy step were never recorded as escrowed
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(1_000_000, UUSD));
+    let recover = ExecuteMsg::RecoverStaleRequest { id: 10, refund: true };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), recover).unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(ids(&stale_requests(&deps, mock_env(), None)), vec!["9", "89"]);
+}
+
+#[test]
+fn withdrawn_assets_go_on_to_the_provider() {
+    let mut deps = setup();
+    let msg = ExecuteMsg::Configure {
+        pool_pair_address: None,
+        liquidity_token: Some(LP_TOKEN.to_string()),
+        swap_opening_date: Uint64::zero(),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    // The proxy looks at its balances once the pair has refunded it
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(5_000, UUSD));
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: GAMER.to_string(),
+        amount: Uint128::from(POOL_SHARE / 10),
+        msg: to_binary(&ProxyCw20HookMsg::WithdrawLiquidity {}).unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info(LP_TOKEN, &[]), msg).unwrap();
+    assert_eq!(res.messages.len(), 2);
+    let next = ExecuteMsg::ContinueWithdrawLiquidity {
+        balances_before: [ust(5_000), fury(PROXY_FURY)],
+        to: GAMER.to_string(),
+    };
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&next).unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // Whatever the pair refunded, tax and rounding included
+    let next = ExecuteMsg::ContinueWithdrawLiquidity {
+        balances_before: [ust(5_000), fury(PROXY_FURY - 1_000_000)],
+        to: GAMER.to_string(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), next.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(104_999, UUSD));
+    let res = execute(deps.as_mut(), mock_env(), mock_info(MOCK_CONTRACT_ADDR, &[]), next).unwrap();
+    assert_eq!(bank_sends(&res), vec![(GAMER.to_string(), 99_999)]);
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: FURY.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: GAMER.to_string(),
+                amount: Uint128::from(1_000_000u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+}
+
+// The FURY/UST pair reports the given cumulative FURY price
+fn set_fury_price_cumulative(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, cumulative: u128) {
+    set_pair_price_cumulative(deps, PAIR, cumulative)
+}
+
+// Only the given pair reports cumulative prices, the given one for FURY
+fn set_pair_price_cumulative(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    pair: &'static str,
+    cumulative: u128,
+) {
+    deps.querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { contract_addr, msg } if contract_addr == pair => match from_binary(msg) {
+            Ok(PairQueryMsg::CumulativePrices {}) => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&CumulativePricesResponse {
+                    assets: [ust(POOL_UST), fury(POOL_FURY)],
+                    total_share: Uint128::from(POOL_SHARE),
+                    price0_cumulative_last: Uint128::zero(),
+                    price1_cumulative_last: Uint128::from(cumulative),
+                    block_time_last: 0,
+                })
+                .unwrap(),
+            )),
+            _ => wasm_query(query),
+        },
+        _ => wasm_query(query),
+    });
+}
+
+fn env_at(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(seconds);
+    env
+}
+
+fn twap(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, env: Env, window_secs: u64) -> StdResult<TwapResponse> {
+    twap_on(deps, env, window_secs, None)
+}
+
+fn twap_on(
+    deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    env: Env,
+    window_secs: u64,
+    pair: Option<[AssetInfo; 2]>,
+) -> StdResult<TwapResponse> {
+    query(deps.as_ref(), env, QueryMsg::TwapFuryToUst { window_secs, pair }).map(|res| from_binary(&res).unwrap())
+}
+
+fn record_price_observation(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, env: Env) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), env, mock_info(GAMER, &[]), ExecuteMsg::RecordPriceObservation { pair: None })
+}
+
+// A user message which leaves the pairs alone
+fn deposit_funds(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, env: Env) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), env, mock_info(GAMER, &coins(1, UUSD)), ExecuteMsg::DepositFunds {})
+}
+
+// 0.1 UST per FURY at the cumulative price precision
+const PRICE_PER_SEC: u128 = 100_000_000_000;
+
+#[test]
+fn twap_is_averaged_over_the_window() {
+    let mut deps = setup();
+
+    // Nothing is observed on a pair without cumulative prices, and user
+    // messages make no pair query before the first observation
+    assert!(record_price_observation(&mut deps, env_at(0)).is_err());
+    let res = deposit_funds(&mut deps, env_at(0)).unwrap();
+    assert!(res.attributes.iter().all(|attr| attr.key != "observed" || attr.value == "false"));
+    let err = twap(&deps, env_at(0), 60).unwrap_err();
+    assert_eq!(err, StdError::generic_err("No price observation old enough for the window"));
+
+    set_fury_price_cumulative(&mut deps, 0);
+    let res = record_price_observation(&mut deps, env_at(0)).unwrap();
+    assert!(res.attributes.iter().any(|attr| attr.key == "observed" && attr.value == "true"));
+    // One observation per interval
+    let res = record_price_observation(&mut deps, env_at(30)).unwrap();
+    assert!(res.attributes.iter().any(|attr| attr.key == "observed" && attr.value == "false"));
+
+    set_fury_price_cumulative(&mut deps, 3600 * PRICE_PER_SEC);
+    assert_eq!(twap(&deps, env_at(3600), 3600).unwrap(), TwapResponse {
+        ust_per_fury: Decimal::from_ratio(1u128, 10u128),
+        observed_secs: 3600,
+    });
+    let ust: Uint128 = from_binary(&query(deps.as_ref(), env_at(3600), QueryMsg::GetUstEquivalentToFury {
+        fury_count: Uint128::from(1000u128),
+        twap_window_secs: Some(3600),
+        pair: None,
+    }).unwrap()).unwrap();
+    assert_eq!(ust, Uint128::from(100u128));
+
+    // Once started every user message takes an observation. FURY doubles
+    // to 0.2 UST after it, which is all the next half hour sees
+    deposit_funds(&mut deps, env_at(3600)).unwrap();
+    set_fury_price_cumulative(&mut deps, (3600 + 1800 * 2) * PRICE_PER_SEC);
+    assert_eq!(twap(&deps, env_at(5400), 1800).unwrap(), TwapResponse {
+        ust_per_fury: Decimal::from_ratio(2u128, 10u128),
+        observed_secs: 1800,
+    });
+    let fury: Uint128 = from_binary(&query(deps.as_ref(), env_at(5400), QueryMsg::GetFuryEquivalentToUst {
+        ust_count: Uint128::from(100u128),
+        twap_window_secs: Some(1800),
+        pair: None,
+    }).unwrap()).unwrap();
+    assert_eq!(fury, Uint128::from(500u128));
+}
+
+#[test]
+fn twap_is_refused_when_the_observations_are_off_the_window() {
+    let mut deps = setup();
+    set_fury_price_cumulative(&mut deps, 0);
+    record_price_observation(&mut deps, env_at(0)).unwrap();
+    set_fury_price_cumulative(&mut deps, 7200 * PRICE_PER_SEC);
+
+    // The only observation is two hours old, a one hour window would
+    // really be the average of two hours
+    let err = twap(&deps, env_at(7200), 3600).unwrap_err();
+    assert_eq!(err, StdError::generic_err("Price observations cover 7200 seconds, too far off the 3600 seconds window"));
+    // A tenth of the window, or the observation interval, is let through
+    assert_eq!(twap(&deps, env_at(7200), 6600).unwrap().observed_secs, 7200);
+    assert_eq!(twap(&deps, env_at(7200), 7150).unwrap().observed_secs, 7200);
+    let err = query(deps.as_ref(), env_at(7200), QueryMsg::GetFuryEquivalentToUst {
+        ust_count: Uint128::from(100u128),
+        twap_window_secs: Some(3600),
+        pair: None,
+    })
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("Price observations cover 7200 seconds, too far off the 3600 seconds window"));
+
+    for window_secs in [0, 7 * 24 * 60 * 60 + 1] {
+        let err = twap(&deps, env_at(7200), window_secs).unwrap_err();
+        assert_eq!(err, StdError::generic_err("TWAP window must be between 1 and 604800 seconds"));
+    }
+}
+
+#[test]
+fn failing_pairs_and_admin_messages_take_no_observation() {
+    let mut deps = setup();
+    set_fury_price_cumulative(&mut deps, 0);
+    record_price_observation(&mut deps, env_at(0)).unwrap();
+
+    // The pair stops answering, user and admin messages still go through
+    deps.querier.update_wasm(wasm_query);
+    deposit_funds(&mut deps, env_at(3600)).unwrap();
+    execute(
+        deps.as_mut(),
+        env_at(3600),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::ProposeNewAdmin { admin_address: GAMER.to_string() },
+    )
+    .unwrap();
+
+    // Neither of them added an observation
+    set_fury_price_cumulative(&mut deps, 5400 * PRICE_PER_SEC);
+    let err = twap(&deps, env_at(5400), 1800).unwrap_err();
+    assert_eq!(err, StdError::generic_err("Price observations cover 5400 seconds, too far off the 1800 seconds window"));
+}
+
+#[test]
+fn observations_are_dropped_when_the_pair_address_changes() {
+    let mut deps = setup();
+    set_fury_price_cumulative(&mut deps, 0);
+    record_price_observation(&mut deps, env_at(0)).unwrap();
+
+    let msg = ExecuteMsg::Configure {
+        pool_pair_address: Some(PAIR_X.to_string()),
+        liquidity_token: None,
+        swap_opening_date: Uint64::zero(),
+    };
+    execute(deps.as_mut(), env_at(3600), mock_info(ADMIN, &[]), msg).unwrap();
+    set_pair_price_cumulative(&mut deps, PAIR_X, 3600 * PRICE_PER_SEC);
+    let err = twap(&deps, env_at(3600), 3600).unwrap_err();
+    assert_eq!(err, StdError::generic_err("No price observation old enough for the window"));
+}
+
+#[test]
+fn twap_is_kept_per_pair() {
+    let mut deps = setup();
+    set_factory(&mut deps);
+    register_luna_pair(&mut deps, 0);
+    let luna_pair = Some([luna(0).info, fury(0).info]);
+
+    set_pair_price_cumulative(&mut deps, PAIR_LUNA, 0);
+    let msg = ExecuteMsg::RecordPriceObservation { pair: luna_pair.clone() };
+    let res = execute(deps.as_mut(), env_at(0), mock_info(GAMER, &[]), msg).unwrap();
+    assert!(res.attributes.iter().any(|attr| attr.key == "observed" && attr.value == "true"));
+    let err = twap(&deps, env_at(3600), 3600).unwrap_err();
+    assert_eq!(err, StdError::generic_err("No price observation old enough for the window"));
+
+    // 0.3 LUNA per FURY over the first hour
+    set_pair_price_cumulative(&mut deps, PAIR_LUNA, 3600 * 3 * PRICE_PER_SEC);
+    assert_eq!(twap_on(&deps, env_at(3600), 3600, luna_pair.clone()).unwrap(), TwapResponse {
+        ust_per_fury: Decimal::from_ratio(3u128, 10u128),
+        observed_secs: 3600,
+    });
+    let luna_amount: Uint128 = from_binary(&query(deps.as_ref(), env_at(3600), QueryMsg::GetUstEquivalentToFury {
+        fury_count: Uint128::from(1000u128),
+        twap_window_secs: Some(3600),
+        pair: luna_pair.clone(),
+    }).unwrap()).unwrap();
+    assert_eq!(luna_amount, Uint128::from(300u128));
+
+    // User messages observe the pair from then on
+    deposit_funds(&mut deps, env_at(3600)).unwrap();
+    set_pair_price_cumulative(&mut deps, PAIR_LUNA, (3600 * 3 + 1800) * PRICE_PER_SEC);
+    assert_eq!(twap_on(&deps, env_at(5400), 1800, luna_pair.clone()).unwrap(), TwapResponse {
+        ust_per_fury: Decimal::from_ratio(1u128, 10u128),
+        observed_secs: 1800,
+    });
+
+    // A deregistered pair is neither observed nor averaged
+    let msg = ExecuteMsg::DeregisterPair {
+        asset_infos: [luna(0).info, fury(0).info],
+    };
+    execute(deps.as_mut(), env_at(5400), mock_info(ADMIN, &[]), msg).unwrap();
+    deps.querier.update_wasm(wasm_query);
+    deposit_funds(&mut deps, env_at(7200)).unwrap();
+    let err = twap_on(&deps, env_at(7200), 1800, luna_pair).unwrap_err();
+    assert_eq!(err, StdError::generic_err("Pair fury/uluna is not managed"));
+}
+
+fn config(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>) -> Config {
+    from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Configuration { pair: None }).unwrap()).unwrap()
+}
+
+fn pending_admin(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>) -> Option<Addr> {
+    from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PendingAdmin {}).unwrap()).unwrap()
+}
+
+#[test]
+fn admin_is_handed_over_once_the_new_admin_accepts() {
+    let mut deps = setup();
+    let propose = ExecuteMsg::ProposeNewAdmin { admin_address: GAMER.to_string() };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), propose.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), ExecuteMsg::AcceptAdmin {}).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), propose).unwrap();
+    assert_eq!(pending_admin(&deps), Some(Addr::unchecked(GAMER)));
+    // Only the proposed admin can accept, the current one stays in charge till then
+    let err = execute(deps.as_mut(), mock_env(), mock_info(FEE_COLLECTOR, &[]), ExecuteMsg::AcceptAdmin {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    assert_eq!(config(&deps).admin_address, Addr::unchecked(ADMIN));
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), ExecuteMsg::AcceptAdmin {}).unwrap();
+    assert_eq!(res.attributes[1].value, ADMIN);
+    assert_eq!(config(&deps).admin_address, Addr::unchecked(GAMER));
+    assert_eq!(pending_admin(&deps), None);
+
+    // The proposal is used up and the old admin is out
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), ExecuteMsg::AcceptAdmin {}).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        swap_fees: Some(Uint128::from(50u128)),
+        ..UpdateConfigMsg::default()
+    });
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), msg).unwrap();
+}
+
+#[test]
+fn update_config_changes_only_the_given_settings() {
+    let mut deps = setup();
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        platform_fees_collector_wallet: Some("new_collector".to_string()),
+        platform_fees: Some(Uint128::from(200u128)),
+        swap_fees: Some(Uint128::from(100u128)),
+        ..UpdateConfigMsg::default()
+    });
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // The unchanged swap fee is left out of the attributes
+    let res = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    let keys: Vec<&str> = res.attributes.iter().map(|attr| attr.key.as_str()).collect();
+    assert_eq!(keys, vec!["action", "platform_fees_collector_wallet", "platform_fees"]);
+    let updated = config(&deps);
+    assert_eq!(updated.platform_fees_collector_wallet, Addr::unchecked("new_collector"));
+    assert_eq!(updated.platform_fees, Uint128::from(200u128));
+    assert_eq!(updated.transaction_fees, Uint128::from(100u128));
+}
+
+#[test]
+fn update_config_rejects_settings_that_do_not_add_up() {
+    let mut deps = setup();
+    let before = config(&deps);
+    for (update, error) in [
+        (
+            UpdateConfigMsg {
+                pair_discount_rate: Some(10_001),
+                ..UpdateConfigMsg::default()
+            },
+            "Discount rate can not be more than 100 percent",
+        ),
+        (
+            UpdateConfigMsg {
+                native_discount_rate: Some(10_001),
+                ..UpdateConfigMsg::default()
+            },
+            "Discount rate can not be more than 100 percent",
+        ),
+        (
+            UpdateConfigMsg {
+                platform_fees: Some(Uint128::from(9_800u128)),
+                ..UpdateConfigMsg::default()
+            },
+            "Fees together must be less than 100 percent",
+        ),
+        (
+            UpdateConfigMsg {
+                max_bonding_limit_per_user: Some(0),
+                ..UpdateConfigMsg::default()
+            },
+            "Bonding limit per user must be at least 1",
+        ),
+    ] {
+        let err = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), ExecuteMsg::UpdateConfig(update))
+            .unwrap_err();
+        assert_eq!(err, ContractError::Std(StdError::generic_err(error)));
+    }
+    assert_eq!(config(&deps), before);
+
+    // Fees just short of 100 percent are fine
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        platform_fees: Some(Uint128::from(9_799u128)),
+        ..UpdateConfigMsg::default()
+    });
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+}
+
+fn create_bond_series(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, kind: BondKind, capacity: u128) -> Response {
+    let msg = ExecuteMsg::CreateBondSeries {
+        kind,
+        capacity: Uint128::from(capacity),
+        max_discount_rate: 2000,
+        min_discount_rate: 1000,
+        debt_decay_period_in_sec: 1000,
+        vesting_period_in_sec: 500,
+        pair: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap()
+}
+
+fn sell_native_bond(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    seconds: u64,
+    fury_pre_discount: u128,
+) -> Result<BondTerms, ContractError> {
+    let config = config(deps);
+    sell_bond(
+        deps.as_mut().storage,
+        &config,
+        &None,
+        BondKind::Native,
+        env_at(seconds).block.time.seconds(),
+        Uint128::from(fury_pre_discount),
+    )
+}
+
+fn bond_series(deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, series_id: u64) -> BondSeries {
+    from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::BondSeries { series_id }).unwrap()).unwrap()
+}
+
+#[test]
+fn bond_series_are_checked_and_replace_each_other() {
+    let mut deps = setup();
+    let msg = ExecuteMsg::CreateBondSeries {
+        kind: BondKind::Native,
+        capacity: Uint128::from(1000u128),
+        max_discount_rate: 1000,
+        min_discount_rate: 2000,
+        debt_decay_period_in_sec: 1000,
+        vesting_period_in_sec: 500,
+        pair: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[]), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err("Discount rates must satisfy min <= max <= 100 percent")));
+
+    create_bond_series(&mut deps, BondKind::Native, 1000);
+    let res = create_bond_series(&mut deps, BondKind::Native, 1000);
+    assert_eq!(res.attributes[1].value, "1");
+    assert!(bond_series(&deps, 1).closed);
+    assert!(!bond_series(&deps, 2).closed);
+    assert_eq!(sell_native_bond(&mut deps, 0, 100).unwrap().series_id, Some(2));
+
+    // Without an open series bonds go back to the configured terms
+    execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), ExecuteMsg::CloseBondSeries { series_id: 2 }).unwrap();
+    let terms = sell_native_bond(&mut deps, 0, 100).unwrap();
+    assert_eq!(terms.series_id, None);
+    assert_eq!(terms.fury_amount, Uint128::from(100u128));
+    assert!(!terms.linear_vesting);
+}
+
+#[test]
+fn bond_discount_shrinks_with_the_debt_and_recovers_as_it_decays() {
+    let mut deps = setup();
+    create_bond_series(&mut deps, BondKind::Native, 3_000_000);
+
+    // No debt yet, the full 20 percent
+    let terms = sell_native_bond(&mut deps, 0, 1_000_000).unwrap();
+    assert_eq!((terms.series_id, terms.discount_rate), (Some(1), 2000));
+    assert_eq!(terms.fury_amount, Uint128::from(1_200_000u128));
+    assert_eq!((terms.vesting_period_in_sec, terms.linear_vesting), (500, true));
+
+    // 1_200_000 of debt is 40 percent of the capacity, so 40 percent of the spread is taken off
+    let terms = sell_native_bond(&mut deps, 0, 1_000_000).unwrap();
+    assert_eq!(terms.discount_rate, 1600);
+    assert_eq!(terms.fury_amount, Uint128::from(1_160_000u128));
+    let series = bond_series(&deps, 1);
+    assert_eq!((series.sold, series.debt), (Uint128::from(2_360_000u128), Uint128::from(2_360_000u128)));
+
+    // The series cannot sell past its capacity
+    let err = sell_native_bond(&mut deps, 0, 1_000_000).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err("Bond series 1 has only 640000 FURY left")));
+
+    // Half the decay period pays off half the debt
+    let terms = sell_native_bond(&mut deps, 500, 500_000).unwrap();
+    assert_eq!(terms.discount_rate, 1607);
+    assert_eq!(terms.fury_amount, Uint128::from(580_350u128));
+    assert_eq!(bond_series(&deps, 1).debt, Uint128::from(1_760_350u128));
+
+    // And after the whole period the discount is back at its maximum
+    let terms = sell_native_bond(&mut deps, 1500, 40_000).unwrap();
+    assert_eq!(terms.discount_rate, 2000);
+    assert_eq!(terms.fury_amount, Uint128::from(48_000u128));
+    assert_eq!(bond_series(&deps, 1).sold, Uint128::from(2_988_350u128));
+}
+
+#[test]
+fn bond_quotes_follow_the_open_series() {
+    let mut deps = setup();
+    let quote = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, kind: BondKind| -> BondQuoteResponse {
+        let msg = QueryMsg::BondQuote {
+            kind,
+            ust_amount: Uint128::from(1_000_000u128),
+            pair: None,
+        };
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+
+    // 1 UST buys 9_990_010 FURY off the pair, the configured rates give no discount
+    assert_eq!(quote(&deps, BondKind::Native), BondQuoteResponse {
+        series_id: None,
+        discount_rate: 0,
+        fury_amount: Uint128::from(9_990_010u128),
+        capacity_remaining: None,
+        vesting_period_in_sec: 0,
+        linear_vesting: false,
+    });
+
+    create_bond_series(&mut deps, BondKind::Native, 30_000_000);
+    assert_eq!(quote(&deps, BondKind::Native), BondQuoteResponse {
+        series_id: Some(1),
+        discount_rate: 2000,
+        fury_amount: Uint128::from(11_988_012u128),
+        capacity_remaining: Some(Uint128::from(30_000_000u128)),
+        vesting_period_in_sec: 500,
+        linear_vesting: true,
+    });
+    // Pair bonds count the FURY provided along with the UST, and have no series of their own yet
+    let pair_quote = quote(&deps, BondKind::Pair);
+    assert_eq!((pair_quote.series_id, pair_quote.fury_amount), (None, Uint128::from(19_980_020u128)));
+
+    sell_native_bond(&mut deps, 0, 10_000_000).unwrap();
+    let native_quote = quote(&deps, BondKind::Native);
+    assert_eq!(native_quote.capacity_remaining, Some(Uint128::from(18_000_000u128)));
+    assert_eq!(native_quote.discount_rate, 1600);
+}
+
+#[test]
+fn bond_series_are_kept_per_pair() {
+    let mut deps = setup();
+    set_factory(&mut deps);
+    register_luna_pair(&mut deps, 0);
+    let luna_pair = Some([luna(0).info, fury(0).info]);
+    let series_on = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, pair: Option<[AssetInfo; 2]>| {
+        let msg = ExecuteMsg::CreateBondSeries {
+            kind: BondKind::Native,
+            capacity: Uint128::from(30_000_000u128),
+            max_discount_rate: 2000,
+            min_discount_rate: 1000,
+            debt_decay_period_in_sec: 1000,
+            vesting_period_in_sec: 500,
+            pair,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg)
+    };
+    let quote = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, pair: Option<[AssetInfo; 2]>| {
+        let msg = QueryMsg::BondQuote {
+            kind: BondKind::Native,
+            ust_amount: Uint128::from(1_000_000u128),
+            pair,
+        };
+        from_binary::<BondQuoteResponse>(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+
+    let err = series_on(&mut deps, Some([fury(0).info, token_x(0).info])).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err("Pair fury/token_x is not managed")));
+
+    series_on(&mut deps, luna_pair.clone()).unwrap();
+    assert_eq!(quote(&deps, luna_pair.clone()).series_id, Some(1));
+    assert_eq!(quote(&deps, None).series_id, None);
+    assert_eq!(bond_series(&deps, 1).pair, luna_pair);
+
+    // A series of the FURY/UST pair leaves the one of the LUNA pair open
+    let res = series_on(&mut deps, None).unwrap();
+    assert!(res.attributes.iter().all(|attr| attr.key != "closed_series_id"));
+    assert_eq!(quote(&deps, None).series_id, Some(2));
+    assert_eq!(quote(&deps, luna_pair.clone()).series_id, Some(1));
+    assert!(!bond_series(&deps, 1).closed);
+
+    // Sales count against the series of their own pair only
+    let config = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Configuration {
+        pair: luna_pair.clone(),
+    }).unwrap()).unwrap();
+    let now = mock_env().block.time.seconds();
+    sell_bond(deps.as_mut().storage, &config, &luna_pair, BondKind::Native, now, Uint128::from(10_000_000u128))
+        .unwrap();
+    assert_eq!(bond_series(&deps, 1).sold, Uint128::from(12_000_000u128));
+    assert_eq!(bond_series(&deps, 2).sold, Uint128::zero());
+}
+
+#[test]
+fn bonds_are_listed_and_claimed_per_pair() {
+    let mut deps = setup();
+    set_factory(&mut deps);
+    register_luna_pair(&mut deps, 0);
+    let luna_pair = Some([luna(0).info, fury(0).info]);
+    let now = mock_env().block.time.seconds();
+    let luna_bond = BondedRewardsDetails {
+        pair: luna_pair.clone(),
+        ..bond(2_000_000, now - 100, false, 0)
+    };
+    BONDED_REWARDS_DETAILS
+        .save(deps.as_mut().storage, GAMER.to_string(), &vec![bond(1_000_000, now - 100, false, 0), luna_bond.clone()])
+        .unwrap();
+
+    let msg = QueryMsg::GetBondingDetails {
+        user_address: GAMER.to_string(),
+        pair: luna_pair.clone(),
+    };
+    let bonds: Option<Vec<BondedRewardsDetails>> = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(bonds, Some(vec![luna_bond]));
+    let msg = QueryMsg::GetClaimableReward {
+        user_address: GAMER.to_string(),
+        pair: None,
+    };
+    let claimable: Uint128 = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(claimable, Uint128::from(1_000_000u128));
+
+    let msg = ExecuteMsg::RewardClaim {
+        receiver: GAMER.to_string(),
+        withdrawal_amount: None,
+        pair: luna_pair,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[Coin::new(100_000, UUSD)]), msg).unwrap();
+    assert_eq!(fury_transferred(&res), Uint128::from(2_000_000u128));
+    let bonds = BONDED_REWARDS_DETAILS.load(&deps.storage, GAMER.to_string()).unwrap();
+    assert_eq!(bonds, vec![bond(1_000_000, now - 100, false, 0)]);
+}
+
+fn bond(bonded_amount: u128, start: u64, linear_vesting: bool, claimed_amount: u128) -> BondedRewardsDetails {
+    BondedRewardsDetails {
+        user_address: GAMER.to_string(),
+        bonded_amount: Uint128::from(bonded_amount),
+        bonding_period: 100,
+        bonding_start_timestamp: Timestamp::from_seconds(start),
+        series_id: None,
+        linear_vesting,
+        claimed_amount: Uint128::from(claimed_amount),
+        pair: None,
+    }
+}
+
+#[test]
+fn bonds_vest_linearly_or_at_the_end_of_the_period() {
+    let opening = Timestamp::from_seconds(2000);
+    let linear = bond(1000, 1000, true, 0);
+    assert_eq!(vested_amount(&linear, opening, 1000), Uint128::zero());
+    assert_eq!(vested_amount(&linear, opening, 1025), Uint128::from(250u128));
+    assert_eq!(vested_amount(&linear, opening, 5000), Uint128::from(1000u128));
+
+    let cliff = bond(1000, 1000, false, 0);
+    assert_eq!(vested_amount(&cliff, opening, 1099), Uint128::zero());
+    assert_eq!(vested_amount(&cliff, opening, 1100), Uint128::from(1000u128));
+
+    // Bonds taken before the swap opening start vesting at the opening
+    let early = bond(1000, 0, true, 0);
+    assert_eq!(vested_amount(&early, opening, 1050), Uint128::zero());
+    assert_eq!(vested_amount(&early, opening, 2050), Uint128::from(500u128));
+
+    // Claimed FURY is left out, and never more than vested
+    let bonds = vec![bond(1000, 1000, true, 100), cliff, bond(1000, 1000, true, 600)];
+    assert_eq!(claimable_amount(&bonds, opening, 1050), Uint128::from(400u128));
+    assert_eq!(claimable_amount(&bonds, opening, 1100), Uint128::from(2300u128));
+}
+
+// The FURY sent to the receiver by the response
+fn fury_transferred(res: &Response) -> Uint128 {
+    res.messages
+        .iter()
+        .find_map(|sub_msg| match &sub_msg.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) if contract_addr == FURY => {
+                match from_binary(msg).unwrap() {
+                    Cw20ExecuteMsg::Transfer { amount, .. } => Some(amount),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn reward_claim_takes_the_amount_asked_for_from_the_oldest_bonds() {
+    let mut deps = setup();
+    let now = mock_env().block.time.seconds();
+    // Half of the 5 FURY of the linear bond and all the 1 FURY of the cliff bond are vested
+    let linear = BondedRewardsDetails {
+        bonding_period: 1000,
+        ..bond(5_000_000, now - 500, true, 0)
+    };
+    BONDED_REWARDS_DETAILS
+        .save(deps.as_mut().storage, GAMER.to_string(), &vec![linear, bond(1_000_000, now - 100, false, 0)])
+        .unwrap();
+    let claim = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, withdrawal_amount: Option<u128>| {
+        let msg = ExecuteMsg::RewardClaim {
+            receiver: GAMER.to_string(),
+            withdrawal_amount: withdrawal_amount.map(Uint128::from),
+            pair: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(GAMER, &[Coin::new(100_000, UUSD)]), msg)
+    };
+
+    let err = claim(&mut deps, Some(3_500_001)).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err(
+        "Withdraw Amount requested is more than Claimable Uint128(3500000)",
+    )));
+    let res = claim(&mut deps, Some(3_000_000)).unwrap();
+    assert_eq!(fury_transferred(&res), Uint128::from(3_000_000u128));
+    let bonds = BONDED_REWARDS_DETAILS.load(&deps.storage, GAMER.to_string()).unwrap();
+    assert_eq!(bonds.iter().map(|bond| bond.claimed_amount.u128()).collect::<Vec<_>>(), vec![2_500_000, 500_000]);
+
+    // The rest of the vested FURY when no amount is given, the cliff bond is done with
+    let res = claim(&mut deps, None).unwrap();
+    assert_eq!(fury_transferred(&res), Uint128::from(500_000u128));
+    let bonds = BONDED_REWARDS_DETAILS.load(&deps.storage, GAMER.to_string()).unwrap();
+    assert_eq!(bonds.len(), 1);
+    assert_eq!(bonds[0].claimed_amount, Uint128::from(2_500_000u128));
+
+    let err = claim(&mut deps, None).unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err(
+        "Nothing vested yet, check the bonding details for the vesting times",
+    )));
+}
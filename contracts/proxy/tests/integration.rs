@@ -0,0 +1,697 @@
+//! Runs every investment, swap and claim of the proxy against FURY, the
+//! terraswap pair and its LP token, so the submessage chains of the proxy
+//! reply through the real contracts.
+
+use cosmwasm_std::{coins, to_binary, Addr, Coin, Decimal, Empty, Uint128, Uint64};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use serde::Deserialize;
+
+use terra_swap_proxy::msg::{BondKind, BondQuoteResponse, ExecuteMsg, InstantiateMsg, ProxyCw20HookMsg, QueryMsg};
+use terraswap::asset::{Asset, AssetInfo, PairInfo};
+use terraswap::pair::{PoolResponse, SimulationResponse};
+
+const UUSD: &str = "uusd";
+const ADMIN: &str = "admin";
+const INVESTOR: &str = "investor";
+const REWARD_WALLET: &str = "reward_wallet";
+const LP_HOLDER: &str = "lp_holder";
+const NATIVE_RECEIVER: &str = "native_receiver";
+const FEE_COLLECTOR: &str = "fee_collector";
+
+// 10 FURY per UST at the start
+const POOL_UST: u128 = 100_000_000;
+const POOL_FURY: u128 = 1_000_000_000;
+const PAIR_BONDING_PERIOD: u64 = 100;
+const NATIVE_BONDING_PERIOD: u64 = 200;
+
+fn cw20_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    ))
+}
+
+/// The LP token of the terraswap pair
+fn lp_token_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        terraswap_token::contract::execute,
+        terraswap_token::contract::instantiate,
+        terraswap_token::contract::query,
+    ))
+}
+
+fn terraswap_pair_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            terraswap_pair::contract::execute,
+            terraswap_pair::contract::instantiate,
+            terraswap_pair::contract::query,
+        )
+        .with_reply(terraswap_pair::contract::reply),
+    )
+}
+
+fn proxy_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            terra_swap_proxy::contract::execute,
+            terra_swap_proxy::contract::instantiate,
+            terra_swap_proxy::contract::query,
+        )
+        .with_reply(terra_swap_proxy::contract::reply),
+    )
+}
+
+/// The part of a bond the suite checks, the proxy keeps the rest of it to itself
+#[derive(Deserialize)]
+struct Bond {
+    bonded_amount: Uint128,
+    claimed_amount: Uint128,
+}
+
+struct Suite {
+    app: App,
+    fury: Addr,
+    proxy: Addr,
+    pair: Addr,
+    lp_token: Addr,
+}
+
+impl Suite {
+    /// Deploys the contracts and has the authorized liquidity provider seed the pair
+    fn new() -> Self {
+        let mut app = App::new(|router, _, storage| {
+            for (wallet, amount) in [(ADMIN, 1_000_000_000u128), (INVESTOR, 100_000_000)] {
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(wallet), coins(amount, UUSD))
+                    .unwrap();
+            }
+        });
+        let admin = Addr::unchecked(ADMIN);
+
+        let cw20_code = app.store_code(cw20_contract());
+        let fury = app
+            .instantiate_contract(
+                cw20_code,
+                admin.clone(),
+                &cw20_base::msg::InstantiateMsg {
+                    name: String::from("Fury"),
+                    symbol: String::from("FURY"),
+                    decimals: 6,
+                    initial_balances: vec![
+                        Cw20Coin {
+                            address: ADMIN.to_string(),
+                            amount: Uint128::from(POOL_FURY),
+                        },
+                        Cw20Coin {
+                            address: INVESTOR.to_string(),
+                            amount: Uint128::from(1_000_000_000u128),
+                        },
+                        Cw20Coin {
+                            address: REWARD_WALLET.to_string(),
+                            amount: Uint128::from(10_000_000_000u128),
+                        },
+                    ],
+                    mint: None,
+                    marketing: None,
+                },
+                &[],
+                "fury",
+                None,
+            )
+            .unwrap();
+
+        // The pair only takes swaps and liquidity from the proxy, so the
+        // proxy comes first and is pointed at the pair once it exists
+        let proxy_code = app.store_code(proxy_contract());
+        let proxy = app
+            .instantiate_contract(
+                proxy_code,
+                admin.clone(),
+                &InstantiateMsg {
+                    admin_address: ADMIN.to_string(),
+                    custom_token_address: fury.to_string(),
+                    pair_discount_rate: 1000,
+                    pair_bonding_period_in_sec: PAIR_BONDING_PERIOD,
+                    pair_fury_reward_wallet: REWARD_WALLET.to_string(),
+                    pair_lp_tokens_holder: LP_HOLDER.to_string(),
+                    native_discount_rate: 500,
+                    native_bonding_period_in_sec: NATIVE_BONDING_PERIOD,
+                    native_investment_reward_wallet: REWARD_WALLET.to_string(),
+                    native_investment_receive_wallet: NATIVE_RECEIVER.to_string(),
+                    authorized_liquidity_provider: ADMIN.to_string(),
+                    swap_opening_date: Uint64::zero(),
+                    pool_pair_address: None,
+                    platform_fees_collector_wallet: FEE_COLLECTOR.to_string(),
+                    platform_fees: Uint128::from(100u128),
+                    transaction_fees: Uint128::from(50u128),
+                    swap_fees: Uint128::from(50u128),
+                    max_bonding_limit_per_user: 10,
+                    usdc_ibc_symbol: UUSD.to_string(),
+                },
+                &[],
+                "proxy",
+                None,
+            )
+            .unwrap();
+
+        let lp_token_code = app.store_code(lp_token_contract());
+        let pair_code = app.store_code(terraswap_pair_contract());
+        let pair = app
+            .instantiate_contract(
+                pair_code,
+                admin.clone(),
+                &terraswap::pair::InstantiateMsg {
+                    asset_infos: [
+                        AssetInfo::NativeToken {
+                            denom: UUSD.to_string(),
+                        },
+                        AssetInfo::Token {
+                            contract_addr: fury.to_string(),
+                        },
+                    ],
+                    token_code_id: lp_token_code,
+                    asset_decimals: [6, 6],
+                    proxy_contract_addr: proxy.clone(),
+                },
+                &[],
+                "pair",
+                None,
+            )
+            .unwrap();
+        let pair_info: PairInfo = app
+            .wrap()
+            .query_wasm_smart(&pair, &terraswap::pair::QueryMsg::Pair {})
+            .unwrap();
+        let lp_token = Addr::unchecked(pair_info.liquidity_token);
+        app.execute_contract(
+            admin.clone(),
+            proxy.clone(),
+            &ExecuteMsg::Configure {
+                pool_pair_address: Some(pair.to_string()),
+                liquidity_token: Some(lp_token.to_string()),
+                swap_opening_date: Uint64::zero(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let mut suite = Suite {
+            app,
+            fury,
+            proxy,
+            pair,
+            lp_token,
+        };
+        suite.approve(ADMIN, POOL_FURY);
+        suite.approve(REWARD_WALLET, 10_000_000_000);
+        suite.approve(INVESTOR, 1_000_000_000);
+        suite
+            .app
+            .execute_contract(
+                admin,
+                suite.proxy.clone(),
+                &ExecuteMsg::ProvideLiquidity {
+                    assets: [ust(POOL_UST), suite.fury_asset(POOL_FURY)],
+                    slippage_tolerance: None,
+                    auto_stake: None,
+                    pair: None,
+                },
+                &coins(POOL_UST, UUSD),
+            )
+            .unwrap();
+        suite
+    }
+
+    // Lets the proxy move FURY of the owner
+    fn approve(&mut self, owner: &str, amount: u128) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(owner),
+                self.fury.clone(),
+                &Cw20ExecuteMsg::IncreaseAllowance {
+                    spender: self.proxy.to_string(),
+                    amount: Uint128::from(amount),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    fn fury_asset(&self, amount: u128) -> Asset {
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: self.fury.to_string(),
+            },
+            amount: Uint128::from(amount),
+        }
+    }
+
+    fn execute(&mut self, sender: &str, msg: &ExecuteMsg, funds: &[Coin]) -> Result<(), String> {
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.proxy.clone(), msg, funds)
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    fn token_balance(&self, token: &Addr, address: &str) -> Uint128 {
+        let response: BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                token,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        response.balance
+    }
+
+    fn fury_balance(&self, address: &str) -> Uint128 {
+        self.token_balance(&self.fury, address)
+    }
+
+    fn lp_balance(&self, address: &str) -> Uint128 {
+        self.token_balance(&self.lp_token, address)
+    }
+
+    fn ust_balance(&self, address: &str) -> Uint128 {
+        self.app.wrap().query_balance(address, UUSD).unwrap().amount
+    }
+
+    fn query<T: serde::de::DeserializeOwned>(&self, msg: &QueryMsg) -> T {
+        self.app.wrap().query_wasm_smart(&self.proxy, msg).unwrap()
+    }
+
+    fn pool(&self) -> PoolResponse {
+        self.query(&QueryMsg::Pool { pair: None })
+    }
+
+    fn platform_fee(&self, msg: &ExecuteMsg) -> Uint128 {
+        self.query(&QueryMsg::QueryPlatformFees {
+            msg: to_binary(msg).unwrap(),
+        })
+    }
+
+    fn bonds(&self, user: &str) -> Vec<Bond> {
+        let bonds: Option<Vec<Bond>> = self.query(&QueryMsg::GetBondingDetails {
+            user_address: user.to_string(),
+            pair: None,
+        });
+        bonds.unwrap_or_default()
+    }
+
+    fn bond_quote(&self, kind: BondKind, ust_amount: u128) -> Uint128 {
+        let quote: BondQuoteResponse = self.query(&QueryMsg::BondQuote {
+            kind,
+            ust_amount: Uint128::from(ust_amount),
+            pair: None,
+        });
+        quote.fury_amount
+    }
+
+    fn simulate(&self, offer_asset: Asset) -> Uint128 {
+        let simulation: SimulationResponse = self.query(&QueryMsg::Simulation { offer_asset, pair: None });
+        simulation.return_amount
+    }
+
+    fn pass_seconds(&mut self, seconds: u64) {
+        self.app
+            .update_block(|block| block.time = block.time.plus_seconds(seconds));
+    }
+}
+
+fn ust(amount: u128) -> Asset {
+    Asset {
+        info: AssetInfo::NativeToken {
+            denom: UUSD.to_string(),
+        },
+        amount: Uint128::from(amount),
+    }
+}
+
+fn ust_and_fee(amount: u128, fee: Uint128) -> Vec<Coin> {
+    coins(amount + fee.u128(), UUSD)
+}
+
+#[test]
+fn authorized_provider_seeds_the_pair_for_no_fee() {
+    let mut suite = Suite::new();
+
+    let pool = suite.pool();
+    assert_eq!(pool.assets[0].amount, Uint128::from(POOL_UST));
+    assert_eq!(pool.assets[1].amount, Uint128::from(POOL_FURY));
+    assert!(!pool.total_share.is_zero());
+    assert_eq!(suite.lp_balance(ADMIN), pool.total_share);
+    assert_eq!(suite.ust_balance(ADMIN), Uint128::from(1_000_000_000 - POOL_UST));
+    assert_eq!(suite.fury_balance(ADMIN), Uint128::zero());
+    assert_eq!(suite.ust_balance(FEE_COLLECTOR), Uint128::zero());
+    // The proxy only passes the liquidity through
+    assert_eq!(suite.ust_balance(suite.proxy.as_str()), Uint128::zero());
+    assert_eq!(suite.fury_balance(suite.proxy.as_str()), Uint128::zero());
+    assert!(suite.bonds(ADMIN).is_empty());
+
+    // Nobody else provides plain liquidity
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [ust(1_000_000), suite.fury_asset(10_000_000)],
+        slippage_tolerance: None,
+        auto_stake: None,
+        pair: None,
+    };
+    suite.execute(INVESTOR, &msg, &coins(1_000_000, UUSD)).unwrap_err();
+}
+
+#[test]
+fn pair_investment_bonds_discounted_fury_claimed_after_vesting() {
+    let mut suite = Suite::new();
+    let pool = suite.pool();
+    let ust_before = suite.ust_balance(INVESTOR);
+    let fury_before = suite.fury_balance(INVESTOR);
+    let reward_wallet_before = suite.fury_balance(REWARD_WALLET);
+
+    let msg = ExecuteMsg::ProvidePairForReward {
+        assets: [ust(10_000_000), suite.fury_asset(100_000_000)],
+        slippage_tolerance: None,
+        auto_stake: None,
+        pair: None,
+    };
+    let fee = suite.platform_fee(&msg);
+    assert!(!fee.is_zero());
+    let bonded = suite.bond_quote(BondKind::Pair, 10_000_000);
+
+    // Short of the fee
+    suite
+        .execute(INVESTOR, &msg, &coins(10_000_000, UUSD))
+        .unwrap_err();
+    // Overpaying gets the rest back
+    suite
+        .execute(INVESTOR, &msg, &ust_and_fee(10_000_000 + 1_000_000, fee))
+        .unwrap();
+
+    assert_eq!(suite.ust_balance(INVESTOR), ust_before - Uint128::from(10_000_000u128) - fee);
+    assert_eq!(suite.fury_balance(INVESTOR), fury_before - Uint128::from(100_000_000u128));
+    assert_eq!(suite.ust_balance(FEE_COLLECTOR), fee);
+
+    // The liquidity goes into the pair and its LP tokens to the holder
+    let share = Uint128::from(10_000_000u128).multiply_ratio(pool.total_share, POOL_UST);
+    let after = suite.pool();
+    assert_eq!(after.assets[0].amount, Uint128::from(POOL_UST + 10_000_000));
+    assert_eq!(after.assets[1].amount, Uint128::from(POOL_FURY + 100_000_000));
+    assert_eq!(suite.lp_balance(LP_HOLDER), share);
+    assert_eq!(suite.lp_balance(INVESTOR), Uint128::zero());
+
+    // The reward is fetched into the proxy and bonded
+    let bonds = suite.bonds(INVESTOR);
+    assert_eq!(bonds.len(), 1);
+    assert_eq!(bonds[0].bonded_amount, bonded);
+    assert_eq!(bonds[0].claimed_amount, Uint128::zero());
+    assert_eq!(suite.fury_balance(REWARD_WALLET), reward_wallet_before - bonded);
+    assert_eq!(suite.fury_balance(suite.proxy.as_str()), bonded);
+    assert_eq!(suite.ust_balance(suite.proxy.as_str()), Uint128::zero());
+
+    // Nothing vests before the end of the bonding period
+    let claim = ExecuteMsg::RewardClaim {
+        receiver: INVESTOR.to_string(),
+        withdrawal_amount: None,
+        pair: None,
+    };
+    suite.execute(INVESTOR, &claim, &[]).unwrap_err();
+
+    suite.pass_seconds(PAIR_BONDING_PERIOD);
+    let claim_fee = suite.platform_fee(&claim);
+    assert!(!claim_fee.is_zero());
+    // Only the bond holder claims it
+    suite
+        .execute(ADMIN, &claim, &coins(claim_fee.u128(), UUSD))
+        .unwrap_err();
+    suite
+        .execute(INVESTOR, &claim, &coins(claim_fee.u128(), UUSD))
+        .unwrap();
+
+    assert_eq!(
+        suite.fury_balance(INVESTOR),
+        fury_before - Uint128::from(100_000_000u128) + bonded
+    );
+    assert_eq!(suite.fury_balance(suite.proxy.as_str()), Uint128::zero());
+    assert_eq!(suite.ust_balance(FEE_COLLECTOR), fee + claim_fee);
+    assert!(suite.bonds(INVESTOR).is_empty());
+
+    // Claiming twice pays nothing more
+    suite.execute(INVESTOR, &claim, &[]).unwrap_err();
+}
+
+#[test]
+fn native_investment_sends_the_ust_to_the_receive_wallet() {
+    let mut suite = Suite::new();
+    let pool = suite.pool();
+    let ust_before = suite.ust_balance(INVESTOR);
+    let reward_wallet_before = suite.fury_balance(REWARD_WALLET);
+
+    let msg = ExecuteMsg::ProvideNativeForReward {
+        asset: ust(5_000_000),
+        slippage_tolerance: None,
+        auto_stake: None,
+        pair: None,
+    };
+    let fee = suite.platform_fee(&msg);
+    assert_eq!(fee, Uint128::from(5_000_000u128 * 150 / 10000));
+    let bonded = suite.bond_quote(BondKind::Native, 5_000_000);
+
+    // Only native assets are provided for a native investment
+    let fury_msg = ExecuteMsg::ProvideNativeForReward {
+        asset: suite.fury_asset(50_000_000),
+        slippage_tolerance: None,
+        auto_stake: None,
+        pair: None,
+    };
+    suite.execute(INVESTOR, &fury_msg, &[]).unwrap_err();
+
+    suite
+        .execute(INVESTOR, &msg, &ust_and_fee(5_000_000, fee))
+        .unwrap();
+
+    assert_eq!(suite.ust_balance(INVESTOR), ust_before - Uint128::from(5_000_000u128) - fee);
+    assert_eq!(suite.ust_balance(NATIVE_RECEIVER), Uint128::from(5_000_000u128));
+    assert_eq!(suite.ust_balance(FEE_COLLECTOR), fee);
+    // The pair is left as it is
+    assert_eq!(suite.pool(), pool);
+
+    let bonds = suite.bonds(INVESTOR);
+    assert_eq!(bonds.len(), 1);
+    assert_eq!(bonds[0].bonded_amount, bonded);
+    assert_eq!(suite.fury_balance(REWARD_WALLET), reward_wallet_before - bonded);
+    assert_eq!(suite.fury_balance(suite.proxy.as_str()), bonded);
+
+    // Part of the bond is claimed once it vested
+    suite.pass_seconds(NATIVE_BONDING_PERIOD);
+    let part = bonded.multiply_ratio(1u128, 4u128);
+    let claim = ExecuteMsg::RewardClaim {
+        receiver: INVESTOR.to_string(),
+        withdrawal_amount: Some(part),
+        pair: None,
+    };
+    let claim_fee = suite.platform_fee(&claim);
+    let fury_before = suite.fury_balance(INVESTOR);
+    suite
+        .execute(INVESTOR, &claim, &coins(claim_fee.u128(), UUSD))
+        .unwrap();
+    assert_eq!(suite.fury_balance(INVESTOR), fury_before + part);
+    assert_eq!(suite.ust_balance(FEE_COLLECTOR), fee + claim_fee);
+    let bonds = suite.bonds(INVESTOR);
+    assert_eq!(bonds[0].claimed_amount, part);
+    assert_eq!(suite.fury_balance(suite.proxy.as_str()), bonded - part);
+}
+
+#[test]
+fn native_swap_pays_the_fee_on_top_of_the_offer() {
+    let mut suite = Suite::new();
+    let ust_before = suite.ust_balance(INVESTOR);
+    let fury_before = suite.fury_balance(INVESTOR);
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: ust(1_000_000),
+        belief_price: None,
+        max_spread: None,
+        to: Some(INVESTOR.to_string()),
+        pair: None,
+    };
+    let fee = suite.platform_fee(&msg);
+    assert_eq!(fee, Uint128::from(1_000_000u128 * 200 / 10000));
+    let returned = suite.simulate(ust(1_000_000));
+    assert!(!returned.is_zero());
+
+    suite
+        .execute(INVESTOR, &msg, &ust_and_fee(1_000_000, fee))
+        .unwrap();
+
+    assert_eq!(suite.ust_balance(INVESTOR), ust_before - Uint128::from(1_000_000u128) - fee);
+    assert_eq!(suite.fury_balance(INVESTOR), fury_before + returned);
+    assert_eq!(suite.ust_balance(FEE_COLLECTOR), fee);
+    let pool = suite.pool();
+    assert_eq!(pool.assets[0].amount, Uint128::from(POOL_UST + 1_000_000));
+    assert_eq!(pool.assets[1].amount, Uint128::from(POOL_FURY) - returned);
+
+    // The pair takes no swaps past the proxy
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(INVESTOR),
+            suite.pair.clone(),
+            &terraswap::pair::ExecuteMsg::Swap {
+                offer_asset: ust(1_000_000),
+                belief_price: None,
+                max_spread: None,
+                to: None,
+            },
+            &coins(1_000_000, UUSD),
+        )
+        .unwrap_err();
+}
+
+#[test]
+fn fury_swap_through_the_allowance_pays_the_fee_in_ust() {
+    let mut suite = Suite::new();
+    let ust_before = suite.ust_balance(INVESTOR);
+    let fury_before = suite.fury_balance(INVESTOR);
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: suite.fury_asset(10_000_000),
+        belief_price: None,
+        max_spread: None,
+        to: Some(INVESTOR.to_string()),
+        pair: None,
+    };
+    let fee = suite.platform_fee(&msg);
+    assert!(!fee.is_zero());
+    let returned = suite.simulate(suite.fury_asset(10_000_000));
+
+    suite
+        .execute(INVESTOR, &msg, &coins(fee.u128() - 1, UUSD))
+        .unwrap_err();
+    suite
+        .execute(INVESTOR, &msg, &coins(fee.u128(), UUSD))
+        .unwrap();
+
+    assert_eq!(suite.fury_balance(INVESTOR), fury_before - Uint128::from(10_000_000u128));
+    assert_eq!(suite.ust_balance(INVESTOR), ust_before - fee + returned);
+    assert_eq!(suite.ust_balance(FEE_COLLECTOR), fee);
+    assert_eq!(suite.fury_balance(suite.proxy.as_str()), Uint128::zero());
+}
+
+#[test]
+fn fury_swap_hook_pays_the_fee_out_of_the_deposit() {
+    let mut suite = Suite::new();
+    let swap = ExecuteMsg::Swap {
+        offer_asset: suite.fury_asset(10_000_000),
+        belief_price: None,
+        max_spread: None,
+        to: Some(INVESTOR.to_string()),
+        pair: None,
+    };
+    let fee = suite.platform_fee(&swap);
+    let returned = suite.simulate(suite.fury_asset(10_000_000));
+
+    suite
+        .execute(INVESTOR, &ExecuteMsg::DepositFunds {}, &ust_and_fee(1_000_000, fee))
+        .unwrap();
+    let deposit: Vec<Coin> = suite.query(&QueryMsg::Deposit {
+        address: INVESTOR.to_string(),
+    });
+    assert_eq!(deposit, ust_and_fee(1_000_000, fee));
+    let ust_before = suite.ust_balance(INVESTOR);
+    let fury_before = suite.fury_balance(INVESTOR);
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(INVESTOR),
+            suite.fury.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: suite.proxy.to_string(),
+                amount: Uint128::from(10_000_000u128),
+                msg: to_binary(&ProxyCw20HookMsg::Swap {
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                    pair: None,
+                })
+                .unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    // The fee comes out of the deposit and the rest of it comes back
+    assert_eq!(suite.fury_balance(INVESTOR), fury_before - Uint128::from(10_000_000u128));
+    assert_eq!(
+        suite.ust_balance(INVESTOR),
+        ust_before + Uint128::from(1_000_000u128) + returned
+    );
+    assert_eq!(suite.ust_balance(FEE_COLLECTOR), fee);
+    let deposit: Vec<Coin> = suite.query(&QueryMsg::Deposit {
+        address: INVESTOR.to_string(),
+    });
+    assert!(deposit.is_empty());
+    assert_eq!(suite.ust_balance(suite.proxy.as_str()), Uint128::zero());
+    assert_eq!(suite.fury_balance(suite.proxy.as_str()), Uint128::zero());
+}
+
+#[test]
+fn withdrawn_liquidity_reaches_the_provider() {
+    let mut suite = Suite::new();
+    let pool = suite.pool();
+    let lp_before = suite.lp_balance(ADMIN);
+    let ust_before = suite.ust_balance(ADMIN);
+    let withdrawn = pool.total_share.multiply_ratio(1u128, 10u128);
+    let ratio = Decimal::from_ratio(withdrawn, pool.total_share);
+
+    // The LP tokens of the pair are all the proxy withdraws for
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(INVESTOR),
+            suite.fury.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: suite.proxy.to_string(),
+                amount: Uint128::from(10_000_000u128),
+                msg: to_binary(&ProxyCw20HookMsg::WithdrawLiquidity {}).unwrap(),
+            },
+            &[],
+        )
+        .unwrap_err();
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            suite.lp_token.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: suite.proxy.to_string(),
+                amount: withdrawn,
+                msg: to_binary(&ProxyCw20HookMsg::WithdrawLiquidity {}).unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    let ust_refund = pool.assets[0].amount * ratio;
+    let fury_refund = pool.assets[1].amount * ratio;
+    assert_eq!(suite.lp_balance(ADMIN), lp_before - withdrawn);
+    assert_eq!(suite.ust_balance(ADMIN), ust_before + ust_refund);
+    assert_eq!(suite.fury_balance(ADMIN), fury_refund);
+
+    // The LP tokens are burnt and the proxy keeps nothing of the refund
+    let after = suite.pool();
+    assert_eq!(after.total_share, pool.total_share - withdrawn);
+    assert_eq!(after.assets[0].amount, pool.assets[0].amount - ust_refund);
+    assert_eq!(after.assets[1].amount, pool.assets[1].amount - fury_refund);
+    assert_eq!(suite.lp_balance(suite.proxy.as_str()), Uint128::zero());
+    assert_eq!(suite.ust_balance(suite.proxy.as_str()), Uint128::zero());
+    assert_eq!(suite.fury_balance(suite.proxy.as_str()), Uint128::zero());
+    assert_eq!(suite.ust_balance(FEE_COLLECTOR), Uint128::zero());
+}
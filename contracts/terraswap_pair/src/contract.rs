@@ -4,7 +4,7 @@ use std::str::FromStr;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
     Addr, Binary, CanonicalAddr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Empty, Env,
-    from_binary, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, to_binary, Uint128,
+    from_binary, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, Storage, SubMsg, to_binary, Uint128,
     WasmMsg,
 };
 #[cfg(not(feature = "library"))]
@@ -15,15 +15,15 @@ use protobuf::Message;
 
 use terraswap::asset::{Asset, AssetInfo, PairInfo, PairInfoRaw};
 use terraswap::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse, QueryMsg,
-    ReverseSimulationResponse, SimulationResponse,
+    CUMULATIVE_PRICE_PRECISION, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg,
+    MigrateMsg, PoolResponse, QueryMsg, ReverseSimulationResponse, SimulationResponse,
 };
 use terraswap::querier::query_token_info;
 use terraswap::token::InstantiateMsg as TokenInstantiateMsg;
 
 use crate::error::ContractError;
 use crate::response::MsgInstantiateContractResponse;
-use crate::state::{PAIR_INFO, PROXY_ADDRESS};
+use crate::state::{PAIR_INFO, PRICE_ACCUMULATOR, PriceAccumulator, PROXY_ADDRESS};
 
 const INSTANTIATE_REPLY_ID: u64 = 1;
 
@@ -49,6 +49,13 @@ pub fn instantiate(
     PROXY_ADDRESS.save(deps.storage, &msg.proxy_contract_addr);
 
     PAIR_INFO.save(deps.storage, pair_info)?;
+    PRICE_ACCUMULATOR.save(
+        deps.storage,
+        &PriceAccumulator {
+            block_time_last: env.block.time.seconds(),
+            ..PriceAccumulator::default()
+        },
+    )?;
 
     Ok(Response::new().add_submessage(SubMsg {
         // Create LP token
@@ -274,6 +281,8 @@ pub fn provide_liquidity(
         }
     }
 
+    accumulate_prices(deps.storage, &env, &pools)?;
+
     // assert slippage tolerance
     assert_slippage_tolerance(&slippage_tolerance, &deposits, &pools)?;
 
@@ -332,8 +341,9 @@ pub fn withdraw_liquidity(
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
     let liquidity_addr: Addr = deps.api.addr_humanize(&pair_info.liquidity_token)?;
 
-    let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, env.contract.address)?;
+    let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
     let total_share: Uint128 = query_token_info(&deps.querier, liquidity_addr)?.total_supply;
+    accumulate_prices(deps.storage, &env, &pools)?;
 
     let share_ratio: Decimal = Decimal::from_ratio(amount, total_share);
     let refund_assets: Vec<Asset> = pools
@@ -386,7 +396,7 @@ pub fn swap(
 
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
 
-    let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, env.contract.address)?;
+    let mut pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
@@ -417,6 +427,14 @@ pub fn swap(
         return Err(ContractError::AssetMismatch {});
     }
 
+    // The prices run up to this swap on the reserves held before it
+    for pool in pools.iter_mut() {
+        if pool.info.equal(&offer_pool.info) {
+            pool.amount = offer_pool.amount;
+        }
+    }
+    accumulate_prices(deps.storage, &env, &pools)?;
+
     let offer_amount = offer_asset.amount;
     let (return_amount, spread_amount, commission_amount) =
         compute_swap(offer_pool.amount, ask_pool.amount, offer_amount);
@@ -460,7 +478,7 @@ pub fn swap(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<Empty>, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+pub fn query(deps: Deps<Empty>, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
         QueryMsg::Pair {} => Ok(to_binary(&query_pair_info(deps)?)?),
         QueryMsg::Pool {} => Ok(to_binary(&query_pool(deps)?)?),
@@ -470,6 +488,7 @@ pub fn query(deps: Deps<Empty>, _env: Env, msg: QueryMsg) -> Result<Binary, Cont
         QueryMsg::ReverseSimulation { ask_asset } => {
             Ok(to_binary(&query_reverse_simulation(deps, ask_asset)?)?)
         }
+        QueryMsg::CumulativePrices {} => Ok(to_binary(&query_cumulative_prices(deps, env)?)?),
     }
 }
 
@@ -498,6 +517,23 @@ pub fn query_pool(deps: Deps<Empty>) -> Result<PoolResponse, ContractError> {
     Ok(resp)
 }
 
+pub fn query_cumulative_prices(
+    deps: Deps<Empty>,
+    env: Env,
+) -> Result<CumulativePricesResponse, ContractError> {
+    let pool = query_pool(deps)?;
+    let accumulator = PRICE_ACCUMULATOR.may_load(deps.storage)?.unwrap_or_default();
+    let accumulator = advance_prices(&accumulator, env.block.time.seconds(), &pool.assets);
+
+    Ok(CumulativePricesResponse {
+        assets: pool.assets,
+        total_share: pool.total_share,
+        price0_cumulative_last: accumulator.price0_cumulative_last,
+        price1_cumulative_last: accumulator.price1_cumulative_last,
+        block_time_last: accumulator.block_time_last,
+    })
+}
+
 pub fn query_simulation(
     deps: Deps<Empty>,
     offer_asset: Asset,
@@ -651,6 +687,41 @@ fn compute_offer_amount(
     )
 }
 
+/// Brings the cumulative prices up to the block time with the reserves held
+/// before the current change. Only the first change in a block moves them,
+/// so a price pushed around within one block does not count.
+fn accumulate_prices(storage: &mut dyn Storage, env: &Env, pools: &[Asset; 2]) -> StdResult<()> {
+    let accumulator = PRICE_ACCUMULATOR.may_load(storage)?.unwrap_or_default();
+    let accumulator = advance_prices(&accumulator, env.block.time.seconds(), pools);
+    PRICE_ACCUMULATOR.save(storage, &accumulator)
+}
+
+fn advance_prices(
+    accumulator: &PriceAccumulator,
+    now: u64,
+    pools: &[Asset; 2],
+) -> PriceAccumulator {
+    let mut advanced = accumulator.clone();
+    advanced.block_time_last = now;
+    // Pairs from before the accumulator start counting from their first change
+    if accumulator.block_time_last == 0 || now <= accumulator.block_time_last {
+        return advanced;
+    }
+    if pools[0].amount.is_zero() || pools[1].amount.is_zero() {
+        return advanced;
+    }
+    let elapsed = Uint128::from(now - accumulator.block_time_last);
+    let price0 = pools[1].amount.multiply_ratio(CUMULATIVE_PRICE_PRECISION, pools[0].amount);
+    let price1 = pools[0].amount.multiply_ratio(CUMULATIVE_PRICE_PRECISION, pools[1].amount);
+    advanced.price0_cumulative_last = accumulator
+        .price0_cumulative_last
+        .wrapping_add(price0.wrapping_mul(elapsed));
+    advanced.price1_cumulative_last = accumulator
+        .price1_cumulative_last
+        .wrapping_add(price1.wrapping_mul(elapsed));
+    advanced
+}
+
 /// If `belief_price` and `max_spread` both are given,
 /// we compute new spread else we just use terraswap
 /// spread to check `max_spread`
@@ -1,6 +1,19 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Uint128};
 use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use terraswap::asset::PairInfoRaw;
 
 pub const PAIR_INFO: Item<PairInfoRaw> = Item::new("pair_info");
 pub const PROXY_ADDRESS: Item<Addr> = Item::new("proxy_address");
+
+/// Running sums of the price of each asset times the seconds it held, as of
+/// `block_time_last`. They only ever move forward and wrap on overflow.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PriceAccumulator {
+    pub price0_cumulative_last: Uint128,
+    pub price1_cumulative_last: Uint128,
+    pub block_time_last: u64,
+}
+
+pub const PRICE_ACCUMULATOR: Item<PriceAccumulator> = Item::new("price_accumulator");
@@ -1,6 +1,6 @@
 use crate::contract::{
-    assert_max_spread, execute, instantiate, query_pair_info, query_pool, query_reverse_simulation,
-    query_simulation, reply,
+    assert_max_spread, execute, instantiate, query_cumulative_prices, query_pair_info, query_pool,
+    query_reverse_simulation, query_simulation, reply,
 };
 use crate::error::ContractError;
 use terraswap::mock_querier::mock_dependencies;
@@ -13,8 +13,8 @@ use cosmwasm_std::{
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use terraswap::asset::{Asset, AssetInfo, PairInfo};
 use terraswap::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, ReverseSimulationResponse,
-    SimulationResponse,
+    CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse,
+    ReverseSimulationResponse, SimulationResponse,
 };
 use terraswap::token::InstantiateMsg as TokenInstantiateMsg;
 
@@ -1248,3 +1248,78 @@ fn test_query_pool() {
     );
     assert_eq!(res.total_share, total_share_amount);
 }
+
+#[test]
+fn test_query_cumulative_prices() {
+    let total_share_amount = Uint128::from(1000u128);
+    let asset_0_amount = Uint128::from(1000u128);
+    let asset_1_amount = Uint128::from(10000u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_1_amount)],
+        ),
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: "asset0000".to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 6u8],
+        proxy_contract_addr: Addr::unchecked("addr0000"),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // store liquidity token
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(
+                vec![
+                    10, 13, 108, 105, 113, 117, 105, 100, 105, 116, 121, 48, 48, 48, 48,
+                ]
+                .into(),
+            ),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // nothing accrued at instantiation time
+    let res: CumulativePricesResponse = query_cumulative_prices(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(res.price0_cumulative_last, Uint128::zero());
+    assert_eq!(res.price1_cumulative_last, Uint128::zero());
+    assert_eq!(res.total_share, total_share_amount);
+
+    // 100 seconds at 10 asset0000 per uusd
+    let mut later = env.clone();
+    later.block.time = env.block.time.plus_seconds(100);
+    let res: CumulativePricesResponse = query_cumulative_prices(deps.as_ref(), later.clone()).unwrap();
+    assert_eq!(
+        res.price0_cumulative_last,
+        Uint128::from(10u128 * 1_000_000_000_000u128 * 100u128)
+    );
+    assert_eq!(
+        res.price1_cumulative_last,
+        Uint128::from(1_000_000_000_000u128 / 10u128 * 100u128)
+    );
+    assert_eq!(res.block_time_last, later.block.time.seconds());
+}
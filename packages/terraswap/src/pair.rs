@@ -55,8 +55,13 @@ pub enum QueryMsg {
     Pool {},
     Simulation { offer_asset: Asset },
     ReverseSimulation { ask_asset: Asset },
+    CumulativePrices {},
 }
 
+/// Scale of the cumulative prices, the price of an asset in units of the
+/// other one times this precision is summed up every second
+pub const CUMULATIVE_PRICE_PRECISION: u128 = 1_000_000_000_000;
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PoolResponse {
@@ -72,6 +77,18 @@ pub struct SimulationResponse {
     pub commission_amount: Uint128,
 }
 
+/// CumulativePricesResponse returns the cumulative prices advanced to the
+/// current block time. price0 is asset 0 priced in asset 1, price1 the other
+/// way around, both wrap around on overflow.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CumulativePricesResponse {
+    pub assets: [Asset; 2],
+    pub total_share: Uint128,
+    pub price0_cumulative_last: Uint128,
+    pub price1_cumulative_last: Uint128,
+    pub block_time_last: u64,
+}
+
 /// ReverseSimulationResponse returns reverse swap simulation response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ReverseSimulationResponse {
@@ -21,6 +21,12 @@ pub enum SwapOperation {
 }
 
 impl SwapOperation {
+    pub fn get_offer_asset_info(&self) -> AssetInfo {
+        match self {
+            SwapOperation::TerraSwap { offer_asset_info, .. } => offer_asset_info.clone(),
+        }
+    }
+
     pub fn get_target_asset_info(&self) -> AssetInfo {
         match self {
             SwapOperation::TerraSwap { ask_asset_info, .. } => ask_asset_info.clone(),